@@ -0,0 +1,144 @@
+//! Commitment hash schemes for subject identifiers.
+//!
+//! The guest only sees 32-bit limbs of the digest, so every scheme produces 32 bytes that are
+//! split into eight limbs using the canonical [`encoding`](crate::encoding).
+use crate::{encoding::digest_words, error::KycError};
+use ark_bn254::Fr;
+use light_poseidon::{Poseidon, PoseidonBytesHasher};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fmt, str::FromStr};
+use tiny_keccak::{Hasher, Keccak};
+
+/// Bytes per Poseidon input element; 31 keeps every chunk below the BN254 scalar modulus.
+const POSEIDON_CHUNK: usize = 31;
+/// Widest circom-compatible Poseidon instance.
+const POSEIDON_MAX_INPUTS: usize = 12;
+
+/// Hash function used to commit to the subject identifier.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashScheme {
+    /// Keccak-256 (Ethereum compatible, historical default)
+    #[default]
+    Keccak256,
+    /// SHA-256
+    Sha256,
+    /// BLAKE3 (32-byte output)
+    Blake3,
+    /// Poseidon over BN254, circom parameters, input packed into 31-byte big-endian chunks
+    Poseidon,
+}
+
+impl HashScheme {
+    /// All supported schemes.
+    pub const ALL: [HashScheme; 4] = [
+        HashScheme::Keccak256,
+        HashScheme::Sha256,
+        HashScheme::Blake3,
+        HashScheme::Poseidon,
+    ];
+
+    /// Stable name, as used in config files and envelopes.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashScheme::Keccak256 => "keccak256",
+            HashScheme::Sha256 => "sha256",
+            HashScheme::Blake3 => "blake3",
+            HashScheme::Poseidon => "poseidon",
+        }
+    }
+
+    /// 32-byte digest of `data`.
+    pub fn digest(&self, data: &[u8]) -> Result<[u8; 32], KycError> {
+        let mut out = [0u8; 32];
+        match self {
+            HashScheme::Keccak256 => {
+                let mut k = Keccak::v256();
+                k.update(data);
+                k.finalize(&mut out);
+            }
+            HashScheme::Sha256 => out.copy_from_slice(&Sha256::digest(data)),
+            HashScheme::Blake3 => out = *blake3::hash(data).as_bytes(),
+            HashScheme::Poseidon => out = poseidon_bytes(data)?,
+        }
+        Ok(out)
+    }
+
+    /// Digest of `data` as eight little-endian `u32` limbs.
+    pub fn limbs(&self, data: &[u8]) -> Result<[u32; 8], KycError> {
+        Ok(digest_words(&self.digest(data)?))
+    }
+}
+
+fn poseidon_bytes(data: &[u8]) -> Result<[u8; 32], KycError> {
+    let chunks: Vec<&[u8]> = if data.is_empty() {
+        vec![&[0u8][..]]
+    } else {
+        data.chunks(POSEIDON_CHUNK).collect()
+    };
+    if chunks.len() > POSEIDON_MAX_INPUTS {
+        return Err(KycError::CommitmentInput(format!(
+            "poseidon accepts at most {} bytes",
+            POSEIDON_CHUNK * POSEIDON_MAX_INPUTS
+        )));
+    }
+    let mut hasher = Poseidon::<Fr>::new_circom(chunks.len())
+        .map_err(|e| KycError::CommitmentInput(e.to_string()))?;
+    hasher
+        .hash_bytes_be(&chunks)
+        .map_err(|e| KycError::CommitmentInput(e.to_string()))
+}
+
+impl fmt::Display for HashScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for HashScheme {
+    type Err = KycError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        HashScheme::ALL
+            .into_iter()
+            .find(|h| h.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| KycError::UnknownHash(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keccak_matches_known_vector() {
+        // keccak256("") = c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470
+        let l = HashScheme::Keccak256.limbs(b"").unwrap();
+        assert_eq!(l[0], 0x0146d2c5);
+        assert_eq!(l[7], 0x70a4855d);
+    }
+
+    #[test]
+    fn schemes_disagree_and_round_trip_names() {
+        let w = b"0x742d35Cc6634C0532925a3b844Bc454e4438f44e";
+        let digests: Vec<_> = HashScheme::ALL
+            .iter()
+            .map(|h| h.digest(w).unwrap())
+            .collect();
+        for i in 0..digests.len() {
+            for j in i + 1..digests.len() {
+                assert_ne!(digests[i], digests[j]);
+            }
+        }
+        for h in HashScheme::ALL {
+            assert_eq!(h.as_str().parse::<HashScheme>().unwrap(), h);
+        }
+    }
+
+    #[test]
+    fn poseidon_rejects_oversized_input() {
+        let big = vec![1u8; POSEIDON_CHUNK * POSEIDON_MAX_INPUTS + 1];
+        assert!(HashScheme::Poseidon.digest(&big).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! `zkkyc://present` deep links for wallet presentation.
+//!
+//! A verifier (say, a point-of-sale terminal) creates a presentation request on its server and
+//! shows the resulting link as a QR code or opens it on the same device:
+//!
+//! ```text
+//! zkkyc://present?v=1&req=<request id>&scope=<relying party>&nonce=<hex>&exp=<unix secs>&cb=<callback url>
+//! ```
+//!
+//! The wallet parses it with [`PresentationRequest::parse`], asks the user to confirm the `scope`
+//! and callback origin, and POSTs `{"proof_id", "nonce"}` to `cb`. The verifier learns the
+//! outcome from its server. Values are percent-encoded; unknown parameters are ignored so later
+//! versions can add optional ones.
+use crate::error::KycError;
+
+/// Scheme and path of a presentation request.
+pub const PRESENT: &str = "zkkyc://present";
+const VERSION: &str = "1";
+
+/// A verifier's request for a wallet to present a proof.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PresentationRequest {
+    /// Server-side id of the request.
+    pub request_id: String,
+    /// Relying party the proof is presented to; the proof's nullifier is spent for it.
+    pub scope: String,
+    /// Random value the wallet echoes back, binding the presentation to this request.
+    pub nonce: String,
+    /// Unix seconds after which the request is no longer accepted.
+    pub expires_at: u64,
+    /// URL the wallet POSTs the presentation to.
+    pub callback: String,
+}
+
+impl PresentationRequest {
+    /// Render as a `zkkyc://present?…` link.
+    pub fn to_uri(&self) -> String {
+        format!(
+            "{PRESENT}?v={VERSION}&req={}&scope={}&nonce={}&exp={}&cb={}",
+            pct_encode(&self.request_id),
+            pct_encode(&self.scope),
+            pct_encode(&self.nonce),
+            self.expires_at,
+            pct_encode(&self.callback),
+        )
+    }
+
+    /// Parse a link produced by [`to_uri`](Self::to_uri).
+    pub fn parse(uri: &str) -> Result<Self, KycError> {
+        let bad = |m: String| KycError::Encoding(format!("presentation link: {m}"));
+        let query = uri
+            .trim()
+            .strip_prefix(PRESENT)
+            .and_then(|q| q.strip_prefix('?'))
+            .ok_or_else(|| bad(format!("not a {PRESENT} link")))?;
+        let (mut v, mut req, mut scope, mut nonce, mut exp, mut cb) =
+            (None, None, None, None, None, None);
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (k, val) = pair.split_once('=').unwrap_or((pair, ""));
+            let val = pct_decode(val).ok_or_else(|| bad(format!("bad encoding of {k}")))?;
+            match k {
+                "v" => v = Some(val),
+                "req" => req = Some(val),
+                "scope" => scope = Some(val),
+                "nonce" => nonce = Some(val),
+                "exp" => exp = Some(val),
+                "cb" => cb = Some(val),
+                _ => {}
+            }
+        }
+        if v.as_deref() != Some(VERSION) {
+            return Err(bad(format!("unsupported version {v:?}")));
+        }
+        let need = |name: &str, x: Option<String>| {
+            x.filter(|s| !s.is_empty())
+                .ok_or_else(|| bad(format!("missing {name}")))
+        };
+        Ok(PresentationRequest {
+            request_id: need("req", req)?,
+            scope: need("scope", scope)?,
+            nonce: need("nonce", nonce)?,
+            expires_at: need("exp", exp)?
+                .parse()
+                .map_err(|_| bad("exp is not unix seconds".into()))?,
+            callback: need("cb", cb)?,
+        })
+    }
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters.
+fn pct_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+fn pct_decode(s: &str) -> Option<String> {
+    let mut out = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'%' => {
+                let hi = (bytes.next()? as char).to_digit(16)?;
+                let lo = (bytes.next()? as char).to_digit(16)?;
+                out.push((hi * 16 + lo) as u8);
+            }
+            b'+' => out.push(b' '),
+            _ => out.push(b),
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> PresentationRequest {
+        PresentationRequest {
+            request_id: "3f1c2a9e-0b1d-4c5e-8f00-1234567890ab".into(),
+            scope: "Café #12 & Bar".into(),
+            nonce: "a1b2c3".into(),
+            expires_at: 1_800_000_000,
+            callback: "https://pos.example.com/presentations/3f1c?x=1".into(),
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let uri = sample().to_uri();
+        assert!(uri.starts_with("zkkyc://present?v=1&"));
+        assert!(!uri[PRESENT.len() + 1..].contains([' ', '#', '?', '/']));
+        assert_eq!(PresentationRequest::parse(&uri).unwrap(), sample());
+        let extra = format!("{uri}&future=1");
+        assert_eq!(PresentationRequest::parse(&extra).unwrap(), sample());
+    }
+
+    #[test]
+    fn rejects_malformed_links() {
+        let uri = sample().to_uri();
+        assert!(PresentationRequest::parse(&uri.replace("zkkyc://", "https://")).is_err());
+        assert!(PresentationRequest::parse(&uri.replace("v=1", "v=2")).is_err());
+        assert!(PresentationRequest::parse(&uri.replace("exp=", "exp=x")).is_err());
+        assert!(PresentationRequest::parse(&uri.replace("&nonce=a1b2c3", "")).is_err());
+        assert!(PresentationRequest::parse(&uri.replace("nonce=a1", "nonce=%zz")).is_err());
+    }
+}
@@ -0,0 +1,103 @@
+//! Canonical encoding of circuit inputs.
+//!
+//! Byte strings (digests, commitments) are split into little-endian `u32` words, zero-padded to a
+//! multiple of four bytes. Each word crosses the guest ABI as a non-negative `i64` parameter whose
+//! value is exactly the `u32`, written in decimal — no two's-complement reinterpretation anywhere.
+//! Flags stay `i32`.
+use crate::error::KycError;
+
+/// Bytes per guest word.
+pub const WORD_BYTES: usize = 4;
+
+/// Split `bytes` into little-endian `u32` words, zero-padding the tail.
+pub fn words_le(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks(WORD_BYTES)
+        .map(|ch| {
+            let mut w = [0u8; WORD_BYTES];
+            w[..ch.len()].copy_from_slice(ch);
+            u32::from_le_bytes(w)
+        })
+        .collect()
+}
+
+/// Inverse of [`words_le`]; `len` drops the zero padding.
+pub fn bytes_le(words: &[u32], len: usize) -> Result<Vec<u8>, KycError> {
+    let mut out: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+    if len > out.len() || out[len..].iter().any(|&b| b != 0) {
+        return Err(KycError::Encoding(format!(
+            "{} words cannot hold {len} bytes without non-zero padding",
+            words.len()
+        )));
+    }
+    out.truncate(len);
+    Ok(out)
+}
+
+/// A 32-byte digest as eight guest words.
+pub fn digest_words(d: &[u8; 32]) -> [u32; 8] {
+    let mut w = [0u32; 8];
+    for (i, ch) in d.chunks(WORD_BYTES).enumerate() {
+        w[i] = u32::from_le_bytes([ch[0], ch[1], ch[2], ch[3]]);
+    }
+    w
+}
+
+/// Decimal guest arguments for `words` (each parsed by the guest as `i64`).
+pub fn guest_args(words: &[u32]) -> Vec<String> {
+    words.iter().map(|w| w.to_string()).collect()
+}
+
+/// Parse guest arguments back into words, rejecting anything outside `0..2^32`.
+pub fn parse_guest_args(args: &[String]) -> Result<Vec<u32>, KycError> {
+    args.iter()
+        .map(|a| {
+            a.parse::<u32>()
+                .map_err(|_| KycError::Encoding(format!("not a canonical u32 word: {a}")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+      #[test]
+      fn bytes_round_trip(bytes in proptest::collection::vec(any::<u8>(), 0..128)) {
+        let words = words_le(&bytes);
+        prop_assert_eq!(words.len(), (bytes.len() + WORD_BYTES - 1) / WORD_BYTES);
+        prop_assert_eq!(bytes_le(&words, bytes.len()).unwrap(), bytes);
+      }
+
+      #[test]
+      fn guest_args_round_trip(words in proptest::collection::vec(any::<u32>(), 0..16)) {
+        let args = guest_args(&words);
+        prop_assert!(args.iter().all(|a| !a.starts_with('-')));
+        prop_assert_eq!(parse_guest_args(&args).unwrap(), words);
+      }
+
+      #[test]
+      fn digest_words_match_generic_split(d in any::<[u8; 32]>()) {
+        prop_assert_eq!(digest_words(&d).to_vec(), words_le(&d));
+        prop_assert_eq!(bytes_le(&digest_words(&d), 32).unwrap(), d.to_vec());
+      }
+    }
+
+    #[test]
+    fn rejects_negative_and_oversized_words() {
+        assert!(parse_guest_args(&["-1".to_string()]).is_err());
+        assert!(parse_guest_args(&["4294967296".to_string()]).is_err());
+        assert_eq!(
+            parse_guest_args(&["4294967295".to_string()]).unwrap(),
+            vec![u32::MAX]
+        );
+    }
+
+    #[test]
+    fn bytes_le_rejects_dirty_padding() {
+        assert!(bytes_le(&[0x0100_0000], 3).is_err());
+        assert!(bytes_le(&[1], 5).is_err());
+    }
+}
@@ -0,0 +1,286 @@
+//! The KYC proving pipeline on zkEngine (feature `engine`).
+//!
+//! One home for what kyc_host, kyc_verifier, kyc_equiv and zk_server each used to spell out: the
+//! Nova type aliases, the guest call for a subject ([`kyc_call`], or [`kyc_call_keccak`] to have the
+//! guest hash the wallet itself), and [`prove_kyc`] / [`verify_kyc`]. Public parameters are a pure
+//! function of the step size; build them with [`setup`], reuse them across proofs, and name them
+//! by [`pp_digest`].
+use crate::{encoding, error::KycError, HashScheme, IdentifierType};
+use std::path::Path;
+use zk_engine::{
+    nova::{
+        provider::{ipa_pc, Bn256EngineIPA},
+        spartan::{
+            batched::BatchedRelaxedR1CSSNARK as BatchedSNARK,
+            snark::RelaxedR1CSSNARK as RelaxedSNARK,
+        },
+        traits::Dual,
+    },
+    wasm_ctx::{WASMArgsBuilder, WASMCtx},
+    wasm_snark::{PublicParams, StepSize, WasmSNARK, ZKWASMInstance},
+};
+
+/* ---------- Nova type aliases ------------------------------------ */
+/// Primary curve engine.
+pub type E = Bn256EngineIPA;
+/// Polynomial commitment evaluation engine.
+pub type EE = ipa_pc::EvaluationEngine<E>;
+/// Primary SNARK.
+pub type S1 = BatchedSNARK<E, EE>;
+/// Secondary curve engine.
+pub type ED = Dual<E>;
+/// Secondary SNARK.
+pub type S2 = RelaxedSNARK<ED, ipa_pc::EvaluationEngine<ED>>;
+/// Public parameters for one step size.
+pub type Params = PublicParams<E, S1, S2>;
+/// A proof of one guest call.
+pub type Snark = WasmSNARK<E, S1, S2>;
+/// The instance a [`Snark`] verifies against.
+pub type Instance = ZKWASMInstance<E>;
+
+fn engine_err(e: impl std::fmt::Display) -> KycError {
+    KycError::Engine(e.to_string())
+}
+
+/// Guest export and arguments proving a subject's KYC approval.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KycCall {
+    /// `check_kyc_full`, or `check_kyc` for the legacy 5-limb commitment.
+    pub invoke: &'static str,
+    /// Commitment limbs then the two flags, as decimal guest arguments.
+    pub args: Vec<String>,
+    /// Leading `args` that are private witness rather than public inputs: the raw address words
+    /// of `check_kyc_full_keccak`, none otherwise.
+    pub witness: usize,
+}
+
+impl KycCall {
+    /// The arguments a verifier sees, as laid out by [`crate::public_inputs::Layout`].
+    pub fn public_inputs(&self) -> &[String] {
+        &self.args[self.witness..]
+    }
+}
+
+/// Build the guest call for `subject` (already canonical) with its flags.
+pub fn kyc_call(
+    scheme: HashScheme,
+    id_type: IdentifierType,
+    subject: &str,
+    kyc: i32,
+    sig_valid: i32,
+    legacy_limbs: bool,
+) -> Result<KycCall, KycError> {
+    id_type.validate(subject)?;
+    let limbs = scheme.limbs(&id_type.preimage(subject))?;
+    let (n, invoke) = if legacy_limbs {
+        (5, "check_kyc")
+    } else {
+        (8, "check_kyc_full")
+    };
+    let mut args = encoding::guest_args(&limbs[..n]);
+    args.extend([kyc.to_string(), sig_valid.to_string()]);
+    Ok(KycCall {
+        invoke,
+        args,
+        witness: 0,
+    })
+}
+
+/// Build the `check_kyc_full_keccak` call for `address` (a canonical `evm_address`): its 20 raw
+/// bytes as 5 witness words, then its Keccak-256 commitment limbs and the flags. The guest
+/// recomputes the commitment from the address and traps on limbs that don't match it (or on
+/// unset flags), so [`prove_kyc`] fails rather than producing a proof for them.
+pub fn kyc_call_keccak(address: &str, kyc: i32, sig_valid: i32) -> Result<KycCall, KycError> {
+    let id_type = IdentifierType::EvmAddress;
+    id_type.validate(address)?;
+    if address != id_type.canonicalize(address) {
+        // The guest hashes the lowercase spelling.
+        return Err(KycError::InvalidIdentifier(
+            "evm_address: not canonical".into(),
+        ));
+    }
+    let raw = hex::decode(&address[2..])
+        .map_err(|e| KycError::InvalidIdentifier(format!("evm_address: {e}")))?;
+    let mut args = encoding::guest_args(&encoding::words_le(&raw));
+    args.extend(encoding::guest_args(
+        &HashScheme::Keccak256.limbs(&id_type.preimage(address))?,
+    ));
+    args.extend([kyc.to_string(), sig_valid.to_string()]);
+    Ok(KycCall {
+        invoke: "check_kyc_full_keccak",
+        args,
+        witness: 5,
+    })
+}
+
+/// Public parameters for `step`; slow, so build once per step size.
+pub fn setup(step: usize) -> Params {
+    Snark::setup(StepSize::new(step))
+}
+
+/// Key for caching the output of [`setup`] at `step`: a hash of what setup is given, the Nova
+/// backend (engine and SNARK types) and the step size. It names the parameters to build, not the
+/// ones built; [`pp_digest`] identifies those.
+pub fn setup_key(step: usize) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+    let mut h = Sha256::new();
+    h.update(std::any::type_name::<Snark>().as_bytes());
+    h.update((step as u64).to_be_bytes());
+    h.finalize().into()
+}
+
+/// Digest identifying public parameters: the SHA-256 of their bincode, as `kyc_host setup --out`
+/// writes them. Proof envelopes carry it, and verifiers compare it with their own parameters'.
+pub fn pp_digest(pp: &Params) -> Result<[u8; 32], KycError> {
+    let raw = bincode::serialize(pp).map_err(engine_err)?;
+    Ok(crate::envelope::digest(&raw))
+}
+
+/// A proof and its instance.
+pub struct KycProof {
+    /// The SNARK.
+    pub snark: Snark,
+    /// The instance it verifies against.
+    pub instance: Instance,
+}
+
+impl KycProof {
+    /// Bincode of the SNARK and of the instance.
+    pub fn to_bytes(&self) -> Result<(Vec<u8>, Vec<u8>), KycError> {
+        Ok((
+            bincode::serialize(&self.snark).map_err(engine_err)?,
+            bincode::serialize(&self.instance).map_err(engine_err)?,
+        ))
+    }
+
+    /// Inverse of [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes(proof: &[u8], instance: &[u8]) -> Result<Self, KycError> {
+        Ok(KycProof {
+            snark: bincode::deserialize(proof)
+                .map_err(|e| KycError::Encoding(format!("proof: {e}")))?,
+            instance: bincode::deserialize(instance)
+                .map_err(|e| KycError::Encoding(format!("instance: {e}")))?,
+        })
+    }
+}
+
+/// Prove `invoke(args)` of the guest at `wasm` under `pp` (built for `step`).
+pub fn prove_kyc(
+    pp: &Params,
+    wasm: &Path,
+    invoke: &str,
+    args: Vec<String>,
+    step: usize,
+) -> Result<KycProof, KycError> {
+    let program =
+        std::fs::read(wasm).map_err(|e| engine_err(format!("{}: {e}", wasm.display())))?;
+    prove_kyc_bytes(pp, program, invoke, args, step)
+}
+
+/// [`prove_kyc`] of a guest already in memory, for callers proving many subjects with one guest.
+pub fn prove_kyc_bytes(
+    pp: &Params,
+    program: Vec<u8>,
+    invoke: &str,
+    args: Vec<String>,
+    step: usize,
+) -> Result<KycProof, KycError> {
+    let wasm_args = WASMArgsBuilder::default()
+        .bytecode(program)
+        .invoke(invoke)
+        .func_args(args)
+        .build();
+    let (snark, instance) =
+        Snark::prove(pp, &WASMCtx::new(wasm_args), StepSize::new(step)).map_err(engine_err)?;
+    Ok(KycProof { snark, instance })
+}
+
+/// Verify `proof` under `pp`; needs no guest and no subject data.
+///
+/// The zkEngine instance commits to the execution, not to the guest's arguments or return value,
+/// so there is no output to check here: that `check_kyc_full_keccak`'s limbs match the wallet
+/// rests on the guest trapping otherwise. Which limbs a proof was made over is known only to
+/// whoever recorded its call (zk_server's proof store).
+pub fn verify_kyc(pp: &Params, proof: &KycProof) -> Result<(), KycError> {
+    proof
+        .snark
+        .verify(pp, &proof.instance)
+        .map_err(engine_err)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn call_layout() {
+        let w = "0x742d35cc6634c0532925a3b844bc454e4438f44e";
+        let full = kyc_call(
+            HashScheme::default(),
+            IdentifierType::EvmAddress,
+            w,
+            1,
+            1,
+            false,
+        )
+        .unwrap();
+        assert_eq!(full.invoke, "check_kyc_full");
+        assert_eq!(full.args.len(), 10);
+        assert_eq!(&full.args[8..], ["1", "1"]);
+        let legacy = kyc_call(
+            HashScheme::default(),
+            IdentifierType::EvmAddress,
+            w,
+            1,
+            0,
+            true,
+        )
+        .unwrap();
+        assert_eq!(legacy.invoke, "check_kyc");
+        assert_eq!(legacy.args[..5], full.args[..5]);
+        assert_eq!(&legacy.args[5..], ["1", "0"]);
+    }
+
+    #[test]
+    fn keccak_call_layout() {
+        let w = "0x742d35cc6634c0532925a3b844bc454e4438f44e";
+        let call = kyc_call_keccak(w, 1, 1).unwrap();
+        assert_eq!(call.invoke, "check_kyc_full_keccak");
+        assert_eq!(call.args.len(), 15);
+        assert_eq!(
+            call.args[..5],
+            [
+                "3426037108",
+                "1405105254",
+                "3097699625",
+                "1313193028",
+                "1324628036"
+            ]
+        );
+        let full = kyc_call(
+            HashScheme::Keccak256,
+            IdentifierType::EvmAddress,
+            w,
+            1,
+            1,
+            false,
+        )
+        .unwrap();
+        assert_eq!(call.public_inputs(), &full.args[..]);
+        assert_eq!(full.public_inputs(), &full.args[..]);
+    }
+
+    #[test]
+    fn call_rejects_invalid_subject() {
+        assert!(kyc_call(
+            HashScheme::default(),
+            IdentifierType::EvmAddress,
+            "alice",
+            1,
+            1,
+            false
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,423 @@
+//! Versioned binary proof envelope, shared by kyc_host and zk_server.
+//!
+//! An [`Envelope`] carries a proof with everything a later build needs to check it was made for
+//! the parameters it is verified under: the guest export and build, the SHA-256 of the guest
+//! module, the public-parameter digest, the step size and the public inputs. All integers are
+//! big-endian:
+//!
+//! ```text
+//! magic "ZKKYCENV" (8) | format version u16 | circuit (u16 len + utf8) | circuit version (u16 len + utf8)
+//! | wasm sha256 (32) | pp digest (32) | step u32 | inputs u16 count, each (u16 len + utf8)
+//! | proof (u32 len + bincode) | instance (u32 len + bincode)
+//! ```
+//!
+//! Format version 2 appends the wallet-ownership check made before proving ([`Ownership`]):
+//!
+//! ```text
+//! ... | ownership message (u16 len + utf8) | signature (65)
+//! ```
+//!
+//! Format version 3 records the hash scheme of the wallet commitment, and makes the ownership
+//! check optional:
+//!
+//! ```text
+//! ... | commitment scheme (u16 len + utf8 name) | has ownership u8 (0 or 1) | [version 2 fields]
+//! ```
+//!
+//! An envelope is written in the lowest version that holds its fields, so envelopes without a
+//! commitment scheme or an ownership record stay readable by version-1 builds. A build reads every format version up to
+//! its own [`FORMAT_VERSION`] and refuses newer ones by name, rather than misreading them. New
+//! fields only ever go in a new version.
+//!
+//! Signatures and hashes over an envelope cover its canonical bytes ([`Envelope::canonical_bytes`]),
+//! never a re-serialization of the caller's choosing, so every SDK that follows these rules gets
+//! the same bytes for the same proof:
+//!
+//! * the layout above at the lowest version that holds the envelope's fields (3 with a
+//!   commitment scheme, else 2 with an ownership record, else 1), whatever version it was read
+//!   from;
+//! * `circuit` and `circuit_version` in Unicode NFC;
+//! * each public input as the shortest decimal of its `u32` word (no sign, no leading zeros),
+//!   which is what [`encoding::guest_args`](crate::encoding::guest_args) writes.
+//!
+//! [`canonical_digest`](Envelope::canonical_digest) is the SHA-256 of those bytes; the test
+//! vector in this module's tests pins it for other implementations.
+use crate::{encoding, error::KycError, HashScheme};
+use sha2::{Digest, Sha256};
+use unicode_normalization::UnicodeNormalization;
+
+/// First bytes of every envelope.
+pub const MAGIC: &[u8; 8] = b"ZKKYCENV";
+/// Format version written by this build.
+pub const FORMAT_VERSION: u16 = 3;
+
+/// Proof that the caller controlled the subject wallet when the proof was requested: a
+/// Sign-In-With-Ethereum (EIP-4361) message and the wallet's `personal_sign` signature over it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ownership {
+    /// The signed message, byte for byte.
+    pub message: String,
+    /// EIP-191 signature, `r || s || v`.
+    pub signature: [u8; 65],
+}
+
+impl Ownership {
+    /// Address the message claims, from its second line (EIP-4361); the signature is checked
+    /// against it by the issuer, not here.
+    pub fn address(&self) -> Option<&str> {
+        self.message.lines().nth(1).map(str::trim)
+    }
+}
+
+/// A proof and the context needed to verify it later.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Envelope {
+    /// Guest export that was proven, e.g. `check_kyc_full`.
+    pub circuit: String,
+    /// Guest build label, e.g. `v1`.
+    pub circuit_version: String,
+    /// SHA-256 of the guest module ([`digest`]).
+    pub wasm_digest: [u8; 32],
+    /// Digest of the public parameters the proof verifies under.
+    pub pp_digest: [u8; 32],
+    /// Step size.
+    pub step: u32,
+    /// Guest arguments, as decimal strings in call order.
+    pub public_inputs: Vec<String>,
+    /// Bincode of the SNARK.
+    pub proof: Vec<u8>,
+    /// Bincode of the instance it verifies against.
+    pub instance: Vec<u8>,
+    /// Wallet-ownership check recorded at issuance (format version 2).
+    pub ownership: Option<Ownership>,
+    /// Hash scheme of the wallet commitment limbs (format version 3).
+    pub commitment_scheme: Option<HashScheme>,
+}
+
+/// SHA-256 of `bytes`, as used for [`Envelope::wasm_digest`].
+pub fn digest(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(bytes).into()
+}
+
+/// Whether `bytes` start like an envelope (of any version).
+pub fn is_envelope(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+fn err(msg: impl Into<String>) -> KycError {
+    KycError::Encoding(format!("envelope: {}", msg.into()))
+}
+
+fn put_str(out: &mut Vec<u8>, s: &str) -> Result<(), KycError> {
+    let len = u16::try_from(s.len()).map_err(|_| err("string field over 65535 bytes"))?;
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+fn put_bytes(out: &mut Vec<u8>, b: &[u8]) -> Result<(), KycError> {
+    let len = u32::try_from(b.len()).map_err(|_| err("byte field over 4 GiB"))?;
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(b);
+    Ok(())
+}
+
+/// Cursor over an envelope being decoded.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], KycError> {
+        if self.0.len() < n {
+            return Err(err("truncated"));
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u16(&mut self) -> Result<u16, KycError> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, KycError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn digest(&mut self) -> Result<[u8; 32], KycError> {
+        Ok(self.take(32)?.try_into().unwrap())
+    }
+
+    fn str(&mut self) -> Result<String, KycError> {
+        let n = self.u16()? as usize;
+        String::from_utf8(self.take(n)?.to_vec()).map_err(|_| err("string field is not utf-8"))
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, KycError> {
+        let n = self.u32()? as usize;
+        Ok(self.take(n)?.to_vec())
+    }
+}
+
+impl Envelope {
+    /// Format version [`encode`](Self::encode) writes: the lowest that holds every field.
+    pub fn version(&self) -> u16 {
+        if self.commitment_scheme.is_some() {
+            3
+        } else if self.ownership.is_some() {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Serialize in [`version`](Self::version).
+    pub fn encode(&self) -> Result<Vec<u8>, KycError> {
+        let mut out = Vec::with_capacity(128 + self.proof.len() + self.instance.len());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&self.version().to_be_bytes());
+        put_str(&mut out, &self.circuit)?;
+        put_str(&mut out, &self.circuit_version)?;
+        out.extend_from_slice(&self.wasm_digest);
+        out.extend_from_slice(&self.pp_digest);
+        out.extend_from_slice(&self.step.to_be_bytes());
+        let count =
+            u16::try_from(self.public_inputs.len()).map_err(|_| err("too many public inputs"))?;
+        out.extend_from_slice(&count.to_be_bytes());
+        for input in &self.public_inputs {
+            put_str(&mut out, input)?;
+        }
+        put_bytes(&mut out, &self.proof)?;
+        put_bytes(&mut out, &self.instance)?;
+        if let Some(scheme) = self.commitment_scheme {
+            put_str(&mut out, scheme.as_str())?;
+            out.push(self.ownership.is_some() as u8);
+        }
+        if let Some(o) = &self.ownership {
+            put_str(&mut out, &o.message)?;
+            out.extend_from_slice(&o.signature);
+        }
+        Ok(out)
+    }
+
+    /// Parse an envelope of any format version up to [`FORMAT_VERSION`].
+    pub fn decode(bytes: &[u8]) -> Result<Self, KycError> {
+        let mut r = Reader(bytes);
+        if r.take(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err(err("not a zkKYC proof envelope"));
+        }
+        let version = match r.u16()? {
+            v @ 1..=3 => v,
+            v if v > FORMAT_VERSION => {
+                return Err(err(format!(
+                    "format version {v} is newer than this build reads ({FORMAT_VERSION})"
+                )))
+            }
+            v => return Err(err(format!("unknown format version {v}"))),
+        };
+        let circuit = r.str()?;
+        let circuit_version = r.str()?;
+        let wasm_digest = r.digest()?;
+        let pp_digest = r.digest()?;
+        let step = r.u32()?;
+        let count = r.u16()?;
+        let public_inputs = (0..count).map(|_| r.str()).collect::<Result<_, _>>()?;
+        let proof = r.bytes()?;
+        let instance = r.bytes()?;
+        let commitment_scheme: Option<HashScheme> = match version {
+            3 => Some(r.str()?.parse().map_err(|e: KycError| err(e.to_string()))?),
+            _ => None,
+        };
+        let has_ownership = match version {
+            1 => false,
+            2 => true,
+            _ => match r.take(1)?[0] {
+                0 => false,
+                1 => true,
+                b => return Err(err(format!("ownership flag {b}"))),
+            },
+        };
+        let ownership = match has_ownership {
+            false => None,
+            true => Some(Ownership {
+                message: r.str()?,
+                signature: r.take(65)?.try_into().unwrap(),
+            }),
+        };
+        if !r.0.is_empty() {
+            return Err(err("trailing bytes"));
+        }
+        Ok(Envelope {
+            circuit,
+            circuit_version,
+            wasm_digest,
+            pp_digest,
+            step,
+            public_inputs,
+            proof,
+            instance,
+            ownership,
+            commitment_scheme,
+        })
+    }
+
+    /// The same envelope in canonical form (see the module docs); fails on a public input that is
+    /// not a `u32` word.
+    pub fn canonical(&self) -> Result<Self, KycError> {
+        let words = encoding::parse_guest_args(&self.public_inputs)
+            .map_err(|e| err(format!("public input {e}")))?;
+        Ok(Envelope {
+            circuit: self.circuit.nfc().collect(),
+            circuit_version: self.circuit_version.nfc().collect(),
+            public_inputs: encoding::guest_args(&words),
+            ..self.clone()
+        })
+    }
+
+    /// The bytes signatures and hashes over this envelope cover.
+    pub fn canonical_bytes(&self) -> Result<Vec<u8>, KycError> {
+        self.canonical()?.encode()
+    }
+
+    /// SHA-256 of [`canonical_bytes`](Self::canonical_bytes).
+    pub fn canonical_digest(&self) -> Result<[u8; 32], KycError> {
+        Ok(digest(&self.canonical_bytes()?))
+    }
+
+    /// Check that this envelope was made under the parameters with digest `pp_digest`.
+    pub fn check_params(&self, pp_digest: &[u8; 32]) -> Result<(), KycError> {
+        if &self.pp_digest != pp_digest {
+            return Err(err(format!(
+                "made under public parameters {}, not this build's {} for step {}",
+                hex::encode(self.pp_digest),
+                hex::encode(pp_digest),
+                self.step
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Envelope {
+        Envelope {
+            circuit: "check_kyc_full".into(),
+            circuit_version: "v1".into(),
+            wasm_digest: digest(b"guest"),
+            pp_digest: [9; 32],
+            step: 8,
+            public_inputs: vec!["123".into(), "1".into(), "1".into()],
+            proof: vec![1, 2, 3, 4],
+            instance: vec![5, 6],
+            ownership: None,
+            commitment_scheme: None,
+        }
+    }
+
+    fn owned() -> Envelope {
+        let message = "example.com wants you to sign in with your Ethereum account:\n\
+                       0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed\n";
+        Envelope {
+            ownership: Some(Ownership {
+                message: message.into(),
+                signature: [7; 65],
+            }),
+            ..sample()
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let raw = sample().encode().unwrap();
+        assert!(is_envelope(&raw));
+        assert_eq!(Envelope::decode(&raw).unwrap(), sample());
+    }
+
+    #[test]
+    fn ownership_needs_version_2() {
+        assert_eq!(sample().encode().unwrap()[8..10], 1u16.to_be_bytes());
+        let raw = owned().encode().unwrap();
+        assert_eq!(raw[8..10], 2u16.to_be_bytes());
+        let back = Envelope::decode(&raw).unwrap();
+        assert_eq!(back, owned());
+        assert_eq!(
+            back.ownership.unwrap().address(),
+            Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed")
+        );
+        assert!(Envelope::decode(&raw[..raw.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn commitment_scheme_needs_version_3() {
+        for ownership in [None, owned().ownership] {
+            let e = Envelope {
+                commitment_scheme: Some(HashScheme::Poseidon),
+                ownership,
+                ..sample()
+            };
+            let raw = e.encode().unwrap();
+            assert_eq!(raw[8..10], 3u16.to_be_bytes());
+            assert_eq!(Envelope::decode(&raw).unwrap(), e);
+        }
+        let mut raw = Envelope {
+            commitment_scheme: Some(HashScheme::Sha256),
+            ..sample()
+        }
+        .encode()
+        .unwrap();
+        let name = raw.len() - 1 - "sha256".len();
+        raw[name..name + 6].copy_from_slice(b"md5sum");
+        assert!(Envelope::decode(&raw).is_err());
+    }
+
+    #[test]
+    fn refuses_foreign_truncated_and_padded_input() {
+        let raw = sample().encode().unwrap();
+        assert!(Envelope::decode(b"{\"proof\": \"00\"}").is_err());
+        assert!(Envelope::decode(&raw[..raw.len() - 1]).is_err());
+        let mut padded = raw;
+        padded.push(0);
+        assert!(Envelope::decode(&padded).is_err());
+    }
+
+    #[test]
+    fn names_newer_versions() {
+        let mut raw = sample().encode().unwrap();
+        raw[8..10].copy_from_slice(&(FORMAT_VERSION + 1).to_be_bytes());
+        let e = Envelope::decode(&raw).unwrap_err().to_string();
+        assert!(e.contains("newer than this build"), "{e}");
+    }
+
+    #[test]
+    fn canonical_form_ignores_spelling() {
+        let mut e = sample();
+        e.circuit_version = "cafe\u{301}".into();
+        e.public_inputs = vec!["0123".into(), "+1".into(), "1".into()];
+        let mut c = sample();
+        c.circuit_version = "caf\u{e9}".into();
+        assert_eq!(e.canonical_bytes().unwrap(), c.encode().unwrap());
+        assert_eq!(e.canonical_digest().unwrap(), c.canonical_digest().unwrap());
+
+        e.public_inputs.push("4294967296".into());
+        assert!(e.canonical().is_err());
+    }
+
+    #[test]
+    fn canonical_digest_vector() {
+        // Shared with the other SDKs: 125 canonical bytes of `sample()`.
+        assert_eq!(sample().canonical_bytes().unwrap().len(), 125);
+        assert_eq!(
+            hex::encode(sample().canonical_digest().unwrap()),
+            "3ae2036bc6017510a48e417c01b86f4bf5a38102585ba804bda0bea179d5c5c5"
+        );
+    }
+
+    #[test]
+    fn checks_params() {
+        let e = sample();
+        assert!(e.check_params(&[9; 32]).is_ok());
+        assert!(e.check_params(&[0; 32]).is_err());
+    }
+}
@@ -0,0 +1,25 @@
+//! Error type for kyc_core.
+use thiserror::Error;
+
+/// Errors raised by host-side KYC helpers.
+#[derive(Debug, Error)]
+pub enum KycError {
+    /// Unknown commitment hash name
+    #[error("unknown commitment hash: {0}")]
+    UnknownHash(String),
+    /// Input cannot be committed with the selected scheme
+    #[error("commitment input rejected: {0}")]
+    CommitmentInput(String),
+    /// Value is not a canonical circuit-input encoding
+    #[error("bad circuit input encoding: {0}")]
+    Encoding(String),
+    /// Subject identifier fails validation for its type
+    #[error("invalid identifier: {0}")]
+    InvalidIdentifier(String),
+    /// Webhook signature missing, stale or invalid
+    #[error("webhook signature rejected: {0}")]
+    Signature(String),
+    /// zkEngine setup, proving or verification failed
+    #[error("proof engine: {0}")]
+    Engine(String),
+}
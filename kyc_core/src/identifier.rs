@@ -0,0 +1,345 @@
+//! Subject identifiers of arbitrary length.
+//!
+//! The commitment preimage for every non-EVM identifier is
+//! `type_tag (1 byte) || len (u32 LE) || utf-8 bytes`, so identifiers of different types or
+//! lengths can never collide. `evm_address` keeps the bare-string preimage, but that alone does not
+//! make older commitments recompute: addresses are now lowercased first and the digest is split into
+//! canonical little-endian words (see [`crate::encoding`]), so limbs issued before then don't match.
+//!
+//! Inputs should pass through [`IdentifierType::canonicalize`] before validation and hashing so
+//! that visually identical strings commit identically. Canonicalization lowercases EVM addresses,
+//! so their EIP-55 checksum has to be checked on the raw input first
+//! ([`IdentifierType::check_checksum`]).
+use crate::error::KycError;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+use tiny_keccak::{Hasher, Keccak};
+use unicode_normalization::UnicodeNormalization;
+
+/// Longest identifier accepted, in bytes.
+pub const MAX_IDENTIFIER_LEN: usize = 1024;
+
+/// Kind of subject identifier; controls validation and the commitment preimage.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentifierType {
+    /// `0x` + 40 hex chars
+    #[default]
+    EvmAddress,
+    /// W3C DID, `did:<method>:<method-specific-id>`
+    Did,
+    /// Hex SHA-256 of an e-mail address (64 hex chars, optional `0x`)
+    EmailHash,
+    /// RFC 4122 UUID in 8-4-4-4-12 hex form
+    AccountUuid,
+}
+
+impl IdentifierType {
+    /// All identifier types.
+    pub const ALL: [IdentifierType; 4] = [
+        IdentifierType::EvmAddress,
+        IdentifierType::Did,
+        IdentifierType::EmailHash,
+        IdentifierType::AccountUuid,
+    ];
+
+    /// Stable name, as used on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IdentifierType::EvmAddress => "evm_address",
+            IdentifierType::Did => "did",
+            IdentifierType::EmailHash => "email_hash",
+            IdentifierType::AccountUuid => "account_uuid",
+        }
+    }
+
+    fn tag(&self) -> u8 {
+        match self {
+            IdentifierType::EvmAddress => 0,
+            IdentifierType::Did => 1,
+            IdentifierType::EmailHash => 2,
+            IdentifierType::AccountUuid => 3,
+        }
+    }
+
+    /// Canonical form of `id`: NFC-normalized, surrounding whitespace trimmed, then
+    ///
+    /// * `evm_address`, `account_uuid` — lowercased (hex is case-insensitive)
+    /// * `email_hash` — lowercased, `0x` prefix dropped
+    /// * `did` — scheme and method lowercased, method-specific id left as is (it is case-sensitive)
+    pub fn canonicalize(&self, id: &str) -> String {
+        let nfc: String = id.nfc().collect();
+        let t = nfc.trim();
+        match self {
+            IdentifierType::EvmAddress | IdentifierType::AccountUuid => t.to_lowercase(),
+            IdentifierType::EmailHash => {
+                let l = t.to_lowercase();
+                l.strip_prefix("0x").map(str::to_string).unwrap_or(l)
+            }
+            IdentifierType::Did => {
+                let mut parts = t.splitn(3, ':');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(scheme), Some(method), Some(rest)) => {
+                        format!("{}:{}:{rest}", scheme.to_lowercase(), method.to_lowercase())
+                    }
+                    _ => t.to_string(),
+                }
+            }
+        }
+    }
+
+    /// Check `id` against the format of this type.
+    pub fn validate(&self, id: &str) -> Result<(), KycError> {
+        let bad = |why: &str| {
+            Err(KycError::InvalidIdentifier(format!(
+                "{}: {why}",
+                self.as_str()
+            )))
+        };
+        if id.is_empty() || id.len() > MAX_IDENTIFIER_LEN {
+            return bad("length out of range");
+        }
+        match self {
+            IdentifierType::EvmAddress => match id.strip_prefix("0x") {
+                Some(h) if h.len() == 40 && is_hex(h) && h.bytes().all(|b| b == b'0') => {
+                    bad("zero address")
+                }
+                Some(h) if h.len() == 40 && is_hex(h) => Ok(()),
+                _ => bad("expected 0x + 40 hex chars"),
+            },
+            IdentifierType::Did => {
+                let mut parts = id.splitn(3, ':');
+                let (scheme, method, rest) = (parts.next(), parts.next(), parts.next());
+                let method_ok = method.map_or(false, |m| {
+                    !m.is_empty()
+                        && m.bytes()
+                            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+                });
+                let rest_ok = rest.map_or(false, |r| {
+                    !r.is_empty()
+                        && r.bytes()
+                            .all(|b| b.is_ascii_alphanumeric() || b".-_:%".contains(&b))
+                });
+                if scheme == Some("did") && method_ok && rest_ok {
+                    Ok(())
+                } else {
+                    bad("expected did:<method>:<id>")
+                }
+            }
+            IdentifierType::EmailHash => {
+                let h = id.strip_prefix("0x").unwrap_or(id);
+                if h.len() == 64 && is_hex(h) {
+                    Ok(())
+                } else {
+                    bad("expected 64 hex chars")
+                }
+            }
+            IdentifierType::AccountUuid => {
+                let groups: Vec<&str> = id.split('-').collect();
+                let lens = [8, 4, 4, 4, 12];
+                if groups.len() == 5
+                    && groups
+                        .iter()
+                        .zip(lens)
+                        .all(|(g, n)| g.len() == n && is_hex(g))
+                {
+                    Ok(())
+                } else {
+                    bad("expected 8-4-4-4-12 hex UUID")
+                }
+            }
+        }
+    }
+
+    /// Check the EIP-55 checksum of a raw (not yet canonicalized) `evm_address`. Addresses written
+    /// in a single case carry no checksum and pass; mixed-case ones must match it exactly. Other
+    /// types always pass.
+    pub fn check_checksum(&self, raw: &str) -> Result<(), KycError> {
+        if *self != IdentifierType::EvmAddress {
+            return Ok(());
+        }
+        let nfc: String = raw.nfc().collect();
+        let Some(h) = nfc
+            .trim()
+            .strip_prefix("0x")
+            .filter(|h| h.len() == 40 && is_hex(h))
+        else {
+            return Ok(()); // left to `validate`
+        };
+        let mixed =
+            h.bytes().any(|b| b.is_ascii_lowercase()) && h.bytes().any(|b| b.is_ascii_uppercase());
+        if mixed && h != eip55(&h.to_ascii_lowercase()) {
+            return Err(KycError::InvalidIdentifier(
+                "evm_address: EIP-55 checksum mismatch".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Bytes that get hashed into the commitment (see module docs).
+    pub fn preimage(&self, id: &str) -> Vec<u8> {
+        match self {
+            IdentifierType::EvmAddress => id.as_bytes().to_vec(),
+            _ => {
+                let mut out = Vec::with_capacity(5 + id.len());
+                out.push(self.tag());
+                out.extend_from_slice(&(id.len() as u32).to_le_bytes());
+                out.extend_from_slice(id.as_bytes());
+                out
+            }
+        }
+    }
+}
+
+/// EIP-55 checksummed spelling of an `evm_address`, in any case; `None` when it is not one.
+pub fn checksummed(address: &str) -> Option<String> {
+    let h = address
+        .trim()
+        .strip_prefix("0x")
+        .filter(|h| h.len() == 40 && is_hex(h))?;
+    Some(format!("0x{}", eip55(&h.to_ascii_lowercase())))
+}
+
+fn is_hex(s: &str) -> bool {
+    s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// EIP-55 spelling of 40 lowercase hex chars (without `0x`): a letter is uppercased when the
+/// matching nibble of keccak256(lowercase hex) is 8 or more.
+fn eip55(lower: &str) -> String {
+    let mut hash = [0u8; 32];
+    let mut k = Keccak::v256();
+    k.update(lower.as_bytes());
+    k.finalize(&mut hash);
+    lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+impl fmt::Display for IdentifierType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for IdentifierType {
+    type Err = KycError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        IdentifierType::ALL
+            .into_iter()
+            .find(|t| t.as_str() == s)
+            .ok_or_else(|| KycError::InvalidIdentifier(format!("unknown identifier type {s}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_each_type() {
+        use IdentifierType::*;
+        assert!(EvmAddress
+            .validate("0x742d35Cc6634C0532925a3b844Bc454e4438f44e")
+            .is_ok());
+        assert!(EvmAddress
+            .validate("742d35Cc6634C0532925a3b844Bc454e4438f44e")
+            .is_err());
+        assert!(EvmAddress
+            .validate(&format!("0x{}", "0".repeat(40)))
+            .is_err());
+        assert!(Did.validate("did:web:example.com:users:alice").is_ok());
+        assert!(Did.validate("did:Web:x").is_err());
+        assert!(Did.validate("did:key:").is_err());
+        assert!(EmailHash.validate(&"ab".repeat(32)).is_ok());
+        assert!(EmailHash.validate("alice@example.com").is_err());
+        assert!(AccountUuid
+            .validate("123e4567-e89b-12d3-a456-426614174000")
+            .is_ok());
+        assert!(AccountUuid
+            .validate("123e4567e89b12d3a456426614174000")
+            .is_err());
+    }
+
+    #[test]
+    fn checks_eip55_checksums() {
+        use IdentifierType::*;
+        // Test vectors from EIP-55.
+        for a in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            assert!(EvmAddress.check_checksum(a).is_ok(), "{a}");
+            assert!(EvmAddress.check_checksum(&format!(" {a}\n")).is_ok(), "{a}");
+        }
+        assert!(EvmAddress
+            .check_checksum("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD")
+            .is_err());
+        assert!(EvmAddress
+            .check_checksum("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed")
+            .is_ok());
+        assert!(EvmAddress
+            .check_checksum("0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED")
+            .is_ok());
+        assert!(Did.check_checksum("did:web:Example").is_ok());
+        assert_eq!(
+            checksummed("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").as_deref(),
+            Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed")
+        );
+        assert_eq!(checksummed("alice"), None);
+    }
+
+    #[test]
+    fn canonicalize_per_type() {
+        use IdentifierType::*;
+        assert_eq!(
+            EvmAddress.canonicalize(" 0x742d35Cc6634C0532925a3b844Bc454e4438f44E\n"),
+            "0x742d35cc6634c0532925a3b844bc454e4438f44e"
+        );
+        assert_eq!(
+            EmailHash.canonicalize(&format!("0x{}", "AB".repeat(32))),
+            "ab".repeat(32)
+        );
+        assert_eq!(
+            Did.canonicalize("DID:Web:Example.com:Alice"),
+            "did:web:Example.com:Alice"
+        );
+        // "é" precomposed vs. "e" + combining acute
+        assert_eq!(
+            Did.canonicalize("did:web:caf\u{e9}"),
+            Did.canonicalize("did:web:cafe\u{301}")
+        );
+        let id = " did:key:z6Mk ";
+        assert_eq!(
+            Did.canonicalize(&Did.canonicalize(id)),
+            Did.canonicalize(id)
+        );
+    }
+
+    #[test]
+    fn preimage_is_length_prefixed_and_typed() {
+        let id = "did:example:123";
+        let p = IdentifierType::Did.preimage(id);
+        assert_eq!(p[0], 1);
+        assert_eq!(
+            u32::from_le_bytes(p[1..5].try_into().unwrap()) as usize,
+            id.len()
+        );
+        assert_eq!(&p[5..], id.as_bytes());
+        assert_ne!(p, IdentifierType::EmailHash.preimage(id));
+        let w = "0x742d35Cc6634C0532925a3b844Bc454e4438f44e";
+        assert_eq!(IdentifierType::EvmAddress.preimage(w), w.as_bytes());
+    }
+}
@@ -0,0 +1,18 @@
+//! Shared host-side logic for the KYC prover CLI and HTTP server.
+#![deny(missing_docs)]
+
+pub mod commitment;
+pub mod deeplink;
+pub mod encoding;
+#[cfg(feature = "engine")]
+pub mod engine;
+pub mod envelope;
+pub mod error;
+pub mod identifier;
+pub mod public_inputs;
+pub mod qr;
+pub mod webhook;
+
+pub use commitment::HashScheme;
+pub use error::KycError;
+pub use identifier::IdentifierType;
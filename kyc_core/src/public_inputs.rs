@@ -0,0 +1,171 @@
+//! Named view of a guest call's public inputs.
+//!
+//! A proof commits to the arguments of one guest export, in call order: the identifier
+//! commitment limbs (8, or 5 for the legacy exports), the `kyc` and `sig_valid` flags, then — for
+//! the Travel Rule exports — the eight words of the IVMS101 payload digest and — for the `_nonce`
+//! exports — the eight words of a verifier's 32-byte challenge nonce. [`decode`] turns that flat
+//! list back into fields a verifier can apply business rules to. `check_kyc_full_keccak` takes the
+//! raw address words before all of these; they are witness, not public inputs, and no layout
+//! covers them. The guests take no expiry; beyond the nonce, freshness has to come from wherever
+//! the proof was issued or stored, which is what [`PublicInputs::expires_at`] carries.
+//!
+//! These are unverified call arguments. The zkEngine instance doesn't expose the guest's
+//! arguments, so [`decode`] reads them as recorded with the call (kyc_host's proof file,
+//! zk_server's proof store) or as supplied by whoever hands over the proof, and a proof that
+//! verifies does not confirm them. Trust them as far as you trust their recorder.
+use crate::{
+    encoding::{bytes_le, parse_guest_args, WORD_BYTES},
+    error::KycError,
+};
+use serde::Serialize;
+
+/// Argument layout of a guest export.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// Number of identifier commitment limbs (8, or 5 for the legacy exports).
+    pub commitment_limbs: usize,
+    /// Whether eight Travel Rule digest words follow the flags.
+    pub travel_rule: bool,
+    /// Whether eight challenge-nonce words come last.
+    pub nonce: bool,
+}
+
+impl Layout {
+    /// Total argument count.
+    pub fn arity(&self) -> usize {
+        self.commitment_limbs
+            + 2
+            + if self.travel_rule { 8 } else { 0 }
+            + if self.nonce { 8 } else { 0 }
+    }
+}
+
+/// Decoded call arguments; unverified (see the module docs).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PublicInputs {
+    /// Identifier commitment limbs as passed to the guest.
+    pub commitment_limbs: Vec<u32>,
+    /// `0x`-hex of the committed digest bytes (20 bytes for the legacy exports).
+    pub commitment: String,
+    /// KYC approval flag (1 = approved).
+    pub kyc: i32,
+    /// Signature-validity flag (1 = valid).
+    pub sig_valid: i32,
+    /// `0x`-hex Keccak-256 of the IVMS101 payload, for Travel Rule exports.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub travel_rule_commitment: Option<String>,
+    /// `0x`-hex challenge nonce the proof was made for, for `_nonce` exports.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    /// Unix seconds past which the issuer no longer vouches for the proof. Not a guest argument,
+    /// so [`decode`] leaves it `None` for the caller to fill in from the issuance record.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+/// Decode `args` (decimal guest arguments, in call order) laid out as `layout`.
+pub fn decode(layout: Layout, args: &[String]) -> Result<PublicInputs, KycError> {
+    if args.len() != layout.arity() {
+        return Err(KycError::Encoding(format!(
+            "expected {} public inputs, got {}",
+            layout.arity(),
+            args.len()
+        )));
+    }
+    let (limbs, rest) = args.split_at(layout.commitment_limbs);
+    let commitment_limbs = parse_guest_args(limbs)?;
+    let flag = |a: &String| -> Result<i32, KycError> {
+        a.parse()
+            .map_err(|_| KycError::Encoding(format!("not an i32 flag: {a}")))
+    };
+    let (travel, nonce) = rest[2..].split_at(if layout.travel_rule { 8 } else { 0 });
+    let digest = |args: &[String]| -> Result<String, KycError> {
+        Ok(hex0x(&bytes_le(&parse_guest_args(args)?, 32)?))
+    };
+    let travel_rule_commitment = layout.travel_rule.then(|| digest(travel)).transpose()?;
+    let nonce = layout.nonce.then(|| digest(nonce)).transpose()?;
+    Ok(PublicInputs {
+        commitment: hex0x(&bytes_le(
+            &commitment_limbs,
+            commitment_limbs.len() * WORD_BYTES,
+        )?),
+        commitment_limbs,
+        kyc: flag(&rest[0])?,
+        sig_valid: flag(&rest[1])?,
+        travel_rule_commitment,
+        nonce,
+        expires_at: None,
+    })
+}
+
+fn hex0x(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::{digest_words, guest_args};
+
+    const FULL: Layout = Layout {
+        commitment_limbs: 8,
+        travel_rule: false,
+        nonce: false,
+    };
+    const TRAVEL: Layout = Layout {
+        commitment_limbs: 8,
+        travel_rule: true,
+        nonce: false,
+    };
+    const TRAVEL_NONCE: Layout = Layout {
+        commitment_limbs: 8,
+        travel_rule: true,
+        nonce: true,
+    };
+
+    #[test]
+    fn decodes_guest_args() {
+        let d = [0xab; 32];
+        let mut args = guest_args(&digest_words(&d));
+        args.extend(["1".to_string(), "0".to_string()]);
+        let p = decode(FULL, &args).unwrap();
+        assert_eq!(p.commitment, format!("0x{}", "ab".repeat(32)));
+        assert_eq!((p.kyc, p.sig_valid), (1, 0));
+        assert_eq!(p.travel_rule_commitment, None);
+
+        args.extend(guest_args(&digest_words(&[0x01; 32])));
+        let p = decode(TRAVEL, &args).unwrap();
+        assert_eq!(
+            p.travel_rule_commitment,
+            Some(format!("0x{}", "01".repeat(32)))
+        );
+        assert_eq!(p.nonce, None);
+
+        args.extend(guest_args(&digest_words(&[0x5a; 32])));
+        let p = decode(TRAVEL_NONCE, &args).unwrap();
+        assert_eq!(
+            p.travel_rule_commitment,
+            Some(format!("0x{}", "01".repeat(32)))
+        );
+        assert_eq!(p.nonce, Some(format!("0x{}", "5a".repeat(32))));
+    }
+
+    #[test]
+    fn rejects_wrong_arity_and_bad_words() {
+        let args = guest_args(&[0; 10]);
+        assert!(decode(FULL, &args).is_ok());
+        assert!(decode(TRAVEL, &args).is_err());
+        assert!(decode(
+            Layout {
+                commitment_limbs: 5,
+                travel_rule: false,
+                nonce: false
+            },
+            &args
+        )
+        .is_err());
+        let mut bad = args.clone();
+        bad[0] = "-1".into();
+        assert!(decode(FULL, &bad).is_err());
+    }
+}
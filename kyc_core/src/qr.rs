@@ -0,0 +1,253 @@
+//! Compact QR payloads that point at a stored proof.
+//!
+//! A [`ProofRef`] names a proof (its id), where to verify it (the server's base URL) and the
+//! verifying-key digest the proof must verify under. [`ProofRef::encode`] packs it into
+//! `ZKKYC:` followed by unpadded RFC 4648 base32 — only characters in the QR alphanumeric set, so
+//! scanners can use the denser alphanumeric mode. The packed bytes are:
+//!
+//! ```text
+//! version (1) | id tag (1) | id (16, or len u8 + utf8) | vk digest (32) | base url (utf8) | check (4)
+//! ```
+//!
+//! where a canonical hyphenated UUID id is stored as its 16 bytes and `check` is the first four
+//! bytes of SHA-256 over everything before it. [`ProofRef::decode`] undoes that and
+//! [`ProofRef::check`] is what a presentation verifier runs before trusting the payload.
+use crate::error::KycError;
+use sha2::{Digest, Sha256};
+
+/// Prefix of every payload.
+pub const PREFIX: &str = "ZKKYC:";
+const VERSION: u8 = 1;
+const ID_UUID: u8 = 0;
+const ID_TEXT: u8 = 1;
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Reference to a stored proof, as carried in a QR code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofRef {
+    /// Proof id on the issuing server.
+    pub proof_id: String,
+    /// Server base URL, e.g. `https://kyc.example.com`.
+    pub base_url: String,
+    /// Digest of the verifying key the proof must verify under.
+    pub vk_digest: [u8; 32],
+}
+
+impl ProofRef {
+    /// Endpoint that re-verifies the proof.
+    pub fn verify_url(&self) -> String {
+        format!(
+            "{}/proofs/{}/verify",
+            self.base_url.trim_end_matches('/'),
+            self.proof_id
+        )
+    }
+
+    /// Pack into a QR-ready payload.
+    pub fn encode(&self) -> Result<String, KycError> {
+        let mut b = vec![VERSION];
+        match uuid_bytes(&self.proof_id) {
+            Some(u) => {
+                b.push(ID_UUID);
+                b.extend_from_slice(&u);
+            }
+            None => {
+                let len = u8::try_from(self.proof_id.len())
+                    .map_err(|_| KycError::Encoding("proof id longer than 255 bytes".into()))?;
+                b.extend([ID_TEXT, len]);
+                b.extend_from_slice(self.proof_id.as_bytes());
+            }
+        }
+        b.extend_from_slice(&self.vk_digest);
+        b.extend_from_slice(self.base_url.as_bytes());
+        let check = Sha256::digest(&b);
+        b.extend_from_slice(&check[..4]);
+        Ok(format!("{PREFIX}{}", base32_encode(&b)))
+    }
+
+    /// Unpack a payload produced by [`encode`](Self::encode), checking its checksum.
+    pub fn decode(payload: &str) -> Result<Self, KycError> {
+        let bad = |m: &str| KycError::Encoding(format!("QR payload: {m}"));
+        let body = payload
+            .trim()
+            .strip_prefix(PREFIX)
+            .ok_or_else(|| bad("missing ZKKYC: prefix"))?;
+        let b = base32_decode(body).ok_or_else(|| bad("not base32"))?;
+        if b.len() < 4 {
+            return Err(bad("truncated"));
+        }
+        let (b, check) = b.split_at(b.len() - 4);
+        if Sha256::digest(b)[..4] != *check {
+            return Err(bad("checksum mismatch"));
+        }
+        let (&version, rest) = b.split_first().ok_or_else(|| bad("truncated"))?;
+        if version != VERSION {
+            return Err(bad(&format!("unsupported version {version}")));
+        }
+        let (&tag, rest) = rest.split_first().ok_or_else(|| bad("truncated"))?;
+        let (proof_id, rest) = match tag {
+            ID_UUID if rest.len() >= 16 => (uuid_string(&rest[..16]), &rest[16..]),
+            ID_TEXT if !rest.is_empty() && rest.len() > rest[0] as usize => {
+                let n = rest[0] as usize;
+                let id =
+                    std::str::from_utf8(&rest[1..=n]).map_err(|_| bad("proof id is not utf-8"))?;
+                (id.to_string(), &rest[n + 1..])
+            }
+            _ => return Err(bad("malformed proof id")),
+        };
+        if rest.len() < 32 {
+            return Err(bad("truncated"));
+        }
+        let (vk, url) = rest.split_at(32);
+        let base_url = std::str::from_utf8(url).map_err(|_| bad("base url is not utf-8"))?;
+        Ok(ProofRef {
+            proof_id,
+            base_url: base_url.to_string(),
+            vk_digest: vk.try_into().expect("32 bytes"),
+        })
+    }
+
+    /// Accept this reference only if it points at one of `trusted_origins` (`scheme://host[:port]`)
+    /// over HTTPS and names the verifying key the verifier expects.
+    pub fn check(&self, expected_vk: &[u8; 32], trusted_origins: &[&str]) -> Result<(), KycError> {
+        let base = self.base_url.trim_end_matches('/');
+        if !base.starts_with("https://")
+            || !trusted_origins
+                .iter()
+                .any(|o| base == o.trim_end_matches('/'))
+        {
+            return Err(KycError::Encoding(format!(
+                "untrusted verifier origin {base}"
+            )));
+        }
+        if &self.vk_digest != expected_vk {
+            return Err(KycError::Encoding("verifying-key digest mismatch".into()));
+        }
+        Ok(())
+    }
+}
+
+/// 16 bytes of a canonical (lowercase, hyphenated) UUID, so that [`uuid_string`] gives the id
+/// back exactly; `None` for anything else.
+fn uuid_bytes(id: &str) -> Option<[u8; 16]> {
+    let hyphen = |i: usize| matches!(i, 8 | 13 | 18 | 23);
+    let canonical = id.len() == 36
+        && id.bytes().enumerate().all(|(i, c)| {
+            if hyphen(i) {
+                c == b'-'
+            } else {
+                matches!(c, b'0'..=b'9' | b'a'..=b'f')
+            }
+        });
+    if !canonical {
+        return None;
+    }
+    let hex: Vec<u8> = id.bytes().filter(|&c| c != b'-').collect();
+    let mut out = [0u8; 16];
+    for (o, pair) in out.iter_mut().zip(hex.chunks(2)) {
+        *o = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(out)
+}
+
+fn uuid_string(b: &[u8]) -> String {
+    let h: String = b.iter().map(|x| format!("{x:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &h[..8],
+        &h[8..12],
+        &h[12..16],
+        &h[16..20],
+        &h[20..]
+    )
+}
+
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity((bytes.len() + 4) / 5 * 8);
+    let (mut acc, mut bits) = (0u32, 0u32);
+    for &b in bytes {
+        acc = (acc << 8) | b as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32[((acc >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32[((acc << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+fn base32_decode(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 5 / 8);
+    let (mut acc, mut bits) = (0u32, 0u32);
+    for c in s.bytes() {
+        let v = BASE32.iter().position(|&a| a == c)? as u32;
+        acc = (acc << 5) | v;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    // Leftover bits are padding and must be zero.
+    (acc & ((1 << bits) - 1) == 0).then_some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(id: &str) -> ProofRef {
+        ProofRef {
+            proof_id: id.into(),
+            base_url: "https://kyc.example.com".into(),
+            vk_digest: [7; 32],
+        }
+    }
+
+    #[test]
+    fn round_trips_uuid_and_text_ids() {
+        for id in ["123e4567-e89b-12d3-a456-426614174000", "proof-42"] {
+            let r = sample(id);
+            let p = r.encode().unwrap();
+            assert!(p
+                .bytes()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == b':'));
+            assert_eq!(ProofRef::decode(&p).unwrap(), r);
+        }
+        let uuid = sample("123e4567-e89b-12d3-a456-426614174000")
+            .encode()
+            .unwrap();
+        assert!(uuid.len() < sample(&"x".repeat(36)).encode().unwrap().len());
+    }
+
+    #[test]
+    fn rejects_tampered_payloads() {
+        let p = sample("proof-42").encode().unwrap();
+        let mut t = p.clone().into_bytes();
+        let i = PREFIX.len() + 3;
+        t[i] = if t[i] == b'A' { b'B' } else { b'A' };
+        assert!(ProofRef::decode(std::str::from_utf8(&t).unwrap()).is_err());
+        assert!(ProofRef::decode(&p[PREFIX.len()..]).is_err());
+        assert!(ProofRef::decode("ZKKYC:abc").is_err());
+    }
+
+    #[test]
+    fn check_pins_origin_and_vk() {
+        let r = sample("proof-42");
+        assert!(r.check(&[7; 32], &["https://kyc.example.com/"]).is_ok());
+        assert!(r.check(&[8; 32], &["https://kyc.example.com"]).is_err());
+        assert!(r.check(&[7; 32], &["https://other.example.com"]).is_err());
+        let http = ProofRef {
+            base_url: "http://kyc.example.com".into(),
+            ..r
+        };
+        assert!(http.check(&[7; 32], &["http://kyc.example.com"]).is_err());
+        assert_eq!(
+            http.verify_url(),
+            "http://kyc.example.com/proofs/proof-42/verify"
+        );
+    }
+}
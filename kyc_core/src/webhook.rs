@@ -0,0 +1,246 @@
+//! Signing and verification of outgoing webhooks (billing events, job callbacks).
+//!
+//! Every delivery carries three headers:
+//!
+//! ```text
+//! x-zk-timestamp:  <unix seconds>
+//! x-zk-key-id:     <id of the signing key>
+//! x-zk-signature:  v1=<hex HMAC-SHA256(secret, "<timestamp>.<body>")>
+//!                | ed25519=<hex Ed25519 signature over "<timestamp>.<body>">
+//! ```
+//!
+//! The server signs with a [`Signer`]; receivers build a [`Verifier`] from the keys they were
+//! given (shared secrets or Ed25519 public keys, by key id) and call [`Verifier::verify`] on the
+//! raw body before parsing it. The key id lets senders rotate keys without a flag day, and the
+//! timestamp tolerance bounds how long a captured delivery can be replayed.
+use crate::error::KycError;
+use ed25519_dalek::{Signer as _, SigningKey, VerifyingKey};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+
+/// Header carrying the signature.
+pub const SIGNATURE_HEADER: &str = "x-zk-signature";
+/// Header carrying the signing timestamp (unix seconds).
+pub const TIMESTAMP_HEADER: &str = "x-zk-timestamp";
+/// Header naming the signing key.
+pub const KEY_ID_HEADER: &str = "x-zk-key-id";
+/// Default replay window for [`Verifier`].
+pub const DEFAULT_TOLERANCE_SECS: u64 = 300;
+
+/// Sender side: one key and its id.
+pub enum Signer {
+    /// Shared-secret HMAC-SHA256 (`v1=`).
+    Hmac {
+        /// Key id sent in [`KEY_ID_HEADER`].
+        key_id: String,
+        /// Shared secret.
+        secret: Vec<u8>,
+    },
+    /// Ed25519 (`ed25519=`); receivers only need the public key.
+    Ed25519 {
+        /// Key id sent in [`KEY_ID_HEADER`].
+        key_id: String,
+        /// Signing key.
+        key: SigningKey,
+    },
+}
+
+impl Signer {
+    /// Ed25519 signer from a 32-byte hex seed.
+    pub fn ed25519_from_hex(key_id: impl Into<String>, seed_hex: &str) -> Result<Self, KycError> {
+        let seed: [u8; 32] = hex::decode(seed_hex.trim_start_matches("0x"))
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| KycError::Signature("Ed25519 seed must be 32 hex bytes".into()))?;
+        Ok(Signer::Ed25519 {
+            key_id: key_id.into(),
+            key: SigningKey::from_bytes(&seed),
+        })
+    }
+
+    /// Id to send in [`KEY_ID_HEADER`].
+    pub fn key_id(&self) -> &str {
+        match self {
+            Signer::Hmac { key_id, .. } | Signer::Ed25519 { key_id, .. } => key_id,
+        }
+    }
+
+    /// Value for [`SIGNATURE_HEADER`].
+    pub fn sign(&self, ts: u64, body: &[u8]) -> String {
+        let msg = signed_payload(ts, body);
+        match self {
+            Signer::Hmac { secret, .. } => {
+                let mut mac =
+                    Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes any key length");
+                mac.update(&msg);
+                format!("v1={}", hex::encode(mac.finalize().into_bytes()))
+            }
+            Signer::Ed25519 { key, .. } => {
+                format!("ed25519={}", hex::encode(key.sign(&msg).to_bytes()))
+            }
+        }
+    }
+
+    /// Hex public key receivers configure for an Ed25519 signer; `None` for HMAC.
+    pub fn public_key_hex(&self) -> Option<String> {
+        match self {
+            Signer::Hmac { .. } => None,
+            Signer::Ed25519 { key, .. } => Some(hex::encode(key.verifying_key().to_bytes())),
+        }
+    }
+}
+
+/// A key a receiver accepts.
+#[derive(Clone)]
+pub enum VerifyKey {
+    /// Shared HMAC secret.
+    Hmac(Vec<u8>),
+    /// Sender's Ed25519 public key.
+    Ed25519(VerifyingKey),
+}
+
+impl VerifyKey {
+    /// Ed25519 public key from 32 hex bytes.
+    pub fn ed25519_from_hex(public_hex: &str) -> Result<Self, KycError> {
+        let bytes: [u8; 32] = hex::decode(public_hex.trim_start_matches("0x"))
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| KycError::Signature("Ed25519 public key must be 32 hex bytes".into()))?;
+        VerifyingKey::from_bytes(&bytes)
+            .map(VerifyKey::Ed25519)
+            .map_err(|_| KycError::Signature("invalid Ed25519 public key".into()))
+    }
+}
+
+/// Receiver side: accepted keys by id, plus the replay window.
+#[derive(Clone, Default)]
+pub struct Verifier {
+    keys: HashMap<String, VerifyKey>,
+    tolerance_secs: u64,
+}
+
+impl Verifier {
+    /// No keys yet, [`DEFAULT_TOLERANCE_SECS`] window.
+    pub fn new() -> Self {
+        Verifier {
+            keys: HashMap::new(),
+            tolerance_secs: DEFAULT_TOLERANCE_SECS,
+        }
+    }
+
+    /// Accept `key` under `key_id`.
+    pub fn with_key(mut self, key_id: impl Into<String>, key: VerifyKey) -> Self {
+        self.keys.insert(key_id.into(), key);
+        self
+    }
+
+    /// Reject deliveries whose timestamp is more than `secs` from now.
+    pub fn tolerance(mut self, secs: u64) -> Self {
+        self.tolerance_secs = secs;
+        self
+    }
+
+    /// Check one delivery against the header values and the raw body, at unix time `now`.
+    pub fn verify(
+        &self,
+        key_id: &str,
+        timestamp: &str,
+        signature: &str,
+        body: &[u8],
+        now: u64,
+    ) -> Result<(), KycError> {
+        let bad = |m: &str| Err(KycError::Signature(m.into()));
+        let Some(key) = self.keys.get(key_id) else {
+            return bad("unknown key id");
+        };
+        let Ok(ts) = timestamp.trim().parse::<u64>() else {
+            return bad("timestamp is not unix seconds");
+        };
+        if ts.abs_diff(now) > self.tolerance_secs {
+            return bad("timestamp outside tolerance");
+        }
+        let msg = signed_payload(ts, body);
+        let (scheme, sig) = signature.trim().split_once('=').unwrap_or(("", ""));
+        let Ok(sig) = hex::decode(sig) else {
+            return bad("signature is not hex");
+        };
+        let ok = match (scheme, key) {
+            ("v1", VerifyKey::Hmac(secret)) => {
+                let mut mac =
+                    Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes any key length");
+                mac.update(&msg);
+                mac.verify_slice(&sig).is_ok()
+            }
+            ("ed25519", VerifyKey::Ed25519(pk)) => ed25519_dalek::Signature::from_slice(&sig)
+                .map(|s| pk.verify_strict(&msg, &s).is_ok())
+                .unwrap_or(false),
+            _ => return bad("signature scheme does not match the key"),
+        };
+        if ok {
+            Ok(())
+        } else {
+            bad("signature mismatch")
+        }
+    }
+}
+
+/// `"<ts>.<body>"`, the bytes that are signed.
+fn signed_payload(ts: u64, body: &[u8]) -> Vec<u8> {
+    let mut msg = format!("{ts}.").into_bytes();
+    msg.extend_from_slice(body);
+    msg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = br#"{"id":"e1","type":"proof.completed"}"#;
+
+    #[test]
+    fn hmac_round_trip() {
+        let s = Signer::Hmac {
+            key_id: "k1".into(),
+            secret: b"s3cret".to_vec(),
+        };
+        let sig = s.sign(1_700_000_000, BODY);
+        assert!(sig.starts_with("v1="));
+        let v = Verifier::new().with_key("k1", VerifyKey::Hmac(b"s3cret".to_vec()));
+        assert!(v
+            .verify("k1", "1700000000", &sig, BODY, 1_700_000_100)
+            .is_ok());
+        assert!(v
+            .verify("k1", "1700000000", &sig, b"{}", 1_700_000_100)
+            .is_err());
+        assert!(v
+            .verify("k2", "1700000000", &sig, BODY, 1_700_000_100)
+            .is_err());
+        assert!(v
+            .verify("k1", "1700000001", &sig, BODY, 1_700_000_100)
+            .is_err());
+        assert!(v
+            .verify("k1", "1700000000", &sig, BODY, 1_700_001_000)
+            .is_err());
+    }
+
+    #[test]
+    fn ed25519_round_trip() {
+        let s = Signer::ed25519_from_hex("ed1", &"07".repeat(32)).unwrap();
+        let sig = s.sign(1_700_000_000, BODY);
+        assert!(sig.starts_with("ed25519="));
+        let pk = VerifyKey::ed25519_from_hex(&s.public_key_hex().unwrap()).unwrap();
+        let v = Verifier::new()
+            .with_key("ed1", pk)
+            .with_key("h", VerifyKey::Hmac(b"x".to_vec()));
+        assert!(v
+            .verify("ed1", "1700000000", &sig, BODY, 1_700_000_000)
+            .is_ok());
+        assert!(v
+            .verify("ed1", "1700000000", &sig, b"tampered", 1_700_000_000)
+            .is_err());
+        assert!(v
+            .verify("h", "1700000000", &sig, BODY, 1_700_000_000)
+            .is_err());
+    }
+}
@@ -0,0 +1,141 @@
+//! kyc_equiv [--server <url>] [--kyc-host <path>] [--hash <scheme>] [--id-type <type>] [--legacy-limbs] <subject> <kycStatus> <sigValid> [stepSize]
+//! Proves the same inputs with kyc_host and with a running zk_server, then checks the two agree:
+//! same circuit, same commitment hash, same guest arguments, and both proofs verify under one set
+//! of public parameters.  Nova parameters are a pure function of the step size, so verifying both
+//! proofs under a freshly generated set is verifying each under the other binary's parameters.
+//! Exits non-zero on the first mismatch — run it in CI against a staging server to catch encoding
+//! drift between the CLI and the server.
+
+use std::{env, path::PathBuf, process::Command};
+
+use anyhow::{anyhow, bail, Context, Result};
+use kyc_core::engine::{self, KycProof};
+use serde::Deserialize;
+use zk_engine::utils::logging::init_logger;
+
+/// What each side produced; kyc_host's `--proof-out` file and the server's
+/// `?full_proof=true` response both deserialize into this.
+#[derive(Deserialize)]
+struct Produced {
+    circuit:       String,
+    #[serde(default)]
+    commitment_scheme: Option<String>,
+    public_inputs: Vec<String>,
+    proof:         String,
+    instance:      String,
+}
+
+impl Produced {
+    fn decode(&self) -> Result<KycProof> {
+        Ok(KycProof::from_bytes(&hex::decode(&self.proof)?, &hex::decode(&self.instance)?)?)
+    }
+}
+
+/// Pull `flag <value>` out of `cli`.
+fn take_opt(cli: &mut Vec<String>, flag: &str) -> Option<String> {
+    let i = cli.iter().position(|a| a == flag)?;
+    if i + 1 >= cli.len() {
+        eprintln!("{flag} needs a value"); std::process::exit(1);
+    }
+    let v = cli[i + 1].clone();
+    cli.drain(i..=i + 1);
+    Some(v)
+}
+
+/* ---- main ----------------------------------------------------------- */
+fn main() -> Result<()> {
+    init_logger();
+
+    /* parse CLI */
+    let mut cli: Vec<String> = env::args().skip(1).collect();
+    let server   = take_opt(&mut cli, "--server").unwrap_or_else(|| "http://127.0.0.1:8080".into());
+    let kyc_host = match take_opt(&mut cli, "--kyc-host") {
+        Some(p) => PathBuf::from(p),
+        None    => env::current_exe()?.with_file_name("kyc_host"),
+    };
+    let hash    = take_opt(&mut cli, "--hash");
+    let id_type = take_opt(&mut cli, "--id-type");
+    let legacy  = match cli.iter().position(|a| a == "--legacy-limbs") {
+        Some(i) => { cli.remove(i); true }
+        None    => false,
+    };
+    if cli.len() < 3 || cli.len() > 4 {
+        eprintln!("USAGE  kyc_equiv [--server <url>] [--kyc-host <path>] [--hash <scheme>] [--id-type <type>] [--legacy-limbs] <subject> <kycStatus> <sigValid> [stepSize]");
+        std::process::exit(1);
+    }
+    let step_sz: usize = cli.get(3).map(|s| s.parse().unwrap_or(8)).unwrap_or(8);
+
+    /* 1. CLI proof */
+    let out = env::temp_dir().join(format!("kyc_equiv-{}.json", std::process::id()));
+    let mut cmd = Command::new(&kyc_host);
+    if let Some(h) = &hash    { cmd.args(["--hash", h]); }
+    if let Some(t) = &id_type { cmd.args(["--id-type", t]); }
+    if legacy                 { cmd.arg("--legacy-limbs"); }
+    cmd.arg("--proof-out").arg(&out).args(&cli);
+    let status = cmd.status().with_context(|| format!("running {}", kyc_host.display()))?;
+    if !status.success() {
+        bail!("kyc_host exited with {status}");
+    }
+    let local: Produced = serde_json::from_slice(&std::fs::read(&out)?)?;
+    std::fs::remove_file(&out).ok();
+
+    /* 2. Server proof */
+    let mut body = serde_json::json!({
+        "schema_version": 2,
+        "wallet":         cli[0],
+        "kyc":            cli[1].parse::<i32>()?,
+        "sig_valid":      cli[2].parse::<i32>()?,
+        "step":           step_sz,
+        "legacy_limbs":   legacy,
+    });
+    if let Some(t) = &id_type {
+        body["identifier_type"] = t.clone().into();
+    }
+    let resp = reqwest::blocking::Client::new()
+        .post(format!("{}/prove?full_proof=true", server.trim_end_matches('/')))
+        .json(&body)
+        .send()
+        .with_context(|| format!("POST {server}/prove"))?;
+    if !resp.status().is_success() {
+        bail!("server returned {}: {}", resp.status(), resp.text().unwrap_or_default());
+    }
+    let remote: Produced = resp.json().context("server response lacks full proof fields")?;
+
+    /* 3. Same statement? */
+    let mut drift = Vec::new();
+    if local.circuit != remote.circuit {
+        drift.push(format!("circuit: cli {} vs server {}", local.circuit, remote.circuit));
+    }
+    let want = hash.as_deref().unwrap_or("keccak256");
+    if let Some(s) = &remote.commitment_scheme {
+        if s != want {
+            drift.push(format!("commitment hash: cli {want} vs server {s}"));
+        }
+    }
+    if local.public_inputs != remote.public_inputs {
+        drift.push(format!("public inputs:\n  cli    {:?}\n  server {:?}",
+                           local.public_inputs, remote.public_inputs));
+    }
+
+    /* 4. Both verify under one set of parameters */
+    let pp = engine::setup(step_sz);
+    for (who, p) in [("cli", &local), ("server", &remote)] {
+        let kp = p.decode().with_context(|| format!("decoding {who} proof"))?;
+        if let Err(e) = engine::verify_kyc(&pp, &kp) {
+            drift.push(format!("{who} proof does not verify: {e}"));
+        }
+    }
+
+    println!("\n──── Equivalence ───────────────────────────");
+    println!("server     : {}", server);
+    println!("circuit    : {}", local.circuit);
+    println!("step_size  : {}", step_sz);
+    println!("inputs     : {}", local.public_inputs.len());
+    println!("─────────────────────────────────────────────");
+    if !drift.is_empty() {
+        for d in &drift { eprintln!("❌ {d}"); }
+        return Err(anyhow!("{} mismatch(es) between kyc_host and zk_server", drift.len()));
+    }
+    println!("✅ kyc_host and zk_server proofs are equivalent");
+    Ok(())
+}
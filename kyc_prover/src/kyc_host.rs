@@ -1,33 +1,249 @@
-//! kyc_host <0xWallet> <kycStatus> <sigValid> [stepSize]
-//! Proves Circle-style KYC approval: 5 Keccak limbs + 2 flags → return 0.
+//! kyc_host <prove|verify|setup|bench|inspect> …   (`kyc_host help <command>` for its options)
+//! Proves Circle-style KYC approval: 8 commitment limbs + 2 flags → return 0.
+//!
+//! * `prove [--hash <scheme>] [--id-type <type>] [--legacy-limbs | --keccak-in-guest] [--wasm <file>] [--step <n>] [--out <file>] <subject> <kycStatus> <sigValid>`
+//!   `--legacy-limbs` keeps the old 5-limb (160-bit) `check_kyc` guest; `--keccak-in-guest` proves
+//!   `check_kyc_full_keccak`, which hashes the raw wallet address in the guest; `--out` (alias
+//!   `--proof-out`) writes the proof envelope (see [`Envelope`]), which kyc_verifier and kyc_equiv read;
+//!   `--envelope <file>` writes the binary, versioned [`kyc_core::envelope`] that zk_server also
+//!   serves and verifies.
+//! * `verify [--step <n>] [--pp <file>] <proof.json>` checks a proof file or binary envelope
+//!   (kyc_verifier also checks its inputs); `--pp` loads parameters saved by `setup --out`.
+//! * `setup --step <n> [--out <file>]` builds the Nova public parameters, optionally saving them.
+//! * `bench [--runs <n>] [--step <n>] [--hash <scheme>] [--wasm <file>]` times repeated proofs.
+//! * `inspect <proof.json>` decodes a proof file's public inputs without verifying.
+//! * `batch [--jobs <n>] [--out-dir <dir>] [--results <file>] [prove flags] <subjects>` (alias
+//!   `prove-batch --input <file>`) proves one subject per row concurrently, sharing one setup and
+//!   guest.  Rows are `<subject> [kycStatus sigValid]` lines, or CSV / JSONL by file extension.
+//!
+//! `--format json` (any subcommand) prints one JSON document on stdout instead of the table, with
+//! every metric, the commitment limbs and the proof path; logs and progress go to stderr.
+//!
+//! Without a subcommand the old form still works: `kyc_host [flags] <subject> <kycStatus>
+//! <sigValid> [stepSize]` is `prove`.
 
-use std::{env, path::PathBuf, time::Instant};
+use std::{
+    env, ffi::OsString, io::Read,
+    path::{Path, PathBuf},
+    sync::{atomic::{AtomicUsize, Ordering}, Mutex},
+    thread,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
 
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use kyc_core::{engine::{self, KycProof}, envelope, public_inputs::{self, Layout}, HashScheme, IdentifierType};
 use libc::{getrusage, rusage, RUSAGE_SELF};
-use regex::Regex;
-use tiny_keccak::{Hasher, Keccak};
-use zk_engine::{
-    utils::logging::init_logger,
-    wasm_ctx::{WASMArgsBuilder, WASMCtx},
-    wasm_snark::{StepSize, WasmSNARK},
-    nova::{
-        provider::{ipa_pc, Bn256EngineIPA},
-        spartan::{
-            batched::BatchedRelaxedR1CSSNARK as BatchedSNARK,
-            snark::RelaxedR1CSSNARK          as RelaxedSNARK,
-        },
-        traits::Dual,
-    },
-};
-use bincode;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing_subscriber::EnvFilter;
+use zk_engine::utils::logging::init_logger;
 use hex;
 
-/* ---- Nova type aliases --------------------------------------------- */
-type E  = Bn256EngineIPA;
-type EE = ipa_pc::EvaluationEngine<E>;
-type S1 = BatchedSNARK<E, EE>;
-type ED = Dual<E>;
-type S2 = RelaxedSNARK<ED, ipa_pc::EvaluationEngine<ED>>;
+/// Version of the [`Envelope`] layout.
+const ENVELOPE_VERSION: u32 = 1;
+
+/// Subject proven by `bench`.
+const BENCH_SUBJECT: &str = "0x742d35cc6634c0532925a3b844bc454e4438f44e";
+
+#[derive(Parser)]
+#[command(name = "kyc_host", about = "Prove, verify and inspect zkEngine KYC proofs")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// `json` prints one machine-readable document on stdout; logs go to stderr
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text)]
+    format:  Format,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prove a subject's KYC approval, then verify the proof
+    Prove(ProveArgs),
+    /// Verify a proof file written by `prove --out` (or a `?full_proof=true` response)
+    Verify {
+        proof: PathBuf,
+        /// Step size; defaults to the file's `step`
+        #[arg(long)]
+        step:  Option<usize>,
+        /// Load the public parameters from a `setup --out` file instead of building them
+        #[arg(long)]
+        pp:    Option<PathBuf>,
+    },
+    /// Build the Nova public parameters for a step size
+    Setup {
+        #[arg(long, default_value_t = 8)]
+        step: usize,
+        /// Write the parameters here (bincode)
+        #[arg(long)]
+        out:  Option<PathBuf>,
+    },
+    /// Time repeated proofs of a sample subject under one set of parameters
+    Bench {
+        #[arg(long, default_value_t = 5)]
+        runs: usize,
+        #[arg(long, default_value_t = 8)]
+        step: usize,
+        #[arg(long, default_value_t = HashScheme::default())]
+        hash: HashScheme,
+        #[arg(long, default_value = "examples/kyc_wasm.wasm")]
+        wasm: PathBuf,
+    },
+    /// Show a proof file's circuit, step, sizes and decoded public inputs
+    Inspect {
+        proof: PathBuf,
+    },
+    /// Prove a file of subjects concurrently under one set of parameters and one loaded guest
+    #[command(visible_alias = "prove-batch")]
+    Batch(BatchArgs),
+}
+
+#[derive(Args)]
+struct ProveArgs {
+    /// Subject identifier (a wallet address unless --id-type says otherwise)
+    subject:    String,
+    /// KYC status (1 = approved)
+    kyc:        i32,
+    /// Signature validity (1 = valid)
+    sig_valid:  i32,
+    /// Step size, as in the old positional form; --step wins
+    #[arg(hide = true)]
+    step_pos:   Option<usize>,
+    #[arg(long)]
+    step:       Option<usize>,
+    /// Commitment hash: keccak256, sha256, blake3 or poseidon
+    #[arg(long, default_value_t = HashScheme::default())]
+    hash:       HashScheme,
+    #[arg(long, default_value_t = IdentifierType::default())]
+    id_type:    IdentifierType,
+    /// Commit only 5 of the 8 digest limbs (old check_kyc guest)
+    #[arg(long)]
+    legacy_limbs: bool,
+    /// Pass the raw wallet address and compute its Keccak-256 commitment in the guest
+    /// (check_kyc_full_keccak; evm_address subjects, keccak256 only)
+    #[arg(long, conflicts_with = "legacy_limbs")]
+    keccak_in_guest: bool,
+    #[arg(long, default_value = "examples/kyc_wasm.wasm")]
+    wasm:       PathBuf,
+    /// Write the proof envelope (JSON) here
+    #[arg(long, visible_alias = "proof-out")]
+    out:        Option<PathBuf>,
+    /// Write the binary, versioned proof envelope here
+    #[arg(long)]
+    envelope:   Option<PathBuf>,
+    /// Guest version recorded in the envelope, as zk_server's rollout names it
+    #[arg(long, default_value = "v1")]
+    circuit_version: String,
+}
+
+#[derive(Args)]
+struct BatchArgs {
+    /// One `<subject> [kycStatus sigValid]` per line (flags default to 1 1); `-` reads stdin.
+    /// A `.csv` file has `wallet,kyc,sig_valid` rows (header optional); a `.jsonl` file has
+    /// `{"wallet": …, "kyc": …, "sig_valid": …}` objects
+    #[arg(required_unless_present = "input")]
+    subjects:   Option<PathBuf>,
+    /// Same as the positional file
+    #[arg(long, conflicts_with = "subjects")]
+    input:      Option<PathBuf>,
+    /// Write per-row status, timings and proof paths here (CSV for `.csv`, else JSONL)
+    #[arg(long)]
+    results:    Option<PathBuf>,
+    /// Proofs in flight at once; defaults to the number of CPUs
+    #[arg(long)]
+    jobs:       Option<usize>,
+    #[arg(long, default_value_t = 8)]
+    step:       usize,
+    #[arg(long, default_value_t = HashScheme::default())]
+    hash:       HashScheme,
+    #[arg(long, default_value_t = IdentifierType::default())]
+    id_type:    IdentifierType,
+    #[arg(long)]
+    legacy_limbs: bool,
+    /// As for prove: hash each raw wallet address in the guest (check_kyc_full_keccak)
+    #[arg(long, conflicts_with = "legacy_limbs")]
+    keccak_in_guest: bool,
+    #[arg(long, default_value = "examples/kyc_wasm.wasm")]
+    wasm:       PathBuf,
+    /// Write each proof envelope here as `<line>.json`
+    #[arg(long)]
+    out_dir:    Option<PathBuf>,
+    #[arg(long, default_value = "v1")]
+    circuit_version: String,
+}
+
+/// One line of a batch file.
+struct BatchItem {
+    line:      usize,
+    subject:   String,
+    kyc:       i32,
+    sig_valid: i32,
+}
+
+/// A batch item that proved and verified.
+struct Proved {
+    prove_s:   f64,
+    verify_s:  f64,
+    proof_len: usize,
+}
+
+/// Archival record of one proof, written by `prove --out` and `batch --out-dir`: the full
+/// hex bincode proof and instance, plus what is needed to verify it later and to tell which guest
+/// made it.  A superset of zk_server's `?full_proof=true` fields, so every reader of those reads it.
+#[derive(Serialize)]
+struct Envelope<'a> {
+    envelope:          u32,
+    circuit:           &'a str,
+    circuit_version:   &'a str,
+    step:              usize,
+    commitment_scheme: HashScheme,
+    /// SHA-256 of the guest module, hex.
+    wasm_sha256:       &'a str,
+    public_inputs:     &'a [String],
+    proof:             String,
+    instance:          String,
+    /// Unix seconds when proving began and when the proof verified.
+    started_at:        u64,
+    proved_at:         u64,
+    prove_sec:         f64,
+    verify_sec:        f64,
+}
+
+/// A proof as written by `prove --out` or returned by zk_server; envelope-only fields are optional.
+#[derive(Deserialize)]
+struct ProofFile {
+    #[serde(default)]
+    circuit:           Option<String>,
+    #[serde(default)]
+    circuit_version:   Option<String>,
+    #[serde(default)]
+    step:              Option<usize>,
+    #[serde(default)]
+    commitment_scheme: Option<String>,
+    #[serde(default)]
+    wasm_sha256:       Option<String>,
+    #[serde(default)]
+    public_inputs:     Option<Vec<String>>,
+    #[serde(default)]
+    proved_at:         Option<u64>,
+    proof:             String,
+    instance:          String,
+    /// Public-parameter digest, from a binary envelope.
+    #[serde(skip)]
+    pp_digest:         Option<[u8; 32]>,
+    /// SHA-256 of a binary envelope's canonical bytes, what signatures over it cover.
+    #[serde(skip)]
+    envelope_digest:   Option<[u8; 32]>,
+    /// Wallet whose ownership a binary envelope records (from its SIWE message).
+    #[serde(skip)]
+    owner:             Option<String>,
+}
 
 /* ---- helpers -------------------------------------------------------- */
 fn peak_rss_mb() -> f64 {
@@ -38,77 +254,223 @@ fn peak_rss_mb() -> f64 {
     #[cfg(not(any(target_os = "linux", target_os = "macos")))] { 0.0 }
 }
 
-fn keccak_u32s(s: &str) -> [u32; 8] {
-    let mut h = Keccak::v256();
-    h.update(s.as_bytes());
-    let mut out = [0u8; 32];
-    h.finalize(&mut out);
-    let mut limbs = [0u32; 8];
-    for (i, ch) in out.chunks(4).enumerate() {
-        limbs[i] = u32::from_be_bytes(ch.try_into().unwrap());
+/// The command line, with `prove` inserted when the old subcommand-less form is used.
+fn args() -> Vec<OsString> {
+    let mut args: Vec<OsString> = env::args_os().collect();
+    let first = args.get(1).and_then(|a| a.to_str()).unwrap_or("");
+    let known = ["prove", "verify", "setup", "bench", "inspect", "batch", "prove-batch", "help", "-h", "--help"];
+    if !first.is_empty() && !known.contains(&first) {
+        args.insert(1, "prove".into());
+    }
+    args
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+fn write_envelope(path: &Path, env: &Envelope) -> Result<()> {
+    std::fs::write(path, serde_json::to_vec_pretty(env)?).with_context(|| format!("writing {}", path.display()))
+}
+
+/// `min … avg … max` of a set of timings.
+fn spread(v: &[f64]) -> String {
+    let min = v.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = v.iter().copied().fold(0.0, f64::max);
+    format!("min {:.3}  avg {:.3}  max {:.3}", min, v.iter().sum::<f64>() / v.len() as f64, max)
+}
+
+/// Print a `--format json` document.
+fn emit(report: &Value) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(report)?);
+    Ok(())
+}
+
+/// Peak RSS for a report; `null` where the platform doesn't say.
+fn rss_value() -> Value {
+    let rss_mb = peak_rss_mb();
+    if rss_mb > 0.0 { json!(rss_mb) } else { Value::Null }
+}
+
+/// A JSON proof file, or a binary [`kyc_core::envelope`] (told apart by its magic bytes).
+fn read_proof_file(path: &Path) -> Result<ProofFile> {
+    let raw = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    if envelope::is_envelope(&raw) {
+        let env = envelope::Envelope::decode(&raw).with_context(|| format!("reading {}", path.display()))?;
+        let envelope_digest = Some(env.canonical_digest()?);
+        let owner = env.ownership.as_ref().and_then(|o| o.address()).map(String::from);
+        return Ok(ProofFile {
+            circuit:           Some(env.circuit),
+            circuit_version:   Some(env.circuit_version),
+            step:              Some(env.step as usize),
+            commitment_scheme: env.commitment_scheme.map(|h| h.to_string()),
+            wasm_sha256:       Some(hex::encode(env.wasm_digest)),
+            public_inputs:     Some(env.public_inputs),
+            proved_at:         None,
+            proof:             hex::encode(env.proof),
+            instance:          hex::encode(env.instance),
+            pp_digest:         Some(env.pp_digest),
+            envelope_digest,
+            owner,
+        });
     }
-    limbs
+    serde_json::from_slice(&raw).with_context(|| format!("{} is not a proof file", path.display()))
+}
+
+/// Argument layout of a guest export, by name.
+fn layout(circuit: &str) -> Result<Layout> {
+    let (circuit, nonce) = match circuit.strip_suffix("_nonce") {
+        Some(b) => (b, true),
+        None    => (circuit, false),
+    };
+    let (base, travel_rule) = match circuit.strip_suffix("_travel_rule") {
+        Some(b) => (b, true),
+        None    => (circuit, false),
+    };
+    let commitment_limbs = match base {
+        // Public inputs only; the address words are witness.
+        "check_kyc_full" | "check_kyc_full_keccak" => 8,
+        "check_kyc"      => 5,
+        other            => bail!("unknown circuit {other}"),
+    };
+    Ok(Layout { commitment_limbs, travel_rule, nonce })
 }
 
 /* ---- main ----------------------------------------------------------- */
-fn main() -> anyhow::Result<()> {
-    init_logger();
+fn main() -> Result<()> {
+    let cli = Cli::parse_from(args());
+    let f = cli.format;
+    match f {
+        Format::Text => init_logger(),
+        // stdout carries only the JSON document
+        Format::Json => tracing_subscriber::fmt().with_writer(std::io::stderr).with_env_filter(EnvFilter::from_default_env()).init(),
+    }
+    match cli.command {
+        Command::Prove(a)               => prove(a, f),
+        Command::Verify { proof, step, pp } => verify(&proof, step, pp.as_deref(), f),
+        Command::Setup { step, out }    => setup(step, out.as_deref(), f),
+        Command::Bench { runs, step, hash, wasm } => bench(runs, step, hash, &wasm, f),
+        Command::Inspect { proof }      => inspect(&proof, f),
+        Command::Batch(a)               => batch(a, f),
+    }
+}
 
-    /* parse CLI */
-    let cli: Vec<String> = env::args().skip(1).collect();
-    if cli.len() < 3 || cli.len() > 4 {
-        eprintln!("USAGE  kyc_host <0xWallet> <kycStatus> <sigValid> [stepSize]");
-        std::process::exit(1);
+fn check_keccak_in_guest(keccak_in_guest: bool, scheme: HashScheme, id_type: IdentifierType) -> Result<()> {
+    if keccak_in_guest && (scheme != HashScheme::Keccak256 || id_type != IdentifierType::EvmAddress) {
+        bail!("--keccak-in-guest needs --hash keccak256 and an evm_address subject");
     }
-    let wallet = &cli[0];
-    let kyc: i32 = cli[1].parse()?;
-    let sig: i32 = cli[2].parse()?;
-    let step_sz: usize = cli.get(3).map(|s| s.parse().unwrap_or(8)).unwrap_or(8);
+    Ok(())
+}
+
+/// The guest call for one subject: `check_kyc_full_keccak` with `--keccak-in-guest`, else the
+/// host-side commitment of `scheme`.
+fn guest_call(scheme: HashScheme, id_type: IdentifierType, wallet: &str, kyc: i32, sig_valid: i32,
+              legacy_limbs: bool, keccak_in_guest: bool) -> Result<engine::KycCall> {
+    Ok(if keccak_in_guest {
+        engine::kyc_call_keccak(wallet, kyc, sig_valid)?
+    } else {
+        engine::kyc_call(scheme, id_type, wallet, kyc, sig_valid, legacy_limbs)?
+    })
+}
+
+fn prove(a: ProveArgs, f: Format) -> Result<()> {
+    let (scheme, id_type) = (a.hash, a.id_type);
+    let wallet = &id_type.canonicalize(&a.subject);
+    let step_sz = a.step.or(a.step_pos).unwrap_or(8);
 
     /* validate inputs */
-    let re = Regex::new(r"^0x[0-9a-fA-F]{40}$").unwrap();
-    if !re.is_match(wallet) {
-        eprintln!("Bad wallet string (0x + 40 hex chars)"); std::process::exit(1);
+    if let Err(e) = id_type.check_checksum(&a.subject).and_then(|()| id_type.validate(wallet)) {
+        eprintln!("Bad subject string ({e})"); std::process::exit(1);
     }
-    if kyc != 1 || sig != 1 {
+    if a.kyc != 1 || a.sig_valid != 1 {
         eprintln!("Proof of KYC approval failed."); std::process::exit(1);
     }
 
-    /* compute 160-bit hash commitment */
-    let h = keccak_u32s(wallet);          // 8 limbs, we use first 5
-
-    /* build Wasm context */
-    let mut args: Vec<String> = h[..5]
-        .iter()
-        .map(|&u| (u as i32).to_string())   // cast u32 → i32 (two’s-comp)
-        .collect();
-    args.extend([kyc.to_string(), sig.to_string()]);
-
-    let wasm_args = WASMArgsBuilder::default()
-        .file_path(PathBuf::from("examples/kyc_wasm.wasm"))?   // regular guest
-        .invoke("check_kyc")
-        .func_args(args)
-        .build();
-    let wasm_ctx = WASMCtx::new(wasm_args);
+    /* 256-bit hash commitment (160-bit with --legacy-limbs) → guest call */
+    check_keccak_in_guest(a.keccak_in_guest, scheme, id_type)?;
+    let call = guest_call(scheme, id_type, wallet, a.kyc, a.sig_valid, a.legacy_limbs, a.keccak_in_guest)?;
+    // The address words of check_kyc_full_keccak are witness: proven, never recorded.
+    let (invoke, args) = (call.invoke, call.public_inputs().to_vec());
 
     /* Nova setup → prove → verify */
-    let step = StepSize::new(step_sz);
+    let program = std::fs::read(&a.wasm).with_context(|| format!("reading {}", a.wasm.display()))?;
+    let wasm_digest = envelope::digest(&program);
+    let wasm_sha256 = hex::encode(wasm_digest);
 
     let t_setup = Instant::now();
-    let pp = WasmSNARK::<E, S1, S2>::setup(step);
+    let pp = engine::setup(step_sz);
     let setup_s = t_setup.elapsed().as_secs_f64();
 
+    let started_at = unix_now();
     let t_prove = Instant::now();
-    let (snark, inst) = WasmSNARK::<E, S1, S2>::prove(&pp, &wasm_ctx, step)?;
+    let kp = engine::prove_kyc_bytes(&pp, program, invoke, call.args, step_sz)?;
     let prove_s = t_prove.elapsed().as_secs_f64();
 
     let t_verify = Instant::now();
-    snark.verify(&pp, &inst)?;
+    engine::verify_kyc(&pp, &kp)?;
     let verify_s = t_verify.elapsed().as_secs_f64();
 
+    let (proof, instance) = kp.to_bytes()?;
+    if let Some(path) = &a.out {
+        write_envelope(path, &Envelope {
+            envelope:          ENVELOPE_VERSION,
+            circuit:           invoke,
+            circuit_version:   &a.circuit_version,
+            step:              step_sz,
+            commitment_scheme: scheme,
+            wasm_sha256:       &wasm_sha256,
+            public_inputs:     &args,
+            proof:             hex::encode(&proof),
+            instance:          hex::encode(&instance),
+            started_at,
+            proved_at:         unix_now(),
+            prove_sec:         prove_s,
+            verify_sec:        verify_s,
+        })?;
+    }
+    if let Some(path) = &a.envelope {
+        let env = envelope::Envelope {
+            circuit:         invoke.to_string(),
+            circuit_version: a.circuit_version.clone(),
+            wasm_digest,
+            pp_digest:       engine::pp_digest(&pp)?,
+            step:            step_sz as u32,
+            public_inputs:   args.clone(),
+            proof:           proof.clone(),
+            instance:        instance.clone(),
+            ownership:       None,
+            commitment_scheme: Some(scheme),
+        };
+        std::fs::write(path, env.canonical_bytes()?).with_context(|| format!("writing {}", path.display()))?;
+    }
+
+    if f == Format::Json {
+        let inputs = public_inputs::decode(layout(invoke)?, &args)?;
+        return emit(&json!({
+            "verified":          true,
+            "subject":           wallet,
+            "identifier_type":   id_type,
+            "circuit":           invoke,
+            "circuit_version":   a.circuit_version,
+            "step":              step_sz,
+            "commitment_scheme": scheme,
+            "commitment":        inputs.commitment,
+            "commitment_limbs":  inputs.commitment_limbs,
+            "public_inputs":     args,
+            "wasm_sha256":       wasm_sha256,
+            "setup_sec":         setup_s,
+            "prove_sec":         prove_s,
+            "verify_sec":        verify_s,
+            "peak_rss_mb":       rss_value(),
+            "proof_len":         proof.len(),
+            "instance_len":      instance.len(),
+            "proof_path":        a.out,
+            "envelope_path":     a.envelope,
+        }));
+    }
+
     /* metrics */
     let rss_mb  = peak_rss_mb();
-    let proof   = bincode::serialize(&snark)?;
     let preview = format!("{} … {}", hex::encode(&proof[..16]),
                                       hex::encode(&proof[proof.len() - 16..]));
 
@@ -117,11 +479,452 @@ fn main() -> anyhow::Result<()> {
     println!("prove_sec  : {:.3}", prove_s);
     println!("verify_sec : {:.3}", verify_s);
     println!("step_size  : {}",   step_sz);
+    println!("hash       : {}",   scheme);
+    println!("circuit    : {}",   invoke);
     if rss_mb > 0.0 { println!("peak_rss   : {:.1} MB", rss_mb); }
     println!("proof_len  : {} bytes", proof.len());
     println!("proof_hex  : {}", preview);
     println!("─────────────────────────────────────────────");
-println!("wallet     : {}", wallet);    
-println!("✅ KYC proof verified");
+    println!("subject    : {} ({})", wallet, id_type);
+    println!("✅ KYC proof verified");
+
+    if let Some(path) = a.out {
+        println!("proof written to {}", path.display());
+    }
+    if let Some(path) = a.envelope {
+        println!("envelope written to {}", path.display());
+    }
+    Ok(())
+}
+
+fn verify(path: &Path, step: Option<usize>, pp_path: Option<&Path>, f: Format) -> Result<()> {
+    let file = read_proof_file(path)?;
+    let step_sz = step.or(file.step).ok_or_else(|| anyhow!("the proof file has no step; pass --step"))?;
+    let kp = KycProof::from_bytes(
+        &hex::decode(&file.proof).context("proof is not hex")?,
+        &hex::decode(&file.instance).context("instance is not hex")?,
+    )?;
+
+    // Saved parameters don't record their step; ones for another step just fail to verify.
+    let t_setup = Instant::now();
+    let pp: engine::Params = match pp_path {
+        Some(p) => {
+            let raw = std::fs::read(p).with_context(|| format!("reading {}", p.display()))?;
+            bincode::deserialize(&raw).with_context(|| format!("{} is not saved public parameters", p.display()))?
+        }
+        None => engine::setup(step_sz),
+    };
+    let setup_s = t_setup.elapsed().as_secs_f64();
+    if let Some(made_under) = &file.pp_digest {
+        let ours = engine::pp_digest(&pp)?;
+        if *made_under != ours {
+            bail!("{} was made under public parameters {}, not these ({}) for step {step_sz}",
+                  path.display(), hex::encode(made_under), hex::encode(ours));
+        }
+    }
+
+    let t_verify = Instant::now();
+    let verified = engine::verify_kyc(&pp, &kp);
+    let verify_s = t_verify.elapsed().as_secs_f64();
+
+    if f == Format::Json {
+        emit(&json!({
+            "verified":   verified.is_ok(),
+            "proof_path": path,
+            "circuit":    file.circuit,
+            "step":       step_sz,
+            "setup_sec":  setup_s,
+            "verify_sec": verify_s,
+            "pp_path":    pp_path,
+        }))?;
+        return Ok(verified?);
+    }
+    println!("\n──── Verification ───────────────────────────");
+    println!("setup_sec  : {:.3}", setup_s);
+    println!("verify_sec : {:.3}", verify_s);
+    println!("step_size  : {}", step_sz);
+    println!("─────────────────────────────────────────────");
+    match verified {
+        Ok(()) => println!("✅ KYC proof verified"),
+        Err(e) => {
+            println!("❌ KYC proof rejected");
+            return Err(e.into());
+        }
+    }
+    Ok(())
+}
+
+fn setup(step_sz: usize, out: Option<&Path>, f: Format) -> Result<()> {
+    let t_setup = Instant::now();
+    let pp = engine::setup(step_sz);
+    let setup_s = t_setup.elapsed().as_secs_f64();
+
+    // The digest is the SHA-256 of exactly the bytes `--out` saves.
+    let raw = bincode::serialize(&pp)?;
+    let pp_digest = hex::encode(envelope::digest(&raw));
+    let params_len = match out {
+        Some(path) => {
+            std::fs::write(path, &raw).with_context(|| format!("writing {}", path.display()))?;
+            Some(raw.len())
+        }
+        None => None,
+    };
+    if f == Format::Json {
+        return emit(&json!({
+            "step":        step_sz,
+            "setup_sec":   setup_s,
+            "peak_rss_mb": rss_value(),
+            "pp_digest":   pp_digest,
+            "params_len":  params_len,
+            "params_path": out,
+        }));
+    }
+
+    println!("\n──── Setup ──────────────────────────────────");
+    println!("setup_sec  : {:.3}", setup_s);
+    println!("step_size  : {}", step_sz);
+    println!("pp_digest  : {}", pp_digest);
+    let rss_mb = peak_rss_mb();
+    if rss_mb > 0.0 { println!("peak_rss   : {:.1} MB", rss_mb); }
+    if let (Some(path), Some(len)) = (out, params_len) {
+        println!("params     : {} bytes → {}", len, path.display());
+    }
+    println!("─────────────────────────────────────────────");
+    Ok(())
+}
+
+fn bench(runs: usize, step_sz: usize, scheme: HashScheme, wasm: &Path, f: Format) -> Result<()> {
+    if runs == 0 {
+        bail!("--runs must be at least 1");
+    }
+    let call = engine::kyc_call(scheme, IdentifierType::EvmAddress, BENCH_SUBJECT, 1, 1, false)?;
+
+    let t_setup = Instant::now();
+    let pp = engine::setup(step_sz);
+    let setup_s = t_setup.elapsed().as_secs_f64();
+
+    let (mut prove_s, mut verify_s) = (Vec::with_capacity(runs), Vec::with_capacity(runs));
+    for run in 1..=runs {
+        let t_prove = Instant::now();
+        let kp = engine::prove_kyc(&pp, wasm, call.invoke, call.args.clone(), step_sz)?;
+        prove_s.push(t_prove.elapsed().as_secs_f64());
+        let t_verify = Instant::now();
+        engine::verify_kyc(&pp, &kp)?;
+        verify_s.push(t_verify.elapsed().as_secs_f64());
+        let line = format!("run {run}/{runs}: prove {:.3}s, verify {:.3}s", prove_s[run - 1], verify_s[run - 1]);
+        if f == Format::Json { eprintln!("{line}") } else { println!("{line}") }
+    }
+
+    if f == Format::Json {
+        return emit(&json!({
+            "runs":              runs,
+            "step":              step_sz,
+            "commitment_scheme": scheme,
+            "setup_sec":         setup_s,
+            "prove_sec":         prove_s,
+            "verify_sec":        verify_s,
+            "peak_rss_mb":       rss_value(),
+        }));
+    }
+
+    println!("\n──── Benchmark ──────────────────────────────");
+    println!("setup_sec  : {:.3}", setup_s);
+    println!("prove_sec  : {}", spread(&prove_s));
+    println!("verify_sec : {}", spread(&verify_s));
+    println!("runs       : {}", runs);
+    println!("step_size  : {}", step_sz);
+    println!("hash       : {}", scheme);
+    let rss_mb = peak_rss_mb();
+    if rss_mb > 0.0 { println!("peak_rss   : {:.1} MB", rss_mb); }
+    println!("─────────────────────────────────────────────");
+    Ok(())
+}
+
+fn inspect(path: &Path, f: Format) -> Result<()> {
+    let file = read_proof_file(path)?;
+    let proof_len = hex::decode(&file.proof).context("proof is not hex")?.len();
+    let instance_len = hex::decode(&file.instance).context("instance is not hex")?.len();
+
+    if f == Format::Json {
+        let decoded = match (&file.public_inputs, &file.circuit) {
+            (Some(args), Some(c)) => Some(public_inputs::decode(layout(c)?, args)?),
+            _                     => None,
+        };
+        return emit(&json!({
+            "proof_path":        path,
+            "circuit":           file.circuit,
+            "circuit_version":   file.circuit_version,
+            "step":              file.step,
+            "commitment_scheme": file.commitment_scheme,
+            "wasm_sha256":       file.wasm_sha256,
+            "pp_digest":         file.pp_digest.map(hex::encode),
+            "envelope_digest":   file.envelope_digest.map(hex::encode),
+            "owner":             file.owner,
+            "proved_at":         file.proved_at,
+            "proof_len":         proof_len,
+            "instance_len":      instance_len,
+            "public_inputs":     file.public_inputs,
+            "decoded":           decoded,
+        }));
+    }
+
+    println!("\n──── Proof file ─────────────────────────────");
+    println!("circuit    : {}", file.circuit.as_deref().unwrap_or("(not given)"));
+    if let Some(v) = &file.circuit_version { println!("version    : {}", v); }
+    println!("step_size  : {}", file.step.map_or("(not given)".into(), |s| s.to_string()));
+    if let Some(h) = &file.commitment_scheme { println!("hash       : {}", h); }
+    if let Some(w) = &file.wasm_sha256 { println!("wasm_sha256: {}", w); }
+    if let Some(d) = &file.pp_digest { println!("pp_digest  : {}", hex::encode(d)); }
+    if let Some(d) = &file.envelope_digest { println!("env_digest : {}", hex::encode(d)); }
+    if let Some(o) = &file.owner { println!("owner      : {} (SIWE)", o); }
+    if let Some(t) = file.proved_at { println!("proved_at  : {} (unix)", t); }
+    println!("proof_len  : {} bytes", proof_len);
+    println!("instance   : {} bytes", instance_len);
+    match (&file.public_inputs, &file.circuit) {
+        (Some(args), Some(c)) => {
+            let d = public_inputs::decode(layout(c)?, args)?;
+            println!("commitment : {}", d.commitment);
+            println!("flags      : kyc={} sig_valid={}", d.kyc, d.sig_valid);
+            if let Some(t) = &d.travel_rule_commitment {
+                println!("travel_rule: {}", t);
+            }
+            if let Some(n) = &d.nonce {
+                println!("nonce      : {}", n);
+            }
+        }
+        _ => println!("inputs     : not decoded (needs public inputs and a circuit)"),
+    }
+    println!("─────────────────────────────────────────────");
+    Ok(())
+}
+
+/// A `.jsonl` row; `subject` and `sig` are accepted for `wallet` and `sig_valid`.
+#[derive(Deserialize)]
+struct JsonRow {
+    #[serde(alias = "subject")]
+    wallet:    String,
+    #[serde(default = "approved")]
+    kyc:       i32,
+    #[serde(default = "approved", alias = "sig")]
+    sig_valid: i32,
+}
+
+fn approved() -> i32 {
+    1
+}
+
+fn read_batch(path: &Path) -> Result<Vec<BatchItem>> {
+    let mut text = String::new();
+    if path == Path::new("-") {
+        std::io::stdin().read_to_string(&mut text)?;
+    } else {
+        text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    }
+    let ext = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+    let mut items = Vec::new();
+    for (i, raw) in text.lines().enumerate() {
+        let n = i + 1;
+        if ext.as_deref() == Some("jsonl") {
+            if raw.trim().is_empty() {
+                continue;
+            }
+            let row: JsonRow = serde_json::from_str(raw).with_context(|| format!("line {n}: not a wallet row"))?;
+            items.push(BatchItem { line: n, subject: row.wallet, kyc: row.kyc, sig_valid: row.sig_valid });
+            continue;
+        }
+        let line = raw.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = match ext.as_deref() {
+            Some("csv") => line.split(',').map(|f| f.trim().trim_matches('"')).collect(),
+            _           => line.split_whitespace().collect(),
+        };
+        // an optional CSV header row
+        if ext.as_deref() == Some("csv") && items.is_empty() && ["wallet", "subject"].contains(&fields[0].to_ascii_lowercase().as_str()) {
+            continue;
+        }
+        let flag = |f: &str| f.parse::<i32>().with_context(|| format!("line {n}: {f:?} is not a flag"));
+        let (kyc, sig_valid) = match fields[..] {
+            [_]         => (1, 1),
+            [_, k, s]   => (flag(k)?, flag(s)?),
+            _           => bail!("line {n}: expected `<subject> [kycStatus sigValid]`"),
+        };
+        items.push(BatchItem { line: n, subject: fields[0].to_string(), kyc, sig_valid });
+    }
+    if items.is_empty() {
+        bail!("{} lists no subjects", path.display());
+    }
+    Ok(items)
+}
+
+/// One batch row's outcome, for `--format json` and `--results`.
+fn item_report(a: &BatchArgs, item: &BatchItem, r: &Result<Proved>) -> Value {
+    match r {
+        Ok(p)  => json!({
+            "line": item.line, "subject": item.subject, "status": "proved",
+            "prove_sec": p.prove_s, "verify_sec": p.verify_s, "proof_len": p.proof_len,
+            "proof_path": a.out_dir.as_ref().map(|d| d.join(format!("{}.json", item.line))),
+        }),
+        Err(e) => json!({ "line": item.line, "subject": item.subject, "status": "failed", "error": format!("{e:#}") }),
+    }
+}
+
+/// Write the per-row reports as CSV (for a `.csv` path) or JSONL.
+fn write_results(path: &Path, rows: &[Value]) -> Result<()> {
+    let mut out = String::new();
+    if path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("csv")) {
+        let cols = ["line", "subject", "status", "prove_sec", "verify_sec", "proof_len", "proof_path", "error"];
+        out.push_str(&cols.join(","));
+        out.push('\n');
+        for row in rows {
+            let fields: Vec<String> = cols.iter().map(|c| match &row[*c] {
+                Value::Null      => String::new(),
+                Value::String(s) if s.contains([',', '"', '\n']) => format!("\"{}\"", s.replace('"', "\"\"")),
+                Value::String(s) => s.clone(),
+                v                => v.to_string(),
+            }).collect();
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        }
+    } else {
+        for row in rows {
+            out.push_str(&row.to_string());
+            out.push('\n');
+        }
+    }
+    std::fs::write(path, out).with_context(|| format!("writing {}", path.display()))
+}
+
+fn prove_item(a: &BatchArgs, pp: &engine::Params, program: &[u8], wasm_sha256: &str, item: &BatchItem) -> Result<Proved> {
+    let wallet = a.id_type.canonicalize(&item.subject);
+    a.id_type.check_checksum(&item.subject).and_then(|()| a.id_type.validate(&wallet))
+        .map_err(|e| anyhow!("bad subject string ({e})"))?;
+    if item.kyc != 1 || item.sig_valid != 1 {
+        bail!("not approved (kyc={}, sig_valid={})", item.kyc, item.sig_valid);
+    }
+    let call = guest_call(a.hash, a.id_type, &wallet, item.kyc, item.sig_valid, a.legacy_limbs, a.keccak_in_guest)?;
+
+    let started_at = unix_now();
+    let t_prove = Instant::now();
+    let kp = engine::prove_kyc_bytes(pp, program.to_vec(), call.invoke, call.args.clone(), a.step)?;
+    let prove_s = t_prove.elapsed().as_secs_f64();
+
+    let t_verify = Instant::now();
+    engine::verify_kyc(pp, &kp)?;
+    let verify_s = t_verify.elapsed().as_secs_f64();
+
+    let (proof, instance) = kp.to_bytes()?;
+    if let Some(dir) = &a.out_dir {
+        write_envelope(&dir.join(format!("{}.json", item.line)), &Envelope {
+            envelope:          ENVELOPE_VERSION,
+            circuit:           call.invoke,
+            circuit_version:   &a.circuit_version,
+            step:              a.step,
+            commitment_scheme: a.hash,
+            wasm_sha256,
+            public_inputs:     call.public_inputs(),
+            proof:             hex::encode(&proof),
+            instance:          hex::encode(&instance),
+            started_at,
+            proved_at:         unix_now(),
+            prove_sec,
+            verify_sec,
+        })?;
+    }
+    Ok(Proved { prove_s, verify_s, proof_len: proof.len() })
+}
+
+fn batch(a: BatchArgs, f: Format) -> Result<()> {
+    let input = a.input.as_ref().or(a.subjects.as_ref()).ok_or_else(|| anyhow!("no input file"))?;
+    let items = read_batch(input)?;
+    let program = std::fs::read(&a.wasm).with_context(|| format!("reading {}", a.wasm.display()))?;
+    check_keccak_in_guest(a.keccak_in_guest, a.hash, a.id_type)?;
+    let wasm_sha256 = hex::encode(envelope::digest(&program));
+    if let Some(dir) = &a.out_dir {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    let jobs = a.jobs
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()))
+        .clamp(1, items.len());
+
+    let t_wall = Instant::now();
+    let t_setup = Instant::now();
+    let pp = engine::setup(a.step);
+    let setup_s = t_setup.elapsed().as_secs_f64();
+
+    /* workers take the next unproven line until none are left */
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<Proved>>>> = Mutex::new((0..items.len()).map(|_| None).collect());
+    thread::scope(|s| {
+        for _ in 0..jobs {
+            s.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(i) else { break };
+                let res = prove_item(&a, &pp, &program, &wasm_sha256, item);
+                let line = match &res {
+                    Ok(p)  => format!("line {:<5}: {}  prove {:.3}s  verify {:.3}s  {} bytes",
+                                      item.line, item.subject, p.prove_s, p.verify_s, p.proof_len),
+                    Err(e) => format!("line {:<5}: {}  failed: {e:#}", item.line, item.subject),
+                };
+                if f == Format::Json { eprintln!("{line}") } else { println!("{line}") }
+                results.lock().unwrap()[i] = Some(res);
+            });
+        }
+    });
+    let wall_s = t_wall.elapsed().as_secs_f64();
+
+    let results: Vec<Result<Proved>> = results.into_inner().unwrap().into_iter().flatten().collect();
+    let per_item: Vec<Value> = items.iter().zip(&results).map(|(item, r)| item_report(&a, item, r)).collect();
+    if let Some(path) = &a.results {
+        write_results(path, &per_item)?;
+    }
+    if f == Format::Json {
+        let failed = results.iter().filter(|r| r.is_err()).count();
+        emit(&json!({
+            "items":             items.len(),
+            "proved":            items.len() - failed,
+            "failed":            failed,
+            "jobs":              jobs,
+            "step":              a.step,
+            "commitment_scheme": a.hash,
+            "setup_sec":         setup_s,
+            "wall_sec":          wall_s,
+            "peak_rss_mb":       rss_value(),
+            "results":           per_item,
+        }))?;
+        if failed > 0 {
+            bail!("{failed} of {} subjects failed", items.len());
+        }
+        return Ok(());
+    }
+    let proved: Vec<&Proved> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+    let failed = items.len() - proved.len();
+    let prove_s: Vec<f64> = proved.iter().map(|p| p.prove_s).collect();
+    let verify_s: Vec<f64> = proved.iter().map(|p| p.verify_s).collect();
+
+    println!("\n──── Batch ──────────────────────────────────");
+    println!("items      : {}", items.len());
+    println!("proved     : {}", proved.len());
+    println!("failed     : {}", failed);
+    println!("jobs       : {}", jobs);
+    println!("step_size  : {}", a.step);
+    println!("hash       : {}", a.hash);
+    println!("setup_sec  : {:.3}", setup_s);
+    println!("wall_sec   : {:.3}", wall_s);
+    if !proved.is_empty() {
+        println!("prove_sec  : {}", spread(&prove_s));
+        println!("verify_sec : {}", spread(&verify_s));
+        println!("proof_bytes: {}", proved.iter().map(|p| p.proof_len).sum::<usize>());
+    }
+    let rss_mb = peak_rss_mb();
+    if rss_mb > 0.0 { println!("peak_rss   : {:.1} MB", rss_mb); }
+    if let Some(path) = &a.results {
+        println!("results    : {}", path.display());
+    }
+    println!("─────────────────────────────────────────────");
+    if failed > 0 {
+        bail!("{failed} of {} subjects failed", items.len());
+    }
     Ok(())
 }
@@ -0,0 +1,148 @@
+//! kyc_verifier [--public-inputs <file>] [--circuit <name>] <proof.json> [stepSize]
+//! Verifies a KYC proof without proving anything: no WASM guest is loaded and no KYC data is
+//! needed, only Nova public parameters for the step size, so auditors and counterparties can run
+//! it on machines that never see subjects.
+//!
+//! `<proof.json>` is a kyc_host `--proof-out` file or a zk_server `?full_proof=true` response
+//! (hex `proof` and `instance`, plus `circuit`, `step` and `public_inputs` when present).
+//! `stepSize` overrides the file's `step`.  `--public-inputs` names a JSON array of the guest
+//! arguments the verifier expects; they must match the file's, and are decoded and checked for
+//! `kyc = 1, sig_valid = 1`.  Exits non-zero unless the proof verifies and every check passes.
+
+use std::{env, path::PathBuf, time::Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use kyc_core::{engine::{self, KycProof}, public_inputs::{self, Layout}};
+use serde::Deserialize;
+use zk_engine::utils::logging::init_logger;
+
+/// A proof as written by kyc_host or returned by zk_server.
+#[derive(Deserialize)]
+struct ProofFile {
+    #[serde(default)]
+    circuit:       Option<String>,
+    #[serde(default)]
+    step:          Option<usize>,
+    #[serde(default)]
+    public_inputs: Option<Vec<String>>,
+    proof:         String,
+    instance:      String,
+}
+
+/// Argument layout of a guest export, by name.
+fn layout(circuit: &str) -> Result<Layout> {
+    let (circuit, nonce) = match circuit.strip_suffix("_nonce") {
+        Some(b) => (b, true),
+        None    => (circuit, false),
+    };
+    let (base, travel_rule) = match circuit.strip_suffix("_travel_rule") {
+        Some(b) => (b, true),
+        None    => (circuit, false),
+    };
+    let commitment_limbs = match base {
+        "check_kyc_full" | "check_kyc_full_keccak" => 8,
+        "check_kyc"      => 5,
+        other            => bail!("unknown circuit {other}"),
+    };
+    Ok(Layout { commitment_limbs, travel_rule, nonce })
+}
+
+/// Pull `flag <value>` out of `cli`.
+fn take_opt(cli: &mut Vec<String>, flag: &str) -> Option<String> {
+    let i = cli.iter().position(|a| a == flag)?;
+    if i + 1 >= cli.len() {
+        eprintln!("{flag} needs a value"); std::process::exit(1);
+    }
+    let v = cli[i + 1].clone();
+    cli.drain(i..=i + 1);
+    Some(v)
+}
+
+/* ---- main ----------------------------------------------------------- */
+fn main() -> Result<()> {
+    init_logger();
+
+    /* parse CLI */
+    let mut cli: Vec<String> = env::args().skip(1).collect();
+    let expected = take_opt(&mut cli, "--public-inputs").map(PathBuf::from);
+    let circuit  = take_opt(&mut cli, "--circuit");
+    if cli.is_empty() || cli.len() > 2 {
+        eprintln!("USAGE  kyc_verifier [--public-inputs <file>] [--circuit <name>] <proof.json> [stepSize]");
+        std::process::exit(1);
+    }
+    let path = PathBuf::from(&cli[0]);
+    let file: ProofFile = serde_json::from_slice(
+        &std::fs::read(&path).with_context(|| format!("reading {}", path.display()))?,
+    ).with_context(|| format!("{} is not a proof file", path.display()))?;
+    let step_sz = match cli.get(1) {
+        Some(s) => s.parse().context("stepSize must be a number")?,
+        None    => file.step.ok_or_else(|| anyhow!("the proof file has no step; pass stepSize"))?,
+    };
+    let circuit = circuit.or(file.circuit.clone());
+
+    /* public inputs: the caller's expectation must match what the file claims */
+    let mut failures = Vec::new();
+    let inputs = match (&expected, &file.public_inputs) {
+        (Some(p), claimed) => {
+            let want: Vec<String> = serde_json::from_slice(&std::fs::read(p)
+                .with_context(|| format!("reading {}", p.display()))?)
+                .context("--public-inputs must be a JSON array of strings")?;
+            if claimed.as_ref().map_or(false, |c| *c != want) {
+                failures.push(format!("public inputs differ from the proof file's:\n  expected {want:?}\n  file     {claimed:?}"));
+            }
+            Some(want)
+        }
+        (None, claimed) => claimed.clone(),
+    };
+    let decoded = match (&inputs, &circuit) {
+        (Some(args), Some(c)) => Some(public_inputs::decode(layout(c)?, args)?),
+        _ => None,
+    };
+    if let Some(d) = &decoded {
+        if d.kyc != 1 || d.sig_valid != 1 {
+            failures.push(format!("public inputs do not attest approval (kyc={}, sig_valid={})", d.kyc, d.sig_valid));
+        }
+    }
+
+    /* decode and verify: setup needs only the step size, never the guest */
+    let proof = KycProof::from_bytes(
+        &hex::decode(&file.proof).context("proof is not hex")?,
+        &hex::decode(&file.instance).context("instance is not hex")?,
+    )?;
+
+    let t_setup = Instant::now();
+    let pp = engine::setup(step_sz);
+    let setup_s = t_setup.elapsed().as_secs_f64();
+
+    let t_verify = Instant::now();
+    let verified = engine::verify_kyc(&pp, &proof);
+    let verify_s = t_verify.elapsed().as_secs_f64();
+    if let Err(e) = &verified {
+        failures.push(format!("proof does not verify: {e}"));
+    }
+
+    println!("\n──── Verification ───────────────────────────");
+    println!("setup_sec  : {:.3}", setup_s);
+    println!("verify_sec : {:.3}", verify_s);
+    println!("step_size  : {}", step_sz);
+    println!("circuit    : {}", circuit.as_deref().unwrap_or("(not given)"));
+    if let Some(d) = &decoded {
+        println!("commitment : {}", d.commitment);
+        println!("flags      : kyc={} sig_valid={}", d.kyc, d.sig_valid);
+        if let Some(t) = &d.travel_rule_commitment {
+            println!("travel_rule: {}", t);
+        }
+        if let Some(n) = &d.nonce {
+            println!("nonce      : {}", n);
+        }
+    } else {
+        println!("inputs     : not decoded (needs public inputs and a circuit)");
+    }
+    println!("─────────────────────────────────────────────");
+    if !failures.is_empty() {
+        for f in &failures { eprintln!("❌ {f}"); }
+        return Err(anyhow!("{} check(s) failed", failures.len()));
+    }
+    println!("✅ KYC proof verified");
+    Ok(())
+}
@@ -0,0 +1,257 @@
+//! kyc_wasm — guest program proven by zkEngine.
+//! Exports return 0 when the Circle KYC and signature flags are both set.
+//! `*_full` exports take all 8 limbs of the wallet digest; the 5-limb
+//! exports are kept for compatibility with existing integrations.
+//!
+//! `*_nonce` exports also take the 8 limbs of a verifier's challenge nonce
+//! last, and trap when they are all zero.  The SNARK doesn't expose the
+//! nonce, so it doesn't by itself tie a proof to a challenge: zk_server
+//! does that from its record of the arguments each proof was made with
+//! (see its challenge.rs).
+//!
+//! `check_kyc_full_keccak` takes the raw wallet address instead of trusting
+//! the host's hash: it computes the Keccak-256 commitment itself and traps
+//! unless it equals the 8 digest limbs passed after the address and both
+//! flags are set.  zkEngine commits to no return value, so a `0`/`1` result
+//! is never checked by a verifier; trapping means no execution trace, and so
+//! no proof, exists for an address whose commitment differs from the limbs.
+//!
+//! ABI: digest limbs are little-endian `u32` words passed as `i64`
+//! (see `kyc_core::encoding`); every export traps on a limb outside
+//! `0..2^32`, so no proof exists for a non-canonical encoding.  Flags are
+//! `i32`.
+
+/* ---- helpers -------------------------------------------------------- */
+#[inline(always)]
+fn approved(kyc: i32, sig: i32) -> i32 {
+    if kyc == 1 && sig == 1 { 0 } else { 1 }
+}
+
+/// OR of all limbs' out-of-range bits; non-zero means a non-canonical input.
+#[inline(always)]
+fn high_bits(limbs: &[i64]) -> i64 {
+    limbs.iter().fold(0, |acc, &l| acc | (l >> 32))
+}
+
+/// Abort the call: the trace, and any proof of it, can't be built.
+#[inline(always)]
+fn reject() -> ! {
+    #[cfg(target_arch = "wasm32")]
+    core::arch::wasm32::unreachable();
+    #[cfg(not(target_arch = "wasm32"))]
+    panic!("rejected");
+}
+
+#[inline(always)]
+fn any_set(limbs: &[i64]) -> bool {
+    limbs.iter().any(|&l| l != 0)
+}
+
+const KECCAK_RC: [u64; 24] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+    0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+    0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+    0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+];
+const KECCAK_ROTC: [u32; 24] = [1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44];
+const KECCAK_PILN: [usize; 24] = [10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1];
+
+/// Keccak-f[1600] permutation; lane `x + 5y` of the state.
+fn keccak_f(st: &mut [u64; 25]) {
+    for rc in KECCAK_RC {
+        /* theta */
+        let mut bc = [0u64; 5];
+        for i in 0..5 {
+            bc[i] = st[i] ^ st[i + 5] ^ st[i + 10] ^ st[i + 15] ^ st[i + 20];
+        }
+        for i in 0..5 {
+            let t = bc[(i + 4) % 5] ^ bc[(i + 1) % 5].rotate_left(1);
+            for j in (0..25).step_by(5) {
+                st[j + i] ^= t;
+            }
+        }
+        /* rho, pi */
+        let mut t = st[1];
+        for i in 0..24 {
+            let j = KECCAK_PILN[i];
+            let next = st[j];
+            st[j] = t.rotate_left(KECCAK_ROTC[i]);
+            t = next;
+        }
+        /* chi */
+        for j in (0..25).step_by(5) {
+            let row = [st[j], st[j + 1], st[j + 2], st[j + 3], st[j + 4]];
+            for i in 0..5 {
+                st[j + i] = row[i] ^ (!row[(i + 1) % 5] & row[(i + 2) % 5]);
+            }
+        }
+        /* iota */
+        st[0] ^= rc;
+    }
+}
+
+/// Keccak-256 of `0x` + the lowercase hex of `addr`, the preimage
+/// `kyc_core` commits for an `evm_address`, as 8 little-endian words.
+fn address_commitment(addr: &[u8; 20]) -> [u32; 8] {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    /* 42 message bytes fit one 136-byte block, padded 0x01 … 0x80 */
+    let mut block = [0u8; 136];
+    block[0] = b'0';
+    block[1] = b'x';
+    for (i, b) in addr.iter().enumerate() {
+        block[2 + 2 * i] = HEX[(b >> 4) as usize];
+        block[3 + 2 * i] = HEX[(b & 0x0f) as usize];
+    }
+    block[42] = 0x01;
+    block[135] |= 0x80;
+    let mut st = [0u64; 25];
+    for (lane, bytes) in st.iter_mut().zip(block.chunks_exact(8)) {
+        let mut le = [0u8; 8];
+        le.copy_from_slice(bytes);
+        *lane ^= u64::from_le_bytes(le);
+    }
+    keccak_f(&mut st);
+    let mut w = [0u32; 8];
+    for i in 0..4 {
+        w[2 * i] = st[i] as u32;
+        w[2 * i + 1] = (st[i] >> 32) as u32;
+    }
+    w
+}
+
+/* ---- exports -------------------------------------------------------- */
+
+/// 5 digest limbs of the wallet + 2 flags → 0 on approval (legacy, 160-bit).
+#[no_mangle]
+pub extern "C" fn check_kyc(
+    h0: i64, h1: i64, h2: i64, h3: i64, h4: i64,
+    kyc: i32, sig: i32,
+) -> i32 {
+    if high_bits(&[h0, h1, h2, h3, h4]) != 0 {
+        reject();
+    }
+    approved(kyc, sig)
+}
+
+/// Same as [`check_kyc`], plus the 8 digest limbs of an IVMS101
+/// Travel Rule payload so the trace commits to it.  Traps when the
+/// digest is all zero.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub extern "C" fn check_kyc_travel_rule(
+    h0: i64, h1: i64, h2: i64, h3: i64, h4: i64,
+    kyc: i32, sig: i32,
+    t0: i64, t1: i64, t2: i64, t3: i64, t4: i64, t5: i64, t6: i64, t7: i64,
+) -> i32 {
+    let t = [t0, t1, t2, t3, t4, t5, t6, t7];
+    if high_bits(&[h0, h1, h2, h3, h4]) | high_bits(&t) != 0 {
+        reject();
+    }
+    /* an all-zero digest means the host sent no payload: no proof without one */
+    if !any_set(&t) {
+        reject();
+    }
+    approved(kyc, sig)
+}
+
+/// 8 limbs (full 256-bit digest) of the wallet + 2 flags → 0 on approval.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub extern "C" fn check_kyc_full(
+    h0: i64, h1: i64, h2: i64, h3: i64, h4: i64, h5: i64, h6: i64, h7: i64,
+    kyc: i32, sig: i32,
+) -> i32 {
+    if high_bits(&[h0, h1, h2, h3, h4, h5, h6, h7]) != 0 {
+        reject();
+    }
+    approved(kyc, sig)
+}
+
+/// [`check_kyc_full`] plus the 8 Travel Rule limbs; traps when they are
+/// all zero.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub extern "C" fn check_kyc_full_travel_rule(
+    h0: i64, h1: i64, h2: i64, h3: i64, h4: i64, h5: i64, h6: i64, h7: i64,
+    kyc: i32, sig: i32,
+    t0: i64, t1: i64, t2: i64, t3: i64, t4: i64, t5: i64, t6: i64, t7: i64,
+) -> i32 {
+    let t = [t0, t1, t2, t3, t4, t5, t6, t7];
+    if high_bits(&[h0, h1, h2, h3, h4, h5, h6, h7]) | high_bits(&t) != 0 {
+        reject();
+    }
+    if !any_set(&t) {
+        reject();
+    }
+    approved(kyc, sig)
+}
+
+/// [`check_kyc_full`] plus the 8 limbs of a challenge nonce; traps when
+/// they are all zero.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub extern "C" fn check_kyc_full_nonce(
+    h0: i64, h1: i64, h2: i64, h3: i64, h4: i64, h5: i64, h6: i64, h7: i64,
+    kyc: i32, sig: i32,
+    n0: i64, n1: i64, n2: i64, n3: i64, n4: i64, n5: i64, n6: i64, n7: i64,
+) -> i32 {
+    let n = [n0, n1, n2, n3, n4, n5, n6, n7];
+    if high_bits(&[h0, h1, h2, h3, h4, h5, h6, h7]) | high_bits(&n) != 0 {
+        reject();
+    }
+    /* an all-zero nonce means the host sent no challenge */
+    if !any_set(&n) {
+        reject();
+    }
+    approved(kyc, sig)
+}
+
+/// [`check_kyc_full_travel_rule`] plus the 8 limbs of a challenge nonce.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub extern "C" fn check_kyc_full_travel_rule_nonce(
+    h0: i64, h1: i64, h2: i64, h3: i64, h4: i64, h5: i64, h6: i64, h7: i64,
+    kyc: i32, sig: i32,
+    t0: i64, t1: i64, t2: i64, t3: i64, t4: i64, t5: i64, t6: i64, t7: i64,
+    n0: i64, n1: i64, n2: i64, n3: i64, n4: i64, n5: i64, n6: i64, n7: i64,
+) -> i32 {
+    let t = [t0, t1, t2, t3, t4, t5, t6, t7];
+    let n = [n0, n1, n2, n3, n4, n5, n6, n7];
+    if high_bits(&[h0, h1, h2, h3, h4, h5, h6, h7]) | high_bits(&t) | high_bits(&n) != 0 {
+        reject();
+    }
+    if !any_set(&t) || !any_set(&n) {
+        reject();
+    }
+    approved(kyc, sig)
+}
+
+/// Raw wallet address (its 20 bytes as 5 little-endian words) + the 8
+/// limbs of its Keccak-256 commitment + 2 flags → 0 on approval.  The
+/// commitment is recomputed here; the call traps (see [`reject`]) when it
+/// differs from the limbs or the flags aren't both set, so it never
+/// returns anything but 0.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub extern "C" fn check_kyc_full_keccak(
+    a0: i64, a1: i64, a2: i64, a3: i64, a4: i64,
+    h0: i64, h1: i64, h2: i64, h3: i64, h4: i64, h5: i64, h6: i64, h7: i64,
+    kyc: i32, sig: i32,
+) -> i32 {
+    let a = [a0, a1, a2, a3, a4];
+    let h = [h0, h1, h2, h3, h4, h5, h6, h7];
+    if high_bits(&a) | high_bits(&h) != 0 {
+        reject();
+    }
+    let mut addr = [0u8; 20];
+    for (bytes, &w) in addr.chunks_exact_mut(4).zip(a.iter()) {
+        bytes.copy_from_slice(&(w as u32).to_le_bytes());
+    }
+    let digest = address_commitment(&addr);
+    if digest.iter().zip(h.iter()).any(|(&d, &l)| d as i64 != l) || approved(kyc, sig) != 0 {
+        reject();
+    }
+    0
+}
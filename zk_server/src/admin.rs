@@ -0,0 +1,531 @@
+//! Operator endpoints under `/admin`.
+//! Every route requires `Authorization: Bearer $ZK_ADMIN_TOKEN`; without the
+//! variable set the admin API is disabled.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, Request, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::{
+    deadletter::{DeadLetters, Kind}, features::Flag, incidents::Report, killswitch::{Halt, Resume, Resumed}, maintenance::Toggle, numa::NodeReport, preissue::Subject, rollout::RolloutConfig, scaling::ScalingHints, selftest, swap::{self, SwapRequest}, sweep::{self, SweepRequest}, usage::UsageLog, watchdog::WatchdogReport, webhook::Event, AppState,
+};
+
+pub fn router(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/loglevel", get(get_loglevel).put(put_loglevel))
+        .route("/features", get(list_features))
+        .route("/features/:name", put(put_feature).delete(delete_feature))
+        .route("/rollout", get(get_rollout).put(put_rollout))
+        .route("/rollout/swap", get(get_swap).post(post_swap))
+        .route("/step-sweep", get(get_sweep).post(post_sweep))
+        .route("/selftest", post(post_selftest))
+        .route("/canary", get(get_canary))
+        .route("/scaling", get(get_scaling))
+        .route("/usage", get(get_usage))
+        .route("/sla", get(get_sla))
+        .route("/honeypot", get(get_honeypot))
+        .route("/maintenance", get(get_maintenance).put(put_maintenance))
+        .route("/issuance", get(get_issuance))
+        .route("/issuance/halt", post(halt_issuance))
+        .route("/issuance/resume", post(resume_issuance))
+        .route("/issuers/:id/compromise", post(report_compromise))
+        .route("/preissue", get(get_preissue).put(put_preissue))
+        .route("/compliance", get(get_compliance))
+        .route("/proofs/:id/revoke", post(revoke_proof))
+        .route("/api-keys", get(list_api_keys))
+        .route("/api-keys/:id", delete(revoke_api_key))
+        .route("/prove-keys", get(list_prove_keys))
+        .route("/dead-letters", get(list_dead_letters))
+        .route("/dead-letters/:id", get(get_dead_letter).delete(delete_dead_letter))
+        .route("/dead-letters/:id/replay", post(replay_dead_letter))
+        .route_layer(middleware::from_fn_with_state(state, require_admin))
+}
+
+async fn require_admin<B>(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let Some(expected) = state.admin_token.as_deref() else {
+        return (StatusCode::FORBIDDEN, Json("admin API disabled (set ZK_ADMIN_TOKEN)")).into_response();
+    };
+    let given = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if given != Some(expected) {
+        return (StatusCode::UNAUTHORIZED, Json("bad admin token")).into_response();
+    }
+    next.run(req).await
+}
+
+/* ---------- /admin/loglevel -------------------------------------- */
+#[derive(Serialize)]
+struct LogLevel {
+    filter: String,
+}
+
+/// Either a full directive string, or a default level plus per-target levels.
+#[derive(Deserialize)]
+struct SetLogLevel {
+    #[serde(default)]
+    filter:  Option<String>,
+    #[serde(default)]
+    level:   Option<String>,
+    #[serde(default)]
+    targets: BTreeMap<String, String>,
+}
+
+async fn get_loglevel(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(LogLevel { filter: state.log.current() })
+}
+
+async fn put_loglevel(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<SetLogLevel>,
+) -> impl IntoResponse {
+    let filter = match body.filter {
+        Some(f) => f,
+        None => {
+            let mut parts: Vec<String> = body.level.into_iter().collect();
+            parts.extend(body.targets.iter().map(|(t, l)| format!("{t}={l}")));
+            parts.join(",")
+        }
+    };
+    if filter.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json("give `filter`, or `level` and/or `targets`".to_string()))
+            .into_response();
+    }
+    match state.log.set(&filter) {
+        Ok(()) => {
+            tracing::warn!("log filter changed to {filter}");
+            (StatusCode::OK, Json(LogLevel { filter })).into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, Json(format!("{e:#}"))).into_response(),
+    }
+}
+
+/* ---------- /admin/features -------------------------------------- */
+async fn list_features(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(state.features.snapshot().into_iter().collect::<BTreeMap<_, _>>())
+}
+
+async fn put_feature(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(flag): Json<Flag>,
+) -> impl IntoResponse {
+    if !(0.0..=100.0).contains(&flag.percent) {
+        return (StatusCode::BAD_REQUEST, Json("percent must be within 0..=100")).into_response();
+    }
+    tracing::warn!("feature {name} set: {flag:?}");
+    state.features.set(&name, flag.clone());
+    (StatusCode::OK, Json(flag)).into_response()
+}
+
+async fn delete_feature(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> StatusCode {
+    if state.features.remove(&name) {
+        tracing::warn!("feature {name} override removed");
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/* ---------- /admin/rollout --------------------------------------- */
+#[derive(Serialize)]
+struct RolloutView {
+    #[serde(flatten)]
+    config:   RolloutConfig,
+    accepted: Vec<String>,
+}
+
+async fn get_rollout(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(RolloutView { config: state.rollout.config(), accepted: state.rollout.accepted_versions() })
+}
+
+async fn put_rollout(
+    State(state): State<Arc<AppState>>,
+    Json(cfg): Json<RolloutConfig>,
+) -> impl IntoResponse {
+    match state.rollout.set(cfg) {
+        Ok(()) => {
+            tracing::warn!("rollout changed: {:?}", state.rollout.config());
+            get_rollout(State(state)).await.into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, Json(e.to_string())).into_response(),
+    }
+}
+
+/* ---------- /admin/rollout/swap ---------------------------------- */
+async fn get_swap(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match state.swaps.status() {
+        Some(s) => Json(s).into_response(),
+        None    => (StatusCode::NOT_FOUND, Json(serde_json::json!("no swap yet"))).into_response(),
+    }
+}
+
+async fn post_swap(State(state): State<Arc<AppState>>, Json(req): Json<SwapRequest>) -> Response {
+    match swap::start(state, req) {
+        Ok(status) => (StatusCode::ACCEPTED, Json(status)).into_response(),
+        Err(e)     => (StatusCode::CONFLICT, Json(serde_json::json!(e.to_string()))).into_response(),
+    }
+}
+
+/* ---------- /admin/step-sweep ----------------------------------- */
+async fn get_sweep(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match state.sweeps.status() {
+        Some(s) => Json(s).into_response(),
+        None    => (StatusCode::NOT_FOUND, Json(serde_json::json!("no sweep yet"))).into_response(),
+    }
+}
+
+async fn post_sweep(State(state): State<Arc<AppState>>, Json(req): Json<SweepRequest>) -> Response {
+    match sweep::start(state, req) {
+        Ok(status) => (StatusCode::ACCEPTED, Json(status)).into_response(),
+        Err(e)     => (StatusCode::CONFLICT, Json(serde_json::json!(e.to_string()))).into_response(),
+    }
+}
+
+/* ---------- /admin/selftest -------------------------------------- */
+/// 200 when the vector proves and verifies on every serving guest, 500 with the same report when not.
+async fn post_selftest(State(state): State<Arc<AppState>>) -> Response {
+    match selftest::run(&state).await {
+        Ok(report) if report.passed => (StatusCode::OK, Json(report)).into_response(),
+        Ok(report) => (StatusCode::INTERNAL_SERVER_ERROR, Json(report)).into_response(),
+        Err(e)     => (StatusCode::CONFLICT, Json(serde_json::json!(e.to_string()))).into_response(),
+    }
+}
+
+/* ---------- /admin/canary ---------------------------------------- */
+async fn get_canary(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(state.canary.stats())
+}
+
+/* ---------- /admin/scaling --------------------------------------- */
+#[derive(Serialize)]
+struct ScalingReport {
+    #[serde(flatten)]
+    hints:    ScalingHints,
+    /// Memory watchdog state, when `ZK_MEMORY_CEILING_MB` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    watchdog: Option<WatchdogReport>,
+    /// Per-node worker placement, when `ZK_NUMA_NODES` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    numa:     Option<Vec<NodeReport>>,
+}
+
+async fn get_scaling(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(ScalingReport {
+        hints:    state.load.hints(),
+        watchdog: state.watchdog.as_ref().map(|w| w.report()),
+        numa:     state.prover.numa().map(|n| n.report()),
+    })
+}
+
+/* ---------- /admin/usage ----------------------------------------- */
+#[derive(Deserialize)]
+struct UsageQuery {
+    /// Unix seconds, inclusive; default 0.
+    #[serde(default)]
+    from:    u64,
+    /// Unix seconds, exclusive; default now.
+    #[serde(default)]
+    to:      Option<u64>,
+    #[serde(default)]
+    tenant:  Option<String>,
+    /// Include the individual records, not just totals.
+    #[serde(default)]
+    records: bool,
+}
+
+async fn get_usage(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<UsageQuery>,
+) -> impl IntoResponse {
+    let to = q.to.unwrap_or(u64::MAX);
+    match state.usage.records(q.from, to, q.tenant.as_deref()) {
+        Ok(recs) => {
+            let mut body = serde_json::json!({ "totals": UsageLog::totals(&recs) });
+            if q.records {
+                body["records"] = serde_json::to_value(&recs).unwrap_or_default();
+            }
+            (StatusCode::OK, Json(body)).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(format!("{e:#}"))).into_response(),
+    }
+}
+
+/* ---------- /admin/sla ------------------------------------------- */
+async fn get_sla(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(state.sla.report())
+}
+
+/* ---------- /admin/honeypot -------------------------------------- */
+async fn get_honeypot(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(state.honeypot.report())
+}
+
+/* ---------- /admin/maintenance ----------------------------------- */
+fn maintenance_view(state: &AppState) -> serde_json::Value {
+    let status = state.maintenance.status();
+    serde_json::json!({
+        "enabled":     status.is_some(),
+        "maintenance": status,
+        "queued_jobs": state.jobs.queued().len(),
+    })
+}
+
+async fn get_maintenance(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(maintenance_view(&state))
+}
+
+async fn put_maintenance(State(state): State<Arc<AppState>>, Json(t): Json<Toggle>) -> Response {
+    let enabled = t.enabled;
+    state.maintenance.set(t);
+    if enabled {
+        let queued = state.jobs.queued();
+        if let Err(e) = state.maintenance.persist(&queued) {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!(format!("{e:#}")))).into_response();
+        }
+        tracing::warn!("maintenance on: {} queued jobs saved to {}", queued.len(), state.maintenance.file.display());
+    } else {
+        state.maintenance.discard();
+        tracing::warn!("maintenance off: proving resumed");
+    }
+    Json(maintenance_view(&state)).into_response()
+}
+
+/* ---------- /admin/issuance -------------------------------------- */
+fn issuance_view(state: &AppState) -> serde_json::Value {
+    let halt = state.kill_switch.status();
+    serde_json::json!({
+        "halted": halt.is_some(),
+        "halt":   halt,
+        "audit":  state.kill_switch.audit_log(50).unwrap_or_default(),
+    })
+}
+
+async fn get_issuance(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(issuance_view(&state))
+}
+
+async fn halt_issuance(State(state): State<Arc<AppState>>, Json(h): Json<Halt>) -> Response {
+    match state.kill_switch.halt(h) {
+        Ok((_, true))  => Json(issuance_view(&state)).into_response(),
+        Ok((_, false)) => (StatusCode::CONFLICT, Json(issuance_view(&state))).into_response(),
+        // The halt is in force even when it could not be saved or audited.
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!(format!("issuance halted, but {e:#}")))).into_response(),
+    }
+}
+
+async fn resume_issuance(State(state): State<Arc<AppState>>, Json(r): Json<Resume>) -> Response {
+    match state.kill_switch.resume(r) {
+        Ok(Resumed::Resumed)      => Json(issuance_view(&state)).into_response(),
+        Ok(Resumed::NotHalted)    => (StatusCode::CONFLICT, Json(serde_json::json!("issuance is not halted"))).into_response(),
+        Ok(Resumed::WrongHalt(h)) => (StatusCode::CONFLICT, Json(serde_json::json!({
+            "error": "halt_id does not name the current halt",
+            "halt":  h,
+        }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!(format!("{e:#}")))).into_response(),
+    }
+}
+
+/* ---------- /admin/issuers/:id/compromise ------------------------ */
+async fn report_compromise(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(r): Json<Report>,
+) -> Response {
+    if state.read_only {
+        return (StatusCode::CONFLICT, Json(serde_json::json!("read replica: report compromises on the primary"))).into_response();
+    }
+    match state.incidents.report(&state.issuers, state.store.as_ref(), state.events.as_ref(), &id, r) {
+        Ok(incident) => (StatusCode::CREATED, Json(incident)).into_response(),
+        Err(e)       => (StatusCode::BAD_REQUEST, Json(serde_json::json!(format!("{e:#}")))).into_response(),
+    }
+}
+
+/* ---------- /admin/proofs/:id/revoke ----------------------------- */
+#[derive(Deserialize)]
+struct Revoke {
+    reason: String,
+}
+
+async fn revoke_proof(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(body): Json<Revoke>,
+) -> impl IntoResponse {
+    if state.read_only {
+        return (StatusCode::CONFLICT, Json(serde_json::json!("read replica: revoke on the primary"))).into_response();
+    }
+    let Some(store) = &state.store else {
+        return (StatusCode::NOT_FOUND, Json(serde_json::json!("proof store disabled"))).into_response();
+    };
+    match store.revoke(&id, &body.reason) {
+        Ok(r) => {
+            tracing::warn!("proof {id} revoked: {}", r.reason);
+            if let Some(events) = &state.events {
+                events.emit(Event::new("proof.revoked", "revocation", &r));
+            }
+            (StatusCode::OK, Json(serde_json::to_value(r).unwrap_or_default())).into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, Json(serde_json::json!(e.to_string()))).into_response(),
+    }
+}
+
+/* ---------- /admin/api-keys --------------------------------------- */
+async fn list_api_keys(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match &state.api_keys {
+        Some(keys) => Json(serde_json::to_value(keys.list()).unwrap_or_default()).into_response(),
+        None       => (StatusCode::NOT_FOUND, Json(serde_json::json!("API keys disabled"))).into_response(),
+    }
+}
+
+async fn revoke_api_key(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let Some(keys) = &state.api_keys else {
+        return (StatusCode::NOT_FOUND, Json(serde_json::json!("API keys disabled"))).into_response();
+    };
+    match keys.revoke(&id) {
+        Ok(true)  => {
+            tracing::warn!("API key {id} revoked");
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(false) => (StatusCode::NOT_FOUND, Json(serde_json::json!("unknown API key"))).into_response(),
+        Err(e)    => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!(format!("{e:#}")))).into_response(),
+    }
+}
+
+/* ---------- /admin/prove-keys ------------------------------------ */
+async fn list_prove_keys(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match &state.prove_keys {
+        Some(keys) => Json(keys.list()).into_response(),
+        None       => (StatusCode::NOT_FOUND, Json(serde_json::json!("prove keys disabled"))).into_response(),
+    }
+}
+
+/* ---------- /admin/preissue ------------------------------------- */
+async fn get_preissue(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(state.preissue.report())
+}
+
+async fn put_preissue(State(state): State<Arc<AppState>>, Json(subjects): Json<Vec<Subject>>) -> Response {
+    let n = subjects.len();
+    match state.preissue.set(subjects) {
+        Ok(()) => {
+            tracing::warn!("pre-issuance list replaced: {n} subjects");
+            Json(state.preissue.report()).into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, Json(serde_json::json!(e.to_string()))).into_response(),
+    }
+}
+
+/* ---------- /admin/compliance ------------------------------------- */
+/// Issued proofs in `[from, to)` with their consent and revocation state.
+async fn get_compliance(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<UsageQuery>,
+) -> impl IntoResponse {
+    let Some(store) = &state.store else {
+        return (StatusCode::NOT_FOUND, Json(serde_json::json!("proof store disabled"))).into_response();
+    };
+    let to = q.to.unwrap_or(u64::MAX);
+    let proofs = store.scan(|p| {
+        (q.from..to).contains(&p.created_at) && q.tenant.as_deref().map_or(true, |t| p.tenant == t)
+    });
+    let proofs = match proofs {
+        Ok(p)  => p,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!(format!("{e:#}")))).into_response(),
+    };
+    let mut rows = Vec::with_capacity(proofs.len());
+    for p in proofs {
+        let revocation = store.revocation(&p.id).ok().flatten();
+        rows.push(serde_json::json!({
+            "proof_id":   p.id,
+            "tenant":     p.tenant,
+            "circuit":    p.circuit,
+            "issued_at":  p.created_at,
+            "consent":    p.consent,
+            "revocation": revocation,
+        }));
+    }
+    let without = rows.iter().filter(|r| r["consent"].is_null()).count();
+    Json(serde_json::json!({ "proofs": rows.len(), "without_consent": without, "records": rows })).into_response()
+}
+
+/* ---------- /admin/dead-letters ----------------------------------- */
+fn dead_letters(state: &AppState) -> Result<&DeadLetters, Response> {
+    state.dead_letters.as_deref()
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(serde_json::json!("dead letters disabled (set ZK_STORE_DIR or ZK_DEAD_LETTER_DIR)"))).into_response())
+}
+
+fn internal(e: anyhow::Error) -> Response {
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!(format!("{e:#}")))).into_response()
+}
+
+async fn list_dead_letters(State(state): State<Arc<AppState>>) -> Response {
+    let letters = match dead_letters(&state) { Ok(d) => d, Err(r) => return r };
+    match letters.list() {
+        Ok(l)  => Json(serde_json::json!({ "count": l.len(), "letters": l })).into_response(),
+        Err(e) => internal(e),
+    }
+}
+
+async fn get_dead_letter(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Response {
+    let letters = match dead_letters(&state) { Ok(d) => d, Err(r) => return r };
+    match letters.get(&id) {
+        Ok(Some(l)) => Json(l).into_response(),
+        Ok(None)    => (StatusCode::NOT_FOUND, Json(serde_json::json!("unknown dead letter"))).into_response(),
+        Err(e)      => internal(e),
+    }
+}
+
+async fn delete_dead_letter(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Response {
+    let letters = match dead_letters(&state) { Ok(d) => d, Err(r) => return r };
+    match letters.remove(&id) {
+        Ok(true)  => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, Json(serde_json::json!("unknown dead letter"))).into_response(),
+        Err(e)    => internal(e),
+    }
+}
+
+/// Hand a letter back to its sender and delete it; a renewed failure files a new letter.
+async fn replay_dead_letter(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Response {
+    let letters = match dead_letters(&state) { Ok(d) => d, Err(r) => return r };
+    let letter = match letters.get(&id) {
+        Ok(Some(l)) => l,
+        Ok(None)    => return (StatusCode::NOT_FOUND, Json(serde_json::json!("unknown dead letter"))).into_response(),
+        Err(e)      => return internal(e),
+    };
+    let hook = match letter.kind {
+        Kind::Billing => &state.billing,
+        Kind::Event   => &state.events,
+    };
+    let Some(hook) = hook else {
+        return (StatusCode::CONFLICT, Json(serde_json::json!(format!("{:?} webhook disabled", letter.kind)))).into_response();
+    };
+    match serde_json::from_value::<Event>(letter.payload) {
+        Ok(ev) => hook.emit(ev),
+        Err(e) => return internal(e.into()),
+    }
+    if let Err(e) = letters.remove(&id) {
+        return internal(e);
+    }
+    tracing::info!("dead letter {id} replayed");
+    (StatusCode::ACCEPTED, Json(serde_json::json!({ "replayed": id }))).into_response()
+}
@@ -0,0 +1,223 @@
+//! Self-service, rate-limited API keys for third-party verifiers.
+//! Enabled by `ZK_API_KEYS_FILE` (JSON, hashed keys only).  Anyone may mint a key with
+//! `POST /api-keys` (at most `ZK_API_KEY_MINTS_PER_HOUR` per client IP, default 5); a key is
+//! scoped to `GET /proofs/:id/verify`, `POST /verify`, `GET /challenge`, `POST /envelopes/check`
+//! and `GET /status` and grants nothing else — proving stays with tenants.  Keys are sent as
+//! `x-api-key: zkv_<id>_<secret>` and limited to `ZK_API_KEY_RATE_PER_MIN` requests per minute
+//! (default 60, token bucket).  With
+//! `ZK_VERIFY_REQUIRE_KEY=true` the verify routes refuse keyless requests.
+//!
+//! Operators list keys with `GET /admin/api-keys` and revoke with `DELETE /admin/api-keys/:id`.
+
+use anyhow::Result;
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, VecDeque},
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{errors::Refused, ratelimit::Bucket, store::{self, read_json, write_json}, AppState};
+
+const MINT_WINDOW: Duration = Duration::from_secs(3600);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    Verify,
+    Status,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct KeyRecord {
+    pub id:           String,
+    pub label:        String,
+    /// Hex SHA-256 of the secret part.
+    pub hash:         String,
+    pub scopes:       Vec<Scope>,
+    pub rate_per_min: u32,
+    pub created_at:   u64,
+    #[serde(default)]
+    pub revoked:      bool,
+}
+
+pub struct ApiKeys {
+    path:           PathBuf,
+    rate_per_min:   u32,
+    mints_per_hour: usize,
+    /// `ZK_VERIFY_REQUIRE_KEY`: keyless verify requests are refused.
+    require:        bool,
+    keys:           Mutex<HashMap<String, KeyRecord>>,
+    buckets:        Mutex<HashMap<String, Bucket>>,
+    mints:          Mutex<HashMap<IpAddr, VecDeque<Instant>>>,
+}
+
+impl ApiKeys {
+    /// Build from `ZK_API_KEYS_FILE`; `None` disables API keys.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(path) = std::env::var("ZK_API_KEYS_FILE") else { return Ok(None) };
+        let path = PathBuf::from(path);
+        let list: Vec<KeyRecord> = read_json(&path)?.unwrap_or_default();
+        let num = |k: &str, d: u64| std::env::var(k).ok().and_then(|v| v.parse().ok()).unwrap_or(d);
+        Ok(Some(ApiKeys {
+            path,
+            rate_per_min:   num("ZK_API_KEY_RATE_PER_MIN", 60) as u32,
+            mints_per_hour: num("ZK_API_KEY_MINTS_PER_HOUR", 5) as usize,
+            require:        matches!(std::env::var("ZK_VERIFY_REQUIRE_KEY").as_deref(), Ok("1" | "true")),
+            keys:           Mutex::new(list.into_iter().map(|k| (k.id.clone(), k)).collect()),
+            buckets:        Mutex::default(),
+            mints:          Mutex::default(),
+        }))
+    }
+
+    fn save(&self, keys: &HashMap<String, KeyRecord>) -> Result<()> {
+        let mut list: Vec<&KeyRecord> = keys.values().collect();
+        list.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        write_json(&self.path, &list)
+    }
+
+    /// Mint a verify/status key; returns the record and the full key (shown once).
+    fn mint(&self, label: &str) -> Result<(KeyRecord, String)> {
+        let id = hex::encode(rand::random::<[u8; 4]>());
+        let secret = hex::encode(rand::random::<[u8; 16]>());
+        let rec = KeyRecord {
+            id:           id.clone(),
+            label:        label.to_string(),
+            hash:         hex::encode(Sha256::digest(secret.as_bytes())),
+            scopes:       vec![Scope::Verify, Scope::Status],
+            rate_per_min: self.rate_per_min,
+            created_at:   store::now(),
+            revoked:      false,
+        };
+        let mut keys = self.keys.lock().unwrap();
+        keys.insert(id.clone(), rec.clone());
+        self.save(&keys)?;
+        Ok((rec, format!("zkv_{id}_{secret}")))
+    }
+
+    pub fn list(&self) -> Vec<KeyRecord> {
+        let mut v: Vec<KeyRecord> = self.keys.lock().unwrap().values().cloned().collect();
+        v.sort_by_key(|k| k.created_at);
+        v
+    }
+
+    /// Mark `id` revoked; `false` if unknown.
+    pub fn revoke(&self, id: &str) -> Result<bool> {
+        let mut keys = self.keys.lock().unwrap();
+        let Some(k) = keys.get_mut(id) else { return Ok(false) };
+        k.revoked = true;
+        self.save(&keys)?;
+        Ok(true)
+    }
+
+    /// Whether `ip` may mint another key this hour.
+    fn admit_mint(&self, ip: IpAddr) -> bool {
+        let now = Instant::now();
+        let mut mints = self.mints.lock().unwrap();
+        mints.retain(|_, q| {
+            while q.front().map_or(false, |t| now.duration_since(*t) > MINT_WINDOW) {
+                q.pop_front();
+            }
+            !q.is_empty()
+        });
+        let q = mints.entry(ip).or_default();
+        if q.len() >= self.mints_per_hour {
+            return false;
+        }
+        q.push_back(now);
+        true
+    }
+
+    /// Check `key` for `scope` and take one token from its bucket.
+    fn admit(&self, key: &str, scope: Scope) -> Result<(), Refused> {
+        let unauthorized = || Refused::new(StatusCode::UNAUTHORIZED, "invalid API key");
+        let (id, secret) = key.strip_prefix("zkv_").and_then(|k| k.split_once('_')).ok_or_else(unauthorized)?;
+        let rate = {
+            let keys = self.keys.lock().unwrap();
+            let rec = keys.get(id).filter(|r| !r.revoked).ok_or_else(unauthorized)?;
+            if hex::encode(Sha256::digest(secret.as_bytes())) != rec.hash {
+                return Err(unauthorized());
+            }
+            if !rec.scopes.contains(&scope) {
+                return Err(Refused::new(StatusCode::FORBIDDEN, "API key not scoped for this endpoint"));
+            }
+            rec.rate_per_min.max(1) as f64
+        };
+        let mut buckets = self.buckets.lock().unwrap();
+        let now = Instant::now();
+        let b = buckets.entry(id.to_string()).or_insert_with(|| Bucket::full(rate, now));
+        if let Err(retry) = b.take(rate, rate, now) {
+            return Err(Refused { retry_after: Some(retry), ..Refused::new(StatusCode::TOO_MANY_REQUESTS, "API key rate limit exceeded") });
+        }
+        Ok(())
+    }
+
+    /// Admit a request carrying `key` (if any) for `scope`.
+    pub fn check(&self, key: Option<&str>, scope: Scope) -> Result<(), Refused> {
+        match key {
+            Some(key) => self.admit(key, scope),
+            None if self.require && scope == Scope::Verify => {
+                Err(Refused::new(StatusCode::UNAUTHORIZED, "x-api-key required (mint one with POST /api-keys)"))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct MintBody {
+    label: String,
+}
+
+/// `POST /api-keys`: self-service minting of a verify/status key.
+pub async fn handle_mint(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(body): Json<MintBody>,
+) -> Response {
+    let Some(keys) = &state.api_keys else {
+        return (StatusCode::NOT_FOUND, Json("API keys disabled (set ZK_API_KEYS_FILE)")).into_response();
+    };
+    if body.label.trim().is_empty() || body.label.len() > 100 {
+        return (StatusCode::BAD_REQUEST, Json("label must be 1-100 characters")).into_response();
+    }
+    if !keys.admit_mint(state.ip_limits.client_ip(peer, &headers)) {
+        return (StatusCode::TOO_MANY_REQUESTS, Json("too many keys minted from this address")).into_response();
+    }
+    match keys.mint(body.label.trim()) {
+        Ok((rec, api_key)) => (StatusCode::CREATED, Json(serde_json::json!({
+            "api_key":      api_key,
+            "id":           rec.id,
+            "scopes":       rec.scopes,
+            "rate_per_min": rec.rate_per_min,
+        }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(format!("{e:#}"))).into_response(),
+    }
+}
+
+/// Middleware for the verify/status routes: validate and rate-limit `x-api-key` when present
+/// (required on verify with `ZK_VERIFY_REQUIRE_KEY`).
+pub async fn require_key<B>(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let Some(keys) = &state.api_keys else { return next.run(req).await };
+    let scope = if req.uri().path() == "/status" { Scope::Status } else { Scope::Verify };
+    if let Err(refused) = keys.check(headers.get("x-api-key").and_then(|v| v.to_str().ok()), scope) {
+        return refused.into_response();
+    }
+    next.run(req).await
+}
@@ -0,0 +1,313 @@
+//! Where KYC status comes from.
+//! Each tenant is bound to one [`AttestationSource`]; the resulting flags are what
+//! the guest proves over, and the attributes it vouches for (if any) are all that
+//! `?policy=` predicates see.  Only `http` (optional `kyc_tier`, `country` and
+//! `sanctioned` in its answer), `circle` (`sanctioned` from the screening) and
+//! `signed` (issuer-signed `attributes`) supply them.  Configured from a TOML file:
+//!
+//! ```toml
+//! [tenants.default]
+//! source = "request"
+//!
+//! [tenants.acme]
+//! source      = "circle"
+//! api_key_env = "CIRCLE_API_KEY"
+//!
+//! [tenants.bank]
+//! source = "http"
+//! url    = "https://kyc.bank.example/status"
+//!
+//! [tenants.ops]
+//! source = "database"
+//! url    = "postgres://kyc@db/kyc"
+//! table  = "kyc_status"
+//! issuer = "ops-kyc"          # trusted issuer (issuers.rs) recorded on this tenant's proofs
+//!
+//! [tenants.wallets]
+//! source = "signed"           # issuer-signed attestation in the request body
+//! ```
+//!
+//! A request only gets its tenant's own source when the tenant is authenticated (prove keys, see
+//! prove_keys.rs); otherwise `x-tenant-id` is just a header, so naming a tenant with its own
+//! source is refused and other requests get `default`'s ([`AttestationRegistry::for_caller`]).
+//! The `circle` and `http` sources give up after `ZK_ATTESTATION_TIMEOUT_MS` (default 5000).
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::{collections::HashMap, path::Path, sync::Arc, time::Duration};
+
+use crate::{issuers::{IssuerTrust, SignedAttestation}, policy::SubjectAttributes, store};
+
+/// Flags handed to the guest.
+#[derive(Clone, Debug)]
+pub struct Attestation {
+    pub kyc:       i32,
+    pub sig_valid: i32,
+    /// Issuer that vouched for the flags, when the source names one itself.
+    pub issuer:    Option<String>,
+    /// Subject attributes the source vouches for; unset ones fail the predicates needing them.
+    pub attributes: SubjectAttributes,
+}
+
+/// What the caller put on the wire; only [`RequestFlags`] trusts the flags, and only
+/// [`SignedSource`] reads `signed`.
+pub struct Subject<'a> {
+    pub wallet:    &'a str,
+    pub kyc:       i32,
+    pub sig_valid: i32,
+    pub signed:    Option<&'a SignedAttestation>,
+}
+
+#[async_trait]
+pub trait AttestationSource: Send + Sync {
+    /// Short name recorded in logs.
+    fn name(&self) -> &'static str;
+
+    async fn attest(&self, subject: &Subject<'_>) -> Result<Attestation>;
+}
+
+/* ---------- request-supplied flags (demo default) ----------------- */
+pub struct RequestFlags;
+
+#[async_trait]
+impl AttestationSource for RequestFlags {
+    fn name(&self) -> &'static str { "request" }
+
+    async fn attest(&self, s: &Subject<'_>) -> Result<Attestation> {
+        Ok(Attestation { kyc: s.kyc, sig_valid: s.sig_valid, issuer: None, attributes: SubjectAttributes::default() })
+    }
+}
+
+/* ---------- issuer-signed attestation ----------------------------- */
+/// Flags signed by a trusted issuer and carried in the request; no network call.
+pub struct SignedSource {
+    trust: Arc<IssuerTrust>,
+}
+
+#[async_trait]
+impl AttestationSource for SignedSource {
+    fn name(&self) -> &'static str { "signed" }
+
+    async fn attest(&self, s: &Subject<'_>) -> Result<Attestation> {
+        let a = s.signed.context("this tenant needs an issuer-signed `attestation` in the request")?;
+        self.trust.verify(a, s.wallet, s.kyc, s.sig_valid, store::now())?;
+        Ok(Attestation {
+            kyc:        s.kyc,
+            sig_valid:  s.sig_valid,
+            issuer:     Some(a.issuer.clone()),
+            attributes: a.attributes.clone().unwrap_or_default(),
+        })
+    }
+}
+
+/* ---------- Circle Compliance Engine ------------------------------ */
+pub struct CircleCompliance {
+    base_url: String,
+    api_key:  String,
+    chain:    String,
+    client:   reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct CircleScreening {
+    data: CircleScreeningData,
+}
+
+#[derive(Deserialize)]
+struct CircleScreeningData {
+    result: String,
+}
+
+#[async_trait]
+impl AttestationSource for CircleCompliance {
+    fn name(&self) -> &'static str { "circle" }
+
+    async fn attest(&self, s: &Subject<'_>) -> Result<Attestation> {
+        let resp: CircleScreening = self.client
+            .post(format!("{}/v1/w3s/compliance/screening/addresses", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&serde_json::json!({
+                "idempotencyKey": uuid::Uuid::new_v4().to_string(),
+                "address":        s.wallet,
+                "chain":          self.chain,
+            }))
+            .send().await.context("Circle screening unreachable")?
+            .error_for_status().context("Circle screening error")?
+            .json().await.context("Circle screening returned malformed body")?;
+
+        /* TLS authenticates the response, so the signature flag follows the verdict */
+        let ok = i32::from(resp.data.result.eq_ignore_ascii_case("APPROVED"));
+        let attributes = SubjectAttributes { sanctioned: Some(ok == 0), ..Default::default() };
+        Ok(Attestation { kyc: ok, sig_valid: ok, issuer: None, attributes })
+    }
+}
+
+/* ---------- generic HTTP source ----------------------------------- */
+/// `GET {url}?wallet=0x…` → `{ "kyc": i32, "sig_valid": i32, "kyc_tier"?, "country"?, "sanctioned"? }`.
+pub struct HttpSource {
+    url:    String,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct HttpFlags {
+    kyc:       i32,
+    sig_valid: i32,
+    #[serde(flatten)]
+    attributes: SubjectAttributes,
+}
+
+#[async_trait]
+impl AttestationSource for HttpSource {
+    fn name(&self) -> &'static str { "http" }
+
+    async fn attest(&self, s: &Subject<'_>) -> Result<Attestation> {
+        let f: HttpFlags = self.client
+            .get(&self.url)
+            .query(&[("wallet", s.wallet)])
+            .send().await.context("attestation source unreachable")?
+            .error_for_status().context("attestation source error")?
+            .json().await.context("attestation source returned malformed body")?;
+        Ok(Attestation { kyc: f.kyc, sig_valid: f.sig_valid, issuer: None, attributes: f.attributes })
+    }
+}
+
+/* ---------- database table ---------------------------------------- */
+/// `SELECT kyc, sig_valid FROM <table> WHERE wallet = $1`; no row → not approved.
+pub struct DatabaseSource {
+    pool:  sqlx::PgPool,
+    query: String,
+}
+
+#[async_trait]
+impl AttestationSource for DatabaseSource {
+    fn name(&self) -> &'static str { "database" }
+
+    async fn attest(&self, s: &Subject<'_>) -> Result<Attestation> {
+        let row: Option<(i32, i32)> = sqlx::query_as(&self.query)
+            .bind(s.wallet)
+            .fetch_optional(&self.pool).await
+            .context("attestation table query failed")?;
+        let (kyc, sig_valid) = row.unwrap_or((0, 0));
+        Ok(Attestation { kyc, sig_valid, issuer: None, attributes: SubjectAttributes::default() })
+    }
+}
+
+/* ---------- per-tenant configuration ------------------------------ */
+#[derive(Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+enum SourceConfig {
+    Request,
+    Circle {
+        #[serde(default = "default_circle_url")]
+        base_url:    String,
+        api_key_env: String,
+        #[serde(default = "default_chain")]
+        chain:       String,
+    },
+    Http { url: String },
+    Database { url: String, table: String },
+    Signed,
+}
+
+#[derive(Deserialize)]
+struct TenantConfig {
+    #[serde(flatten)]
+    source: SourceConfig,
+    /// Trusted issuer behind this tenant's source.
+    issuer: Option<String>,
+}
+fn default_circle_url() -> String { "https://api.circle.com".into() }
+fn default_chain() -> String { "ETH".into() }
+
+#[derive(Deserialize, Default)]
+struct AttestationFile {
+    #[serde(default)]
+    tenants: HashMap<String, TenantConfig>,
+}
+
+/// Tenant → source.  Unknown tenants fall back to `default`, then to [`RequestFlags`].
+pub struct AttestationRegistry {
+    sources:  HashMap<String, Arc<dyn AttestationSource>>,
+    fallback: Arc<dyn AttestationSource>,
+    issuers:  HashMap<String, String>,
+}
+
+impl AttestationRegistry {
+    pub fn load(path: &Path, trust: &Arc<IssuerTrust>) -> Result<Self> {
+        let file: AttestationFile = if path.exists() {
+            let raw = std::fs::read_to_string(path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            toml::from_str(&raw).with_context(|| format!("parsing {}", path.display()))?
+        } else {
+            AttestationFile::default()
+        };
+
+        let timeout = std::env::var("ZK_ATTESTATION_TIMEOUT_MS").ok()
+            .map(|v| v.parse().context("ZK_ATTESTATION_TIMEOUT_MS must be a number"))
+            .transpose()?
+            .map_or(Duration::from_secs(5), Duration::from_millis);
+        let client = reqwest::Client::builder()
+            .connect_timeout(timeout)
+            .timeout(timeout)
+            .build()?;
+        let mut sources: HashMap<String, Arc<dyn AttestationSource>> = HashMap::new();
+        let mut issuers = HashMap::new();
+        for (tenant, TenantConfig { source: cfg, issuer }) in file.tenants {
+            if let Some(id) = issuer {
+                if trust.get(&id).is_none() {
+                    bail!("tenant {tenant}: issuer {id} is not in the issuer trust store");
+                }
+                if matches!(cfg, SourceConfig::Signed) {
+                    bail!("tenant {tenant}: signed attestations name their own issuer");
+                }
+                issuers.insert(tenant.clone(), id);
+            }
+            let src: Arc<dyn AttestationSource> = match cfg {
+                SourceConfig::Request => Arc::new(RequestFlags),
+                SourceConfig::Circle { base_url, api_key_env, chain } => Arc::new(CircleCompliance {
+                    base_url,
+                    api_key: std::env::var(&api_key_env)
+                        .map_err(|_| anyhow!("tenant {tenant}: ${api_key_env} not set"))?,
+                    chain,
+                    client: client.clone(),
+                }),
+                SourceConfig::Http { url } => Arc::new(HttpSource { url, client: client.clone() }),
+                SourceConfig::Database { url, table } => {
+                    if !table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+                        bail!("tenant {tenant}: invalid table name {table}");
+                    }
+                    Arc::new(DatabaseSource {
+                        pool:  sqlx::PgPool::connect_lazy(&url)?,
+                        query: format!("SELECT kyc, sig_valid FROM {table} WHERE wallet = $1"),
+                    })
+                }
+                SourceConfig::Signed => Arc::new(SignedSource { trust: trust.clone() }),
+            };
+            tracing::info!("tenant {tenant}: attestation source {}", src.name());
+            sources.insert(tenant, src);
+        }
+        let fallback = sources.get("default").cloned().unwrap_or_else(|| Arc::new(RequestFlags));
+        Ok(Self { sources, fallback, issuers })
+    }
+
+    pub fn for_tenant(&self, tenant: &str) -> &Arc<dyn AttestationSource> {
+        self.sources.get(tenant).unwrap_or(&self.fallback)
+    }
+
+    /// Source for a request made as `tenant`.  Unless the tenant is `authenticated`, a tenant with
+    /// a source of its own is refused: anyone could name it to pick, say, a `request` source.
+    pub fn for_caller(&self, tenant: &str, authenticated: bool) -> Result<&Arc<dyn AttestationSource>> {
+        if !authenticated && tenant != "default" && self.sources.contains_key(tenant) {
+            bail!("tenant {tenant} has its own attestation source and needs a prove key");
+        }
+        Ok(self.for_tenant(tenant))
+    }
+
+    /// Configured issuer behind `tenant`'s source (following the same fallback).
+    pub fn issuer_for(&self, tenant: &str) -> Option<&str> {
+        let key = if self.sources.contains_key(tenant) { tenant } else { "default" };
+        self.issuers.get(key).map(String::as_str)
+    }
+}
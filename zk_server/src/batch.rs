@@ -0,0 +1,155 @@
+//! `POST /prove/batch[?policy=name][&full_proof=true][&proof_encoding=…]`: many subjects at once.
+//! The body is a JSON array of `/prove` bodies (at most `ZK_BATCH_MAX`, default 500) sharing one
+//! `step`, so every item uses the same Nova parameters.  Items are proven concurrently, up to
+//! the pool's capacity, and each goes through the same checks, quota and storage as a single
+//! `/prove`, and counts against the prove key's rate; a busy pool makes items wait rather than fail.  The answer is `200` with one result
+//! per item, in order (`status` plus `result` or `error`), and aggregate `stats`.
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::{ConnectInfo, Extension, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::{net::SocketAddr, sync::Arc, time::{Duration, Instant}};
+use tokio::sync::Semaphore;
+use utoipa::ToSchema;
+
+use crate::{
+    count_failure, error_response, errors::{self, ApiError, ErrorCode}, pool::Overloaded, prove, prove_keys::ProveKey, request,
+    tenant_of, with_full_proof, AppState, ProveParams,
+};
+
+#[derive(Serialize, ToSchema)]
+pub struct ItemResult {
+    index:  usize,
+    status: u16,
+    /// The `/prove` response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    result: Option<Value>,
+    /// The error body `/prove` would have answered with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    error:  Option<Value>,
+}
+
+#[derive(Default, Serialize, ToSchema)]
+pub struct BatchStats {
+    items:             usize,
+    proved:            usize,
+    failed:            usize,
+    step:              usize,
+    wall_sec:          f64,
+    /// Parameter setup, paid by the first item only unless it was already warm.
+    setup_sec:         f64,
+    prove_sec_total:   f64,
+    prove_sec_avg:     Option<f64>,
+    proof_bytes_total: usize,
+}
+
+fn max_items() -> usize {
+    std::env::var("ZK_BATCH_MAX").ok().and_then(|v| v.parse().ok()).unwrap_or(500)
+}
+
+/// Body of the answer, for the OpenAPI document.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct BatchResponse {
+    items: Vec<ItemResult>,
+    stats: BatchStats,
+}
+
+#[utoipa::path(
+    post, path = "/prove/batch", tag = "proving",
+    params(ProveParams),
+    request_body = Vec<crate::request::ProveRequest>,
+    responses(
+        (status = 200, description = "One result per item, in order, and aggregate stats", body = BatchResponse),
+        (status = 400, description = "Empty, too large, or mixed step sizes", body = crate::openapi::ErrorBody),
+        (status = 429, description = "Over the prove key's rate, counting one per item", body = String),
+    ),
+    security((), ("prove_key" = [])),
+)]
+pub async fn handle_batch(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Query(params): Query<ProveParams>,
+    key: Option<Extension<ProveKey>>,
+    headers: HeaderMap,
+    Json(items): Json<Vec<Value>>,
+) -> Response {
+    let tenant = tenant_of(&headers);
+    let caller = state.ip_limits.client_ip(peer, &headers);
+    let step = match check(&items) {
+        Ok(step) => step,
+        Err(e)   => return error_response(e),
+    };
+    // Each item is a proof started, so each counts against the prove key's rate.
+    if let (Some(keys), Some(Extension(key))) = (&state.prove_keys, key) {
+        if let Err(refused) = keys.take_batch(&key, items.len()) {
+            return refused.into_response();
+        }
+    }
+    let t0 = Instant::now();
+    let slots = Arc::new(Semaphore::new(state.load.capacity()));
+    let tasks: Vec<_> = items.into_iter().map(|body| {
+        let (state, slots, tenant, policy) = (state.clone(), slots.clone(), tenant.clone(), params.policy.clone());
+        tokio::spawn(async move {
+            let _slot = slots.acquire_owned().await;
+            loop {
+                let req = request::parse_prove(body.clone())?;
+                match prove(&state, caller, &tenant, policy.as_deref(), None, req, None).await {
+                    Err(e) if e.downcast_ref::<Overloaded>().is_some() => {
+                        let wait = e.downcast_ref::<Overloaded>().map_or(1, |o| o.retry_after.max(1));
+                        tokio::time::sleep(Duration::from_secs(wait)).await;
+                    }
+                    res => break res,
+                }
+            }
+        })
+    }).collect();
+
+    let mut stats = BatchStats { items: tasks.len(), step, ..BatchStats::default() };
+    let mut results = Vec::with_capacity(tasks.len());
+    for (index, task) in tasks.into_iter().enumerate() {
+        let res = task.await.unwrap_or_else(|e| Err(anyhow::anyhow!("batch item task failed: {e}")));
+        results.push(match res {
+            Ok(resp) => {
+                stats.proved += 1;
+                stats.setup_sec += resp.setup_sec;
+                stats.prove_sec_total += resp.prove_sec;
+                stats.proof_bytes_total += resp.proof_len;
+                let resp = with_full_proof(resp, &params);
+                ItemResult { index, status: StatusCode::OK.as_u16(), result: serde_json::to_value(resp).ok(), error: None }
+            }
+            Err(err) => {
+                stats.failed += 1;
+                count_failure(&state, &err);
+                let (status, body, _) = errors::describe(&err);
+                ItemResult { index, status: status.as_u16(), result: None, error: Some(body) }
+            }
+        });
+    }
+    stats.wall_sec = t0.elapsed().as_secs_f64();
+    stats.prove_sec_avg = (stats.proved > 0).then(|| stats.prove_sec_total / stats.proved as f64);
+    tracing::info!(%tenant, items = stats.items, proved = stats.proved, wall_sec = stats.wall_sec, "batch proved");
+    (StatusCode::OK, Json(serde_json::json!({ "items": results, "stats": stats }))).into_response()
+}
+
+/// Size and shared step of a batch; items that do not parse fail on their own later.
+fn check(items: &[Value]) -> Result<usize> {
+    let max = max_items();
+    if items.is_empty() || items.len() > max {
+        return Err(ApiError::new(ErrorCode::InvalidRequest, format!("a batch holds 1 to {max} items (ZK_BATCH_MAX)")).into());
+    }
+    let mut steps = items.iter().filter_map(|b| request::parse_prove(b.clone()).ok()).map(|r| r.step);
+    let step = steps.next().context("no batch item parses as a /prove body")?;
+    if steps.any(|s| s != step) {
+        return Err(ApiError::new(ErrorCode::InvalidRequest, "all batch items must use the same step").into());
+    }
+    Ok(step)
+}
@@ -0,0 +1,264 @@
+//! zk_loadgen [profile.toml]
+//! Synthetic prove/verify traffic against a zk_server, for capacity planning.
+//! Runs each stage of the profile in turn at its concurrency (a ramp is a list of stages), picks
+//! prove or verify per request by the configured mix, and prints latency percentiles and error
+//! rates per operation and per stage.
+//!
+//! ```toml
+//! target  = "http://127.0.0.1:8080"
+//! wallets = "wallets.txt"      # one subject per line; random addresses when omitted
+//! step    = 8
+//! tenant  = "loadtest"         # sent as x-tenant-id
+//! prove_key_env = "ZK_LOADGEN_KEY"   # env var holding a prove key, sent as a bearer token
+//!
+//! [mix]
+//! prove  = 9
+//! verify = 1                   # re-verifies proofs produced earlier in the run
+//!
+//! [[stages]]
+//! concurrency   = 2
+//! duration_secs = 60
+//!
+//! [[stages]]
+//! concurrency   = 8
+//! duration_secs = 120
+//! ```
+
+use anyhow::{bail, Context, Result};
+use rand::{seq::SliceRandom, Rng};
+use serde::Deserialize;
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/* ---------- profile ---------------------------------------------- */
+#[derive(Deserialize)]
+struct Profile {
+    #[serde(default = "default_target")]
+    target:  String,
+    #[serde(default)]
+    wallets: Option<PathBuf>,
+    #[serde(default = "default_step")]
+    step:    usize,
+    #[serde(default)]
+    tenant:  Option<String>,
+    /// Environment variable holding a prove key (kept out of the profile file).
+    #[serde(default)]
+    prove_key_env: Option<String>,
+    #[serde(default)]
+    mix:     Mix,
+    #[serde(default = "default_stages")]
+    stages:  Vec<Stage>,
+}
+fn default_target() -> String { "http://127.0.0.1:8080".into() }
+fn default_step() -> usize { 8 }
+fn default_stages() -> Vec<Stage> { vec![Stage { concurrency: 1, duration_secs: 30 }] }
+
+#[derive(Deserialize)]
+struct Mix {
+    #[serde(default)]
+    prove:  u32,
+    #[serde(default)]
+    verify: u32,
+}
+impl Default for Mix {
+    fn default() -> Self { Mix { prove: 1, verify: 0 } }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+struct Stage {
+    concurrency:   usize,
+    duration_secs: u64,
+}
+
+/* ---------- results ---------------------------------------------- */
+#[derive(Clone, Copy, PartialEq)]
+enum Op { Prove, Verify }
+
+#[derive(Default)]
+struct Samples {
+    prove_ok:  Vec<f64>,
+    verify_ok: Vec<f64>,
+    prove_err:  u64,
+    verify_err: u64,
+}
+
+impl Samples {
+    fn record(&mut self, op: Op, secs: f64, ok: bool) {
+        match (op, ok) {
+            (Op::Prove,  true)  => self.prove_ok.push(secs),
+            (Op::Verify, true)  => self.verify_ok.push(secs),
+            (Op::Prove,  false) => self.prove_err += 1,
+            (Op::Verify, false) => self.verify_err += 1,
+        }
+    }
+}
+
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() { return 0.0; }
+    let i = ((p / 100.0) * (sorted.len() - 1) as f64).round() as usize;
+    sorted[i]
+}
+
+fn report(name: &str, ok: &[f64], err: u64, elapsed: f64) {
+    let total = ok.len() as u64 + err;
+    if total == 0 { return; }
+    let mut s = ok.to_vec();
+    s.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    println!(
+        "  {name:<6} n={total:<6} rps={:<7.2} err={:.1}%  p50={:.3}s p90={:.3}s p99={:.3}s max={:.3}s",
+        total as f64 / elapsed,
+        100.0 * err as f64 / total as f64,
+        percentile(&s, 50.0), percentile(&s, 90.0), percentile(&s, 99.0),
+        s.last().copied().unwrap_or(0.0),
+    );
+}
+
+/* ---------- traffic ---------------------------------------------- */
+struct Ctx {
+    http:    reqwest::Client,
+    profile: Profile,
+    wallets: Vec<String>,
+    /// Proofs produced during the run, replayed by verify requests.
+    proofs:  Mutex<Vec<serde_json::Value>>,
+}
+
+fn random_wallet() -> String {
+    let bytes: [u8; 20] = rand::thread_rng().gen();
+    format!("0x{}", hex::encode(bytes))
+}
+
+impl Ctx {
+    fn pick_op(&self) -> Op {
+        let Mix { prove, verify } = self.profile.mix;
+        let have_proofs = !self.proofs.lock().unwrap().is_empty();
+        if verify == 0 || !have_proofs { return Op::Prove; }
+        if rand::thread_rng().gen_range(0..prove + verify) < prove { Op::Prove } else { Op::Verify }
+    }
+
+    fn post(&self, path: &str) -> reqwest::RequestBuilder {
+        let mut req = self.http.post(format!("{}{path}", self.profile.target.trim_end_matches('/')));
+        if let Some(t) = &self.profile.tenant {
+            req = req.header("x-tenant-id", t);
+        }
+        if let Some(key) = self.profile.prove_key_env.as_ref().and_then(|k| std::env::var(k).ok()) {
+            req = req.bearer_auth(key);
+        }
+        req
+    }
+
+    async fn prove(&self) -> Result<()> {
+        let wallet = self.wallets.choose(&mut rand::thread_rng())
+            .cloned()
+            .unwrap_or_else(random_wallet);
+        let resp = self.post("/prove?full_proof=true")
+            .json(&serde_json::json!({
+                "wallet": wallet, "kyc": 1, "sig_valid": 1, "step": self.profile.step,
+            }))
+            .send().await?
+            .error_for_status()?;
+        let body: serde_json::Value = resp.json().await?;
+        let mut proofs = self.proofs.lock().unwrap();
+        if proofs.len() < 256 {
+            proofs.push(serde_json::json!({
+                "circuit":         body["circuit"],
+                "circuit_version": body["circuit_version"],
+                "step":            self.profile.step,
+                "public_inputs":   body["public_inputs"],
+                "proof":           body["proof"],
+                "instance":        body["instance"],
+            }));
+        }
+        Ok(())
+    }
+
+    async fn verify(&self) -> Result<()> {
+        let proof = self.proofs.lock().unwrap()
+            .choose(&mut rand::thread_rng())
+            .cloned()
+            .context("no proofs to verify yet")?;
+        self.post("/verify").json(&proof).send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+async fn worker(ctx: Arc<Ctx>, until: Instant, samples: Arc<Mutex<Samples>>) {
+    while Instant::now() < until {
+        let op = ctx.pick_op();
+        let t0 = Instant::now();
+        let res = match op {
+            Op::Prove  => ctx.prove().await,
+            Op::Verify => ctx.verify().await,
+        };
+        if let Err(e) = &res {
+            tracing::debug!("{} failed: {e:#}", if op == Op::Prove { "prove" } else { "verify" });
+        }
+        samples.lock().unwrap().record(op, t0.elapsed().as_secs_f64(), res.is_ok());
+    }
+}
+
+/* ---------- main ------------------------------------------------- */
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
+
+    let path = std::env::args().nth(1).unwrap_or_else(|| "loadgen.toml".into());
+    let profile: Profile = match std::fs::read_to_string(&path) {
+        Ok(raw) => toml::from_str(&raw).with_context(|| format!("parsing {path}"))?,
+        Err(_)  => toml::from_str("")?,
+    };
+    if profile.mix.prove + profile.mix.verify == 0 {
+        bail!("mix must have a non-zero weight");
+    }
+    let wallets = match &profile.wallets {
+        Some(p) => std::fs::read_to_string(p)
+            .with_context(|| format!("reading {}", p.display()))?
+            .lines().map(str::trim).filter(|l| !l.is_empty()).map(String::from).collect(),
+        None => Vec::new(),
+    };
+
+    println!("target {}  wallets {}  step {}  mix prove:{} verify:{}",
+             profile.target, wallets.len(), profile.step, profile.mix.prove, profile.mix.verify);
+    let stages = profile.stages.clone();
+    let ctx = Arc::new(Ctx {
+        http: reqwest::Client::builder().timeout(Duration::from_secs(600)).build()?,
+        profile,
+        wallets,
+        proofs: Mutex::new(Vec::new()),
+    });
+
+    let total = Arc::new(Mutex::new(Samples::default()));
+    let run_start = Instant::now();
+    for (i, stage) in stages.iter().enumerate() {
+        let samples = Arc::new(Mutex::new(Samples::default()));
+        let start = Instant::now();
+        let until = start + Duration::from_secs(stage.duration_secs);
+        let workers: Vec<_> = (0..stage.concurrency)
+            .map(|_| tokio::spawn(worker(ctx.clone(), until, samples.clone())))
+            .collect();
+        for w in workers { w.await?; }
+
+        let s = std::mem::take(&mut *samples.lock().unwrap());
+        let elapsed = start.elapsed().as_secs_f64();
+        println!("stage {} (concurrency {}, {:.0}s)", i + 1, stage.concurrency, elapsed);
+        report("prove",  &s.prove_ok,  s.prove_err,  elapsed);
+        report("verify", &s.verify_ok, s.verify_err, elapsed);
+
+        let mut t = total.lock().unwrap();
+        t.prove_ok.extend(s.prove_ok);
+        t.verify_ok.extend(s.verify_ok);
+        t.prove_err  += s.prove_err;
+        t.verify_err += s.verify_err;
+    }
+
+    let t = total.lock().unwrap();
+    let elapsed = run_start.elapsed().as_secs_f64();
+    println!("total ({:.0}s)", elapsed);
+    report("prove",  &t.prove_ok,  t.prove_err,  elapsed);
+    report("verify", &t.verify_ok, t.verify_err, elapsed);
+    Ok(())
+}
@@ -0,0 +1,99 @@
+//! Bloom filters in front of the revocation / nullifier shards.
+//! A negative answer is definitive, so the common case — a proof that is neither revoked nor
+//! spent — is answered from memory; only positives (true or ~1% false) read the shard.  Filters
+//! are sized for `ZK_BLOOM_CAPACITY` keys (default 1M) and rebuilt from the shards every
+//! `ZK_BLOOM_REBUILD_SECS` (default 3600) to shed stale bits and pick up external edits.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::RwLock,
+};
+
+/// Target false-positive rate at capacity.
+const FP_RATE: f64 = 0.01;
+
+#[derive(Clone)]
+pub struct Bloom {
+    bits:   Vec<u64>,
+    nbits:  u64,
+    hashes: u32,
+}
+
+impl Bloom {
+    pub fn with_capacity(capacity: usize) -> Self {
+        let n = capacity.max(1) as f64;
+        let m = (-(n * FP_RATE.ln()) / (2f64.ln().powi(2))).ceil().max(64.0) as u64;
+        let k = ((m as f64 / n) * 2f64.ln()).round().clamp(1.0, 16.0) as u32;
+        Bloom { bits: vec![0; ((m + 63) / 64) as usize], nbits: m, hashes: k }
+    }
+
+    /// Double hashing: probe i is h1 + i·h2.
+    fn probes(&self, key: &str) -> impl Iterator<Item = u64> + '_ {
+        let h = |seed: u64| {
+            let mut s = DefaultHasher::new();
+            (seed, key).hash(&mut s);
+            s.finish()
+        };
+        let (h1, h2) = (h(0x9e37), h(0x85eb) | 1);
+        (0..self.hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % self.nbits)
+    }
+
+    pub fn insert(&mut self, key: &str) {
+        let probes: Vec<u64> = self.probes(key).collect();
+        for b in probes {
+            self.bits[(b / 64) as usize] |= 1 << (b % 64);
+        }
+    }
+
+    pub fn may_contain(&self, key: &str) -> bool {
+        self.probes(key).all(|b| self.bits[(b / 64) as usize] & (1 << (b % 64)) != 0)
+    }
+
+    fn union(&mut self, other: &Bloom) {
+        for (a, b) in self.bits.iter_mut().zip(&other.bits) {
+            *a |= b;
+        }
+    }
+}
+
+/// A filter that can be rebuilt while in use: inserts made during a rebuild are
+/// kept aside and folded into the new filter before it is swapped in.
+pub struct LiveBloom {
+    capacity: usize,
+    inner:    RwLock<(Bloom, Option<Bloom>)>,
+}
+
+impl LiveBloom {
+    pub fn new(capacity: usize) -> Self {
+        LiveBloom { capacity, inner: RwLock::new((Bloom::with_capacity(capacity), None)) }
+    }
+
+    pub fn insert(&self, key: &str) {
+        let mut g = self.inner.write().unwrap();
+        g.0.insert(key);
+        if let Some(pending) = &mut g.1 {
+            pending.insert(key);
+        }
+    }
+
+    pub fn may_contain(&self, key: &str) -> bool {
+        self.inner.read().unwrap().0.may_contain(key)
+    }
+
+    /// Replace the filter with one built from `keys` (the authoritative set).
+    pub fn rebuild(&self, keys: impl FnOnce(&mut dyn FnMut(&str)) -> anyhow::Result<()>) -> anyhow::Result<usize> {
+        self.inner.write().unwrap().1 = Some(Bloom::with_capacity(self.capacity));
+        let mut fresh = Bloom::with_capacity(self.capacity);
+        let mut n = 0;
+        let res = keys(&mut |k| { fresh.insert(k); n += 1; });
+        let mut g = self.inner.write().unwrap();
+        let pending = g.1.take();
+        res?;
+        if let Some(p) = pending {
+            fresh.union(&p);
+        }
+        g.0 = fresh;
+        Ok(n)
+    }
+}
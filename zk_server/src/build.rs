@@ -0,0 +1,7 @@
+// Generates the gRPC service (grpc.rs) from proto/zkkyc.proto.  Needs `protoc` on PATH (or PROTOC).
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tonic_build::configure()
+        .build_client(false)
+        .compile(&["proto/zkkyc.proto"], &["proto"])?;
+    Ok(())
+}
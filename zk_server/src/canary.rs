@@ -0,0 +1,166 @@
+//! Canary dual-proving.
+//! For `canary_percent` of requests (rollout.toml) the same guest call is proven a second time,
+//! off the request path, against the other registered guest build.  Outcomes, proof sizes and
+//! timings are compared; divergences are logged and kept for `GET /admin/canary`.
+//! Canary proofs take a slot in the prove pool like any other; when the pool is saturated the
+//! comparison is skipped rather than queued behind real traffic.
+
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{pool::ProvePool, prover::{ParamCache, ProofRun}, rollout::GuestVersion, worker::{Isolation, Priority}};
+
+/// Reports kept in memory.
+const RECENT: usize = 100;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Outcome {
+    pub version:    String,
+    pub ok:         bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error:      Option<String>,
+    pub prove_sec:  f64,
+    pub verify_sec: f64,
+    pub proof_len:  usize,
+}
+
+impl Outcome {
+    pub fn from_run(version: &str, run: &anyhow::Result<ProofRun>) -> Self {
+        match run {
+            Ok(r) => Outcome {
+                version:    version.to_string(),
+                ok:         true,
+                error:      None,
+                prove_sec:  r.prove_sec,
+                verify_sec: r.verify_sec,
+                proof_len:  r.proof.len(),
+            },
+            Err(e) => Outcome {
+                version:    version.to_string(),
+                ok:         false,
+                error:      Some(e.to_string()),
+                prove_sec:  0.0,
+                verify_sec: 0.0,
+                proof_len:  0,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CanaryReport {
+    pub at:          u64,
+    pub circuit:     String,
+    pub step:        usize,
+    pub baseline:    Outcome,
+    pub candidate:   Outcome,
+    pub divergences: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CanaryStats {
+    pub runs:        u64,
+    pub divergent:   u64,
+    pub recent:      VecDeque<CanaryReport>,
+}
+
+#[derive(Default)]
+pub struct Canary {
+    stats: Mutex<CanaryStats>,
+}
+
+/// Everything needed to re-run the baseline call on another build.
+pub struct CanaryJob {
+    pub circuit:      &'static str,
+    pub args:         Vec<String>,
+    pub step:         usize,
+    pub candidate:    GuestVersion,
+    pub max_slowdown: f64,
+    pub isolation:    Isolation,
+    pub params:       Arc<ParamCache>,
+}
+
+impl Canary {
+    pub fn stats(&self) -> CanaryStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Prove `job` in `pool` and record the comparison with `baseline`.
+    pub fn spawn(self: &Arc<Self>, pool: &Arc<ProvePool>, job: CanaryJob, baseline: Outcome) {
+        let (this, pool) = (Arc::clone(self), Arc::clone(pool));
+        tokio::spawn(async move {
+            if pool.busy() {
+                tracing::debug!(circuit = job.circuit, "canary skipped: prove pool saturated");
+                return;
+            }
+            let (isolation, params, args) = (job.isolation.clone(), job.params.clone(), job.args.clone());
+            let wasm: PathBuf = job.candidate.wasm.clone();
+            let (circuit, step) = (job.circuit, job.step);
+            let run = match pool.run(move || isolation.run(&params, &wasm, circuit, args, step, Priority::Background)).await {
+                Ok(run) => run,
+                Err(e)  => {
+                    tracing::debug!(circuit, "canary skipped: {e}");
+                    return;
+                }
+            };
+            let candidate = Outcome::from_run(&job.candidate.version, &run);
+            let divergences = compare(&baseline, &candidate, job.max_slowdown);
+            if !divergences.is_empty() {
+                tracing::warn!(
+                    circuit = job.circuit,
+                    baseline = %baseline.version,
+                    candidate = %candidate.version,
+                    "canary divergence: {}", divergences.join("; ")
+                );
+            }
+            this.record(CanaryReport {
+                at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+                circuit: job.circuit.to_string(),
+                step: job.step,
+                baseline,
+                candidate,
+                divergences,
+            });
+        });
+    }
+
+    fn record(&self, report: CanaryReport) {
+        let mut s = self.stats.lock().unwrap();
+        s.runs += 1;
+        if !report.divergences.is_empty() {
+            s.divergent += 1;
+        }
+        if s.recent.len() == RECENT {
+            s.recent.pop_front();
+        }
+        s.recent.push_back(report);
+    }
+}
+
+fn compare(a: &Outcome, b: &Outcome, max_slowdown: f64) -> Vec<String> {
+    let mut d = Vec::new();
+    if a.ok != b.ok {
+        d.push(format!("outcome {} vs {}", ok_str(a), ok_str(b)));
+    }
+    if a.ok && b.ok {
+        if a.proof_len != b.proof_len {
+            d.push(format!("proof_len {} vs {}", a.proof_len, b.proof_len));
+        }
+        if a.prove_sec > 0.0 && b.prove_sec / a.prove_sec > max_slowdown {
+            d.push(format!("prove_sec {:.3} vs {:.3}", a.prove_sec, b.prove_sec));
+        }
+        if a.verify_sec > 0.0 && b.verify_sec / a.verify_sec > max_slowdown {
+            d.push(format!("verify_sec {:.3} vs {:.3}", a.verify_sec, b.verify_sec));
+        }
+    }
+    d
+}
+
+fn ok_str(o: &Outcome) -> &'static str {
+    if o.ok { "ok" } else { "failed" }
+}
@@ -0,0 +1,193 @@
+//! Verifier challenges, so a proof made for one relying party can't be replayed to another.
+//!
+//! 1. The relying party calls `GET /challenge` and gets `{ nonce, expires_at }`: 32 random bytes,
+//!    live for `ZK_CHALLENGE_TTL_SECS` (default 300).
+//! 2. The holder sends the nonce as `challenge` in the `/prove` body.  The proof is made with the
+//!    `_nonce` export of its circuit, which takes the nonce's 8 limbs as public inputs after the
+//!    other arguments.  Only the 8-limb circuits have one; legacy-limb requests can't carry a
+//!    challenge.
+//! 3. The relying party posts the proof to `POST /verify?challenge=<nonce>`.  It is valid only if
+//!    the SNARK verifies, the proof's nonce is this challenge, and the challenge is still live here.
+//!    The challenge is spent by that check, so it accepts one proof once.
+//!
+//! The pinned zkEngine instance doesn't expose the guest's arguments, so the SNARK alone doesn't
+//! say which nonce a proof was made with.  The nonce is therefore taken from this server's record
+//! of the proof, found by its instance ([`crate::store::ProofStore::proof_by_instance`]), never
+//! from `public_inputs` sent with it: a proof this server has no record of can't redeem a
+//! challenge, and one posted with `public_inputs` other than those it was proven over is refused
+//! ([`proven_nonce`]).  Challenges therefore need the proof store.
+//!
+//! A relying party that knows the holder's wallet can send it as `GET /challenge?wallet=…`
+//! (and `&policy=…`) to have the proof started speculatively (see preissue.rs).
+//!
+//! `ZK_REQUIRE_CHALLENGE=true` refuses `/prove` requests without a challenge.  Challenges live in
+//! memory on the instance that issued them, so relying parties verify on the instance (or replica)
+//! they got the challenge from.  At most `MAX_OPEN` are kept at once.
+
+use anyhow::{anyhow, bail, Context, Result};
+use axum::{extract::{Query, State}, http::StatusCode, response::{IntoResponse, Response}, Json};
+use serde::Deserialize;
+use std::{collections::HashMap, sync::{Arc, Mutex}};
+
+use crate::{preissue, store, AppState};
+
+const MAX_OPEN: usize = 100_000;
+
+pub struct Challenges {
+    ttl:          u64,
+    /// `ZK_REQUIRE_CHALLENGE`: `/prove` needs a challenge.
+    pub required: bool,
+    /// Live nonces and when they expire.
+    open:         Mutex<HashMap<[u8; 32], u64>>,
+}
+
+impl Challenges {
+    pub fn from_env() -> Self {
+        let ttl = std::env::var("ZK_CHALLENGE_TTL_SECS").ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300);
+        let required = matches!(std::env::var("ZK_REQUIRE_CHALLENGE").as_deref(), Ok("1" | "true"));
+        Challenges { ttl, required, open: Mutex::new(HashMap::new()) }
+    }
+
+    /// A new nonce and its expiry; `None` when too many are open.
+    fn issue(&self) -> Option<([u8; 32], u64)> {
+        let now = store::now();
+        let mut open = self.open.lock().unwrap();
+        open.retain(|_, exp| *exp > now);
+        if open.len() >= MAX_OPEN {
+            return None;
+        }
+        let (nonce, expires_at) = (rand::random::<[u8; 32]>(), now + self.ttl);
+        open.insert(nonce, expires_at);
+        Some((nonce, expires_at))
+    }
+
+    /// Spend `challenge` for a proof whose nonce input is `proven` (`0x`-hex, from
+    /// [`kyc_core::public_inputs::decode`]).
+    pub fn redeem(&self, challenge: &str, proven: Option<&str>) -> Result<()> {
+        let nonce = parse(challenge)?;
+        let proven = proven.ok_or_else(|| anyhow!("the proof carries no challenge nonce"))?;
+        if parse(proven)? != nonce {
+            bail!("the proof was made for another challenge");
+        }
+        let mut open = self.open.lock().unwrap();
+        match open.remove(&nonce) {
+            Some(exp) if exp > store::now() => Ok(()),
+            Some(_) => bail!("challenge expired"),
+            None    => bail!("unknown or already used challenge"),
+        }
+    }
+}
+
+/// The nonce a proof was made with, from `recorded` (the circuit and public inputs this server
+/// proved it over).  `supplied` inputs sent alongside the proof must match the record.
+pub fn proven_nonce(
+    recorded: Option<(&str, &[String])>,
+    supplied: Option<(&str, &[String])>,
+) -> Result<Option<String>> {
+    let (name, args) = recorded.ok_or_else(|| anyhow!("this server has no record of the proof"))?;
+    if supplied.map_or(false, |s| s != (name, args)) {
+        bail!("the public inputs sent are not those the proof was made with");
+    }
+    let circuit = crate::policy::circuit(name).ok_or_else(|| anyhow!("unknown circuit {name}"))?;
+    Ok(kyc_core::public_inputs::decode(circuit.layout(), args)?.nonce)
+}
+
+/// A nonce as sent by clients: 64 hex characters, `0x` optional, not all zero.
+pub fn parse(nonce: &str) -> Result<[u8; 32]> {
+    let raw = hex::decode(nonce.trim_start_matches("0x")).context("challenge is not hex")?;
+    let nonce: [u8; 32] = raw.try_into().map_err(|_| anyhow!("challenge must be 32 bytes"))?;
+    if nonce == [0; 32] {
+        bail!("challenge must not be zero");
+    }
+    Ok(nonce)
+}
+
+#[derive(Deserialize)]
+pub struct ChallengeParams {
+    /// Holder's wallet, to start proving for speculatively.
+    wallet: Option<String>,
+    /// Policy the holder will prove under.
+    policy: Option<String>,
+}
+
+/// `GET /challenge`: a fresh nonce for a holder to prove against.
+pub async fn handle_challenge(State(state): State<Arc<AppState>>, Query(q): Query<ChallengeParams>) -> Response {
+    match state.challenges.issue() {
+        Some((nonce, expires_at)) => {
+            let nonce = format!("0x{}", hex::encode(nonce));
+            if let Some(wallet) = q.wallet.filter(|_| state.store.is_some() && !state.read_only) {
+                preissue::speculate(state.clone(), wallet, q.policy, nonce.clone(), expires_at);
+            }
+            Json(serde_json::json!({ "nonce": nonce, "expires_at": expires_at })).into_response()
+        }
+        None => (StatusCode::SERVICE_UNAVAILABLE, Json("too many open challenges")).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kyc_core::encoding::{digest_words, guest_args};
+
+    fn challenges(ttl: u64) -> Challenges {
+        Challenges { ttl, required: false, open: Mutex::new(HashMap::new()) }
+    }
+
+    /// `check_kyc_full_nonce` arguments proven with `nonce`.
+    fn args(nonce: &[u8; 32]) -> Vec<String> {
+        let mut a = guest_args(&digest_words(&[0xab; 32]));
+        a.extend(["1".to_string(), "1".to_string()]);
+        a.extend(guest_args(&digest_words(nonce)));
+        a
+    }
+
+    #[test]
+    fn nonce_comes_from_the_record() {
+        let (n, args) = ([7; 32], args(&[7; 32]));
+        let recorded = Some(("check_kyc_full_nonce", &args[..]));
+        let nonce = proven_nonce(recorded, None).unwrap();
+        assert_eq!(nonce, Some(format!("0x{}", hex::encode(n))));
+        assert_eq!(proven_nonce(recorded, Some(("check_kyc_full_nonce", &args[..]))).unwrap(), nonce);
+        assert!(proven_nonce(None, Some(("check_kyc_full_nonce", &args[..]))).is_err());
+    }
+
+    #[test]
+    fn swapped_inputs_are_rejected() {
+        let c = challenges(300);
+        let (nonce, _) = c.issue().unwrap();
+        let (proven, swapped) = (args(&[7; 32]), args(&nonce));
+        let recorded = Some(("check_kyc_full_nonce", &proven[..]));
+        assert!(proven_nonce(recorded, Some(("check_kyc_full_nonce", &swapped[..]))).is_err());
+        // Without the supplied inputs the recorded nonce is used, which isn't the challenge.
+        let p = proven_nonce(recorded, None).unwrap();
+        assert!(c.redeem(&hex::encode(nonce), p.as_deref()).is_err());
+    }
+
+    #[test]
+    fn challenge_is_single_use() {
+        let c = challenges(300);
+        let (nonce, _) = c.issue().unwrap();
+        let (n, args) = (hex::encode(nonce), args(&nonce));
+        let p = proven_nonce(Some(("check_kyc_full_nonce", &args[..])), None).unwrap();
+        c.redeem(&n, p.as_deref()).unwrap();
+        assert!(c.redeem(&n, p.as_deref()).is_err());
+    }
+
+    #[test]
+    fn challenge_expires() {
+        let c = challenges(0);
+        let (nonce, _) = c.issue().unwrap();
+        let n = format!("0x{}", hex::encode(nonce));
+        assert!(c.redeem(&n, Some(&n)).is_err());
+    }
+
+    #[test]
+    fn proof_without_nonce_cannot_redeem() {
+        let c = challenges(300);
+        let (nonce, _) = c.issue().unwrap();
+        assert!(c.redeem(&hex::encode(nonce), None).is_err());
+        assert!(c.redeem(&hex::encode(nonce), Some(&hex::encode(nonce))).is_ok());
+    }
+}
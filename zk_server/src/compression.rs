@@ -0,0 +1,71 @@
+//! Compression of the proof artifacts handed to callers: `?compression=none|fast|max` on the
+//! prove routes, defaulting to `ZK_PROOF_COMPRESSION` (default `none`).  `fast` is zstd level 1,
+//! cheap enough for latency-sensitive callers; `max` is level 19, for archival.  It applies to the
+//! `proof` and `instance` of a full-proof response and to the binary attachment, and the response
+//! names it (`compression`, `x-zk-compression`).  `POST /verify` takes the same `compression` to
+//! read them back.  Stored proofs are kept uncompressed, as every reader expects bincode.
+//!
+//! Nova's own compression stage (folding proof → Spartan SNARK) always runs: it happens inside
+//! the engine's prove call and is what lets a proof verify without its folding trace.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use utoipa::ToSchema;
+
+/// Largest artifact a decompression may produce.
+const MAX_ARTIFACT: usize = 256 << 20;
+
+static DEFAULT: OnceLock<Compression> = OnceLock::new();
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    #[default]
+    None,
+    /// zstd level 1.
+    Fast,
+    /// zstd level 19.
+    Max,
+}
+
+impl Compression {
+    /// Read `ZK_PROOF_COMPRESSION` and make it the default for requests that name none.
+    pub fn init_from_env() -> Result<()> {
+        let c = match std::env::var("ZK_PROOF_COMPRESSION").as_deref() {
+            Ok("") | Ok("none") | Err(_) => Compression::None,
+            Ok("fast") => Compression::Fast,
+            Ok("max")  => Compression::Max,
+            Ok(other)  => bail!("unknown ZK_PROOF_COMPRESSION {other:?} (none | fast | max)"),
+        };
+        DEFAULT.set(c).ok();
+        Ok(())
+    }
+
+    /// `requested`, or the server default.
+    pub fn resolve(requested: Option<Compression>) -> Compression {
+        requested.or_else(|| DEFAULT.get().copied()).unwrap_or_default()
+    }
+
+    fn level(self) -> Option<i32> {
+        match self {
+            Compression::None => None,
+            Compression::Fast => Some(1),
+            Compression::Max  => Some(19),
+        }
+    }
+
+    pub fn compress(self, bytes: &[u8]) -> Result<Vec<u8>> {
+        match self.level() {
+            Some(level) => zstd::bulk::compress(bytes, level).context("compressing proof artifact"),
+            None        => Ok(bytes.to_vec()),
+        }
+    }
+
+    pub fn decompress(self, bytes: &[u8]) -> Result<Vec<u8>> {
+        match self.level() {
+            Some(_) => zstd::bulk::decompress(bytes, MAX_ARTIFACT).context("proof artifact is not zstd (or too large)"),
+            None    => Ok(bytes.to_vec()),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Core server settings, layered: built-in defaults, then `server.toml`, then `ZK_*` environment
+//! variables, then command-line flags, each overriding the one before.  The file is named by
+//! `--config` or `ZK_CONFIG_FILE`; a missing default file is skipped.
+//!
+//! ```toml
+//! bind                 = "0.0.0.0:8080"             # ZK_BIND                 --bind
+//! grpc_bind            = "0.0.0.0:50051"            # ZK_GRPC_BIND            --grpc-bind  (no gRPC when unset)
+//! wasm                 = "examples/kyc_wasm.wasm"  # ZK_WASM                 --wasm  (v1 when there is no rollout.toml)
+//! default_step         = 8                         # ZK_DEFAULT_STEP         --default-step
+//! min_step             = 1                         # ZK_MIN_STEP             --min-step  (limits.toml may override)
+//! max_step             = 64                        # ZK_MAX_STEP             --max-step  (limits.toml may override)
+//! prove_concurrency    = 8                         # ZK_PROVE_CONCURRENCY    --prove-concurrency  (default one per core)
+//! prove_queue          = 64                        # ZK_PROVE_QUEUE          --prove-queue
+//! prover_timeout_secs  = 600                       # ZK_PROVER_TIMEOUT_SECS  --prover-timeout-secs  (subprocess workers)
+//! request_timeout_secs = 900                       # ZK_REQUEST_TIMEOUT_SECS --request-timeout-secs
+//! ```
+//!
+//! Flags take `--name value` or `--name=value`.  Every other setting is still read from its own
+//! file or variable.
+
+use anyhow::{bail, ensure, Context, Result};
+use serde::Deserialize;
+use std::{net::SocketAddr, path::PathBuf, time::Duration};
+
+/// Setting names, as TOML keys; the variable is `ZK_` + upper case, the flag `--` + dashes.
+const KEYS: [&str; 10] = [
+    "bind", "grpc_bind", "wasm", "default_step", "min_step", "max_step",
+    "prove_concurrency", "prove_queue", "prover_timeout_secs", "request_timeout_secs",
+];
+
+/// One source's settings; `None` defers to the layer below.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Layer {
+    bind:                 Option<SocketAddr>,
+    grpc_bind:            Option<SocketAddr>,
+    wasm:                 Option<PathBuf>,
+    default_step:         Option<usize>,
+    min_step:             Option<usize>,
+    max_step:             Option<usize>,
+    prove_concurrency:    Option<usize>,
+    prove_queue:          Option<usize>,
+    prover_timeout_secs:  Option<u64>,
+    request_timeout_secs: Option<u64>,
+}
+
+impl Layer {
+    /// Parse `raw` into the setting `key`; `source` names it in errors.
+    fn set(&mut self, key: &str, raw: &str, source: &str) -> Result<()> {
+        let num = || raw.parse::<u64>().with_context(|| format!("{source} must be a number, got {raw:?}"));
+        match key {
+            "bind" => self.bind = Some(raw.parse().with_context(|| format!("{source} must be host:port, got {raw:?}"))?),
+            "grpc_bind" => self.grpc_bind = Some(raw.parse().with_context(|| format!("{source} must be host:port, got {raw:?}"))?),
+            "wasm" => self.wasm = Some(raw.into()),
+            "default_step"         => self.default_step = Some(num()? as usize),
+            "min_step"             => self.min_step = Some(num()? as usize),
+            "max_step"             => self.max_step = Some(num()? as usize),
+            "prove_concurrency"    => self.prove_concurrency = Some(num()? as usize),
+            "prove_queue"          => self.prove_queue = Some(num()? as usize),
+            "prover_timeout_secs"  => self.prover_timeout_secs = Some(num()?),
+            "request_timeout_secs" => self.request_timeout_secs = Some(num()?),
+            _ => bail!("unknown setting {key:?}"),
+        }
+        Ok(())
+    }
+
+    /// `self`, falling back to `below` for anything unset.
+    fn over(self, below: Layer) -> Layer {
+        Layer {
+            bind:                 self.bind.or(below.bind),
+            grpc_bind:            self.grpc_bind.or(below.grpc_bind),
+            wasm:                 self.wasm.or(below.wasm),
+            default_step:         self.default_step.or(below.default_step),
+            min_step:             self.min_step.or(below.min_step),
+            max_step:             self.max_step.or(below.max_step),
+            prove_concurrency:    self.prove_concurrency.or(below.prove_concurrency),
+            prove_queue:          self.prove_queue.or(below.prove_queue),
+            prover_timeout_secs:  self.prover_timeout_secs.or(below.prover_timeout_secs),
+            request_timeout_secs: self.request_timeout_secs.or(below.request_timeout_secs),
+        }
+    }
+
+    fn from_env() -> Result<Self> {
+        let mut layer = Layer::default();
+        for key in KEYS {
+            let var = format!("ZK_{}", key.to_ascii_uppercase());
+            match std::env::var(&var) {
+                Ok(v) if !v.is_empty() => layer.set(key, &v, &var)?,
+                _ => {}
+            }
+        }
+        Ok(layer)
+    }
+}
+
+#[derive(Debug)]
+pub struct ServerConfig {
+    pub bind:              SocketAddr,
+    /// Address of the gRPC API, which is off without one.
+    pub grpc_bind:         Option<SocketAddr>,
+    /// Guest served as `v1` when there is no `rollout.toml`.
+    pub wasm:              PathBuf,
+    /// `step` of requests that leave it out.
+    pub default_step:      usize,
+    /// Step bounds for tenants `limits.toml` says nothing about.
+    pub min_step:          usize,
+    pub max_step:          usize,
+    pub prove_concurrency: usize,
+    pub prove_queue:       usize,
+    /// Kill a subprocess prover worker after this long.
+    pub prover_timeout:    Option<Duration>,
+    /// Answer `408` to any request still running after this long.
+    pub request_timeout:   Option<Duration>,
+}
+
+impl ServerConfig {
+    /// Resolve the settings from `args` (the command line without the program name), the
+    /// environment and the config file.
+    pub fn load(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let (flags, config_flag) = parse_flags(args)?;
+        let explicit = config_flag.or_else(|| std::env::var("ZK_CONFIG_FILE").ok().filter(|p| !p.is_empty()));
+        let path = PathBuf::from(explicit.as_deref().unwrap_or("server.toml"));
+        let file: Layer = if path.exists() || explicit.is_some() {
+            let raw = std::fs::read_to_string(&path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            toml::from_str(&raw).with_context(|| format!("parsing {}", path.display()))?
+        } else {
+            Layer::default()
+        };
+        let l = flags.over(Layer::from_env()?.over(file));
+
+        let cfg = ServerConfig {
+            bind:              l.bind.unwrap_or_else(|| SocketAddr::from(([0, 0, 0, 0], 8080))),
+            grpc_bind:         l.grpc_bind,
+            wasm:              l.wasm.unwrap_or_else(|| "examples/kyc_wasm.wasm".into()),
+            default_step:      l.default_step.unwrap_or(8),
+            min_step:          l.min_step.unwrap_or(1),
+            max_step:          l.max_step.unwrap_or(64),
+            prove_concurrency: l.prove_concurrency
+                .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get())),
+            prove_queue:       l.prove_queue.unwrap_or(64),
+            prover_timeout:    l.prover_timeout_secs.map(Duration::from_secs),
+            request_timeout:   l.request_timeout_secs.map(Duration::from_secs),
+        };
+        ensure!(cfg.min_step >= 1 && cfg.min_step <= cfg.max_step, "need 1 <= min_step <= max_step");
+        ensure!((cfg.min_step..=cfg.max_step).contains(&cfg.default_step),
+                "default_step {} is outside {}..={}", cfg.default_step, cfg.min_step, cfg.max_step);
+        ensure!(cfg.prove_concurrency > 0, "prove_concurrency must be at least 1");
+        Ok(cfg)
+    }
+}
+
+/// The settings given as flags, and `--config` if present.
+fn parse_flags(args: impl IntoIterator<Item = String>) -> Result<(Layer, Option<String>)> {
+    let (mut layer, mut config) = (Layer::default(), None);
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let Some(name) = arg.strip_prefix("--") else {
+            bail!("unexpected argument {arg:?}");
+        };
+        let (name, value) = match name.split_once('=') {
+            Some((n, v)) => (n.to_string(), v.to_string()),
+            None => (name.to_string(), args.next().with_context(|| format!("--{name} needs a value"))?),
+        };
+        let key = name.replace('-', "_");
+        if key == "config" {
+            config = Some(value);
+        } else if KEYS.contains(&key.as_str()) {
+            layer.set(&key, &value, &format!("--{name}"))?;
+        } else {
+            bail!("unknown flag --{name} (settings: --config, {})",
+                  KEYS.map(|k| format!("--{}", k.replace('_', "-"))).join(", "));
+        }
+    }
+    Ok((layer, config))
+}
@@ -0,0 +1,96 @@
+//! Subject consent bound to an issuance.
+//! Processing KYC data needs provable consent, so `/prove` (schema 2) accepts a `consent` object:
+//! the Keccak-256 of the consent text the subject was shown, when they agreed, and their
+//! signature over
+//!
+//! ```text
+//! zkKYC consent
+//! text: 0x<text_hash>
+//! subject: <canonical identifier>
+//! at: <consented_at>
+//! ```
+//!
+//! For `evm_address` subjects the signature is an EIP-191 `personal_sign` (65 bytes, r‖s‖v) and
+//! must recover to the wallet, otherwise the request is rejected.  Other identifier types have no
+//! key to check against; their signatures are recorded as given (`verified: false`).  The record
+//! is stored with the proof and listed by `GET /admin/compliance`.  `ZK_REQUIRE_CONSENT=true`
+//! rejects issuance without one.
+
+use anyhow::{bail, ensure, Context, Result};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use kyc_core::{HashScheme, IdentifierType};
+use serde::{Deserialize, Serialize};
+
+/// Consent timestamps further in the future than this are rejected (clock skew allowance).
+const MAX_SKEW_SECS: u64 = 300;
+
+/// Consent as sent by the client.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Consent {
+    /// `0x`-hex Keccak-256 of the consent text shown to the subject.
+    pub text_hash:    String,
+    /// Unix seconds.
+    pub consented_at: u64,
+    /// `0x`-hex subject signature over [`Consent::message`].
+    pub signature:    String,
+}
+
+/// Consent as stored with a proof.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ConsentRecord {
+    #[serde(flatten)]
+    pub consent:     Consent,
+    /// The signature was checked against the subject's key.
+    pub verified:    bool,
+    /// Unix seconds the server recorded it.
+    pub recorded_at: u64,
+}
+
+impl Consent {
+    /// The message the subject signs.
+    pub fn message(&self, subject: &str) -> String {
+        format!("zkKYC consent\ntext: {}\nsubject: {subject}\nat: {}", self.text_hash.to_lowercase(), self.consented_at)
+    }
+
+    /// Validate against the (canonical) subject and produce the record to store.
+    pub fn check(self, id_type: IdentifierType, subject: &str, now: u64) -> Result<ConsentRecord> {
+        let hash = self.text_hash.strip_prefix("0x").unwrap_or(&self.text_hash);
+        ensure!(hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit()),
+                "consent.text_hash must be a 32-byte hex digest");
+        ensure!(self.consented_at <= now + MAX_SKEW_SECS, "consent.consented_at is in the future");
+        let sig = hex::decode(self.signature.strip_prefix("0x").unwrap_or(&self.signature))
+            .context("consent.signature is not hex")?;
+        ensure!(!sig.is_empty(), "consent.signature is empty");
+        let verified = match id_type {
+            IdentifierType::EvmAddress => {
+                let signer = recover_personal_sign(self.message(subject).as_bytes(), &sig)?;
+                if !signer.eq_ignore_ascii_case(subject) {
+                    bail!("consent signature is from {signer}, not the subject");
+                }
+                true
+            }
+            _ => false,
+        };
+        Ok(ConsentRecord { consent: self, verified, recorded_at: now })
+    }
+}
+
+/// Address that produced an EIP-191 `personal_sign` signature over `msg`.
+pub(crate) fn recover_personal_sign(msg: &[u8], sig: &[u8]) -> Result<String> {
+    ensure!(sig.len() == 65, "signature must be 65 bytes (r || s || v)");
+    let mut pre = format!("\x19Ethereum Signed Message:\n{}", msg.len()).into_bytes();
+    pre.extend_from_slice(msg);
+    let digest = HashScheme::Keccak256.digest(&pre)?;
+    let v = match sig[64] {
+        27 | 28 => sig[64] - 27,
+        v @ (0 | 1) => v,
+        v => bail!("bad signature recovery byte {v}"),
+    };
+    let signature = Signature::from_slice(&sig[..64]).context("malformed signature")?;
+    let id = RecoveryId::from_byte(v).context("bad signature recovery byte")?;
+    let key = VerifyingKey::recover_from_prehash(&digest, &signature, id)
+        .context("signature does not recover")?;
+    let point = key.to_encoded_point(false);
+    let h = HashScheme::Keccak256.digest(&point.as_bytes()[1..])?;
+    Ok(format!("0x{}", hex::encode(&h[12..])))
+}
@@ -0,0 +1,102 @@
+//! Dead letters: side effects that were given up on.
+//! A webhook event (billing or lifecycle) that exhausts its retries, or is dropped because the
+//! delivery queue is full, is written to `ZK_DEAD_LETTER_DIR` (default `$ZK_STORE_DIR/dead_letters`) as one JSON file
+//! instead of only being logged.  Operators inspect them with `GET /admin/dead-letters` and
+//! `GET /admin/dead-letters/:id`, hand one back to its sender with
+//! `POST /admin/dead-letters/:id/replay`, or discard it with `DELETE /admin/dead-letters/:id`.
+//! A replay that fails again comes back as a new letter.
+//!
+//! Webhooks are the only outbound side effect today; on-chain attestation writes would add a
+//! [`Kind`] here and a replay arm in `admin.rs`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::store::{self, read_json, write_json};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    /// `payload` is a `webhook::Event` for the billing hook.
+    Billing,
+    /// `payload` is a `webhook::Event` for the lifecycle hook.
+    Event,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Letter {
+    pub id:        String,
+    pub kind:      Kind,
+    pub payload:   serde_json::Value,
+    /// Why delivery was given up.
+    pub error:     String,
+    pub failed_at: u64,
+}
+
+pub struct DeadLetters {
+    dir: PathBuf,
+}
+
+impl DeadLetters {
+    /// Build from `ZK_DEAD_LETTER_DIR` or `ZK_STORE_DIR`; `None` when neither is set.
+    pub fn from_env() -> Result<Option<Self>> {
+        let dir = match (std::env::var("ZK_DEAD_LETTER_DIR"), std::env::var("ZK_STORE_DIR")) {
+            (Ok(d), _) if !d.is_empty() => PathBuf::from(d),
+            (_, Ok(s)) if !s.is_empty() => PathBuf::from(s).join("dead_letters"),
+            _ => return Ok(None),
+        };
+        std::fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+        Ok(Some(DeadLetters { dir }))
+    }
+
+    fn path(&self, id: &str) -> Option<PathBuf> {
+        uuid::Uuid::parse_str(id).ok().map(|u| self.dir.join(format!("{u}.json")))
+    }
+
+    /// Record a failed side effect.  Errors are logged: there is nowhere further to fall back to.
+    pub fn put(&self, kind: Kind, payload: &impl Serialize, error: &str) {
+        let error = &crate::redact::scrub(error);
+        let letter = Letter {
+            id:        uuid::Uuid::new_v4().to_string(),
+            kind,
+            payload:   serde_json::to_value(payload).unwrap_or_default(),
+            error:     error.to_string(),
+            failed_at: store::now(),
+        };
+        let path = self.dir.join(format!("{}.json", letter.id));
+        match write_json(&path, &letter) {
+            Ok(()) => tracing::warn!(letter = %letter.id, ?kind, "side effect dead-lettered: {error}"),
+            Err(e) => tracing::error!(?kind, "dead letter lost ({error}): {e:#}"),
+        }
+    }
+
+    /// All letters, oldest first.
+    pub fn list(&self) -> Result<Vec<Letter>> {
+        let mut out = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") { continue; }
+            out.extend(read_json::<Letter>(&path)?);
+        }
+        out.sort_by(|a, b| a.failed_at.cmp(&b.failed_at).then(a.id.cmp(&b.id)));
+        Ok(out)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<Letter>> {
+        match self.path(id) {
+            Some(p) => read_json(&p),
+            None    => Ok(None),
+        }
+    }
+
+    /// Delete `id`; `false` if unknown.
+    pub fn remove(&self, id: &str) -> Result<bool> {
+        let Some(p) = self.path(id) else { return Ok(false) };
+        match std::fs::remove_file(&p) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
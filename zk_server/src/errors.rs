@@ -0,0 +1,245 @@
+//! Error bodies for the prove and verify routes.
+//! Every failure is answered with `{ "code", "message", "details"? }`, where `code` is one of the
+//! stable [`ErrorCode`]s below (clients branch on it; `message` is for humans and may change) and
+//! `details` carries structured context such as quota or load figures.  `error` repeats the code
+//! in lower case, as older clients read that field.
+//!
+//! Failures raised as an [`ApiError`], or as one of the typed errors ([`QuotaExceeded`],
+//! [`Overloaded`], …), keep their code however far they propagate; anything else is
+//! `INVALID_REQUEST`.  Wrap a fallible step with [`WithCode::with_code`] to classify it.
+
+use anyhow::Error;
+use axum::{
+    http::{HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    honeypot, killswitch::IssuanceHalted, limits::StepOutOfRange, maintenance::UnderMaintenance, pool::Overloaded, prover::ProverPanic,
+    quota::QuotaExceeded, store, watchdog::ResourceExceeded, worker::WorkerKilled,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The body or query does not parse, or names something unknown.
+    InvalidRequest,
+    /// The subject identifier is malformed for its `identifier_type`.
+    InvalidWallet,
+    /// The subject's KYC or signature flag is not approved.
+    KycNotApproved,
+    /// Consent is required but missing, or does not verify.
+    InvalidConsent,
+    /// The SIWE wallet-ownership check is required but missing, or does not verify.
+    OwnershipUnproven,
+    /// The attestation or its issuer is not trusted for this circuit.
+    UntrustedIssuer,
+    /// A policy, the OPA hook or a plugin refused the subject.
+    PolicyRejected,
+    /// The OPA sidecar could not be asked, so issuance is refused (fail closed).
+    PolicyUnavailable,
+    /// The circuit the request needs is not enabled for this tenant.
+    CircuitDisabled,
+    /// A verifier challenge is missing, malformed, expired, spent or not the proof's.
+    InvalidChallenge,
+    StepOutOfRange,
+    QuotaExceeded,
+    /// The caller was blocked for sending too many invalid requests.
+    Blocked,
+    Overloaded,
+    Maintenance,
+    /// An operator halted all issuance (see killswitch.rs); lasts until they resume it.
+    IssuanceHalted,
+    /// Aborted or refused by the memory watchdog.
+    ResourceExceeded,
+    /// Proving (or its self-check) returned an error.
+    ProverFailure,
+    ProverPanic,
+    ProverKilled,
+    /// The SNARK did not verify.
+    VerifyFailure,
+    /// Storage or another server-side step failed after proving.
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::InvalidRequest | ErrorCode::InvalidWallet | ErrorCode::StepOutOfRange
+                | ErrorCode::InvalidChallenge => StatusCode::BAD_REQUEST,
+            ErrorCode::KycNotApproved | ErrorCode::InvalidConsent | ErrorCode::OwnershipUnproven | ErrorCode::UntrustedIssuer
+                | ErrorCode::PolicyRejected | ErrorCode::CircuitDisabled => StatusCode::FORBIDDEN,
+            ErrorCode::VerifyFailure => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::QuotaExceeded | ErrorCode::Blocked => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::Overloaded | ErrorCode::Maintenance | ErrorCode::IssuanceHalted
+                | ErrorCode::ResourceExceeded | ErrorCode::PolicyUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::ProverFailure | ErrorCode::ProverPanic | ErrorCode::ProverKilled
+                | ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// `SCREAMING_SNAKE_CASE` name, as sent in `code`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::InvalidRequest  => "INVALID_REQUEST",
+            ErrorCode::InvalidWallet   => "INVALID_WALLET",
+            ErrorCode::KycNotApproved  => "KYC_NOT_APPROVED",
+            ErrorCode::InvalidConsent  => "INVALID_CONSENT",
+            ErrorCode::OwnershipUnproven => "OWNERSHIP_UNPROVEN",
+            ErrorCode::UntrustedIssuer => "UNTRUSTED_ISSUER",
+            ErrorCode::PolicyRejected  => "POLICY_REJECTED",
+            ErrorCode::PolicyUnavailable => "POLICY_UNAVAILABLE",
+            ErrorCode::CircuitDisabled => "CIRCUIT_DISABLED",
+            ErrorCode::InvalidChallenge => "INVALID_CHALLENGE",
+            ErrorCode::StepOutOfRange  => "STEP_OUT_OF_RANGE",
+            ErrorCode::QuotaExceeded   => "QUOTA_EXCEEDED",
+            ErrorCode::Blocked         => "BLOCKED",
+            ErrorCode::Overloaded      => "OVERLOADED",
+            ErrorCode::Maintenance     => "MAINTENANCE",
+            ErrorCode::IssuanceHalted  => "ISSUANCE_HALTED",
+            ErrorCode::ResourceExceeded => "RESOURCE_EXCEEDED",
+            ErrorCode::ProverFailure   => "PROVER_FAILURE",
+            ErrorCode::ProverPanic     => "PROVER_PANIC",
+            ErrorCode::ProverKilled    => "PROVER_KILLED",
+            ErrorCode::VerifyFailure   => "VERIFY_FAILURE",
+            ErrorCode::Internal        => "INTERNAL",
+        }
+    }
+}
+
+/// A failure with a stable code, carried inside `anyhow::Error`.
+#[derive(Debug)]
+pub struct ApiError {
+    pub code:    ErrorCode,
+    pub message: String,
+    pub details: Option<Value>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        ApiError { code, message: message.into(), details: None }
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// The honeypot's verdict on a request that can never prove.
+    pub fn bogus(reason: &str, message: String) -> Self {
+        let code = match reason {
+            honeypot::MALFORMED_SUBJECT => ErrorCode::InvalidWallet,
+            honeypot::DENIED_FLAGS      => ErrorCode::KycNotApproved,
+            _                           => ErrorCode::InvalidRequest,
+        };
+        ApiError::new(code, message).with_details(json!({ "reason": reason }))
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+pub trait WithCode<T> {
+    /// Classify a failure under `code`, unless it already carries one.
+    fn with_code(self, code: ErrorCode) -> anyhow::Result<T>;
+}
+
+impl<T, E: Into<Error>> WithCode<T> for Result<T, E> {
+    fn with_code(self, code: ErrorCode) -> anyhow::Result<T> {
+        self.map_err(|e| {
+            let e = e.into();
+            if classify(&e).is_some() {
+                return e;
+            }
+            ApiError::new(code, format!("{e:#}")).into()
+        })
+    }
+}
+
+/// Code, details and `Retry-After` seconds of a classified failure.
+fn classify(err: &Error) -> Option<(ErrorCode, Option<Value>, Option<u64>)> {
+    if let Some(e) = err.downcast_ref::<ApiError>() {
+        return Some((e.code, e.details.clone(), None));
+    }
+    if let Some(q) = err.downcast_ref::<QuotaExceeded>() {
+        return Some((ErrorCode::QuotaExceeded, Some(json!({ "quota": q })), Some(q.resets_at.saturating_sub(store::now()))));
+    }
+    if let Some(o) = err.downcast_ref::<Overloaded>() {
+        return Some((ErrorCode::Overloaded, Some(json!({ "load": o })), Some(o.retry_after)));
+    }
+    if let Some(m) = err.downcast_ref::<UnderMaintenance>() {
+        return Some((ErrorCode::Maintenance, Some(json!({ "maintenance": m })), Some(m.retry_after)));
+    }
+    if let Some(h) = err.downcast_ref::<IssuanceHalted>() {
+        return Some((ErrorCode::IssuanceHalted, Some(json!({ "halt_id": h.halt_id, "since": h.since })), None));
+    }
+    if let Some(b) = err.downcast_ref::<honeypot::Blocked>() {
+        return Some((ErrorCode::Blocked, Some(json!({ "retry_after": b.retry_after })), Some(b.retry_after)));
+    }
+    if let Some(r) = err.downcast_ref::<ResourceExceeded>() {
+        return Some((ErrorCode::ResourceExceeded, None, Some(r.retry_after)));
+    }
+    if err.downcast_ref::<ProverPanic>().is_some() {
+        return Some((ErrorCode::ProverPanic, None, None));
+    }
+    if err.downcast_ref::<WorkerKilled>().is_some() {
+        return Some((ErrorCode::ProverKilled, None, None));
+    }
+    if let Some(e) = err.downcast_ref::<StepOutOfRange>() {
+        return Some((ErrorCode::StepOutOfRange, Some(json!({ "limits": e })), None));
+    }
+    None
+}
+
+/// A prove or verifier key check that failed; answered over HTTP or gRPC alike.
+#[derive(Debug)]
+pub struct Refused {
+    pub status:      StatusCode,
+    pub message:     &'static str,
+    pub retry_after: Option<u64>,
+}
+
+impl Refused {
+    pub fn new(status: StatusCode, message: &'static str) -> Self {
+        Refused { status, message, retry_after: None }
+    }
+}
+
+impl IntoResponse for Refused {
+    fn into_response(self) -> Response {
+        let mut resp = (self.status, Json(self.message)).into_response();
+        if let Some(retry) = self.retry_after {
+            resp.headers_mut().insert("retry-after", HeaderValue::from(retry));
+        }
+        resp
+    }
+}
+
+/// The `{ code, message, details?, error }` body for `code`.
+pub fn body(code: ErrorCode, message: &str, details: Option<Value>) -> Value {
+    let mut body = json!({
+        "code":    code.as_str(),
+        "message": message,
+        "error":   code.as_str().to_ascii_lowercase(),
+    });
+    if let Some(d) = details {
+        body["details"] = d;
+    }
+    body
+}
+
+/// Status, JSON body and `Retry-After` seconds for `err`.
+pub fn describe(err: &Error) -> (StatusCode, Value, Option<u64>) {
+    let (code, details, retry) = classify(err).unwrap_or((ErrorCode::InvalidRequest, None, None));
+    let message = match err.downcast_ref::<ApiError>() {
+        Some(e) => e.message.clone(),
+        None    => err.to_string(),
+    };
+    (code.status(), body(code, &message, details), retry)
+}
@@ -0,0 +1,194 @@
+//! Proof expiry, expiry reminders and pre-authorized renewal.
+//! With `ZK_PROOF_TTL_DAYS` set, newly issued proofs carry `expires_at`; past it, verification
+//! answers `expired: true, valid: false` and presentations are refused.
+//!
+//! An hourly sweep sends `proof.expiring` to the lifecycle webhook (`ZK_EVENTS_URL`) when a proof
+//! comes within each of `ZK_EXPIRY_REMINDER_DAYS` (comma-separated, default `7`) of expiring, once
+//! per threshold.  With `ZK_RENEWAL_SECRET` set the event carries a `renew_url`:
+//! `POST /proofs/:id/renew?token=…`, where the token is an HMAC over the proof id and its expiry,
+//! so it authorizes renewing that proof and nothing else.  The renewal takes a `/prove` body whose
+//! identifier must commit to the same public inputs as the old proof, issues a new proof under the
+//! old proof's tenant, policy and circuit, and works once per proof, up to [`RENEW_GRACE`] after
+//! expiry.  It is refused if the circuit's commitment scheme has changed since the old proof.
+
+use anyhow::{Context, Result};
+use axum::{
+    extract::{ConnectInfo, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use hmac::{Hmac, Mac};
+use kyc_core::encoding;
+use serde::Deserialize;
+use sha2::Sha256;
+use std::{collections::HashSet, net::{IpAddr, SocketAddr}, sync::{Arc, Mutex}, time::Duration};
+
+use crate::{error_response, policy, prove, request, store::{self, ProofStore, StoredProof}, webhook::Event, AppState};
+
+const DAY: u64 = 86_400;
+const SWEEP_EVERY: Duration = Duration::from_secs(3600);
+/// How long after expiry a renewal link still works.
+pub const RENEW_GRACE: u64 = 30 * DAY;
+
+pub struct Expiry {
+    ttl:       Option<u64>,
+    /// Reminder thresholds in days, largest first.
+    reminders: Vec<u64>,
+    secret:    Option<Vec<u8>>,
+    /// Proofs with a renewal in progress, so a link is used once even under concurrency.
+    renewing:  Mutex<HashSet<String>>,
+}
+
+impl Expiry {
+    pub fn from_env() -> Result<Self> {
+        let ttl = match std::env::var("ZK_PROOF_TTL_DAYS") {
+            Ok(d) => Some(d.parse::<u64>().context("ZK_PROOF_TTL_DAYS must be a number of days")? * DAY),
+            Err(_) => None,
+        };
+        let mut reminders = std::env::var("ZK_EXPIRY_REMINDER_DAYS").unwrap_or_else(|_| "7".into())
+            .split(',').map(str::trim).filter(|d| !d.is_empty())
+            .map(|d| d.parse::<u64>().context("ZK_EXPIRY_REMINDER_DAYS must be comma-separated days"))
+            .collect::<Result<Vec<_>>>()?;
+        reminders.sort_unstable_by(|a, b| b.cmp(a));
+        reminders.dedup();
+        let secret = std::env::var("ZK_RENEWAL_SECRET").ok().filter(|s| !s.is_empty()).map(String::into_bytes);
+        Ok(Expiry { ttl, reminders, secret, renewing: Mutex::default() })
+    }
+
+    /// Expiry for a proof issued at `issued_at`; `None` when proofs don't expire.
+    pub fn expires_at(&self, issued_at: u64) -> Option<u64> {
+        self.ttl.map(|t| issued_at + t)
+    }
+
+    fn mac(&self, id: &str, expires_at: u64) -> Option<Hmac<Sha256>> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_ref()?).expect("HMAC takes any key length");
+        mac.update(format!("renew:{id}:{expires_at}").as_bytes());
+        Some(mac)
+    }
+
+    /// Renewal token for a proof; `None` without `ZK_RENEWAL_SECRET`.
+    pub fn token(&self, id: &str, expires_at: u64) -> Option<String> {
+        self.mac(id, expires_at).map(|m| hex::encode(m.finalize().into_bytes()))
+    }
+
+    fn token_ok(&self, id: &str, expires_at: u64, token: &str) -> bool {
+        match (self.mac(id, expires_at), hex::decode(token)) {
+            (Some(mac), Ok(t)) => mac.verify_slice(&t).is_ok(),
+            _ => false,
+        }
+    }
+
+    /// Thresholds `p` has newly crossed; only the tightest is worth a message, but all are marked.
+    fn due(&self, p: &StoredProof, now: u64) -> Vec<u64> {
+        let Some(exp) = p.expires_at.filter(|&e| e > now) else { return Vec::new() };
+        self.reminders.iter().copied()
+            .filter(|d| now + d * DAY >= exp && !p.reminded.contains(d))
+            .collect()
+    }
+}
+
+pub fn spawn(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(SWEEP_EVERY);
+        loop {
+            tick.tick().await;
+            let Some(store) = &state.store else { return };
+            if let Err(e) = sweep(&state, store) {
+                tracing::error!("expiry reminder sweep failed: {e:#}");
+            }
+        }
+    });
+}
+
+fn sweep(state: &AppState, store: &ProofStore) -> Result<()> {
+    let now = store::now();
+    let exp = &state.expiry;
+    for p in store.scan(|p| p.renewed_by.is_none() && !exp.due(p, now).is_empty())? {
+        if store.revocation(&p.id)?.is_some() {
+            continue;
+        }
+        let due = exp.due(&p, now);
+        let expires_at = p.expires_at.unwrap_or(now);
+        let renew_url = exp.token(&p.id, expires_at).map(|t| {
+            let base = state.public_url.as_deref().unwrap_or("").trim_end_matches('/');
+            format!("{base}/proofs/{}/renew?token={t}", p.id)
+        });
+        if let Some(events) = &state.events {
+            events.emit(Event::new("proof.expiring", "expiry", serde_json::json!({
+                "proof_id":   p.id,
+                "tenant":     p.tenant,
+                "expires_at": expires_at,
+                "days_left":  (expires_at - now + DAY - 1) / DAY,
+                "renew_url":  renew_url,
+            })));
+        }
+        store.update(&p.id, |p| p.reminded.extend(&due))?;
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct RenewParams {
+    token: String,
+}
+
+/// `POST /proofs/:id/renew?token=…`: issue a fresh proof for the same subject.
+pub async fn handle_renew(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Path(id): Path<String>,
+    Query(q): Query<RenewParams>,
+    headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
+) -> Response {
+    let Some(store) = &state.store else {
+        return (StatusCode::NOT_FOUND, Json("proof store disabled")).into_response();
+    };
+    let old = match store.get_proof(&id) {
+        Ok(Some(p)) => p,
+        Ok(None)    => return (StatusCode::NOT_FOUND, Json("unknown proof")).into_response(),
+        Err(e)      => return (StatusCode::BAD_REQUEST, Json(e.to_string())).into_response(),
+    };
+    let Some(expires_at) = old.expires_at else {
+        return (StatusCode::CONFLICT, Json("proof does not expire")).into_response();
+    };
+    if !state.expiry.token_ok(&id, expires_at, &q.token) || store::now() > expires_at + RENEW_GRACE {
+        return (StatusCode::FORBIDDEN, Json("invalid or expired renewal token")).into_response();
+    }
+    if old.renewed_by.is_some() || !state.expiry.renewing.lock().unwrap().insert(id.clone()) {
+        return (StatusCode::CONFLICT, Json("proof already renewed")).into_response();
+    }
+    let res = renew(&state, store, state.ip_limits.client_ip(peer, &headers), &old, body).await;
+    state.expiry.renewing.lock().unwrap().remove(&id);
+    match res {
+        Ok(resp) => (StatusCode::CREATED, Json(resp)).into_response(),
+        Err(e)   => error_response(e),
+    }
+}
+
+async fn renew(
+    state: &AppState,
+    store: &ProofStore,
+    caller: IpAddr,
+    old:   &StoredProof,
+    body:  serde_json::Value,
+) -> Result<serde_json::Value> {
+    let req = request::parse_prove(body)?;
+    let circuit = policy::circuit(&old.circuit).context("old proof's circuit is no longer served")?;
+    let scheme = state.policies.commitment_for(circuit);
+    if let Some(old_scheme) = old.commitment_scheme.filter(|s| *s != scheme) {
+        anyhow::bail!("circuit {} now commits with {scheme}, not the renewed proof's {old_scheme}", circuit.name);
+    }
+    let wallet = req.identifier_type.canonicalize(&req.wallet);
+    let limbs = scheme.limbs(&req.identifier_type.preimage(&wallet))?;
+    let commitment = encoding::guest_args(&limbs[..circuit.wallet_limbs]);
+    anyhow::ensure!(
+        old.public_inputs.get(..commitment.len()) == Some(&commitment[..]),
+        "identifier does not match the proof being renewed"
+    );
+    let resp = prove(state, caller, &old.tenant, old.policy.as_deref(), Some(circuit), req, None).await?;
+    store.update(&old.id, |p| p.renewed_by = Some(resp.proof_id.clone()))?;
+    tracing::info!(old = %old.id, new = %resp.proof_id, tenant = %old.tenant, "proof renewed");
+    Ok(serde_json::to_value(&resp)?)
+}
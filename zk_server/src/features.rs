@@ -0,0 +1,100 @@
+//! Runtime feature toggles.
+//! A flag is on for a request when the tenant is allow-listed, or the flag is enabled and the
+//! request's key (subject) falls into the rollout percentage.  Loaded from a TOML file and
+//! editable at runtime through `/admin/features`:
+//!
+//! ```toml
+//! [flags.travel_rule_circuits]
+//! enabled = true
+//! percent = 25.0           # of subjects, stable per subject
+//! tenants = ["acme"]       # always on for these
+//! deny_tenants = ["beta"]  # always off for these
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeSet, HashMap},
+    hash::{Hash, Hasher},
+    path::Path,
+    sync::RwLock,
+};
+
+/* ---------- well-known flags -------------------------------------- */
+/// IVMS101 Travel Rule guest exports.
+pub const TRAVEL_RULE_CIRCUITS: &str = "travel_rule_circuits";
+/// Operator WASM plugins.
+pub const PLUGINS: &str = "plugins";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Flag {
+    #[serde(default)]
+    pub enabled:      bool,
+    #[serde(default = "full")]
+    pub percent:      f64,
+    #[serde(default)]
+    pub tenants:      BTreeSet<String>,
+    #[serde(default)]
+    pub deny_tenants: BTreeSet<String>,
+}
+fn full() -> f64 { 100.0 }
+
+impl Flag {
+    fn decide(&self, name: &str, tenant: &str, key: &str) -> bool {
+        if self.deny_tenants.contains(tenant) { return false; }
+        if self.tenants.contains(tenant)      { return true; }
+        self.enabled && bucket(name, key) < self.percent
+    }
+}
+
+/// Stable bucket in `[0, 100)` for (flag, key).
+fn bucket(name: &str, key: &str) -> f64 {
+    let mut h = DefaultHasher::new();
+    (name, key).hash(&mut h);
+    (h.finish() % 10_000) as f64 / 100.0
+}
+
+#[derive(Deserialize, Default)]
+struct FlagFile {
+    #[serde(default)]
+    flags: HashMap<String, Flag>,
+}
+
+#[derive(Default)]
+pub struct FeatureFlags {
+    flags: RwLock<HashMap<String, Flag>>,
+}
+
+impl FeatureFlags {
+    /// Load from `path`; a missing file configures nothing (every call site's default applies).
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        let file: FlagFile = toml::from_str(&raw)
+            .with_context(|| format!("parsing {}", path.display()))?;
+        Ok(Self { flags: RwLock::new(file.flags) })
+    }
+
+    /// Is `name` on for this tenant/subject?  Unconfigured flags return `default`.
+    pub fn is_enabled(&self, name: &str, tenant: &str, key: &str, default: bool) -> bool {
+        self.flags.read().unwrap()
+            .get(name)
+            .map_or(default, |f| f.decide(name, tenant, key))
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, Flag> {
+        self.flags.read().unwrap().clone()
+    }
+
+    pub fn set(&self, name: &str, flag: Flag) {
+        self.flags.write().unwrap().insert(name.to_string(), flag);
+    }
+
+    /// Drop the override; returns whether one existed.
+    pub fn remove(&self, name: &str) -> bool {
+        self.flags.write().unwrap().remove(name).is_some()
+    }
+}
@@ -0,0 +1,271 @@
+//! gRPC API beside the HTTP one, for integrators on gRPC stacks (`grpc_bind` in `config`).
+//! `zkkyc.v1.Prover` (proto/zkkyc.proto) offers `Prove`, `Verify` and `GetJob`, the
+//! counterparts of `POST /prove`, `POST /verify` and `GET /jobs/:id`.  They share the HTTP
+//! routes' checks, quotas, jobs and storage.  Credentials travel as metadata, as the headers
+//! do over HTTP: `authorization: Bearer <prove key>` and `x-tenant-id` on `Prove` / `GetJob`,
+//! and `x-api-key` on `Verify`.  Proofs and instances are raw bincode bytes, not hex.
+//!
+//! Failures map onto gRPC codes (`400` → `INVALID_ARGUMENT`, `403` → `PERMISSION_DENIED`,
+//! `429` → `RESOURCE_EXHAUSTED`, `503` → `UNAVAILABLE`, …).  The stable error code is sent as
+//! `x-error-code` metadata, with `retry-after` where the HTTP answer has it.  A read replica
+//! serves only `Verify`.
+
+use anyhow::{anyhow, Context, Result};
+use axum::http::{header, HeaderMap, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tonic::{transport::server::TcpIncoming, Code, Request, Response, Status};
+use tracing::Instrument;
+
+use crate::{
+    api_keys::Scope, compression::Compression, count_failure, errors::{self, Refused}, jobs::{Job, JobState}, prove, queue_job, request, screen,
+    tenant_of, verify_bytes, AppState, ProofEncoding, ProveParams, ProveResponse,
+};
+
+pub mod pb {
+    tonic::include_proto!("zkkyc.v1");
+}
+
+use pb::prover_server::{Prover, ProverServer};
+
+struct Service {
+    state: Arc<AppState>,
+}
+
+/// Bind `addr` and serve the gRPC API until shutdown; `timeout` bounds each call.
+pub fn spawn(state: Arc<AppState>, addr: SocketAddr, timeout: Option<Duration>) -> Result<()> {
+    let incoming = TcpIncoming::new(addr, true, None).map_err(|e| anyhow!("binding gRPC to {addr}: {e}"))?;
+    let mut server = tonic::transport::Server::builder();
+    if let Some(t) = timeout {
+        server = server.timeout(t);
+    }
+    tracing::info!("gRPC listening on {addr}");
+    tokio::spawn(async move {
+        let served = server
+            .add_service(ProverServer::new(Service { state }))
+            .serve_with_incoming_shutdown(incoming, crate::shutdown())
+            .await;
+        if let Err(e) = served {
+            tracing::error!("gRPC server failed: {e}");
+        }
+    });
+    Ok(())
+}
+
+#[tonic::async_trait]
+impl Prover for Service {
+    async fn prove(&self, req: Request<pb::ProveRequest>) -> Result<Response<pb::ProveReply>, Status> {
+        let state = &self.state;
+        if state.read_only {
+            return Err(Status::unimplemented("read replica: prove on a proving replica"));
+        }
+        let peer = req.remote_addr().unwrap_or_else(|| SocketAddr::from(([0, 0, 0, 0], 0)));
+        let headers = req.metadata().clone().into_headers();
+        let tenant = self.tenant(&headers, true)?;
+        let ip = state.ip_limits.client_ip(peer, &headers);
+        let _slot = state.ip_limits.admit(ip).map_err(refused)?;
+        let msg = req.into_inner();
+        let body = prove_body(&msg).map_err(status)?;
+        if let Some(err) = screen(state, ip, &tenant, request::parse_prove(body.clone())) {
+            return Err(status(err));
+        }
+        let params = ProveParams {
+            policy:         Some(msg.policy).filter(|p| !p.is_empty()),
+            proof_encoding: Some(ProofEncoding::Hex),
+            compression:    Some(Compression::None),
+            ..ProveParams::default()
+        };
+        if msg.run_async {
+            return match queue_job(state.clone(), ip, &tenant, &params, body) {
+                Ok(Some(id)) => Ok(Response::new(pb::ProveReply { outcome: Some(pb::prove_reply::Outcome::JobId(id)) })),
+                Ok(None)     => Err(Status::unavailable("too many prove jobs held")),
+                Err(e)       => Err(status(e)),
+            };
+        }
+        let span = state.sampling.request_span("/prove", &tenant);
+        let res = match request::parse_prove(body) {
+            Ok(req) => prove(state, ip, &tenant, params.policy.as_deref(), None, req, None).instrument(span).await,
+            Err(e)  => Err(e),
+        };
+        match res {
+            Ok(resp) => Ok(Response::new(pb::ProveReply { outcome: Some(pb::prove_reply::Outcome::Proof(proof_reply(resp))) })),
+            Err(err) => {
+                count_failure(state, &err);
+                Err(status(err))
+            }
+        }
+    }
+
+    async fn verify(&self, req: Request<pb::VerifyRequest>) -> Result<Response<pb::VerifyReply>, Status> {
+        let headers = req.metadata().clone().into_headers();
+        if let Some(keys) = &self.state.api_keys {
+            keys.check(headers.get("x-api-key").and_then(|v| v.to_str().ok()), Scope::Verify).map_err(refused)?;
+        }
+        let msg = req.into_inner();
+        let step = msg.step as usize;
+        self.state.limits.check_step(&tenant_of(&headers), step).map_err(status)?;
+        let (verdict, cached) = verify_bytes(&self.state, msg.proof, msg.instance, step).await;
+        Ok(Response::new(match verdict {
+            Ok(secs) => pb::VerifyReply { valid: true, verify_sec: secs, cached, error: String::new() },
+            Err(e)   => pb::VerifyReply { valid: false, verify_sec: 0.0, cached, error: e },
+        }))
+    }
+
+    async fn get_job(&self, req: Request<pb::GetJobRequest>) -> Result<Response<pb::Job>, Status> {
+        if self.state.read_only {
+            return Err(Status::unimplemented("read replica: jobs live on proving replicas"));
+        }
+        let tenant = self.tenant(&req.metadata().clone().into_headers(), false)?;
+        match self.state.jobs.get(&req.get_ref().job_id, &tenant) {
+            Some(job) => Ok(Response::new(job_reply(job))),
+            None      => Err(Status::not_found("unknown or expired job")),
+        }
+    }
+}
+
+impl Service {
+    /// The caller's tenant: its prove key's when prove keys are required, else `x-tenant-id`.
+    fn tenant(&self, headers: &HeaderMap, starts_proof: bool) -> Result<String, Status> {
+        let Some(keys) = &self.state.prove_keys else { return Ok(tenant_of(headers)) };
+        let secret = headers.get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        let claimed = headers.get("x-tenant-id").and_then(|v| v.to_str().ok());
+        keys.authorize(secret, claimed, starts_proof).map(|k| k.tenant).map_err(refused)
+    }
+}
+
+/// The `/prove` JSON body equivalent to `msg`.
+fn prove_body(msg: &pb::ProveRequest) -> Result<Value> {
+    let mut body: serde_json::Map<String, Value> = match msg.extra_json.trim() {
+        ""  => serde_json::Map::new(),
+        raw => serde_json::from_str(raw).context("extra_json must be a JSON object")?,
+    };
+    body.insert("wallet".into(), msg.wallet.clone().into());
+    body.insert("kyc".into(), msg.kyc.into());
+    body.insert("sig_valid".into(), msg.sig_valid.into());
+    if !msg.identifier_type.is_empty() {
+        body.insert("identifier_type".into(), msg.identifier_type.clone().into());
+    }
+    if msg.step > 0 {
+        body.insert("step".into(), msg.step.into());
+    }
+    if msg.legacy_limbs {
+        body.insert("legacy_limbs".into(), true.into());
+    }
+    Ok(Value::Object(body))
+}
+
+/// Serialized name of a unit enum variant (`poseidon`, `proving`, …).
+fn name_of(v: impl Serialize) -> String {
+    serde_json::to_value(v).ok().and_then(|v| v.as_str().map(String::from)).unwrap_or_default()
+}
+
+fn proof_reply(r: ProveResponse) -> pb::Proof {
+    let (proof, instance) = r.raw.unwrap_or_default();
+    pb::Proof {
+        proof_id:               r.proof_id,
+        setup_sec:              r.setup_sec,
+        prove_sec:              r.prove_sec,
+        verify_sec:             r.verify_sec,
+        proof,
+        instance,
+        circuit:                r.circuit.to_string(),
+        circuit_version:        r.circuit_version,
+        commitment_scheme:      name_of(r.commitment_scheme),
+        public_inputs:          r.public_inputs,
+        travel_rule_commitment: r.travel_rule_commitment.unwrap_or_default(),
+    }
+}
+
+/// A finished job's `result`: the `/prove` response as stored.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct JobResult {
+    proof_id:               String,
+    setup_sec:              f64,
+    prove_sec:              f64,
+    verify_sec:             f64,
+    proof:                  Option<String>,
+    instance:               Option<String>,
+    proof_encoding:         Option<ProofEncoding>,
+    compression:            Option<Compression>,
+    circuit:                String,
+    circuit_version:        String,
+    commitment_scheme:      String,
+    public_inputs:          Vec<String>,
+    travel_rule_commitment: Option<String>,
+}
+
+fn job_reply(job: Job) -> pb::Job {
+    let mut out = pb::Job {
+        job_id:      job.job_id,
+        state:       job.state.name().to_string(),
+        created_at:  job.created_at,
+        finished_at: job.finished_at.unwrap_or(0),
+        ..pb::Job::default()
+    };
+    match job.state {
+        JobState::Running { phase: Some(p) } => out.phase = name_of(p),
+        JobState::Succeeded { result } => {
+            let r: JobResult = serde_json::from_value(result).unwrap_or_default();
+            // Jobs queued over HTTP without `full_proof` hold no proof bytes.
+            let (enc, c) = (r.proof_encoding.unwrap_or_default(), r.compression.unwrap_or_default());
+            let bytes = |s: Option<String>| s.and_then(|s| enc.decode(&s)).and_then(|b| c.decompress(&b).ok()).unwrap_or_default();
+            out.result = Some(pb::Proof {
+                proof_id:               r.proof_id,
+                setup_sec:              r.setup_sec,
+                prove_sec:              r.prove_sec,
+                verify_sec:             r.verify_sec,
+                proof:                  bytes(r.proof),
+                instance:               bytes(r.instance),
+                circuit:                r.circuit,
+                circuit_version:        r.circuit_version,
+                commitment_scheme:      r.commitment_scheme,
+                public_inputs:          r.public_inputs,
+                travel_rule_commitment: r.travel_rule_commitment.unwrap_or_default(),
+            });
+        }
+        JobState::Failed { error, .. } => {
+            out.error_code = error.get("code").and_then(|c| c.as_str()).unwrap_or_default().to_string();
+            out.error = error.get("message").and_then(|m| m.as_str()).unwrap_or_default().to_string();
+        }
+        _ => {}
+    }
+    out
+}
+
+fn code_for(http: StatusCode) -> Code {
+    match http {
+        StatusCode::BAD_REQUEST          => Code::InvalidArgument,
+        StatusCode::UNAUTHORIZED         => Code::Unauthenticated,
+        StatusCode::FORBIDDEN            => Code::PermissionDenied,
+        StatusCode::NOT_FOUND            => Code::NotFound,
+        StatusCode::UNPROCESSABLE_ENTITY => Code::FailedPrecondition,
+        StatusCode::TOO_MANY_REQUESTS    => Code::ResourceExhausted,
+        StatusCode::SERVICE_UNAVAILABLE  => Code::Unavailable,
+        _                                => Code::Internal,
+    }
+}
+
+fn with_retry(mut s: Status, retry: Option<u64>) -> Status {
+    if let Some(retry) = retry {
+        s.metadata_mut().insert("retry-after", retry.into());
+    }
+    s
+}
+
+/// A prove or verify failure as a gRPC status, its error code in `x-error-code`.
+fn status(err: anyhow::Error) -> Status {
+    let (http, body, retry) = errors::describe(&err);
+    let mut s = Status::new(code_for(http), body["message"].as_str().unwrap_or_default());
+    if let Ok(code) = body["code"].as_str().unwrap_or_default().parse() {
+        s.metadata_mut().insert("x-error-code", code);
+    }
+    with_retry(s, retry)
+}
+
+fn refused(r: Refused) -> Status {
+    with_retry(Status::new(code_for(r.status), r.message), r.retry_after)
+}
@@ -0,0 +1,161 @@
+//! Cheap rejection of bogus `/prove` traffic.
+//! Bodies that can never yield a proof — unparseable, a subject that fails validation for its
+//! identifier type, or `kyc`/`sig_valid` other than 1 when the tenant's flags come from the
+//! request — are turned away before quotas, attestation, plugins or the prove pool are touched.
+//! They answer `400` with the message the full path would have given, and are counted per reason
+//! instead of in `/status`, the SLA report or usage.
+//!
+//! With `ZK_HONEYPOT_STRIKES` set, each caller IP also builds a reputation: that many bogus
+//! requests within `ZK_HONEYPOT_WINDOW_SECS` (default 600) block the IP for
+//! `ZK_HONEYPOT_BLOCK_SECS` (default 900), during which every prove answers `429` with
+//! `Retry-After`.  `GET /admin/honeypot` reports the counters and the currently blocked IPs.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::request::ProveRequest;
+
+/// IPs tracked before stale entries are swept.
+const MAX_TRACKED: usize = 10_000;
+
+pub const MALFORMED_BODY: &str = "malformed_body";
+pub const MALFORMED_SUBJECT: &str = "malformed_subject";
+pub const DENIED_FLAGS: &str = "denied_flags";
+pub const BLOCKED_IP: &str = "blocked_ip";
+
+/// Why a request was diverted, and the message to answer with.
+pub struct Bogus {
+    pub reason:  &'static str,
+    pub message: String,
+}
+
+/// Returned (inside `anyhow::Error`) to an IP that is blocked.
+#[derive(Debug)]
+pub struct Blocked {
+    pub retry_after: u64,
+}
+
+impl std::fmt::Display for Blocked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "too many invalid requests")
+    }
+}
+
+impl std::error::Error for Blocked {}
+
+#[derive(Default)]
+struct Reputation {
+    strikes:       VecDeque<Instant>,
+    blocked_until: Option<Instant>,
+}
+
+pub struct Honeypot {
+    /// Bogus requests per window that block an IP; `None` disables reputation tracking.
+    strikes:  Option<usize>,
+    window:   Duration,
+    block:    Duration,
+    rejected: Mutex<BTreeMap<&'static str, u64>>,
+    ips:      Mutex<HashMap<IpAddr, Reputation>>,
+}
+
+#[derive(Serialize)]
+pub struct HoneypotReport {
+    /// Requests diverted since startup, by reason.
+    pub rejected:    BTreeMap<&'static str, u64>,
+    pub reputation:  bool,
+    /// Blocked IPs and seconds until each is let back in.
+    pub blocked_ips: BTreeMap<String, u64>,
+}
+
+impl Honeypot {
+    pub fn from_env() -> Result<Self> {
+        let secs = |k: &str, default: u64| -> Result<Duration> {
+            match std::env::var(k) {
+                Ok(v) => Ok(Duration::from_secs(v.parse().with_context(|| format!("{k} must be a number of seconds"))?)),
+                Err(_) => Ok(Duration::from_secs(default)),
+            }
+        };
+        let strikes = match std::env::var("ZK_HONEYPOT_STRIKES") {
+            Ok(v) => Some(v.parse::<usize>().context("ZK_HONEYPOT_STRIKES must be a number")?),
+            Err(_) => None,
+        };
+        if strikes == Some(0) {
+            bail!("ZK_HONEYPOT_STRIKES must be at least 1");
+        }
+        Ok(Honeypot {
+            strikes,
+            window:   secs("ZK_HONEYPOT_WINDOW_SECS", 600)?,
+            block:    secs("ZK_HONEYPOT_BLOCK_SECS", 900)?,
+            rejected: Mutex::default(),
+            ips:      Mutex::default(),
+        })
+    }
+
+    /// Checks that need no I/O; `flags_from_body` when the tenant's attestation source is the request.
+    pub fn inspect(req: &ProveRequest, flags_from_body: bool) -> Option<Bogus> {
+        let subject = req.identifier_type.canonicalize(&req.wallet);
+        let checked = req.identifier_type.check_checksum(&req.wallet)
+            .and_then(|()| req.identifier_type.validate(&subject));
+        if let Err(e) = checked {
+            return Some(Bogus { reason: MALFORMED_SUBJECT, message: e.to_string() });
+        }
+        if flags_from_body && (req.kyc != 1 || req.sig_valid != 1) {
+            return Some(Bogus { reason: DENIED_FLAGS, message: "Proof of KYC approval failed.".into() });
+        }
+        None
+    }
+
+    /// Seconds until `ip` is let back in, if it is blocked.
+    pub fn blocked(&self, ip: IpAddr) -> Option<u64> {
+        self.strikes?;
+        let now = Instant::now();
+        let until = self.ips.lock().unwrap().get(&ip)?.blocked_until.filter(|u| *u > now)?;
+        self.count(BLOCKED_IP);
+        Some(until.duration_since(now).as_secs().max(1))
+    }
+
+    /// Count a diverted request from `ip`, blocking the IP once it has struck out.
+    pub fn record(&self, ip: IpAddr, reason: &'static str) {
+        tracing::debug!(%ip, reason, "bogus prove request");
+        self.count(reason);
+        let Some(limit) = self.strikes else { return };
+        let now = Instant::now();
+        let mut ips = self.ips.lock().unwrap();
+        if ips.len() >= MAX_TRACKED {
+            ips.retain(|_, r| r.blocked_until.map_or(false, |u| u > now)
+                || r.strikes.back().map_or(false, |s| now.duration_since(*s) < self.window));
+        }
+        let rep = ips.entry(ip).or_default();
+        while rep.strikes.front().map_or(false, |s| now.duration_since(*s) >= self.window) {
+            rep.strikes.pop_front();
+        }
+        rep.strikes.push_back(now);
+        if rep.strikes.len() >= limit {
+            rep.strikes.clear();
+            rep.blocked_until = Some(now + self.block);
+            tracing::warn!(%ip, "blocking for {}s after {limit} bogus prove requests", self.block.as_secs());
+        }
+    }
+
+    fn count(&self, reason: &'static str) {
+        *self.rejected.lock().unwrap().entry(reason).or_default() += 1;
+    }
+
+    pub fn report(&self) -> HoneypotReport {
+        let now = Instant::now();
+        let blocked_ips = self.ips.lock().unwrap().iter()
+            .filter_map(|(ip, r)| r.blocked_until.filter(|u| *u > now).map(|u| (ip.to_string(), u.duration_since(now).as_secs())))
+            .collect();
+        HoneypotReport {
+            rejected: self.rejected.lock().unwrap().clone(),
+            reputation: self.strikes.is_some(),
+            blocked_ips,
+        }
+    }
+}
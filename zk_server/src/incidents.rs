@@ -0,0 +1,150 @@
+//! Issuer key-compromise recovery.
+//! `POST /admin/issuers/:id/compromise { "since", "reason", "actor", "new_ed25519_key"? }` reports
+//! that an issuer's signing key leaked at `since` (unix seconds).  From then on:
+//!
+//! * the old key verifies no attestations; `new_ed25519_key`, when given, verifies them instead;
+//! * every stored proof backed by the issuer and issued at or after `since` is revoked (with a
+//!   `proof.revoked` event), and verification reports the issuer untrusted for it;
+//! * proofs issued before `since` stay valid as they are.  They carry the issuer's id, not its
+//!   signature, so there is nothing to re-sign.
+//!
+//! Each report becomes an [`Incident`], kept in `ZK_INCIDENTS_FILE` (default `incidents.json`),
+//! re-applied at startup and published at `GET /incidents[/:id]` for relying parties, and sent to
+//! the lifecycle-event endpoint as `issuer.compromised`.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, sync::Mutex};
+
+use crate::{
+    issuers::{self, Compromise, IssuerTrust},
+    store::{self, ProofStore},
+    webhook::{Event, Webhook},
+};
+
+#[derive(Deserialize)]
+pub struct Report {
+    pub since:           u64,
+    pub reason:          String,
+    pub actor:           String,
+    /// Hex Ed25519 public key that replaces the compromised one.
+    #[serde(default)]
+    pub new_ed25519_key: Option<String>,
+}
+
+/// Machine-readable record of one key compromise.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Incident {
+    pub id:                String,
+    pub issuer:            String,
+    pub compromised_since: u64,
+    pub reported_at:       u64,
+    pub reason:            String,
+    pub actor:             String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_ed25519_key:   Option<String>,
+    /// Proofs revoked because they were issued in the compromised window.
+    pub revoked:           Vec<String>,
+    /// Proofs from this issuer issued before `compromised_since`, which stay valid.
+    pub still_valid:       usize,
+}
+
+pub struct Incidents {
+    file: PathBuf,
+    all:  Mutex<Vec<Incident>>,
+}
+
+impl Incidents {
+    /// Load recorded incidents and re-apply them to `trust`.
+    pub fn from_env(trust: &IssuerTrust) -> Result<Self> {
+        let file = PathBuf::from(std::env::var("ZK_INCIDENTS_FILE").unwrap_or_else(|_| "incidents.json".into()));
+        let all: Vec<Incident> = store::read_json(&file)?.unwrap_or_default();
+        for i in &all {
+            trust.mark_compromised(&i.issuer, compromise(i)?);
+        }
+        if !all.is_empty() {
+            tracing::warn!("{} issuer key compromises on record in {}", all.len(), file.display());
+        }
+        Ok(Incidents { file, all: Mutex::new(all) })
+    }
+
+    pub fn list(&self) -> Vec<Incident> {
+        self.all.lock().unwrap().clone()
+    }
+
+    pub fn get(&self, id: &str) -> Option<Incident> {
+        self.all.lock().unwrap().iter().find(|i| i.id == id).cloned()
+    }
+
+    /// Mark `issuer`'s key compromised, revoke what it backed since, and record the incident.
+    pub fn report(
+        &self,
+        trust:  &IssuerTrust,
+        store:  Option<&ProofStore>,
+        events: Option<&Webhook>,
+        issuer: &str,
+        r:      Report,
+    ) -> Result<Incident> {
+        if trust.get(issuer).is_none() {
+            bail!("issuer {issuer} is not trusted");
+        }
+        let now = store::now();
+        if r.since > now {
+            bail!("since is in the future");
+        }
+        let mut incident = Incident {
+            id:                format!("inc_{}", uuid::Uuid::new_v4().simple()),
+            issuer:            issuer.to_string(),
+            compromised_since: r.since,
+            reported_at:       now,
+            reason:            r.reason,
+            actor:             r.actor,
+            new_ed25519_key:   r.new_ed25519_key,
+            revoked:           Vec::new(),
+            still_valid:       0,
+        };
+        // Stop trusting the key first, so nothing new is issued while proofs are being revoked.
+        trust.mark_compromised(issuer, compromise(&incident)?);
+        tracing::error!(incident = %incident.id, actor = %incident.actor, "issuer {issuer}'s key reported compromised since {}", r.since);
+
+        if let Some(store) = store {
+            let backed = store.scan(|p| p.issuer.as_deref() == Some(issuer))?;
+            let reason = format!("issuer {issuer}'s key compromised (incident {})", incident.id);
+            for p in backed {
+                if p.created_at < r.since {
+                    incident.still_valid += 1;
+                    continue;
+                }
+                let rev = store.revoke(&p.id, &reason)?;
+                tracing::warn!(proof = %p.id, tenant = %p.tenant, "proof revoked: {reason}");
+                if let Some(events) = events {
+                    events.emit(Event::new("proof.revoked", "revocation", serde_json::json!({
+                        "proof_id":   rev.proof_id,
+                        "tenant":     p.tenant,
+                        "reason":     rev.reason,
+                        "revoked_at": rev.revoked_at,
+                        "incident":   incident.id,
+                    })));
+                }
+                incident.revoked.push(p.id);
+            }
+        }
+
+        let mut all = self.all.lock().unwrap();
+        all.push(incident.clone());
+        store::write_json(&self.file, &*all)?;
+        drop(all);
+        if let Some(events) = events {
+            events.emit(Event::new("issuer.compromised", "incident", &incident));
+        }
+        Ok(incident)
+    }
+}
+
+fn compromise(i: &Incident) -> Result<Compromise> {
+    Ok(Compromise {
+        since:       i.compromised_since,
+        reported_at: i.reported_at,
+        new_key:     i.new_ed25519_key.as_deref().map(|k| issuers::parse_key(&i.issuer, k)).transpose()?,
+    })
+}
@@ -0,0 +1,183 @@
+//! Per-IP caps on the prove path (`/prove`, `/legacy/prove`, `/proofs/:id/renew`).
+//! Independent of tenants and API keys: `ZK_IP_MAX_CONCURRENT` bounds the prove requests one
+//! client IP may have open at once, and `ZK_IP_RATE_PER_MIN` how many it may start per minute,
+//! in bursts of up to `ZK_IP_BURST` (default the per-minute rate).  Either unset means no cap.
+//! Over a cap the request is answered `429` with `Retry-After` before anything is parsed.
+//!
+//! Behind load balancers every request arrives from the balancer, so `ZK_TRUSTED_PROXIES`
+//! (comma-separated IPs or CIDRs) names the hops whose `X-Forwarded-For` is believed: the client
+//! is the right-most address in that header that is not itself a trusted proxy.  Requests from
+//! any other peer are keyed by the peer address, whatever they claim.
+
+use anyhow::{bail, Context, Result};
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use crate::{errors::Refused, ratelimit::Bucket, AppState};
+
+/// IPs tracked before idle entries are swept.
+const MAX_TRACKED: usize = 10_000;
+
+/// An address block, `addr/prefix`.
+#[derive(Clone, Copy, Debug)]
+struct Cidr {
+    addr:   IpAddr,
+    prefix: u32,
+}
+
+impl Cidr {
+    fn parse(s: &str) -> Result<Self> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((a, p)) => (a, Some(p)),
+            None         => (s, None),
+        };
+        let addr: IpAddr = addr.parse().with_context(|| format!("bad address {s:?}"))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p.parse().with_context(|| format!("bad prefix in {s:?}"))?,
+            None    => max,
+        };
+        if prefix > max {
+            bail!("prefix of {s:?} exceeds {max}");
+        }
+        Ok(Cidr { addr, prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        let mask = |bits: u32, width: u32| if bits == 0 { 0 } else { u128::MAX << (width - bits) };
+        match (self.addr, ip) {
+            (IpAddr::V4(a), IpAddr::V4(b)) => {
+                let m = mask(self.prefix, 32) as u32;
+                u32::from(a) & m == u32::from(b) & m
+            }
+            (IpAddr::V6(a), IpAddr::V6(b)) => {
+                let m = mask(self.prefix, 128);
+                u128::from(a) & m == u128::from(b) & m
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Default)]
+struct PerIp {
+    open:   usize,
+    bucket: Option<Bucket>,
+}
+
+pub struct IpLimits {
+    max_concurrent: Option<usize>,
+    rate_per_min:   Option<f64>,
+    burst:          f64,
+    trusted:        Vec<Cidr>,
+    ips:            Mutex<HashMap<IpAddr, PerIp>>,
+}
+
+/// Holds one of an IP's concurrent prove slots until dropped.
+pub struct Slot<'a> {
+    limits: &'a IpLimits,
+    ip:     IpAddr,
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        if let Some(e) = self.limits.ips.lock().unwrap().get_mut(&self.ip) {
+            e.open = e.open.saturating_sub(1);
+        }
+    }
+}
+
+impl IpLimits {
+    pub fn from_env() -> Result<Self> {
+        let num = |k: &str| -> Result<Option<u64>> {
+            match std::env::var(k) {
+                Ok(v) if !v.is_empty() => Ok(Some(v.parse().with_context(|| format!("{k} must be a number"))?)),
+                _ => Ok(None),
+            }
+        };
+        let trusted = std::env::var("ZK_TRUSTED_PROXIES").unwrap_or_default()
+            .split(',').map(str::trim).filter(|s| !s.is_empty())
+            .map(Cidr::parse)
+            .collect::<Result<Vec<_>>>()
+            .context("ZK_TRUSTED_PROXIES")?;
+        let rate_per_min = num("ZK_IP_RATE_PER_MIN")?.map(|n| n.max(1) as f64);
+        Ok(IpLimits {
+            max_concurrent: num("ZK_IP_MAX_CONCURRENT")?.map(|n| n.max(1) as usize),
+            rate_per_min,
+            burst:          num("ZK_IP_BURST")?.map_or(rate_per_min.unwrap_or(1.0), |n| n.max(1) as f64),
+            trusted,
+            ips:            Mutex::default(),
+        })
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted.iter().any(|c| c.contains(ip))
+    }
+
+    /// The client behind `peer`, looking through trusted proxies' `X-Forwarded-For`.
+    pub fn client_ip(&self, peer: SocketAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted(peer.ip()) {
+            return peer.ip();
+        }
+        let hops: Vec<IpAddr> = headers.get_all("x-forwarded-for").iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .filter_map(|h| h.trim().parse().ok())
+            .collect();
+        hops.into_iter().rev().find(|ip| !self.is_trusted(*ip)).unwrap_or(peer.ip())
+    }
+
+    /// Take a rate token and a concurrency slot for `ip`, or the `429` to answer with.
+    pub fn admit(&self, ip: IpAddr) -> Result<Option<Slot<'_>>, Refused> {
+        if self.max_concurrent.is_none() && self.rate_per_min.is_none() {
+            return Ok(None);
+        }
+        let now = Instant::now();
+        let mut ips = self.ips.lock().unwrap();
+        if ips.len() >= MAX_TRACKED {
+            let rate = self.rate_per_min.unwrap_or(1.0);
+            ips.retain(|_, e| e.open > 0 || e.bucket.map_or(false, |b| !b.is_full(rate, self.burst, now)));
+        }
+        let e = ips.entry(ip).or_default();
+        if self.max_concurrent.map_or(false, |max| e.open >= max) {
+            return Err(too_many("too many concurrent prove requests from this address", 1));
+        }
+        if let Some(rate) = self.rate_per_min {
+            let bucket = e.bucket.get_or_insert_with(|| Bucket::full(self.burst, now));
+            if let Err(retry) = bucket.take(rate, self.burst, now) {
+                return Err(too_many("prove rate limit exceeded for this address", retry));
+            }
+        }
+        e.open += 1;
+        Ok(Some(Slot { limits: self, ip }))
+    }
+}
+
+fn too_many(msg: &'static str, retry: u64) -> Refused {
+    Refused { retry_after: Some(retry.max(1)), ..Refused::new(StatusCode::TOO_MANY_REQUESTS, msg) }
+}
+
+/// Middleware for the prove routes.
+pub async fn guard<B>(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let ip = state.ip_limits.client_ip(peer, &headers);
+    match state.ip_limits.admit(ip) {
+        Ok(_slot) => next.run(req).await,
+        Err(refused) => refused.into_response(),
+    }
+}
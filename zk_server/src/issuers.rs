@@ -0,0 +1,236 @@
+//! Trusted attestation issuers.
+//! An issuer is whoever vouches for a subject's KYC status: a tenant's configured source, or a
+//! party that signs attestations carried in the `/prove` body.  `issuers.toml` (`ZK_ISSUERS_FILE`)
+//! lists the issuers this server accepts:
+//!
+//! ```toml
+//! [issuers.acme-kyc]
+//! did          = "did:web:kyc.acme.example"
+//! ed25519_key  = "…64 hex chars…"        # verifies signed attestations (source = "signed")
+//! circuits     = ["check_kyc_full"]      # optional; absent = every circuit
+//! not_before   = 1704067200              # optional validity window, unix seconds
+//! not_after    = 1798761600
+//! max_age_secs = 86400                   # how old a signed attestation may be (default 1 day)
+//! ```
+//!
+//! A signed attestation is `{ "issuer", "issued_at", "signature" }`, where `signature` is the
+//! hex Ed25519 signature over `zkkyc-attestation:v1:<identifier>:<kyc>:<sig_valid>:<issued_at>`
+//! and `<identifier>` is the canonical form of the subject.  An issuer vouching for the subject's
+//! policy attributes too adds `attributes` (`{ kyc_tier?, country?, sanctioned? }`) and signs
+//! `zkkyc-attestation:v2:<identifier>:<kyc>:<sig_valid>:<issued_at>:<kyc_tier>:<country>:<sanctioned>`,
+//! an absent attribute as the empty string.
+//!
+//! Each proof records the issuer that backed it.  Issuance fails when that issuer is unknown,
+//! outside its window or not accepted for the circuit; verification reports whether the trust
+//! store still accepts it, and `?issuers=` narrows acceptance to a subset (ids or DIDs).
+//!
+//! An issuer whose key is reported compromised (see incidents.rs) backs nothing issued from the
+//! compromise on.  Its old key verifies no further attestations; a replacement key, if given,
+//! takes over for proofs issued after the report.
+
+use anyhow::{anyhow, bail, Context, Result};
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::Path, sync::RwLock};
+
+use crate::policy::SubjectAttributes;
+
+fn default_max_age() -> u64 { 86_400 }
+
+#[derive(Deserialize)]
+struct IssuerConfig {
+    did:          Option<String>,
+    ed25519_key:  Option<String>,
+    #[serde(default)]
+    circuits:     Vec<String>,
+    not_before:   Option<u64>,
+    not_after:    Option<u64>,
+    #[serde(default = "default_max_age")]
+    max_age_secs: u64,
+}
+
+#[derive(Deserialize, Default)]
+struct IssuersFile {
+    #[serde(default)]
+    issuers: HashMap<String, IssuerConfig>,
+}
+
+/// Public view of a trusted issuer, as served by `GET /issuers`.
+#[derive(Clone, Debug, Serialize)]
+pub struct Issuer {
+    pub id:           String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub did:          Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "hex_key")]
+    pub ed25519_key:  Option<VerifyingKey>,
+    /// Circuits the issuer may back; empty means all.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub circuits:     Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not_before:   Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not_after:    Option<u64>,
+    pub max_age_secs: u64,
+}
+
+fn hex_key<S: serde::Serializer>(k: &Option<VerifyingKey>, s: S) -> Result<S::Ok, S::Error> {
+    match k {
+        Some(k) => s.serialize_str(&hex::encode(k.as_bytes())),
+        None    => s.serialize_none(),
+    }
+}
+
+impl Issuer {
+    /// Whether `name` is this issuer's id or DID.
+    pub fn is(&self, name: &str) -> bool {
+        self.id == name || self.did.as_deref() == Some(name)
+    }
+
+    fn in_window(&self, at: u64) -> bool {
+        self.not_before.map_or(true, |nb| at >= nb) && self.not_after.map_or(true, |na| at <= na)
+    }
+
+    fn accepts(&self, circuit: &str) -> bool {
+        self.circuits.is_empty() || self.circuits.iter().any(|c| c == circuit)
+    }
+}
+
+/// Attestation signed by an issuer, carried in the `/prove` body.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SignedAttestation {
+    pub issuer:    String,
+    /// Unix seconds.
+    pub issued_at: u64,
+    /// Hex Ed25519 signature.
+    pub signature: String,
+    /// Policy attributes the issuer vouches for; signed with the v2 message.
+    #[serde(default)]
+    pub attributes: Option<SubjectAttributes>,
+}
+
+impl SignedAttestation {
+    pub fn message(identifier: &str, kyc: i32, sig_valid: i32, issued_at: u64, attributes: Option<&SubjectAttributes>) -> String {
+        let Some(a) = attributes else {
+            return format!("zkkyc-attestation:v1:{identifier}:{kyc}:{sig_valid}:{issued_at}");
+        };
+        let opt = |v: Option<String>| v.unwrap_or_default();
+        format!(
+            "zkkyc-attestation:v2:{identifier}:{kyc}:{sig_valid}:{issued_at}:{}:{}:{}",
+            opt(a.kyc_tier.map(|t| t.to_string())), opt(a.country.clone()), opt(a.sanctioned.map(|s| s.to_string())),
+        )
+    }
+}
+
+/// A reported key compromise, as the trust store applies it.
+#[derive(Clone, Copy)]
+pub struct Compromise {
+    /// Nothing issued from here on is backed by the old key.
+    pub since:       u64,
+    pub reported_at: u64,
+    /// Verifies the issuer's attestations from `reported_at` on.
+    pub new_key:     Option<VerifyingKey>,
+}
+
+impl Compromise {
+    /// Whether a proof issued at `at` may have been backed by the compromised key.
+    fn taints(&self, at: u64) -> bool {
+        at >= self.since && (self.new_key.is_none() || at < self.reported_at)
+    }
+}
+
+#[derive(Default)]
+pub struct IssuerTrust {
+    issuers:     HashMap<String, Issuer>,
+    compromised: RwLock<HashMap<String, Compromise>>,
+}
+
+/// Parse a hex Ed25519 public key.
+pub fn parse_key(id: &str, k: &str) -> Result<VerifyingKey> {
+    let bytes: [u8; 32] = hex::decode(k).ok().and_then(|b| b.try_into().ok())
+        .ok_or_else(|| anyhow!("issuer {id}: ed25519_key must be 32 hex-encoded bytes"))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| anyhow!("issuer {id}: {e}"))
+}
+
+impl IssuerTrust {
+    pub fn load(path: &Path) -> Result<Self> {
+        let file: IssuersFile = if path.exists() {
+            let raw = std::fs::read_to_string(path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            toml::from_str(&raw).with_context(|| format!("parsing {}", path.display()))?
+        } else {
+            IssuersFile::default()
+        };
+        let mut issuers = HashMap::new();
+        for (id, cfg) in file.issuers {
+            let ed25519_key = cfg.ed25519_key.as_deref().map(|k| parse_key(&id, k)).transpose()?;
+            if let (Some(nb), Some(na)) = (cfg.not_before, cfg.not_after) {
+                if nb > na { bail!("issuer {id}: not_before is after not_after"); }
+            }
+            issuers.insert(id.clone(), Issuer {
+                id, did: cfg.did, ed25519_key, circuits: cfg.circuits,
+                not_before: cfg.not_before, not_after: cfg.not_after, max_age_secs: cfg.max_age_secs,
+            });
+        }
+        Ok(Self { issuers, compromised: RwLock::default() })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Issuer> {
+        self.issuers.get(id)
+    }
+
+    /// Every issuer, sorted by id.
+    pub fn list(&self) -> Vec<&Issuer> {
+        let mut all: Vec<_> = self.issuers.values().collect();
+        all.sort_by(|a, b| a.id.cmp(&b.id));
+        all
+    }
+
+    /// Record that `id`'s key is compromised; a later report for the same issuer replaces it.
+    pub fn mark_compromised(&self, id: &str, c: Compromise) {
+        self.compromised.write().unwrap().insert(id.to_string(), c);
+    }
+
+    pub fn compromise(&self, id: &str) -> Option<Compromise> {
+        self.compromised.read().unwrap().get(id).copied()
+    }
+
+    /// Check that `id` may back a `circuit` proof issued at `at`.
+    pub fn admit(&self, id: &str, circuit: &str, at: u64) -> Result<()> {
+        let Some(i) = self.get(id) else { bail!("issuer {id} is not trusted") };
+        if !i.in_window(at) {
+            bail!("issuer {id} is outside its validity window");
+        }
+        if let Some(c) = self.compromise(id).filter(|c| c.taints(at)) {
+            bail!("issuer {id}'s key is compromised since {}", c.since);
+        }
+        // A `_nonce` export is accepted wherever the export it extends is.
+        let base = crate::policy::circuit(circuit).map_or(circuit, |c| c.base());
+        if !i.accepts(base) {
+            bail!("issuer {id} is not accepted for circuit {circuit}");
+        }
+        Ok(())
+    }
+
+    /// [`admit`](Self::admit) as a verdict, for proofs already issued.
+    pub fn trusts(&self, id: &str, circuit: &str, issued_at: u64) -> bool {
+        self.admit(id, circuit, issued_at).is_ok()
+    }
+
+    /// Check `a`'s signature over the subject's flags (and attributes, if any) and its age.
+    pub fn verify(&self, a: &SignedAttestation, identifier: &str, kyc: i32, sig_valid: i32, now: u64) -> Result<()> {
+        let Some(i) = self.get(&a.issuer) else { bail!("issuer {} is not trusted", a.issuer) };
+        let key = match self.compromise(&a.issuer) {
+            Some(c) => c.new_key.ok_or_else(|| anyhow!("issuer {}'s key is compromised and has no replacement", a.issuer))?,
+            None    => i.ed25519_key.ok_or_else(|| anyhow!("issuer {} has no signing key configured", a.issuer))?,
+        };
+        if a.issued_at > now + 300 || now.saturating_sub(a.issued_at) > i.max_age_secs {
+            bail!("attestation from {} is stale or from the future", a.issuer);
+        }
+        let sig = hex::decode(&a.signature).ok()
+            .and_then(|b| Signature::from_slice(&b).ok())
+            .context("attestation signature must be 64 hex-encoded bytes")?;
+        let msg = SignedAttestation::message(identifier, kyc, sig_valid, a.issued_at, a.attributes.as_ref());
+        key.verify_strict(msg.as_bytes(), &sig)
+            .map_err(|_| anyhow!("attestation signature from {} does not verify", a.issuer))
+    }
+}
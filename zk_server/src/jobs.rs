@@ -0,0 +1,223 @@
+//! Asynchronous prove jobs.
+//! `POST /prove?async=true` (or with `Prefer: respond-async`) validates the body, answers `202`
+//! with a `job_id` and proves in the background; `GET /jobs/:id` reports `queued`, `running`,
+//! `succeeded` (with the normal `/prove` response as `result`) or `failed` (with its `status` and
+//! `error` body).  At most `ZK_JOB_CONCURRENCY` jobs prove at once (default: the prove pool's
+//! capacity); the rest wait their turn instead of being turned away as `overloaded`.  Finished
+//! jobs are kept for `ZK_JOB_TTL_SECS` (default 3600) and are only visible to the tenant that
+//! submitted them.  Jobs live in memory; only queued jobs survive a restart, and only when handed
+//! over through maintenance mode (see `maintenance.rs`).
+//!
+//! While running, a job reports its [`Phase`].  `GET /jobs/:id/events` streams every state and
+//! phase change as server-sent events (event name = state, data = the state as in `/jobs/:id`),
+//! starting with the current one and ending after `succeeded` or `failed`.  Folding steps and
+//! compression happen inside one engine call, so they show as a single `proving` phase.
+
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, net::{IpAddr, SocketAddr}, sync::{Arc, Mutex}};
+use tokio::sync::{watch, Semaphore};
+use utoipa::ToSchema;
+
+use crate::store;
+
+/// Open and finished jobs beyond this are refused.
+const MAX_JOBS: usize = 10_000;
+
+/// Where a running job is.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// Attestation, consent, policy and quota checks.
+    Checking,
+    /// Waiting for a slot in the prove pool.
+    Waiting,
+    /// Building the Nova parameters for the step size (inline proving, first use only).
+    Setup,
+    /// Proving and verifying the SNARK.
+    Proving,
+    /// Storing and replicating the proof.
+    Storing,
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        phase: Option<Phase>,
+    },
+    /// `result` is the `/prove` response.
+    Succeeded {
+        #[schema(value_type = Object)]
+        result: serde_json::Value,
+    },
+    /// `error` is the error body `/prove` would have answered with.
+    Failed {
+        status: u16,
+        #[schema(value_type = Object)]
+        error:  serde_json::Value,
+    },
+}
+
+impl JobState {
+    /// The `state` tag, used as the SSE event name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobState::Queued           => "queued",
+            JobState::Running { .. }   => "running",
+            JobState::Succeeded { .. } => "succeeded",
+            JobState::Failed { .. }    => "failed",
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobState::Succeeded { .. } | JobState::Failed { .. })
+    }
+}
+
+/// Reports a running job's phase to `GET /jobs/:id` and its event stream.
+#[derive(Clone)]
+pub struct Progress(Arc<watch::Sender<JobState>>);
+
+impl Progress {
+    pub fn phase(&self, phase: Phase) {
+        self.0.send_replace(JobState::Running { phase: Some(phase) });
+    }
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct Job {
+    pub job_id:      String,
+    #[serde(skip)]
+    pub tenant:      String,
+    #[serde(flatten)]
+    pub state:       JobState,
+    pub created_at:  u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<u64>,
+    /// The request, while the job is still queued.
+    #[serde(skip)]
+    pub pending:     Option<PendingJob>,
+    /// Current state, for subscribers; `state` is refreshed from it.
+    #[serde(skip)]
+    events:          Arc<watch::Sender<JobState>>,
+}
+
+/// What a queued job needs to run, so it can be handed to the next process.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PendingJob {
+    pub job_id:     String,
+    pub tenant:     String,
+    /// Client address, looking through trusted proxies; jobs handed over by older builds carry
+    /// the socket peer.
+    #[serde(alias = "peer", deserialize_with = "ip_or_socket")]
+    pub caller:     IpAddr,
+    /// `/prove` query parameters.
+    pub params:     serde_json::Value,
+    pub body:       serde_json::Value,
+    pub created_at: u64,
+}
+
+fn ip_or_socket<'de, D: serde::Deserializer<'de>>(d: D) -> Result<IpAddr, D::Error> {
+    let s = String::deserialize(d)?;
+    s.parse().or_else(|_| s.parse::<SocketAddr>().map(|a| a.ip())).map_err(serde::de::Error::custom)
+}
+
+pub struct Jobs {
+    ttl:   u64,
+    slots: Arc<Semaphore>,
+    jobs:  Mutex<HashMap<String, Job>>,
+}
+
+impl Jobs {
+    pub fn from_env(capacity: usize) -> Self {
+        let num = |k: &str| std::env::var(k).ok().and_then(|v| v.parse::<u64>().ok());
+        let concurrency = num("ZK_JOB_CONCURRENCY").map_or(capacity, |c| c as usize).max(1);
+        Jobs {
+            ttl:   num("ZK_JOB_TTL_SECS").unwrap_or(3600),
+            slots: Arc::new(Semaphore::new(concurrency)),
+            jobs:  Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn prune(&self, jobs: &mut HashMap<String, Job>, now: u64) {
+        jobs.retain(|_, j| j.finished_at.map_or(true, |f| f + self.ttl > now));
+    }
+
+    /// Register a queued job for `tenant`; `None` when too many are held.
+    pub fn create(&self, tenant: &str, caller: IpAddr, params: serde_json::Value, body: serde_json::Value) -> Option<PendingJob> {
+        let p = PendingJob {
+            job_id:     uuid::Uuid::new_v4().to_string(),
+            tenant:     tenant.to_string(),
+            caller,
+            params,
+            body,
+            created_at: store::now(),
+        };
+        self.restore(p.clone()).then_some(p)
+    }
+
+    /// Re-register a job queued by a previous process, under its id; `false` when too many are held.
+    pub fn restore(&self, p: PendingJob) -> bool {
+        let mut jobs = self.jobs.lock().unwrap();
+        self.prune(&mut jobs, store::now());
+        if jobs.len() >= MAX_JOBS {
+            return false;
+        }
+        jobs.insert(p.job_id.clone(), Job {
+            job_id:      p.job_id.clone(),
+            tenant:      p.tenant.clone(),
+            state:       JobState::Queued,
+            created_at:  p.created_at,
+            finished_at: None,
+            pending:     Some(p),
+            events:      Arc::new(watch::channel(JobState::Queued).0),
+        });
+        true
+    }
+
+    /// Jobs not yet started, oldest first.
+    pub fn queued(&self) -> Vec<PendingJob> {
+        let mut q: Vec<_> = self.jobs.lock().unwrap().values().filter_map(|j| j.pending.clone()).collect();
+        q.sort_by_key(|p| p.created_at);
+        q
+    }
+
+    /// Semaphore gating how many jobs prove at once.
+    pub fn slots(&self) -> Arc<Semaphore> {
+        self.slots.clone()
+    }
+
+    pub fn set(&self, id: &str, state: JobState) {
+        if let Some(j) = self.jobs.lock().unwrap().get_mut(id) {
+            if state.is_finished() {
+                j.finished_at = Some(store::now());
+            }
+            j.events.send_replace(state.clone());
+            j.state = state;
+            j.pending = None;
+        }
+    }
+
+    /// Phase reporter for job `id`.
+    pub fn progress(&self, id: &str) -> Option<Progress> {
+        self.jobs.lock().unwrap().get(id).map(|j| Progress(j.events.clone()))
+    }
+
+    /// `id` if it exists and belongs to `tenant`.
+    pub fn get(&self, id: &str, tenant: &str) -> Option<Job> {
+        let mut jobs = self.jobs.lock().unwrap();
+        self.prune(&mut jobs, store::now());
+        let mut job = jobs.get(id).filter(|j| j.tenant == tenant).cloned()?;
+        job.state = job.events.borrow().clone();
+        Some(job)
+    }
+
+    /// State changes of `id` (if it belongs to `tenant`), starting with the current state.
+    pub fn subscribe(&self, id: &str, tenant: &str) -> Option<watch::Receiver<JobState>> {
+        let mut jobs = self.jobs.lock().unwrap();
+        self.prune(&mut jobs, store::now());
+        jobs.get(id).filter(|j| j.tenant == tenant).map(|j| j.events.subscribe())
+    }
+}
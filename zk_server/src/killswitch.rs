@@ -0,0 +1,166 @@
+//! Emergency stop for issuance, for incident response such as a suspected issuer-key compromise.
+//! `POST /admin/issuance/halt { "reason", "actor" }` stops every proof from being issued at once:
+//! `/prove`, batches, renewals, queued jobs, gRPC `Prove` and pre-issuance fail with
+//! [`IssuanceHalted`] (HTTP 503 `ISSUANCE_HALTED`, no `Retry-After`), and a proof that finishes
+//! after the halt is dropped rather than stored or returned.  Verification, proof retrieval and
+//! revocation carry on.
+//!
+//! Unlike maintenance mode the halt survives restarts: it is kept in `ZK_KILL_SWITCH_FILE`
+//! (default `kill_switch.json`).  Issuance only resumes through
+//! `POST /admin/issuance/resume { "reason", "actor", "halt_id" }`, where `halt_id` must name the
+//! current halt so a stale or replayed request cannot lift a newer one.  Every halt and resume is
+//! appended to `ZK_KILL_SWITCH_AUDIT` (default `kill_switch_audit.jsonl`) and logged.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{io::Write, path::PathBuf, sync::Mutex};
+
+use crate::store;
+
+/// Returned (inside `anyhow::Error`) for prove requests while issuance is halted.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IssuanceHalted {
+    pub halt_id: String,
+    pub reason:  String,
+    pub actor:   String,
+    pub since:   u64,
+}
+
+impl std::fmt::Display for IssuanceHalted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "proof issuance is halted: {}", self.reason)
+    }
+}
+
+impl std::error::Error for IssuanceHalted {}
+
+#[derive(Deserialize)]
+pub struct Halt {
+    pub reason: String,
+    pub actor:  String,
+}
+
+#[derive(Deserialize)]
+pub struct Resume {
+    pub reason:  String,
+    pub actor:   String,
+    pub halt_id: String,
+}
+
+/// One line of the audit log.
+#[derive(Deserialize, Serialize)]
+pub struct AuditEntry {
+    pub at:      u64,
+    /// `halt` or `resume`.
+    pub action:  String,
+    pub halt_id: String,
+    pub actor:   String,
+    pub reason:  String,
+}
+
+pub enum Resumed {
+    Resumed,
+    NotHalted,
+    /// `halt_id` is not the current halt's.
+    WrongHalt(IssuanceHalted),
+}
+
+pub struct KillSwitch {
+    file:  PathBuf,
+    audit: PathBuf,
+    state: Mutex<Option<IssuanceHalted>>,
+}
+
+impl KillSwitch {
+    /// Load a halt left by a previous process.
+    pub fn from_env() -> Result<Self> {
+        let file = PathBuf::from(std::env::var("ZK_KILL_SWITCH_FILE").unwrap_or_else(|_| "kill_switch.json".into()));
+        let audit = PathBuf::from(std::env::var("ZK_KILL_SWITCH_AUDIT").unwrap_or_else(|_| "kill_switch_audit.jsonl".into()));
+        Self::open(file, audit)
+    }
+
+    pub fn open(file: PathBuf, audit: PathBuf) -> Result<Self> {
+        let halted: Option<IssuanceHalted> = store::read_json(&file)?;
+        if let Some(h) = &halted {
+            tracing::warn!(halt_id = %h.halt_id, actor = %h.actor, "issuance halted since {}: {}", h.since, h.reason);
+        }
+        Ok(KillSwitch { file, audit, state: Mutex::new(halted) })
+    }
+
+    /// `Err` while issuance is halted.
+    pub fn check(&self) -> Result<(), IssuanceHalted> {
+        match &*self.state.lock().unwrap() {
+            Some(h) => Err(h.clone()),
+            None    => Ok(()),
+        }
+    }
+
+    pub fn status(&self) -> Option<IssuanceHalted> {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Halt issuance; an existing halt is kept, and `false` says so.
+    pub fn halt(&self, h: Halt) -> Result<(IssuanceHalted, bool)> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(current) = &*state {
+            return Ok((current.clone(), false));
+        }
+        let halted = IssuanceHalted {
+            halt_id: format!("halt_{}", uuid::Uuid::new_v4().simple()),
+            reason:  h.reason,
+            actor:   h.actor,
+            since:   store::now(),
+        };
+        // Stop issuance before anything can fail; the file only makes it outlive the process.
+        *state = Some(halted.clone());
+        tracing::error!(halt_id = %halted.halt_id, actor = %halted.actor, "issuance halted: {}", halted.reason);
+        store::write_json(&self.file, &halted)?;
+        self.record("halt", &halted.halt_id, &halted.actor, &halted.reason)?;
+        Ok((halted, true))
+    }
+
+    pub fn resume(&self, r: Resume) -> Result<Resumed> {
+        let mut state = self.state.lock().unwrap();
+        match &*state {
+            None                               => return Ok(Resumed::NotHalted),
+            Some(h) if h.halt_id != r.halt_id  => return Ok(Resumed::WrongHalt(h.clone())),
+            Some(_)                            => {}
+        }
+        self.record("resume", &r.halt_id, &r.actor, &r.reason)?;
+        match std::fs::remove_file(&self.file) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("removing {}", self.file.display())),
+        }
+        *state = None;
+        tracing::warn!(halt_id = %r.halt_id, actor = %r.actor, "issuance resumed: {}", r.reason);
+        Ok(Resumed::Resumed)
+    }
+
+    /// The last `limit` audit entries, oldest first.
+    pub fn audit_log(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        let raw = match std::fs::read_to_string(&self.audit) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("reading {}", self.audit.display())),
+        };
+        let entries: Vec<AuditEntry> = raw.lines().filter_map(|l| serde_json::from_str(l).ok()).collect();
+        let skip = entries.len().saturating_sub(limit);
+        Ok(entries.into_iter().skip(skip).collect())
+    }
+
+    fn record(&self, action: &str, halt_id: &str, actor: &str, reason: &str) -> Result<()> {
+        let entry = AuditEntry {
+            at:      store::now(),
+            action:  action.into(),
+            halt_id: halt_id.into(),
+            actor:   actor.into(),
+            reason:  reason.into(),
+        };
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        std::fs::OpenOptions::new().create(true).append(true).open(&self.audit)
+            .and_then(|mut f| f.write_all(&line))
+            .with_context(|| format!("appending to {}", self.audit.display()))
+    }
+}
@@ -0,0 +1,102 @@
+//! Server-enforced bounds on the `/prove` step size.
+//! Memory and setup time grow with `step`, so the accepted range is configured in `limits.toml`
+//! (`ZK_LIMITS_FILE`), optionally narrowed to an explicit list and overridden per tenant.
+//! Bounds the file leaves out come from the server config (`min_step` / `max_step`).
+//! Out-of-range requests fail with [`StepOutOfRange`] (HTTP 400 `step_out_of_range`).
+//!
+//! ```toml
+//! min_step = 1
+//! max_step = 64
+//! allowed  = [4, 8, 16, 32, 64]   # optional; must also lie within min..=max
+//!
+//! [tenants.acme]
+//! max_step = 128
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::Path};
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct TenantLimits {
+    pub min_step: Option<usize>,
+    pub max_step: Option<usize>,
+    pub allowed:  Option<Vec<usize>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct LimitsFile {
+    #[serde(default)]
+    min_step: Option<usize>,
+    #[serde(default)]
+    max_step: Option<usize>,
+    #[serde(default)]
+    allowed:  Option<Vec<usize>>,
+    #[serde(default)]
+    tenants:  HashMap<String, TenantLimits>,
+}
+
+/// Returned (inside `anyhow::Error`) when a request's `step` is outside the tenant's bounds.
+#[derive(Debug, Serialize)]
+pub struct StepOutOfRange {
+    pub step:     usize,
+    pub min_step: usize,
+    pub max_step: usize,
+    /// Explicit allow-list, when one is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed:  Option<Vec<usize>>,
+}
+
+impl std::fmt::Display for StepOutOfRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.allowed {
+            Some(a) => write!(f, "step {} is not allowed (allowed: {a:?})", self.step),
+            None    => write!(f, "step {} is out of range (allowed: {}..={})", self.step, self.min_step, self.max_step),
+        }
+    }
+}
+
+impl std::error::Error for StepOutOfRange {}
+
+pub struct Limits {
+    cfg:      LimitsFile,
+    min_step: usize,
+    max_step: usize,
+}
+
+impl Limits {
+    /// Load from `path`; bounds it leaves out (or a missing file) are `min_step..=max_step`.
+    pub fn load(path: &Path, min_step: usize, max_step: usize) -> Result<Self> {
+        let cfg: LimitsFile = if path.exists() {
+            let raw = std::fs::read_to_string(path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            toml::from_str(&raw).with_context(|| format!("parsing {}", path.display()))?
+        } else {
+            LimitsFile::default()
+        };
+        let min_step = cfg.min_step.unwrap_or(min_step);
+        let max_step = cfg.max_step.unwrap_or(max_step);
+        anyhow::ensure!(min_step >= 1 && min_step <= max_step,
+                        "{}: need 1 <= min_step <= max_step", path.display());
+        Ok(Self { cfg, min_step, max_step })
+    }
+
+    /// Accept `step` for `tenant` (its overrides, falling back to the file-wide values), or fail
+    /// with [`StepOutOfRange`].
+    pub fn check_step(&self, tenant: &str, step: usize) -> Result<()> {
+        let t = self.cfg.tenants.get(tenant).cloned().unwrap_or_default();
+        let min_step = t.min_step.unwrap_or(self.min_step);
+        let max_step = t.max_step.unwrap_or(self.max_step);
+        let allowed = t.allowed.or_else(|| self.cfg.allowed.clone()).map(|mut a| {
+            a.retain(|s| (min_step..=max_step).contains(s));
+            a.sort_unstable();
+            a.dedup();
+            a
+        });
+        let ok = match &allowed {
+            Some(a) => a.contains(&step),
+            None    => (min_step..=max_step).contains(&step),
+        };
+        if ok { Ok(()) } else { Err(StepOutOfRange { step, min_step, max_step, allowed }.into()) }
+    }
+}
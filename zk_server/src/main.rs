@@ -1,54 +1,311 @@
 //! HTTP wrapper around the KYC proof.
-//! POST /prove  { wallet, kyc, sig_valid, step? }
+//! POST /prove[?policy=name][&full_proof=true][&proof_encoding=hex|base64]  { wallet, identifier_type?, kyc, sig_valid, step?, attestation?, consent?, siwe? }
+//!      (Accept: application/octet-stream returns the proof and instance as a binary attachment,
+//!       application/vnd.zkkyc.envelope a versioned proof envelope (kyc_core::envelope);
+//!       ?compression=none|fast|max zstd-compresses it and the full proof, default ZK_PROOF_COMPRESSION;
+//!       ?async=true or Prefer: respond-async queues a job and answers 202 { job_id })
+//! POST /prove/batch[?policy=name]  [{ wallet, kyc, sig_valid, … }, …]  one step size; per-item results + stats
+//! GET  /jobs/:id                   async prove job state and result
+//! GET  /jobs/:id/events            the job's state and phase changes as server-sent events
+//! GET  /stats/proofs[?from&to]     differentially private proofs per day and country bucket
+//! POST /legacy/prove               { wallet, kyc, sig_valid, step? }  (original demo contract)
+//! POST /siwe/message               { address, chain_id? }  SIWE message the wallet signs to prove ownership (see siwe)
+//! GET  /status                     public uptime / 1h success rate / latency
+//! GET  /metrics                    Prometheus counters, histograms and gauges
+//! GET  /openapi.json               OpenAPI 3 document of the client API (Swagger UI at /docs with feature `swagger-ui`)
+//! GET  /healthz, /readyz           liveness; readiness once parameters are warm and guests validated
+//! POST /api-keys                   { label }  self-service verify/status API key (ZK_API_KEYS_FILE)
+//! GET  /proofs                     proofs the calling tenant owns or was granted
+//! GET  /proofs/:id                 stored proof + revocation state, ETag / If-None-Match  (ZK_STORE_DIR)
+//! POST /proofs/:id/share           { tenant }  owner grants read access; DELETE …/share/:tenant
+//! GET  /proofs/:id/public-inputs   named public inputs (commitment, flags, Travel Rule digest)
+//! GET  /proofs/:id/qr              compact QR payload referencing the proof  (ZK_PUBLIC_URL)
+//! GET  /proofs/:id/verify[?scope][&issuers]  re-verify a stored proof (LRU-cached), with revocation / spent / expiry / issuer check
+//! POST /proofs/:id/renew?token=    /prove body; renew an expiring proof via its reminder link  (ZK_PROOF_TTL_DAYS)
+//! GET  /challenge[?wallet][&policy] { nonce, expires_at }  verifier nonce a recorded proof redeems (see challenge); wallet starts proving speculatively
+//! POST /verify[?challenge=]        { proof, instance, step, circuit?, public_inputs? } or an envelope  verify a caller-held proof (challenge nonce from the stored record)
+//! GET  /vks                        accepted guest builds and their verifying-key digests
+//! GET  /issuers                    trusted attestation issuers  (ZK_ISSUERS_FILE)
+//! GET  /incidents[/:id]            issuer key-compromise records  (ZK_INCIDENTS_FILE)
+//! POST /envelopes/check            { envelope, relying_parties? }  which relying-party policies an envelope meets
+//! POST /proofs/:id/spend           { scope }  spend the proof's nullifier for a relying party
+//! POST /presentations             { scope, ttl_secs? }  open a zkkyc://present deep-link request
+//! GET|POST /presentations/:id      poll the outcome | wallet presents { proof_id, nonce }
+//! POST /replication/apply          writes from another region  (bearer ZK_REPLICATION_TOKEN)
+//!
+//! Under /admin (bearer ZK_ADMIN_TOKEN):
+//! GET|PUT /admin/loglevel          { filter } | { level?, targets? }
+//! GET /admin/features, PUT|DELETE /admin/features/:name
+//! GET|PUT /admin/rollout           blue/green guest split
+//! GET|POST /admin/rollout/swap     { version, wasm, steps? }  warm a new guest, then switch to it
+//! POST /admin/selftest            prove and verify a built-in known-answer vector on each serving guest
+//! GET|POST /admin/step-sweep     { wasm?, invoke?, args?, start?, min_step?, max_step?, budget_secs? }  find the fastest step size
+//! GET  /admin/canary               dual-proving comparisons
+//! GET  /admin/scaling              queue depth, prove time, memory, replica hint
+//! GET  /admin/usage[?from&to&tenant]  CPU-seconds / memory per tenant and circuit
+//! GET  /admin/sla                  per-tenant SLO attainment and burn rates
+//! GET  /admin/honeypot             bogus prove requests by reason, blocked IPs
+//! GET  /admin/compliance[?from&to&tenant]  issued proofs with their consent records
+//! POST /admin/proofs/:id/revoke    { reason }
+//! GET /admin/api-keys, DELETE /admin/api-keys/:id
+//! GET|PUT /admin/maintenance      { enabled, message?, retry_after_secs? }  drain proving for an upgrade
+//! GET  /admin/issuance             issuance halt state and audit log
+//! POST /admin/issuance/halt        { reason, actor }  emergency stop for all issuance
+//! POST /admin/issuance/resume      { reason, actor, halt_id }  lift the current halt
+//! POST /admin/issuers/:id/compromise  { since, reason, actor, new_ed25519_key? }  revoke what a leaked key backed
+//! GET|PUT /admin/preissue         [{ tenant?, policy?, request }]  subjects to precompute proofs for off-peak
+//! GET  /admin/prove-keys           static prove keys (ZK_PROVE_KEYS_FILE), without secrets
+//! GET /admin/dead-letters[/:id], POST /admin/dead-letters/:id/replay, DELETE /admin/dead-letters/:id
+//!
+//! gRPC (`zkkyc.v1.Prover`: Prove, Verify, GetJob) is served on `grpc_bind` when set (see `grpc`).
+//!
+//! Bind address, default guest, step sizes, proving concurrency and timeouts come from
+//! `server.toml`, `ZK_*` variables or flags (see `config`).
+//!
+//! `ZK_MODE=verifier` runs a read replica: only the read/verify routes, /status, /admin and
+//! /replication/apply are served; nothing is proven or written locally.
 
 use axum::{
-    extract::State,
-    response::IntoResponse,
-    routing::post,
+    extract::{ConnectInfo, Path, Query, State},
+    middleware,
+    http::{header, Extensions, HeaderMap, HeaderValue, StatusCode, Version},
+    response::{sse::{Event, KeepAlive, Sse}, IntoResponse, Response},
+    routing::{delete, get, post},
     Json, Router,
 };
+use base64::Engine as _;
 use serde::{Deserialize, Serialize};
-use std::{path::PathBuf, sync::Arc, time::Instant};
+use sha2::{Digest, Sha256};
+use std::{net::{IpAddr, SocketAddr}, sync::Arc};
 use tokio::signal;
-
-use tiny_keccak::{Hasher, Keccak};
-use zk_engine::{
-    utils::logging::init_logger,
-    wasm_ctx::{WASMArgsBuilder, WASMCtx},
-    wasm_snark::{StepSize, WasmSNARK},
-    nova::{
-        provider::{ipa_pc, Bn256EngineIPA},
-        spartan::{
-            batched::BatchedRelaxedR1CSSNARK as BatchedSNARK,
-            snark::RelaxedR1CSSNARK          as RelaxedSNARK,
-        },
-        traits::Dual,
-    },
+use tower_http::{
+    compression::{predicate::{DefaultPredicate, Predicate}, CompressionLayer},
+    timeout::TimeoutLayer,
 };
-use anyhow::Result;
+
+use tracing::Instrument;
+use utoipa::{IntoParams, ToSchema};
+use anyhow::{Context, Result};
+use kyc_core::{encoding, engine, envelope::{self, Envelope}, public_inputs, qr::ProofRef, HashScheme, IdentifierType};
 use hex;
-use bincode;
 
-/* ---------- Nova type aliases ------------------------------------ */
-type  E  = Bn256EngineIPA;
-type  EE = ipa_pc::EvaluationEngine<E>;
-type  S1 = BatchedSNARK<E, EE>;
-type  ED = Dual<E>;
-type  S2 = RelaxedSNARK<ED, ipa_pc::EvaluationEngine<ED>>;
+mod admin;
+mod api_keys;
+mod attestation;
+mod batch;
+mod bloom;
+mod canary;
+mod challenge;
+mod compression;
+mod config;
+mod consent;
+mod deadletter;
+mod errors;
+mod expiry;
+mod features;
+mod grpc;
+mod honeypot;
+mod incidents;
+mod ip_limits;
+mod issuers;
+mod jobs;
+mod killswitch;
+mod limits;
+mod maintenance;
+mod metrics;
+mod numa;
+mod openapi;
+mod opa;
+mod outbox;
+mod plugins;
+mod policy;
+mod pool;
+mod preissue;
+mod presentation;
+mod prove_keys;
+mod prover;
+mod quota;
+mod ratelimit;
+mod redact;
+mod relying_party;
+mod replication;
+mod rescreen;
+mod request;
+mod rollout;
+mod scaling;
+mod selftest;
+mod shard;
+mod siwe;
+mod sla;
+mod swap;
+mod sweep;
+mod stats;
+mod status;
+mod store;
+mod telemetry;
+mod travel_rule;
+mod usage;
+mod verify_cache;
+mod watchdog;
+mod webhook;
+mod worker;
+use api_keys::ApiKeys;
+use attestation::{AttestationRegistry, Subject};
+use canary::{Canary, CanaryJob, Outcome};
+use challenge::Challenges;
+use compression::Compression;
+use config::ServerConfig;
+use deadletter::{DeadLetters, Kind};
+use errors::{ApiError, ErrorCode, WithCode};
+use expiry::Expiry;
+use features::FeatureFlags;
+use honeypot::Honeypot;
+use incidents::Incidents;
+use ip_limits::IpLimits;
+use issuers::IssuerTrust;
+use jobs::{JobState, Jobs, PendingJob, Phase, Progress};
+use killswitch::KillSwitch;
+use limits::{Limits, StepOutOfRange};
+use maintenance::{Maintenance, UnderMaintenance};
+use metrics::Metrics;
+use opa::{IssuanceInput, OpaHook, Screening};
+use outbox::Outbox;
+use plugins::{PluginHost, PreProveInput};
+use policy::PolicySet;
+use pool::{Overloaded, ProvePool};
+use preissue::Preissue;
+use presentation::Presentations;
+use prove_keys::ProveKeys;
+use prover::{ParamCache, ProverPanic};
+use quota::{QuotaExceeded, QuotaStatus, Quotas};
+use relying_party::RelyingParties;
+use replication::Replicator;
+use request::ProveRequest;
+use rollout::Rollout;
+use siwe::{OwnershipRecord, Siwe};
+use scaling::Load;
+use sla::SlaTracker;
+use swap::Swaps;
+use sweep::Sweeps;
+use stats::Stats;
+use status::{Health, Readiness};
+use store::{ProofStore, StoredProof};
+use telemetry::{LogHandle, SamplingConfig};
+use usage::{Meter, UsageLog};
+use verify_cache::VerifyCache;
+use webhook::Webhook;
+use watchdog::Watchdog;
+use worker::{Isolation, Priority};
 
 /* ---------- request / response structs --------------------------- */
-#[derive(Deserialize)]
-struct ProveRequest {
-    wallet:    String,
-    kyc:       i32,
-    sig_valid: i32,
-    #[serde(default = "default_step")]
-    step:      usize,
+
+#[derive(Default, Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ProveParams {
+    policy: Option<String>,
+    /// Include the full serialized proof and instance (for `kyc_equiv`).
+    #[serde(default)]
+    full_proof: bool,
+    /// Encoding of the full proof; implies `full_proof`.
+    proof_encoding: Option<ProofEncoding>,
+    /// zstd level of the full proof and binary attachment; default `ZK_PROOF_COMPRESSION`.
+    compression: Option<Compression>,
+    /// Queue a job and return its id instead of waiting for the proof.
+    #[serde(default, rename = "async")]
+    #[param(rename = "async")]
+    run_async: bool,
 }
-fn default_step() -> usize { 8 }
 
-#[derive(Serialize)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+enum ProofEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+impl ProofEncoding {
+    fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            ProofEncoding::Hex    => hex::encode(bytes),
+            ProofEncoding::Base64 => base64::engine::general_purpose::STANDARD.encode(bytes),
+        }
+    }
+
+    fn decode(self, s: &str) -> Option<Vec<u8>> {
+        match self {
+            ProofEncoding::Hex    => hex::decode(s).ok(),
+            ProofEncoding::Base64 => base64::engine::general_purpose::STANDARD.decode(s).ok(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 struct ProveResponse {
+    /// Id under which the proof is stored (and billed).
+    proof_id:   String,
+    setup_sec:  f64,
+    prove_sec:  f64,
+    verify_sec: f64,
+    proof_len:  usize,
+    proof_hex:  String,
+    /// Guest export that was proven.
+    #[schema(value_type = String)]
+    circuit:    &'static str,
+    /// Guest build (blue/green rollout) that served the proof.
+    circuit_version: String,
+    /// Hash used for the wallet commitment limbs.
+    #[schema(value_type = String, example = "poseidon")]
+    commitment_scheme: HashScheme,
+    /// Trusted issuer that vouched for the subject, when one is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    issuer: Option<String>,
+    /// Wallet-ownership check made before proving (see siwe.rs).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    ownership: Option<OwnershipRecord>,
+    /// Served from a run precomputed off-peak for this subject (see preissue.rs).
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    preissued:  bool,
+    /// Guest arguments the proof was generated over, in call order.
+    public_inputs: Vec<String>,
+    /// `0x`-prefixed Keccak-256 of the IVMS101 payload, when one was sent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    travel_rule_commitment: Option<String>,
+    /// Post-prove plugin output, keyed by plugin name.
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    #[schema(value_type = Object)]
+    plugins: serde_json::Map<String, serde_json::Value>,
+    /// Bincode of the SNARK, with `?full_proof=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proof:      Option<String>,
+    /// Bincode of the instance it verifies against, with `?full_proof=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instance:   Option<String>,
+    /// How `proof` and `instance` are encoded, when present.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proof_encoding: Option<ProofEncoding>,
+    /// How `proof` and `instance` are compressed before encoding, unless not at all.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compression: Option<Compression>,
+    /// Raw proof and instance bytes, for re-encoding or a binary response.
+    #[serde(skip)]
+    raw:        Option<(Vec<u8>, Vec<u8>)>,
+    /// Step size, guest module and parameter digest, for an envelope response.
+    #[serde(skip)]
+    step:       usize,
+    #[serde(skip)]
+    wasm:       std::path::PathBuf,
+    #[serde(skip)]
+    pp_digest:  [u8; 32],
+    /// Tenant's monthly quota standing, sent as headers.
+    #[serde(skip)]
+    quota:      Option<QuotaStatus>,
+}
+
+/// Response shape of the original `/prove`, served at `/legacy/prove`.
+#[derive(Serialize)]
+struct LegacyProveResponse {
     setup_sec:  f64,
     prove_sec:  f64,
     verify_sec: f64,
@@ -56,95 +313,1527 @@ struct ProveResponse {
     proof_hex:  String,
 }
 
+impl From<ProveResponse> for LegacyProveResponse {
+    fn from(r: ProveResponse) -> Self {
+        LegacyProveResponse {
+            setup_sec:  r.setup_sec,
+            prove_sec:  r.prove_sec,
+            verify_sec: r.verify_sec,
+            proof_len:  r.proof_len,
+            proof_hex:  r.proof_hex,
+        }
+    }
+}
+
+/* ---------- shared state ----------------------------------------- */
+struct AppState {
+    policies: PolicySet,
+    opa:      Option<OpaHook>,
+    plugins:  Option<Arc<PluginHost>>,
+    attest:   AttestationRegistry,
+    issuers:  Arc<IssuerTrust>,
+    /// Issuer key compromises, applied to `issuers`.
+    incidents: Incidents,
+    relying_parties: RelyingParties,
+    sampling: SamplingConfig,
+    log:      LogHandle,
+    features: FeatureFlags,
+    rollout:  Rollout,
+    swaps:    Swaps,
+    sweeps:   Sweeps,
+    canary:   Arc<Canary>,
+    load:     Load,
+    pool:     Arc<ProvePool>,
+    health:   Health,
+    readiness: Readiness,
+    metrics:  Metrics,
+    sla:      SlaTracker,
+    usage:    UsageLog,
+    stats:    Stats,
+    quotas:   Quotas,
+    limits:   Limits,
+    honeypot: Honeypot,
+    ip_limits: IpLimits,
+    prover:   Isolation,
+    watchdog: Option<Arc<Watchdog>>,
+    /// Nova public parameters, shared by every inline prove and verify.
+    params:   Arc<ParamCache>,
+    billing:  Option<Webhook>,
+    /// Lifecycle events (`proof.revoked`, …) for integrators.
+    events:   Option<Webhook>,
+    dead_letters: Option<Arc<DeadLetters>>,
+    store:    Option<ProofStore>,
+    verified: VerifyCache,
+    presentations: Presentations,
+    /// Verifier nonces for `/prove` and `/verify` (see challenge.rs).
+    challenges: Challenges,
+    /// Wallet-ownership messages for `/prove` (see siwe.rs).
+    siwe:     Siwe,
+    jobs:     Jobs,
+    /// Proofs precomputed off-peak for subjects expected soon.
+    preissue: Preissue,
+    maintenance: Maintenance,
+    /// Emergency issuance stop (see killswitch.rs).
+    kill_switch: KillSwitch,
+    replication_token: Option<String>,
+    /// `ZK_MODE=verifier`: no proving, no local writes.
+    read_only: bool,
+    admin_token: Option<String>,
+    /// Externally reachable base URL, embedded in QR payloads.
+    public_url: Option<String>,
+    api_keys: Option<ApiKeys>,
+    /// `ZK_PROVE_KEYS_FILE`: static keys required on the prove routes.
+    prove_keys: Option<ProveKeys>,
+    /// `ZK_REQUIRE_CONSENT`: issuance needs a signed consent record.
+    require_consent: bool,
+    expiry:   Expiry,
+}
+
+/// Tenant named by the `x-tenant-id` header (`"default"` when absent).  Only authenticated on
+/// routes behind [`prove_keys::require`] or [`prove_keys::require_tenant`].
+fn tenant_of(headers: &HeaderMap) -> String {
+    headers
+        .get("x-tenant-id")
+        .and_then(|v| v.to_str().ok())
+        .filter(|t| !t.is_empty())
+        .unwrap_or(prove_keys::DEFAULT_TENANT)
+        .to_string()
+}
+
 /* ---------- main ------------------------------------------------- */
 #[tokio::main]
 async fn main() -> Result<()> {
-    init_logger();
+    if std::env::args().nth(1).as_deref() == Some(worker::WORKER_ARG) {
+        return worker::serve();
+    }
+    let config = ServerConfig::load(std::env::args().skip(1))?;
+    request::set_default_step(config.default_step);
+    Compression::init_from_env()?;
+    let redactor = redact::Redactor::from_env()?;
+    let allowed = redactor.allowed();
+    redact::install(redactor);
+    let log = telemetry::init();
+    if !allowed.is_empty() {
+        tracing::warn!("redaction disabled for {} (ZK_REDACT_ALLOW); do not run this in production", allowed.join(", "));
+    }
+    tracing::info!("server config: {config:?}");
+
+    let policy_path = std::env::var("ZK_POLICY_FILE").unwrap_or_else(|_| "policies.toml".into());
+    let policies = PolicySet::load(policy_path.as_ref())?;
+    tracing::info!("loaded {} policies from {}", policies.policies.len(), policy_path);
+    let opa = OpaHook::from_env()?;
+    if opa.is_some() { tracing::info!("pre-issuance OPA hook enabled"); }
+    let plugins = PluginHost::from_env()?.map(Arc::new);
+    let attest_path = std::env::var("ZK_ATTESTATION_FILE").unwrap_or_else(|_| "attestation.toml".into());
+    let issuers_path = std::env::var("ZK_ISSUERS_FILE").unwrap_or_else(|_| "issuers.toml".into());
+    let issuers = Arc::new(IssuerTrust::load(issuers_path.as_ref())?);
+    tracing::info!("trusting {} attestation issuers from {}", issuers.list().len(), issuers_path);
+    let incidents = Incidents::from_env(&issuers)?;
+    let attest = AttestationRegistry::load(attest_path.as_ref(), &issuers)?;
+    let rp_path = std::env::var("ZK_RELYING_PARTIES_FILE").unwrap_or_else(|_| "relying_parties.toml".into());
+    let relying_parties = RelyingParties::load(rp_path.as_ref())?;
+    let telemetry_path = std::env::var("ZK_TELEMETRY_FILE").unwrap_or_else(|_| "telemetry.toml".into());
+    let sampling = SamplingConfig::load(telemetry_path.as_ref())?;
 
-    let app = Router::new()
-        .route("/prove", post(handle_prove))
-        .with_state(Arc::new(()));
+    let features_path = std::env::var("ZK_FEATURES_FILE").unwrap_or_else(|_| "features.toml".into());
+    let features = FeatureFlags::load(features_path.as_ref())?;
+    let rollout_path = std::env::var("ZK_ROLLOUT_FILE").unwrap_or_else(|_| "rollout.toml".into());
+    let rollout = Rollout::load(rollout_path.as_ref(), &config.wasm)?;
+    let swaps = Swaps::from_env()?;
+    let usage = UsageLog::from_env();
+    let stats = Stats::from_env()?;
+    let quota_path = std::env::var("ZK_QUOTA_FILE").unwrap_or_else(|_| "quotas.toml".into());
+    let quotas = Quotas::load(quota_path.as_ref(), &usage)?;
+    let slo_path = std::env::var("ZK_SLO_FILE").unwrap_or_else(|_| "slo.toml".into());
+    let sla = SlaTracker::load(slo_path.as_ref())?;
+    let limits_path = std::env::var("ZK_LIMITS_FILE").unwrap_or_else(|_| "limits.toml".into());
+    let limits = Limits::load(limits_path.as_ref(), config.min_step, config.max_step)?;
+    let honeypot = Honeypot::from_env()?;
+    let ip_limits = IpLimits::from_env()?;
+    let prover = Isolation::from_env(config.prover_timeout)?;
+    if let Isolation::Subprocess { .. } = prover { tracing::info!("proving in worker subprocesses: {prover:?}"); }
+    let watchdog = Watchdog::from_env()?.map(Arc::new);
+    if let Some(w) = &watchdog {
+        if let Isolation::Inline = prover { tracing::warn!("memory watchdog can only refuse inline proofs; use ZK_PROVER_MODE=subprocess to abort them"); }
+        watchdog::spawn(w.clone());
+    }
+    let read_only = match std::env::var("ZK_MODE").as_deref() {
+        Ok("verifier")      => true,
+        Ok("full") | Err(_) => false,
+        Ok(other)           => anyhow::bail!("unknown ZK_MODE {other:?} (full | verifier)"),
+    };
+    let dead_letters = DeadLetters::from_env()?.map(Arc::new);
+    let outbox = Outbox::from_env()?.map(Arc::new);
+    let (billing, events) = if read_only {
+        (None, None)
+    } else {
+        (Webhook::from_env(Kind::Billing, dead_letters.clone(), outbox.clone())?,
+         Webhook::from_env(Kind::Event, dead_letters.clone(), outbox)?)
+    };
+    if billing.is_some() { tracing::info!("billing events enabled"); }
+    if events.is_some() { tracing::info!("lifecycle events enabled"); }
+    let replicator = if read_only { None } else { Replicator::from_env()? };
+    let store = ProofStore::from_env(replicator)?;
+    if store.is_some() { tracing::info!("proof store enabled"); }
+    if let Some(s) = &store {
+        let (r, n) = s.rebuild_filters()?;
+        tracing::info!("proof store: {r} revocations, {n} spent nullifiers");
+    }
+    if read_only && store.is_none() {
+        anyhow::bail!("ZK_MODE=verifier needs ZK_STORE_DIR (the replicated proof store)");
+    }
+    let replication_token = std::env::var("ZK_REPLICATION_TOKEN").ok().filter(|t| !t.is_empty());
+    let admin_token = std::env::var("ZK_ADMIN_TOKEN").ok().filter(|t| !t.is_empty());
+    let public_url = std::env::var("ZK_PUBLIC_URL").ok().filter(|u| !u.is_empty());
+    let api_keys = ApiKeys::from_env()?;
+    let prove_keys = ProveKeys::from_env()?;
+    let require_consent = matches!(std::env::var("ZK_REQUIRE_CONSENT").as_deref(), Ok("1" | "true"));
+    let expiry = Expiry::from_env()?;
+    let siwe = Siwe::from_env(public_url.as_deref());
+    if api_keys.is_some() { tracing::info!("verifier API keys enabled"); }
+
+    let bloom_every = std::env::var("ZK_BLOOM_REBUILD_SECS").ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3600u64);
+
+    let load = Load::new(config.prove_concurrency);
+    let pool = Arc::new(ProvePool::new(load.capacity(), config.prove_queue));
+    let jobs = Jobs::from_env(load.capacity());
+    let state = Arc::new(AppState {
+        policies, opa, plugins, attest, issuers, incidents, relying_parties, sampling, log, features, rollout, swaps, sweeps: Sweeps::default(),
+        canary: Arc::default(), load, pool, health: Health::new(), readiness: Readiness::new(ParamCache::preload_steps()), metrics: Metrics::new(), sla,
+        usage, stats, quotas, limits, honeypot, ip_limits, prover, watchdog, params: Arc::new(ParamCache::from_env()), billing, events, dead_letters, store, verified: VerifyCache::from_env(),
+        presentations: Presentations::from_env(), challenges: Challenges::from_env(), siwe, jobs, preissue: Preissue::from_env()?, maintenance: Maintenance::from_env(), kill_switch: KillSwitch::from_env()?, replication_token, read_only, admin_token, public_url, api_keys, prove_keys,
+        require_consent, expiry,
+    });
+    for step in ParamCache::preload_steps() {
+        let params = state.params.clone();
+        tokio::task::spawn_blocking(move || prover::isolate(|| {
+            params.get(step);
+            Ok(())
+        }));
+    }
+    if !read_only {
+        match state.maintenance.take_pending() {
+            Ok(pending) => {
+                if !pending.is_empty() {
+                    tracing::info!("resuming {} prove jobs queued before the restart", pending.len());
+                }
+                for job in pending {
+                    if state.jobs.restore(job.clone()) {
+                        spawn_job(state.clone(), job);
+                    }
+                }
+            }
+            Err(e) => tracing::error!("pending prove jobs not resumed: {e:#}"),
+        }
+    }
+    {
+        let state = state.clone();
+        tokio::task::spawn_blocking(move || {
+            // A read replica never proves, so it needs no guest.
+            let outcome = if state.read_only {
+                Ok(())
+            } else {
+                state.rollout.guests().iter().try_for_each(|g| g.validate())
+            };
+            if let Err(e) = &outcome { tracing::error!("guest validation failed: {e:#}"); }
+            state.readiness.set_guests(outcome);
+        });
+    }
+    if let (Some(every), false) = (rescreen::interval_from_env(), read_only) {
+        tracing::info!("re-screening issued proofs every {}s", every.as_secs());
+        rescreen::spawn(state.clone(), every);
+    }
+    if state.store.is_some() && state.events.is_some() {
+        expiry::spawn(state.clone());
+    }
+    if !read_only {
+        preissue::spawn(state.clone());
+    }
+    if state.store.is_some() {
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(std::time::Duration::from_secs(bloom_every.max(1)));
+            tick.tick().await;
+            loop {
+                tick.tick().await;
+                let s = state.clone();
+                match tokio::task::spawn_blocking(move || s.store.as_ref().map(|st| st.rebuild_filters())).await {
+                    Ok(Some(Err(e))) => tracing::error!("bloom rebuild failed: {e:#}"),
+                    Err(e)           => tracing::error!("bloom rebuild task failed: {e}"),
+                    _                => {}
+                }
+            }
+        });
+    }
+    let keyed = Router::new()
+        .route("/status", get(handle_status))
+        .route("/proofs/:id/verify", get(handle_verify_stored))
+        .route("/verify", post(handle_verify))
+        .route("/challenge", get(challenge::handle_challenge))
+        .route("/vks", get(swap::handle_vks))
+        .route("/envelopes/check", post(relying_party::handle_check))
+        .route_layer(middleware::from_fn_with_state(state.clone(), api_keys::require_key));
+    let mut app = Router::new()
+        .merge(keyed)
+        .route("/api-keys", post(api_keys::handle_mint))
+        .route("/issuers", get(handle_issuers))
+        .route("/incidents", get(handle_incidents))
+        .route("/incidents/:id", get(handle_incident))
+        .route("/healthz", get(handle_healthz))
+        .route("/readyz", get(handle_readyz))
+        .route("/metrics", get(metrics::handle_metrics))
+        .route("/openapi.json", get(openapi::handle_openapi))
+        .merge(Router::new()
+            .route("/proofs", get(handle_list_proofs))
+            .route("/proofs/:id", get(handle_get_proof))
+            .route("/proofs/:id/public-inputs", get(handle_public_inputs))
+            .route("/proofs/:id/qr", get(handle_proof_qr))
+            .route("/proofs/:id/spend", post(handle_spend))
+            .route_layer(middleware::from_fn_with_state(state.clone(), prove_keys::require_tenant)))
+        .route("/replication/apply", post(replication::handle_apply))
+        .nest("/admin", admin::router(state.clone()));
+    if !read_only {
+        let proving = Router::new()
+            .route("/prove", post(handle_prove))
+            .route("/legacy/prove", post(handle_legacy_prove))
+            .route("/prove/batch", post(batch::handle_batch))
+            .route("/siwe/message", post(siwe::handle_message))
+            .route_layer(middleware::from_fn_with_state(state.clone(), prove_keys::require))
+            .route("/proofs/:id/renew", post(expiry::handle_renew))
+            .route_layer(middleware::from_fn_with_state(state.clone(), ip_limits::guard));
+        app = app
+            .merge(proving)
+            .route("/jobs/:id", get(handle_job)
+                .route_layer(middleware::from_fn_with_state(state.clone(), prove_keys::require)))
+            .route("/jobs/:id/events", get(handle_job_events)
+                .route_layer(middleware::from_fn_with_state(state.clone(), prove_keys::require)))
+            .route("/stats/proofs", get(stats::handle_proofs))
+            .merge(Router::new()
+                .route("/proofs/:id/share", post(handle_share))
+                .route("/proofs/:id/share/:tenant", delete(handle_unshare))
+                .route("/presentations", post(presentation::handle_create))
+                .route_layer(middleware::from_fn_with_state(state.clone(), prove_keys::require_tenant)))
+            .route("/presentations/:id", get(presentation::handle_status).post(presentation::handle_present));
+    }
+    #[cfg(feature = "swagger-ui")]
+    {
+        let doc = serde_json::to_value(<openapi::ApiDoc as utoipa::OpenApi>::openapi()).unwrap_or_default();
+        app = app.merge(utoipa_swagger_ui::SwaggerUi::new("/docs").external_url_unchecked("/openapi.json", doc));
+    }
+    if let Some(addr) = config.grpc_bind {
+        grpc::spawn(state.clone(), addr, config.request_timeout)?;
+    }
+    let app = app
+        .layer(middleware::from_fn_with_state(state.clone(), metrics::track))
+        .with_state(state)
+        .layer(middleware::from_fn(redact::errors))
+        .layer(CompressionLayer::new().compress_when(DefaultPredicate::new().and(is_json)));
+    let app = match config.request_timeout {
+        Some(t) => app.layer(TimeoutLayer::new(t)),
+        None    => app,
+    };
 
-    tracing::info!("🚀 zk_server listening on http://0.0.0.0:8080");
-    axum::Server::bind(&"0.0.0.0:8080".parse().unwrap())
-        .serve(app.into_make_service())
+    tracing::info!("🚀 zk_server listening on http://{}{}", config.bind, if read_only { " (verifier)" } else { "" });
+    axum::Server::bind(&config.bind)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .with_graceful_shutdown(shutdown())
         .await?;
     Ok(())
 }
 
+/// Compress JSON bodies only (gzip / br / zstd per `Accept-Encoding`); proofs are hex and shrink well.
+fn is_json(_: StatusCode, _: Version, headers: &HeaderMap, _: &Extensions) -> bool {
+    headers.get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |ct| ct.starts_with("application/json"))
+}
+
 async fn shutdown() {
     signal::ctrl_c().await.ok();
     tracing::info!("shutdown");
 }
 
 /* ---------- handler ---------------------------------------------- */
+#[utoipa::path(
+    post, path = "/prove", tag = "proving",
+    params(ProveParams),
+    request_body = ProveRequest,
+    responses(
+        (status = 200, description = "Proof issued (or, with `Accept: application/octet-stream`, the binary attachment; with `Accept: application/vnd.zkkyc.envelope`, the proof envelope)", body = ProveResponse),
+        (status = 202, description = "Job queued (`?async=true` or `Prefer: respond-async`)", body = openapi::JobAccepted),
+        (status = 400, description = "Invalid request", body = openapi::ErrorBody),
+        (status = 403, description = "Refused by KYC, consent, issuer or policy checks", body = openapi::ErrorBody),
+        (status = 429, description = "Quota exhausted or caller blocked", body = openapi::ErrorBody),
+        (status = 503, description = "Overloaded, in maintenance or out of memory", body = openapi::ErrorBody),
+    ),
+    security((), ("prove_key" = [])),
+)]
 async fn handle_prove(
-    State(_): State<Arc<()>>,
-    Json(req): Json<ProveRequest>,
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Query(params): Query<ProveParams>,
+    headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
+) -> Response {
+    let tenant = tenant_of(&headers);
+    let caller = state.ip_limits.client_ip(peer, &headers);
+    if let Some(err) = screen(&state, caller, &tenant, request::parse_prove(body.clone())) {
+        return error_response(err);
+    }
+    let prefer_async = headers.get_all("prefer").iter()
+        .filter_map(|v| v.to_str().ok())
+        .any(|v| v.split(',').any(|p| p.trim().eq_ignore_ascii_case("respond-async")));
+    if params.run_async || prefer_async {
+        return submit_job(state, caller, tenant, params, body);
+    }
+    let span = state.sampling.request_span("/prove", &tenant);
+    let res = match request::parse_prove(body) {
+        Ok(req) => prove(&state, caller, &tenant, params.policy.as_deref(), None, req, None).instrument(span).await,
+        Err(e)  => Err(e),
+    };
+    let accepts = |ty: &str| headers.get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |a| a.split(',').any(|t| t.trim().starts_with(ty)));
+    match res {
+        Ok(resp) if accepts(ENVELOPE_TYPE) => {
+            let quota = resp.quota.clone();
+            with_quota_headers(envelope_proof(resp), quota.as_ref())
+        }
+        Ok(resp) if accepts("application/octet-stream") => {
+            let quota = resp.quota.clone();
+            with_quota_headers(binary_proof(resp, Compression::resolve(params.compression)), quota.as_ref())
+        }
+        Ok(resp) => {
+            let resp = with_full_proof(resp, &params);
+            let quota = resp.quota.clone();
+            with_quota_headers((StatusCode::OK, Json(resp)).into_response(), quota.as_ref())
+        }
+        Err(err) => {
+            count_failure(&state, &err);
+            error_response(err)
+        }
+    }
+}
+
+/// Fill `proof` / `instance` when the caller asked for the full proof.
+fn with_full_proof(mut resp: ProveResponse, params: &ProveParams) -> ProveResponse {
+    let enc = params.proof_encoding.or(params.full_proof.then_some(ProofEncoding::Hex));
+    if let (Some(enc), Some((p, i))) = (enc, &resp.raw) {
+        let (c, p, i) = compressed(Compression::resolve(params.compression), p, i);
+        resp.proof          = Some(enc.encode(&p));
+        resp.instance       = Some(enc.encode(&i));
+        resp.proof_encoding = Some(enc);
+        resp.compression    = (c != Compression::None).then_some(c);
+    }
+    resp
+}
+
+/// Proof and instance compressed with `c`; uncompressed (and `none`) should that fail.
+fn compressed(c: Compression, p: &[u8], i: &[u8]) -> (Compression, Vec<u8>, Vec<u8>) {
+    match c.compress(p).and_then(|p| Ok((p, c.compress(i)?))) {
+        Ok((p, i)) => (c, p, i),
+        Err(e) => {
+            tracing::warn!("sending proof uncompressed: {e:#}");
+            (Compression::None, p.to_vec(), i.to_vec())
+        }
+    }
+}
+
+/// `/prove?async=true`: check the body, queue a job and answer `202` with its id.
+fn submit_job(state: Arc<AppState>, caller: IpAddr, tenant: String, params: ProveParams, body: serde_json::Value) -> Response {
+    let job_id = match queue_job(state, caller, &tenant, &params, body) {
+        Ok(Some(id)) => id,
+        Ok(None)     => return (StatusCode::SERVICE_UNAVAILABLE, Json("too many prove jobs held")).into_response(),
+        Err(e)       => return error_response(e),
+    };
+    let mut resp = (StatusCode::ACCEPTED, Json(serde_json::json!({
+        "job_id":     job_id,
+        "status_url": format!("/jobs/{job_id}"),
+    }))).into_response();
+    if let Ok(v) = HeaderValue::from_str(&format!("/jobs/{job_id}")) {
+        resp.headers_mut().insert(header::LOCATION, v);
+    }
+    resp
+}
+
+/// Check the body and queue a prove job; `None` when too many jobs are held.
+fn queue_job(state: Arc<AppState>, caller: IpAddr, tenant: &str, params: &ProveParams, body: serde_json::Value) -> Result<Option<String>> {
+    state.kill_switch.check()?;
+    state.maintenance.check()?;
+    request::parse_prove(body.clone())?;
+    let params = serde_json::to_value(params).unwrap_or_default();
+    let Some(job) = state.jobs.create(tenant, caller, params, body) else { return Ok(None) };
+    let job_id = job.job_id.clone();
+    spawn_job(state, job);
+    Ok(Some(job_id))
+}
+
+/// Prove a queued job in the background, once a job slot is free and maintenance is off.
+fn spawn_job(state: Arc<AppState>, job: PendingJob) {
+    let params: ProveParams = serde_json::from_value(job.params).unwrap_or_default();
+    let PendingJob { job_id: id, tenant, caller, body, .. } = job;
+    tokio::spawn(async move {
+        let slots = state.jobs.slots();
+        let _slot = loop {
+            state.maintenance.wait_open().await;
+            let Ok(slot) = slots.acquire().await else { return };
+            if state.maintenance.check().is_ok() {
+                break slot;
+            }
+        };
+        state.jobs.set(&id, JobState::Running { phase: None });
+        let progress = state.jobs.progress(&id);
+        let span = state.sampling.request_span("/prove", &tenant);
+        let res = loop {
+            let req = match request::parse_prove(body.clone()) {
+                Ok(r)  => r,
+                Err(e) => break Err(e),
+            };
+            match prove(&state, caller, &tenant, params.policy.as_deref(), None, req, progress.as_ref()).instrument(span.clone()).await {
+                // Jobs wait for the pool instead of failing on a busy server.
+                Err(e) if e.downcast_ref::<Overloaded>().is_some() => {
+                    let wait = e.downcast_ref::<Overloaded>().map_or(1, |o| o.retry_after.max(1));
+                    tokio::time::sleep(std::time::Duration::from_secs(wait)).await;
+                }
+                res => break res,
+            }
+        };
+        let outcome = match res {
+            Ok(resp) => JobState::Succeeded {
+                result: serde_json::to_value(with_full_proof(resp, &params)).unwrap_or_default(),
+            },
+            Err(err) => {
+                count_failure(&state, &err);
+                let (status, error, _) = errors::describe(&err);
+                JobState::Failed { status: status.as_u16(), error }
+            }
+        };
+        state.jobs.set(&id, outcome);
+    });
+}
+
+/// `GET /jobs/:id`: state of one of the caller's prove jobs.
+#[utoipa::path(
+    get, path = "/jobs/{id}", tag = "proving",
+    params(("id" = String, Path, description = "`job_id` from `202 Accepted`")),
+    responses(
+        (status = 200, description = "Job state", body = jobs::Job),
+        (status = 404, description = "Unknown, expired or another tenant's job"),
+    ),
+    security((), ("prove_key" = [])),
+)]
+async fn handle_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    match state.jobs.get(&id, &tenant_of(&headers)) {
+        Some(job) => Json(job).into_response(),
+        None      => (StatusCode::NOT_FOUND, Json("unknown or expired job")).into_response(),
+    }
+}
+
+/// `GET /jobs/:id/events`: the job's state now and on every change, until it finishes.
+async fn handle_job_events(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(rx) = state.jobs.subscribe(&id, &tenant_of(&headers)) else {
+        return (StatusCode::NOT_FOUND, Json("unknown or expired job")).into_response();
+    };
+    let events = futures_util::stream::unfold(Some((rx, true)), |next| async move {
+        let (mut rx, first) = next?;
+        if !first {
+            rx.changed().await.ok()?;
+        }
+        let job_state = rx.borrow_and_update().clone();
+        let event = Event::default().event(job_state.name()).json_data(&job_state).unwrap_or_default();
+        let next = (!job_state.is_finished()).then_some((rx, false));
+        Some((Ok::<_, std::convert::Infallible>(event), next))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+}
+
+/// `Accept: application/octet-stream`: the proof as an attachment, framed as
+/// `u32 BE proof length | proof | instance` (both bincode, each compressed with `compression`);
+/// metadata moves to `x-zk-*` headers.
+fn binary_proof(resp: ProveResponse, compression: Compression) -> Response {
+    let (proof, instance) = resp.raw.unwrap_or_default();
+    let (compression, proof, instance) = compressed(compression, &proof, &instance);
+    let mut body = Vec::with_capacity(4 + proof.len() + instance.len());
+    body.extend_from_slice(&(proof.len() as u32).to_be_bytes());
+    body.extend_from_slice(&proof);
+    body.extend_from_slice(&instance);
+    let mut out = (StatusCode::OK, body).into_response();
+    let h = out.headers_mut();
+    h.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/octet-stream"));
+    for (name, value) in [
+        ("content-disposition", format!("attachment; filename=\"{}.zkproof\"", resp.proof_id)),
+        ("x-zk-proof-id",        resp.proof_id),
+        ("x-zk-circuit",         resp.circuit.to_string()),
+        ("x-zk-circuit-version", resp.circuit_version),
+        ("x-zk-public-inputs",   resp.public_inputs.join(",")),
+        ("x-zk-compression",     serde_json::to_value(compression).ok().and_then(|v| v.as_str().map(String::from)).unwrap_or_default()),
+    ] {
+        if let Ok(v) = HeaderValue::from_str(&value) {
+            h.insert(name, v);
+        }
+    }
+    out
+}
+
+/// Media type of a [`kyc_core::envelope`] proof envelope.
+const ENVELOPE_TYPE: &str = "application/vnd.zkkyc.envelope";
+
+/// `Accept: application/vnd.zkkyc.envelope`: the proof as a versioned envelope
+/// ([`kyc_core::envelope`]), which `kyc_host verify` and `POST /verify` read back.  The body is
+/// in canonical form, and `x-zk-envelope-digest` is its SHA-256 for signing.  It records the
+/// commitment scheme and any SIWE ownership check (format version 3).
+fn envelope_proof(resp: ProveResponse) -> Response {
+    let (proof, instance) = resp.raw.unwrap_or_default();
+    let encoded = std::fs::read(&resp.wasm)
+        .map_err(|e| anyhow::anyhow!("{}: {e}", resp.wasm.display()))
+        .and_then(|wasm| Ok(Envelope {
+            circuit:         resp.circuit.to_string(),
+            circuit_version: resp.circuit_version,
+            wasm_digest:     envelope::digest(&wasm),
+            pp_digest:       resp.pp_digest,
+            step:            resp.step as u32,
+            public_inputs:   resp.public_inputs,
+            proof,
+            instance,
+            ownership:       resp.ownership.as_ref().map(OwnershipRecord::to_envelope).transpose()?,
+            commitment_scheme: Some(resp.commitment_scheme),
+        }.canonical_bytes()?));
+    let body = match encoded {
+        Ok(body) => body,
+        Err(e)   => return error_response(ApiError::new(ErrorCode::Internal, format!("{e:#}")).into()),
+    };
+    let digest = hex::encode(envelope::digest(&body));
+    let mut out = (StatusCode::OK, body).into_response();
+    let h = out.headers_mut();
+    h.insert(header::CONTENT_TYPE, HeaderValue::from_static(ENVELOPE_TYPE));
+    for (name, value) in [
+        ("content-disposition",  format!("attachment; filename=\"{}.zkenv\"", resp.proof_id)),
+        ("x-zk-proof-id",        resp.proof_id),
+        ("x-zk-envelope-digest", digest),
+    ] {
+        if let Ok(v) = HeaderValue::from_str(&value) {
+            h.insert(name, v);
+        }
+    }
+    out
+}
+
+#[utoipa::path(
+    get, path = "/status", tag = "status",
+    responses((status = 200, description = "Uptime, 1h success rate and latency", body = status::StatusSummary)),
+    security((), ("api_key" = [])),
+)]
+async fn handle_status(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(state.health.summary())
+}
+
+/// Liveness: the process is up and serving.
+async fn handle_healthz() -> impl IntoResponse {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+/// Readiness: parameters warmed and guests validated; `503` until then.
+async fn handle_readyz(State(state): State<Arc<AppState>>) -> Response {
+    let report = state.readiness.report(&state.params);
+    let status = if report.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(report)).into_response()
+}
+
+/// `id`, if the calling tenant owns it or it was shared with them.  Proofs of other tenants look
+/// absent, so ids can't be probed.
+fn readable_proof(store: &ProofStore, id: &str, headers: &HeaderMap) -> Result<Option<StoredProof>> {
+    let tenant = tenant_of(headers);
+    Ok(store.get_proof(id)?.filter(|p| p.readable_by(&tenant)))
+}
+
+/// Proofs the calling tenant owns or was granted, without proof bytes.
+async fn handle_list_proofs(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    let Some(store) = &state.store else {
+        return (StatusCode::NOT_FOUND, Json("proof store disabled")).into_response();
+    };
+    let tenant = tenant_of(&headers);
+    match store.list_proofs(&tenant) {
+        Ok(proofs) => Json(proofs.into_iter().map(|p| serde_json::json!({
+            "proof_id":        p.id,
+            "owner":           p.tenant,
+            "circuit":         p.circuit,
+            "circuit_version": p.circuit_version,
+            "created_at":      p.created_at,
+            "shared_with":     if p.tenant == tenant { Some(p.shared_with) } else { None },
+        })).collect::<Vec<_>>()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(format!("{e:#}"))).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct ShareBody {
+    /// Tenant to grant read access to.
+    tenant: String,
+}
+
+/// Owner grants another tenant read access to one proof.
+async fn handle_share(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(body): Json<ShareBody>,
+) -> Response {
+    share(&state, &id, &headers, &body.tenant, true)
+}
+
+/// Owner withdraws a grant.
+async fn handle_unshare(
+    State(state): State<Arc<AppState>>,
+    Path((id, grantee)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    share(&state, &id, &headers, &grantee, false)
+}
+
+fn share(state: &AppState, id: &str, headers: &HeaderMap, grantee: &str, grant: bool) -> Response {
+    let Some(store) = &state.store else {
+        return (StatusCode::NOT_FOUND, Json("proof store disabled")).into_response();
+    };
+    if state.prove_keys.is_none() {
+        // Without prove keys no tenant is authenticated, so there is no one to grant access to.
+        return (StatusCode::FORBIDDEN, Json("sharing proofs needs prove keys (ZK_PROVE_KEYS_FILE)")).into_response();
+    }
+    if grantee.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json("tenant is required")).into_response();
+    }
+    match store.share(id, &tenant_of(headers), grantee, grant) {
+        Ok(Some(p)) => Json(serde_json::json!({ "proof_id": p.id, "shared_with": p.shared_with })).into_response(),
+        Ok(None)    => (StatusCode::NOT_FOUND, Json("unknown proof")).into_response(),
+        Err(e)      => (StatusCode::BAD_REQUEST, Json(e.to_string())).into_response(),
+    }
+}
+
+/// A stored proof with its revocation state.  The strong ETag is the SHA-256 of the body, so it
+/// changes on revocation; a matching `If-None-Match` gets `304 Not Modified`.
+async fn handle_get_proof(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(store) = &state.store else {
+        return (StatusCode::NOT_FOUND, Json("proof store disabled")).into_response();
+    };
+    match readable_proof(store, &id, &headers).and_then(|p| Ok((p, store.revocation(&id)?))) {
+        Ok((Some(proof), revocation)) => {
+            let body = match serde_json::to_vec(&serde_json::json!({ "proof": proof, "revocation": revocation })) {
+                Ok(b)  => b,
+                Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(e.to_string())).into_response(),
+            };
+            let etag = format!("\"{}\"", hex::encode(Sha256::digest(&body)));
+            let mut resp = if if_none_match(&headers, &etag) {
+                StatusCode::NOT_MODIFIED.into_response()
+            } else {
+                ([(header::CONTENT_TYPE, "application/json")], body).into_response()
+            };
+            let h = resp.headers_mut();
+            if let Ok(v) = HeaderValue::from_str(&etag) {
+                h.insert(header::ETAG, v);
+            }
+            // Revocation can change the body at any time: caches must revalidate.
+            h.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+            resp
+        }
+        Ok((None, _)) => (StatusCode::NOT_FOUND, Json("unknown proof")).into_response(),
+        Err(e)        => (StatusCode::BAD_REQUEST, Json(e.to_string())).into_response(),
+    }
+}
+
+/// Whether `If-None-Match` lists `etag` (or is `*`).  Per RFC 9110 the comparison is weak, so a
+/// `W/` prefix on the client's tag is ignored.
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers.get_all(header::IF_NONE_MATCH).iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .any(|t| t == "*" || t.trim_start_matches("W/") == etag)
+}
+
+/// A stored proof's public inputs decoded into named fields.
+async fn handle_public_inputs(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(store) = &state.store else {
+        return (StatusCode::NOT_FOUND, Json("proof store disabled")).into_response();
+    };
+    let proof = match readable_proof(store, &id, &headers) {
+        Ok(Some(p)) => p,
+        Ok(None)    => return (StatusCode::NOT_FOUND, Json("unknown proof")).into_response(),
+        Err(e)      => return (StatusCode::BAD_REQUEST, Json(e.to_string())).into_response(),
+    };
+    let Some(circuit) = policy::circuit(&proof.circuit) else {
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(format!("unknown circuit {}", proof.circuit))).into_response();
+    };
+    match public_inputs::decode(circuit.layout(), &proof.public_inputs) {
+        Ok(inputs) => Json(serde_json::json!({
+            "proof_id":        id,
+            "circuit":         proof.circuit,
+            "circuit_version": proof.circuit_version,
+            "issued_at":       proof.created_at,
+            "expires_at":      proof.expires_at,
+            // The instance doesn't expose the guest's arguments; these are the ones recorded at issuance.
+            "source":          "issuance_record",
+            "public_inputs":   public_inputs::PublicInputs { expires_at: proof.expires_at, ..inputs },
+        })).into_response(),
+        Err(e) => (StatusCode::UNPROCESSABLE_ENTITY, Json(e.to_string())).into_response(),
+    }
+}
+
+/// QR payload (`kyc_core::qr`) pointing at a stored proof on this server.
+async fn handle_proof_qr(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let (Some(store), Some(base_url)) = (&state.store, &state.public_url) else {
+        return (StatusCode::NOT_FOUND, Json("QR payloads need ZK_STORE_DIR and ZK_PUBLIC_URL")).into_response();
+    };
+    let proof = match readable_proof(store, &id, &headers) {
+        Ok(Some(p)) => p,
+        Ok(None)    => return (StatusCode::NOT_FOUND, Json("unknown proof")).into_response(),
+        Err(e)      => return (StatusCode::BAD_REQUEST, Json(e.to_string())).into_response(),
+    };
+    let vk_digest = match params_digest(&state, proof.step).await {
+        Ok(d)  => d,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(format!("{e:#}"))).into_response(),
+    };
+    let r = ProofRef { proof_id: proof.id, base_url: base_url.clone(), vk_digest };
+    match r.encode() {
+        Ok(payload) => Json(serde_json::json!({
+            "payload":    payload,
+            "verify_url": r.verify_url(),
+            "vk_digest":  hex::encode(r.vk_digest),
+        })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(e.to_string())).into_response(),
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct VerifyParams {
+    /// Relying party; reports whether the proof's nullifier is already spent for it.
+    scope: Option<String>,
+    /// Comma-separated issuer ids or DIDs the caller accepts; absent = any trusted issuer.
+    issuers: Option<String>,
+}
+
+/// Attestation issuers this server trusts, with their keys and validity windows.
+async fn handle_issuers(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "issuers": state.issuers.list() }))
+}
+
+/// Issuer key compromises on record, for relying parties to audit what was revoked.
+async fn handle_incidents(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "incidents": state.incidents.list() }))
+}
+
+async fn handle_incident(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Response {
+    match state.incidents.get(&id) {
+        Some(i) => Json(i).into_response(),
+        None    => (StatusCode::NOT_FOUND, Json(serde_json::json!("unknown incident"))).into_response(),
+    }
+}
+
+/// Re-verify a stored proof; answers `valid: false` for revoked, expired, no longer trusted
+/// (or, with `scope`, spent; with `issuers`, differently issued) proofs.
+#[utoipa::path(
+    get, path = "/proofs/{id}/verify", tag = "verifying",
+    params(("id" = String, Path, description = "`proof_id`"), VerifyParams),
+    responses(
+        (status = 200, description = "Verdict; `valid` combines every check", body = openapi::StoredVerifyResult),
+        (status = 404, description = "Unknown proof, or no proof store"),
+    ),
+    security((), ("api_key" = [])),
+)]
+async fn handle_verify_stored(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<VerifyParams>,
+) -> Response {
+    let Some(store) = &state.store else {
+        return (StatusCode::NOT_FOUND, Json("proof store disabled")).into_response();
+    };
+    let (proof, revocation) = match store.get_proof(&id).and_then(|p| Ok((p, store.revocation(&id)?))) {
+        Ok((Some(p), r)) => (p, r),
+        Ok((None, _))    => return (StatusCode::NOT_FOUND, Json("unknown proof")).into_response(),
+        Err(e)           => return (StatusCode::BAD_REQUEST, Json(e.to_string())).into_response(),
+    };
+    let spent = match params.scope.as_deref().map(|sc| store.is_spent(&id, sc)).transpose() {
+        Ok(s)  => s,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(e.to_string())).into_response(),
+    };
+    let version_accepted = state.rollout.accepts(&proof.circuit_version);
+    let expired = proof.expired(store::now());
+    let issuer_trusted = proof.issuer.as_deref()
+        .map_or(true, |i| state.issuers.trusts(i, &proof.circuit, proof.created_at));
+    let issuer_accepted = params.issuers.as_deref().map(|list| {
+        let issuer = proof.issuer.as_deref().and_then(|i| state.issuers.get(i));
+        list.split(',').map(str::trim).any(|name| issuer.map_or(false, |i| i.is(name)))
+    });
+    let (verdict, cached) = verify_cached(&state, &proof).await;
+    let (proof_ok, verify_sec, error) = match verdict {
+        Ok(secs) => (true, Some(secs), None),
+        Err(e)   => (false, None, Some(errors::body(ErrorCode::VerifyFailure, &e, None))),
+    };
+    Json(serde_json::json!({
+        "proof_id":         id,
+        "valid":            proof_ok && revocation.is_none() && version_accepted && !expired
+                            && issuer_trusted && issuer_accepted != Some(false) && spent != Some(true),
+        "proof_ok":         proof_ok,
+        "revoked":          revocation.is_some(),
+        "expired":          expired,
+        "expires_at":       proof.expires_at,
+        "spent":            spent,
+        "issuer":           proof.issuer,
+        "issuer_trusted":   issuer_trusted,
+        "issuer_accepted":  issuer_accepted,
+        "version_accepted": version_accepted,
+        "circuit":          proof.circuit,
+        "circuit_version":  proof.circuit_version,
+        "verify_sec":       verify_sec,
+        "cached":           cached,
+        "error":            error,
+    })).into_response()
+}
+
+/// SNARK-verify a stored proof, answering from the verification cache when possible.
+/// Returns the verdict and whether it came from the cache.
+async fn verify_cached(state: &AppState, proof: &StoredProof) -> (verify_cache::Verdict, bool) {
+    let decoded = hex::decode(&proof.proof).and_then(|p| Ok((p, hex::decode(&proof.instance)?)));
+    match decoded {
+        Ok((p, i)) => verify_bytes(state, p, i, proof.step).await,
+        Err(e)     => (Err(e.to_string()), false),
+    }
+}
+
+/// [`verify_cached`] for a serialized proof/instance pair at `step`.  The cache is keyed by the
+/// parameters' digest, so it is only consulted once they are loaded.
+async fn verify_bytes(state: &AppState, p: Vec<u8>, i: Vec<u8>, step: usize) -> (verify_cache::Verdict, bool) {
+    let cached = state.params.loaded_digest(step)
+        .and_then(|d| state.verified.get(&verify_cache::key(&p, &i, &d)));
+    if let Some(v) = cached {
+        return (v, true);
+    }
+    let params = state.params.clone();
+    let job = move || {
+        let r = prover::verify(&params, &p, &i, step);
+        (r, params.loaded_digest(step).map(|d| verify_cache::key(&p, &i, &d)))
+    };
+    match tokio::task::spawn_blocking(job).await {
+        Ok((r, key)) => {
+            let v = r.map_err(|e| e.to_string());
+            if let Ok(secs) = &v {
+                state.metrics.verified(*secs);
+            }
+            if let Some(key) = key {
+                state.verified.insert(key, v.clone());
+            }
+            (v, false)
+        }
+        Err(e) => (Err(format!("verifier task failed: {e}")), false),
+    }
+}
+
+/// Body of `POST /verify`: the `proof`, `instance` and `step` of a `/prove?full_proof=true`
+/// response (other fields are ignored, so the response can be posted back as is).
+#[derive(Deserialize, ToSchema)]
+struct VerifyBody {
+    /// Hex bincode of the SNARK.
+    proof:    String,
+    /// Hex bincode of the instance it verifies against.
+    instance: String,
+    step:     usize,
+    /// As `compression` in the prove response; `none` when absent.
+    #[serde(default)]
+    compression: Compression,
+    /// Guest export and its arguments, as in the prove response; with `?challenge=` they must match
+    /// those this server proved the instance over.
+    #[serde(default)]
+    circuit:  Option<String>,
+    #[serde(default)]
+    public_inputs: Option<Vec<String>>,
+}
+
+/// Verify a proof the caller holds; the proof store is only consulted for `?challenge=`.
+#[utoipa::path(
+    post, path = "/verify", tag = "verifying",
+    params(VerifyQuery),
+    request_body = VerifyBody,
+    responses(
+        (status = 200, description = "Verdict (`INVALID_CHALLENGE` in `error` when the challenge check fails)", body = openapi::VerifyResult),
+        (status = 400, description = "Not hex, not an envelope for this build, or step out of range", body = openapi::ErrorBody),
+    ),
+    security((), ("api_key" = [])),
+)]
+async fn handle_verify(
+    State(state): State<Arc<AppState>>,
+    Query(q): Query<VerifyQuery>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> Response {
+    let enveloped = headers.get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |t| t.starts_with(ENVELOPE_TYPE));
+    let input = if enveloped { verify_envelope(&body) } else { verify_json(&body) };
+    let input = match input {
+        Ok(input) => input,
+        Err(e)    => return error_response(ApiError::new(ErrorCode::InvalidRequest, format!("{e:#}")).into()),
+    };
+    if let Err(e) = state.limits.check_step(&tenant_of(&headers), input.step) {
+        return error_response(e);
+    }
+    if let Some(made_under) = input.pp_digest {
+        let checked = params_digest(&state, input.step).await.and_then(|ours| {
+            anyhow::ensure!(
+                made_under == ours,
+                "envelope was made under public parameters {}, not this build's {} for step {}",
+                hex::encode(made_under), hex::encode(ours), input.step,
+            );
+            Ok(())
+        });
+        if let Err(e) = checked {
+            return error_response(ApiError::new(ErrorCode::InvalidRequest, format!("{e:#}")).into());
+        }
+    }
+    let step = input.step;
+    let proven = q.challenge.is_some().then(|| input.nonce(&state));
+    let (verdict, cached) = verify_bytes(&state, input.proof, input.instance, step).await;
+    let (valid, verify_sec, error) = match verdict {
+        // Only a proof that verifies spends the challenge.
+        Ok(secs) => match q.challenge.as_deref().zip(proven).map(|(c, p)| state.challenges.redeem(c, p?.as_deref())) {
+            Some(Err(e)) => (false, Some(secs), Some(errors::body(ErrorCode::InvalidChallenge, &format!("{e:#}"), None))),
+            _            => (true, Some(secs), None),
+        },
+        Err(e)   => (false, None, Some(errors::body(ErrorCode::VerifyFailure, &e, None))),
+    };
+    Json(serde_json::json!({
+        "valid":      valid,
+        "step":       step,
+        "verify_sec": verify_sec,
+        "cached":     cached,
+        "error":      error,
+    })).into_response()
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct VerifyQuery {
+    /// Nonce from `GET /challenge` the proof must have been made for.
+    challenge: Option<String>,
+}
+
+/// A caller-held proof, from a JSON [`VerifyBody`] or an envelope.
+struct VerifyInput {
+    proof:         Vec<u8>,
+    instance:      Vec<u8>,
+    step:          usize,
+    circuit:       Option<String>,
+    public_inputs: Option<Vec<String>>,
+    /// Parameter digest an envelope was made under.
+    pp_digest:     Option<[u8; 32]>,
+}
+
+impl VerifyInput {
+    /// The challenge nonce this proof was made with (`None` for a circuit without one), from the
+    /// store's record of its instance; see challenge.rs.
+    fn nonce(&self, state: &AppState) -> Result<Option<String>> {
+        let store = state.store.as_ref().ok_or_else(|| anyhow::anyhow!("challenges need the proof store"))?;
+        let recorded = store.proof_by_instance(&self.instance)?;
+        let supplied = self.circuit.as_deref().zip(self.public_inputs.as_deref());
+        challenge::proven_nonce(recorded.as_ref().map(|p| (p.circuit.as_str(), &p.public_inputs[..])), supplied)
+    }
+}
+
+/// A proof from a JSON [`VerifyBody`].
+fn verify_json(body: &[u8]) -> Result<VerifyInput> {
+    let body: VerifyBody = serde_json::from_slice(body).context("invalid verify body")?;
+    let p = hex::decode(body.proof.trim_start_matches("0x"))
+        .and_then(|p| Ok((p, hex::decode(body.instance.trim_start_matches("0x"))?)));
+    let (p, i) = p.context("proof and instance must be hex")?;
+    Ok(VerifyInput {
+        proof:         body.compression.decompress(&p)?,
+        instance:      body.compression.decompress(&i)?,
+        step:          body.step,
+        circuit:       body.circuit,
+        public_inputs: body.public_inputs,
+        pp_digest:     None,
+    })
+}
+
+/// A proof from a [`kyc_core::envelope`]; `/verify` refuses it when it was made under public
+/// parameters other than this build's for its step.
+fn verify_envelope(body: &[u8]) -> Result<VerifyInput> {
+    let env = Envelope::decode(body)?;
+    Ok(VerifyInput {
+        proof:         env.proof,
+        instance:      env.instance,
+        step:          env.step as usize,
+        circuit:       Some(env.circuit),
+        public_inputs: Some(env.public_inputs),
+        pp_digest:     Some(env.pp_digest),
+    })
+}
+
+/// [`engine::pp_digest`] of this build's parameters for `step`, set up off the executor when they
+/// aren't loaded yet.
+async fn params_digest(state: &AppState, step: usize) -> Result<[u8; 32]> {
+    if let Some(d) = state.params.loaded_digest(step) {
+        return Ok(d);
+    }
+    let params = state.params.clone();
+    Ok(tokio::task::spawn_blocking(move || params.digest(step)).await?)
+}
+
+#[derive(Deserialize)]
+struct SpendBody {
+    /// Relying party the proof is presented to.
+    scope: String,
+}
+
+/// Spend a proof's nullifier for `scope`: 201 the first time, 409 on a replay.
+async fn handle_spend(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(body): Json<SpendBody>,
+) -> Response {
+    if state.read_only {
+        return (StatusCode::CONFLICT, Json("read replica: spend on the primary")).into_response();
+    }
+    let Some(store) = &state.store else {
+        return (StatusCode::NOT_FOUND, Json("proof store disabled")).into_response();
+    };
+    if let Ok(Some(r)) = store.revocation(&id) {
+        return (StatusCode::GONE, Json(serde_json::json!({ "error": "revoked", "revocation": r }))).into_response();
+    }
+    match store.spend(&id, &body.scope) {
+        Ok((s, true))  => (StatusCode::CREATED, Json(s)).into_response(),
+        Ok((s, false)) => (StatusCode::CONFLICT, Json(serde_json::json!({ "error": "already_spent", "spend": s }))).into_response(),
+        Err(e)         => (StatusCode::BAD_REQUEST, Json(e.to_string())).into_response(),
+    }
+}
+
+/// Original minimalist contract, translated onto the current pipeline.
+async fn handle_legacy_prove(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
 ) -> impl IntoResponse {
-    match prove(req).await {
-        Ok(resp)  => (axum::http::StatusCode::OK,   Json(resp)).into_response(),
-        Err(err)  => (axum::http::StatusCode::BAD_REQUEST, Json(err.to_string())).into_response(),
+    let tenant = tenant_of(&headers);
+    let caller = state.ip_limits.client_ip(peer, &headers);
+    if let Some(err) = screen(&state, caller, &tenant, request::parse_legacy(body.clone())) {
+        return error_response(err);
+    }
+    let span = state.sampling.request_span("/legacy/prove", &tenant);
+    let res = match request::parse_legacy(body) {
+        Ok(req) => prove(&state, caller, &tenant, None, None, req, None).instrument(span).await,
+        Err(e)  => Err(e),
+    };
+    match res {
+        Ok(resp) => {
+            let quota = resp.quota.clone();
+            with_quota_headers((StatusCode::OK, Json(LegacyProveResponse::from(resp))).into_response(), quota.as_ref())
+        }
+        Err(err) => {
+            count_failure(&state, &err);
+            error_response(err)
+        }
     }
 }
 
+/// Honeypot: turn away blocked IPs and bodies that can never prove, before any real work.
+fn screen(state: &AppState, ip: std::net::IpAddr, tenant: &str, parsed: Result<ProveRequest>) -> Option<anyhow::Error> {
+    if let Some(retry_after) = state.honeypot.blocked(ip) {
+        return Some(honeypot::Blocked { retry_after }.into());
+    }
+    let bogus = match &parsed {
+        Err(e)  => honeypot::Bogus { reason: honeypot::MALFORMED_BODY, message: e.to_string() },
+        Ok(req) => Honeypot::inspect(req, state.attest.for_caller(tenant, state.prove_keys.is_some()).map_or(false, |s| s.name() == "request"))?,
+    };
+    state.honeypot.record(ip, bogus.reason);
+    Some(ApiError::bogus(bogus.reason, bogus.message).into())
+}
+
+/// Count a failed prove in the metrics under its response's error code.
+fn count_failure(state: &AppState, err: &anyhow::Error) {
+    let (_, body, _) = errors::describe(err);
+    state.metrics.prove_failed(body.get("error").and_then(|e| e.as_str()).unwrap_or("invalid_request"));
+}
+
+fn error_response(err: anyhow::Error) -> Response {
+    let (status, body, retry) = errors::describe(&err);
+    let mut resp = (status, Json(body)).into_response();
+    if let Some(retry) = retry {
+        resp.headers_mut().insert("retry-after", HeaderValue::from(retry));
+    }
+    resp
+}
+
+/// `x-quota-*` headers, plus `Warning` once past the soft limit.  `quota` is sent as it is: a
+/// successful proof's status already counts it (see [`QuotaStatus::consumed`]).
+fn with_quota_headers(mut resp: Response, quota: Option<&QuotaStatus>) -> Response {
+    let Some(q) = quota else { return resp };
+    let h = resp.headers_mut();
+    h.insert("x-quota-limit",     HeaderValue::from(q.limit));
+    h.insert("x-quota-remaining", HeaderValue::from(q.remaining));
+    h.insert("x-quota-reset",     HeaderValue::from(q.resets_at));
+    if let Some(w) = &q.warning {
+        if let Ok(v) = HeaderValue::from_str(&format!("299 zk_server \"{w}\"")) {
+            h.insert("warning", v);
+        }
+    }
+    resp
+}
+
 /* ---------- proof routine ---------------------------------------- */
-async fn prove(req: ProveRequest) -> Result<ProveResponse> {
-    /* 0. Early fail-fast guard */
+async fn prove(
+    state:  &AppState,
+    caller: IpAddr,
+    tenant: &str,
+    policy: Option<&str>,
+    pinned: Option<&'static policy::Circuit>,
+    mut req: ProveRequest,
+    progress: Option<&Progress>,
+) -> Result<ProveResponse> {
+    /* 0. No new proofs while issuance is halted or draining for maintenance, or while memory is near its ceiling */
+    state.kill_switch.check()?;
+    state.maintenance.check()?;
+    if let Some(w) = &state.watchdog {
+        w.check()?;
+    }
+    let phase = |p| if let Some(progress) = progress { progress.phase(p) };
+    phase(Phase::Checking);
+
+    /* 0. Step size within this tenant's bounds */
+    state.limits.check_step(tenant, req.step)?;
+
+    /* 0. Monthly quota */
+    let quota = state.quotas.check(tenant)?;
+
+    /* 0. Check the EIP-55 checksum, then canonicalize (NFC, trim, case) and validate the identifier for its type */
+    req.identifier_type.check_checksum(&req.wallet).with_code(ErrorCode::InvalidWallet)?;
+    req.wallet = req.identifier_type.canonicalize(&req.wallet);
+    req.identifier_type.validate(&req.wallet).with_code(ErrorCode::InvalidWallet)?;
+
+    /* Consent, signed by the subject over the canonical identifier */
+    let consent = match req.consent.take() {
+        Some(c) => Some(c.check(req.identifier_type, &req.wallet, store::now()).with_code(ErrorCode::InvalidConsent)?),
+        None if state.require_consent => {
+            return Err(ApiError::new(ErrorCode::InvalidConsent, "consent is required (schema_version 2 `consent` object)").into());
+        }
+        None => None,
+    };
+
+    /* Wallet ownership: a SIWE message from `/siwe/message`, signed by the wallet */
+    let ownership = match req.siwe.take() {
+        Some(_) if req.identifier_type != IdentifierType::EvmAddress => {
+            return Err(ApiError::new(ErrorCode::OwnershipUnproven, "siwe needs an evm_address subject").into());
+        }
+        Some(m) => Some(state.siwe.check(m, &req.wallet, store::now()).with_code(ErrorCode::OwnershipUnproven)?),
+        None if state.siwe.required && req.identifier_type == IdentifierType::EvmAddress => {
+            return Err(ApiError::new(ErrorCode::OwnershipUnproven, "a signed message from POST /siwe/message is required").into());
+        }
+        None => None,
+    };
+
+    /* 0. Resolve flags and attributes from the tenant's attestation source, then fail fast */
+    if req.attributes.is_some() {
+        return Err(ApiError::new(
+            ErrorCode::InvalidRequest,
+            "attributes come from the tenant's attestation source, not the request (send an issuer-signed attestation)",
+        ).into());
+    }
+    // Without prove keys the tenant is whatever `x-tenant-id` said.
+    let source = state.attest.for_caller(tenant, state.prove_keys.is_some()).with_code(ErrorCode::UntrustedIssuer)?;
+    let att = source.attest(&Subject {
+        wallet:    &req.wallet,
+        kyc:       req.kyc,
+        sig_valid: req.sig_valid,
+        signed:    req.attestation.as_ref(),
+    }).await?;
+    tracing::debug!(tenant, source = source.name(), "attestation kyc={} sig={}", att.kyc, att.sig_valid);
+    let issuer = att.issuer.or_else(|| state.attest.issuer_for(tenant).map(String::from));
+    req.kyc       = att.kyc;
+    req.sig_valid = att.sig_valid;
+    let mut attributes = att.attributes;
     if req.kyc != 1 || req.sig_valid != 1 {
-        anyhow::bail!("Proof of KYC approval failed.");
-    }
-
-    /* 1. Compute 5 Keccak limbs of the wallet string */
-    let limbs = {
-        let mut k = Keccak::v256();
-        k.update(req.wallet.as_bytes());
-        let mut out = [0u8; 32];
-        k.finalize(&mut out);
-        let mut v = [0i32; 5];
-        for (i, chunk) in out.chunks(4).take(5).enumerate() {
-            v[i] = i32::from_be_bytes(chunk.try_into()?);
-        }
-        v
-    };
-
-    /* 2. Build Wasm ctx (7 args) */
-    let mut args: Vec<String> = limbs.iter().map(|x| x.to_string()).collect();
-    args.extend([req.kyc.to_string(), req.sig_valid.to_string()]);
-
-    let wasm_args = WASMArgsBuilder::default()
-        .file_path(PathBuf::from("examples/kyc_wasm.wasm"))?
-        .invoke("check_kyc")
-        .func_args(args)
-        .build();
-    let wasm_ctx = WASMCtx::new(wasm_args);
-
-    /* 3. Nova setup → prove → verify */
-    let step  = StepSize::new(req.step);
-    let t0    = Instant::now();
-    let pp    = WasmSNARK::<E,S1,S2>::setup(step);
-    let setup = t0.elapsed().as_secs_f64();
-
-    let t1    = Instant::now();
-    let (snark, inst) = WasmSNARK::<E,S1,S2>::prove(&pp,&wasm_ctx,step)?;
-    let prove = t1.elapsed().as_secs_f64();
-
-    let t2    = Instant::now();
-    snark.verify(&pp,&inst)?;
-    let verify= t2.elapsed().as_secs_f64();
-
-    /* 4. Serialize preview */
-    let proof = bincode::serialize(&snark)?;
-    let preview = format!("{}…{}",
-        hex::encode(&proof[..16]),
-        hex::encode(&proof[proof.len()-16..]));
-
-    Ok(ProveResponse {
-        setup_sec:  setup,
-        prove_sec:  prove,
-        verify_sec: verify,
-        proof_len:  proof.len(),
-        proof_hex:  preview,
-    })
+        return Err(ApiError::new(ErrorCode::KycNotApproved, "Proof of KYC approval failed.").into());
+    }
+
+    let plugins = state.plugins.as_ref()
+        .filter(|_| state.features.is_enabled(features::PLUGINS, tenant, &req.wallet, true));
+
+    /* 0a. Plugin pre-processing (may enrich attributes or reject) */
+    if let Some(host) = plugins {
+        let input = PreProveInput {
+            wallet:     req.wallet.clone(),
+            kyc:        req.kyc,
+            sig_valid:  req.sig_valid,
+            step:       req.step,
+            policy:     policy.map(String::from),
+            attributes,
+        };
+        attributes = host.pre_prove(input).await.with_code(ErrorCode::PolicyRejected)?;
+    }
+
+    /* 0b. Policy predicates + circuit selection */
+    let default_circuit = policy::default_circuit(req.legacy_limbs, req.travel_rule.is_some());
+    let circuit = match policy {
+        Some(name) => {
+            let p = state.policies.get(name)?;
+            p.evaluate(&attributes, req.travel_rule.is_some()).with_code(ErrorCode::PolicyRejected)?;
+            p.circuit(default_circuit)
+        }
+        None => default_circuit,
+    };
+
+    /* Verifier challenge: proven by the circuit's `_nonce` export */
+    let circuit = match &req.challenge {
+        Some(c) => {
+            challenge::parse(c).with_code(ErrorCode::InvalidChallenge)?;
+            if state.store.is_none() {
+                // `/verify` takes the nonce from the stored record, so it could never be redeemed.
+                return Err(ApiError::new(ErrorCode::InvalidChallenge, "challenges need the proof store").into());
+            }
+            if circuit.nonce {
+                circuit
+            } else {
+                circuit.with_nonce().ok_or_else(|| {
+                    ApiError::new(ErrorCode::InvalidChallenge, format!("circuit {} takes no challenge", circuit.name))
+                })?
+            }
+        }
+        None if state.challenges.required || circuit.nonce => {
+            return Err(ApiError::new(ErrorCode::InvalidChallenge, "a challenge from GET /challenge is required").into());
+        }
+        None => circuit,
+    };
+    // A renewal is proven on the circuit of the proof it replaces, not whatever the request selects.
+    if let Some(pinned) = pinned.filter(|p| p.name != circuit.name) {
+        let msg = format!("this request selects circuit {}, but the proof being renewed used {}", circuit.name, pinned.name);
+        return Err(ApiError::new(ErrorCode::InvalidRequest, msg).into());
+    }
+    if circuit.travel_rule
+        && !state.features.is_enabled(features::TRAVEL_RULE_CIRCUITS, tenant, &req.wallet, true)
+    {
+        let msg = format!("circuit {} is not enabled for this tenant", circuit.name);
+        return Err(ApiError::new(ErrorCode::CircuitDisabled, msg).into());
+    }
+
+    /* Issuer trust: whoever vouched for the flags must be accepted for this circuit today */
+    if let Some(id) = &issuer {
+        state.issuers.admit(id, circuit.name, store::now()).with_code(ErrorCode::UntrustedIssuer)?;
+    }
+
+    /* 0c. Pre-issuance OPA/Rego hook */
+    if let Some(opa) = &state.opa {
+        opa.check(&IssuanceInput {
+            caller:     caller.to_string(),
+            wallet:     &req.wallet,
+            kyc:        req.kyc,
+            sig_valid:  req.sig_valid,
+            step:       req.step,
+            policy,
+            attributes: &attributes,
+            screening:  Screening { source: source.name(), issuer: issuer.as_deref(), kyc: req.kyc, sig_valid: req.sig_valid },
+        }).await.with_code(ErrorCode::PolicyRejected)?;
+    }
+
+    /* 1–2. Commitment limbs, flags and Travel Rule limbs */
+    let (scheme, args, travel_rule) = guest_inputs(state, circuit, &req)?;
+
+    /* 3. Nova setup → prove → verify on the build serving this subject, unless pre-issued */
+    let proof_id = uuid::Uuid::new_v4().to_string();
+    let guest = state.rollout.select(&req.wallet);
+    let started = std::time::Instant::now();
+    let preissued = state.preissue.take(&preissue::key(&guest.version, circuit.name, req.step, &args));
+    let was_preissued = preissued.is_some();
+    let (run, cost, latency) = match preissued {
+        Some((run, cost)) => (Ok(run), cost, started.elapsed().as_secs_f64()),
+        None => {
+            let in_flight = state.load.enter();
+            let (isolation, params) = (state.prover.clone(), state.params.clone());
+            let (wasm, job_args, step) = (guest.wasm.clone(), args.clone(), req.step);
+            let invoke = circuit.name;
+            let job_progress = progress.cloned();
+            phase(Phase::Waiting);
+            let (run, mut cost) = state.pool.run(move || {
+                // Metered on the proving thread: thread CPU time is per-thread.
+                let meter = Meter::start();
+                if let Some(p) = &job_progress {
+                    // Inline jobs share the parameter cache; warm it first so setup shows as its own phase.
+                    if matches!(isolation, Isolation::Inline) && !params.is_ready(step) {
+                        p.phase(Phase::Setup);
+                        let _ = prover::isolate(|| Ok(params.get(step)));
+                    }
+                    p.phase(Phase::Proving);
+                }
+                let run = isolation.run(&params, &wasm, invoke, job_args, step, Priority::Request);
+                (run, meter.finish())
+            }).await?;
+            if let Some((cpu_sec, peak_rss_mb)) = run.as_ref().ok().and_then(|r| r.worker) {
+                cost.cpu_sec     = cpu_sec;
+                cost.peak_rss_mb = peak_rss_mb;
+            }
+            drop(in_flight);
+            if let Ok(r) = &run {
+                state.load.record_prove(r.prove_sec);
+                state.metrics.proved(r.prove_sec, r.proof.len());
+            }
+            (run, cost, cost.wall_sec)
+        }
+    };
+    state.health.record(run.is_ok(), latency);
+    state.sla.record(tenant, run.is_ok(), latency);
+
+    /* 3a. Canary: re-prove on the other build off the request path and compare */
+    if let Some((candidate, max_slowdown)) = state.rollout.canary(&guest.version) {
+        state.canary.spawn(
+            &state.pool,
+            CanaryJob {
+                circuit: circuit.name, args: args.clone(), step: req.step, candidate, max_slowdown,
+                isolation: state.prover.clone(), params: state.params.clone(),
+            },
+            Outcome::from_run(&guest.version, &run),
+        );
+    }
+    let run = run.with_code(ErrorCode::ProverFailure)?;
+    // A halt raised while this proof ran still stops it from being issued, and from being charged.
+    let charged = usage::charge(&state.kill_switch, &state.quotas, &state.usage, usage::Charge {
+        proof_id:        &proof_id,
+        tenant,
+        circuit:         circuit.name,
+        circuit_version: &guest.version,
+        step:            req.step,
+        cost,
+    })?;
+    if let (Some(rec), Some(b)) = (charged, &state.billing) {
+        b.emit_usage(rec);
+    }
+    if let Err(e) = state.stats.record(attributes.country.as_deref()) {
+        tracing::error!("stats record lost: {e:#}");
+    }
+    let quota = quota.map(QuotaStatus::consumed);
+
+    /* 4. Persist (and replicate) */
+    phase(Phase::Storing);
+    if let Some(store) = &state.store {
+        let now = store::now();
+        store.put_proof(StoredProof {
+            id:              proof_id.clone(),
+            tenant:          tenant.to_string(),
+            circuit:         circuit.name.to_string(),
+            circuit_version: guest.version.clone(),
+            step:            req.step,
+            public_inputs:   circuit.public_inputs(&args),
+            proof:           hex::encode(&run.proof),
+            instance:        hex::encode(&run.instance),
+            created_at:      now,
+            updated_at:      now,
+            shared_with:     Vec::new(),
+            consent,
+            ownership:       ownership.clone(),
+            commitment_scheme: Some(scheme),
+            policy:          policy.map(String::from),
+            issuer:          issuer.clone(),
+            expires_at:      state.expiry.expires_at(now),
+            reminded:        Vec::new(),
+            renewed_by:      None,
+        }).with_code(ErrorCode::Internal)?;
+        if rescreen::tracks(state, tenant) {
+            store.put_subject(&store::ScreenedSubject {
+                proof_id:    proof_id.clone(),
+                tenant:      tenant.to_string(),
+                wallet:      req.wallet.clone(),
+                screened_at: now,
+            }).with_code(ErrorCode::Internal)?;
+        }
+    }
+
+    let mut resp = ProveResponse {
+        proof_id,
+        setup_sec:  run.setup_sec,
+        prove_sec:  run.prove_sec,
+        verify_sec: run.verify_sec,
+        proof_len:  run.proof.len(),
+        proof_hex:  run.preview(),
+        circuit:    circuit.name,
+        circuit_version: guest.version.clone(),
+        commitment_scheme: scheme,
+        issuer,
+        ownership,
+        preissued:  was_preissued,
+        public_inputs: circuit.public_inputs(&args),
+        travel_rule_commitment: travel_rule
+            .filter(|_| circuit.travel_rule)
+            .map(|d| format!("0x{}", hex::encode(d))),
+        plugins: serde_json::Map::new(),
+        proof:      None,
+        instance:   None,
+        proof_encoding: None,
+        compression: None,
+        raw:        None,
+        step:       req.step,
+        wasm:       guest.wasm,
+        pp_digest:  run.pp_digest,
+        quota,
+    };
+
+    /* 5. Plugin post-processing; the proof is already stored and billed, so a failure only drops the annotations */
+    if let Some(host) = plugins {
+        match host.post_prove(serde_json::to_value(&resp)?).await {
+            Ok(extra) => resp.plugins = extra,
+            Err(e)    => tracing::warn!("post_prove plugins failed: {e:#}"),
+        }
+    }
+    resp.raw = Some((run.proof, run.instance));
+    Ok(resp)
+}
+
+/// Guest arguments for `req` on `circuit`, with the commitment scheme used and the Travel Rule
+/// digest (when a payload was sent).  They start with the witness words of a circuit that hashes
+/// in the guest; [`policy::Circuit::public_inputs`] drops them.
+fn guest_inputs(state: &AppState, circuit: &policy::Circuit, req: &ProveRequest) -> Result<(HashScheme, Vec<String>, Option<[u8; 32]>)> {
+    /* 1. Compute commitment limbs of the wallet string (hash per circuit) */
+    let scheme = state.policies.commitment_for(circuit);
+    let limbs  = scheme.limbs(&req.identifier_type.preimage(&req.wallet))?;
+
+    /* 2. Build Wasm ctx ([5 raw address words,] 8 or legacy 5 limbs + 2 flags, +8 Travel Rule limbs, +8 nonce limbs) */
+    let mut args = if circuit.keccak_in_guest {
+        anyhow::ensure!(req.identifier_type == IdentifierType::EvmAddress, "circuit {} needs an evm_address subject", circuit.name);
+        engine::kyc_call_keccak(&req.wallet, req.kyc, req.sig_valid)?.args
+    } else {
+        let mut args = encoding::guest_args(&limbs[..circuit.wallet_limbs]);
+        args.extend([req.kyc.to_string(), req.sig_valid.to_string()]);
+        args
+    };
+
+    let travel_rule = req.travel_rule.as_ref().map(|p| p.commitment()).transpose()?;
+    if circuit.travel_rule {
+        let d = travel_rule.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("circuit {} needs a travel_rule payload", circuit.name))?;
+        args.extend(encoding::guest_args(&encoding::digest_words(d)));
+    }
+    if circuit.nonce {
+        let c = req.challenge.as_deref()
+            .ok_or_else(|| anyhow::anyhow!("circuit {} needs a challenge", circuit.name))?;
+        args.extend(encoding::guest_args(&encoding::digest_words(&challenge::parse(c)?)));
+    }
+    Ok((scheme, args, travel_rule))
 }
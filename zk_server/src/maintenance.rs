@@ -0,0 +1,107 @@
+//! Maintenance mode, for draining a server before an upgrade.
+//! `PUT /admin/maintenance { "enabled": true, "message"?, "retry_after_secs"? }` stops new proofs:
+//! `/prove`, `/legacy/prove` and renewals fail with [`UnderMaintenance`] (HTTP 503
+//! `maintenance`, with `Retry-After`), while verification, proof retrieval and job polling carry
+//! on.  Queued async jobs stop being started, and are written to `ZK_PENDING_JOBS_FILE` (default
+//! `pending_jobs.json`) with their request bodies; jobs already proving finish normally.  The next
+//! process to start re-queues every job in that file under the same job id and deletes it, so
+//! clients keep polling the ids they were given.  Turning maintenance off resumes the queue and
+//! discards the file.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, sync::Mutex};
+use tokio::sync::watch;
+
+use crate::{jobs::PendingJob, store};
+
+fn default_retry() -> u64 { 300 }
+
+/// Returned (inside `anyhow::Error`) for prove requests while maintenance is on.
+#[derive(Clone, Debug, Serialize)]
+pub struct UnderMaintenance {
+    pub message:     String,
+    pub since:       u64,
+    pub retry_after: u64,
+}
+
+impl std::fmt::Display for UnderMaintenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for UnderMaintenance {}
+
+#[derive(Deserialize)]
+pub struct Toggle {
+    pub enabled:          bool,
+    #[serde(default)]
+    pub message:          Option<String>,
+    #[serde(default = "default_retry")]
+    pub retry_after_secs: u64,
+}
+
+pub struct Maintenance {
+    /// Pending-job file, read at startup and written when maintenance starts.
+    pub file: PathBuf,
+    state:    Mutex<Option<UnderMaintenance>>,
+    on:       watch::Sender<bool>,
+}
+
+impl Maintenance {
+    pub fn from_env() -> Self {
+        let file = PathBuf::from(std::env::var("ZK_PENDING_JOBS_FILE").unwrap_or_else(|_| "pending_jobs.json".into()));
+        Maintenance { file, state: Mutex::new(None), on: watch::channel(false).0 }
+    }
+
+    /// `Err` while maintenance is on.
+    pub fn check(&self) -> Result<(), UnderMaintenance> {
+        match &*self.state.lock().unwrap() {
+            Some(m) => Err(m.clone()),
+            None    => Ok(()),
+        }
+    }
+
+    pub fn status(&self) -> Option<UnderMaintenance> {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Turn maintenance on or off.
+    pub fn set(&self, t: Toggle) {
+        let next = t.enabled.then(|| UnderMaintenance {
+            message:     t.message.unwrap_or_else(|| "the server is under maintenance; retry later".into()),
+            since:       store::now(),
+            retry_after: t.retry_after_secs.max(1),
+        });
+        *self.state.lock().unwrap() = next;
+        self.on.send_replace(t.enabled);
+    }
+
+    /// Wait until maintenance is off.
+    pub async fn wait_open(&self) {
+        let mut rx = self.on.subscribe();
+        rx.wait_for(|on| !on).await.ok();
+    }
+
+    /// Write the queued jobs for the next process.
+    pub fn persist(&self, jobs: &[PendingJob]) -> Result<()> {
+        store::write_json(&self.file, &jobs)
+    }
+
+    /// Drop the pending-job file (maintenance ended without a restart).
+    pub fn discard(&self) {
+        match std::fs::remove_file(&self.file) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => tracing::error!("removing {}: {e}", self.file.display()),
+        }
+    }
+
+    /// Jobs left by a previous process; the file is removed once read.
+    pub fn take_pending(&self) -> Result<Vec<PendingJob>> {
+        let jobs: Vec<PendingJob> = store::read_json(&self.file)?.unwrap_or_default();
+        self.discard();
+        Ok(jobs)
+    }
+}
@@ -0,0 +1,141 @@
+//! Prometheus metrics (`GET /metrics`, text exposition format 0.0.4).
+//! Counters and histograms are kept in-process since startup; gauges are read when scraped.
+//! Labels carry route templates and error codes only — never proof ids, tenants or subjects —
+//! so the endpoint is as safe to expose as `/status`.
+//!
+//! | Metric | Type | Labels |
+//! |--------|------|--------|
+//! | `zk_http_requests_total` | counter | `method`, `route`, `status` |
+//! | `zk_prove_duration_seconds` | histogram | |
+//! | `zk_verify_duration_seconds` | histogram | |
+//! | `zk_proof_size_bytes` | histogram | |
+//! | `zk_prove_failures_total` | counter | `error` |
+//! | `zk_bogus_requests_total` | counter | `reason` |
+//! | `zk_prove_in_flight`, `zk_prove_queue_depth`, `zk_prove_capacity` | gauge | |
+
+use axum::{
+    extract::{MatchedPath, State},
+    http::{header, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{collections::BTreeMap, fmt::Write, sync::{Arc, Mutex}};
+
+use crate::AppState;
+
+const PROVE_BUCKETS:  &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0];
+const VERIFY_BUCKETS: &[f64] = &[0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+const SIZE_BUCKETS:   &[f64] = &[1e4, 5e4, 1e5, 2.5e5, 5e5, 1e6, 2.5e6, 5e6, 1e7];
+
+struct Histogram {
+    bounds: &'static [f64],
+    /// Per-bucket (non-cumulative) counts, then the `+Inf` overflow; sum.
+    counts: Mutex<(Vec<u64>, f64)>,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Histogram { bounds, counts: Mutex::new((vec![0; bounds.len() + 1], 0.0)) }
+    }
+
+    fn observe(&self, v: f64) {
+        let i = self.bounds.iter().position(|b| v <= *b).unwrap_or(self.bounds.len());
+        let mut c = self.counts.lock().unwrap();
+        c.0[i] += 1;
+        c.1 += v;
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let (counts, sum) = self.counts.lock().unwrap().clone();
+        writeln!(out, "# HELP {name} {help}\n# TYPE {name} histogram").ok();
+        let mut cumulative = 0;
+        for (b, n) in self.bounds.iter().zip(&counts) {
+            cumulative += n;
+            writeln!(out, "{name}_bucket{{le=\"{b}\"}} {cumulative}").ok();
+        }
+        cumulative += counts[self.bounds.len()];
+        writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {cumulative}\n{name}_sum {sum}\n{name}_count {cumulative}").ok();
+    }
+}
+
+pub struct Metrics {
+    requests: Mutex<BTreeMap<(String, String, u16), u64>>,
+    failures: Mutex<BTreeMap<String, u64>>,
+    prove:    Histogram,
+    verify:   Histogram,
+    size:     Histogram,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics {
+            requests: Mutex::default(),
+            failures: Mutex::default(),
+            prove:    Histogram::new(PROVE_BUCKETS),
+            verify:   Histogram::new(VERIFY_BUCKETS),
+            size:     Histogram::new(SIZE_BUCKETS),
+        }
+    }
+
+    /// A proof was produced: its proving time and size.
+    pub fn proved(&self, prove_sec: f64, proof_len: usize) {
+        self.prove.observe(prove_sec);
+        self.size.observe(proof_len as f64);
+    }
+
+    /// A SNARK verification ran (cache hits don't count).
+    pub fn verified(&self, verify_sec: f64) {
+        self.verify.observe(verify_sec);
+    }
+
+    /// A prove request failed with `error` (the error code of its response body).
+    pub fn prove_failed(&self, error: &str) {
+        *self.failures.lock().unwrap().entry(error.to_string()).or_default() += 1;
+    }
+
+    fn render(&self, state: &AppState) -> String {
+        let mut out = String::new();
+        writeln!(out, "# HELP zk_http_requests_total HTTP requests by route template and status.\n# TYPE zk_http_requests_total counter").ok();
+        for ((method, route, status), n) in self.requests.lock().unwrap().iter() {
+            writeln!(out, "zk_http_requests_total{{method=\"{method}\",route=\"{}\",status=\"{status}\"}} {n}", escape(route)).ok();
+        }
+        self.prove.render(&mut out, "zk_prove_duration_seconds", "Time spent proving, per successful proof.");
+        self.verify.render(&mut out, "zk_verify_duration_seconds", "Time spent in SNARK verification, per uncached verify.");
+        self.size.render(&mut out, "zk_proof_size_bytes", "Serialized size of issued proofs.");
+        writeln!(out, "# HELP zk_prove_failures_total Failed prove requests by error code.\n# TYPE zk_prove_failures_total counter").ok();
+        for (error, n) in self.failures.lock().unwrap().iter() {
+            writeln!(out, "zk_prove_failures_total{{error=\"{}\"}} {n}", escape(error)).ok();
+        }
+        writeln!(out, "# HELP zk_bogus_requests_total Prove requests diverted by the honeypot, by reason.\n# TYPE zk_bogus_requests_total counter").ok();
+        for (reason, n) in state.honeypot.report().rejected {
+            writeln!(out, "zk_bogus_requests_total{{reason=\"{reason}\"}} {n}").ok();
+        }
+        let load = state.load.hints();
+        for (name, help, v) in [
+            ("zk_prove_in_flight", "Proofs running or waiting for a prover.", load.in_flight),
+            ("zk_prove_queue_depth", "Proofs beyond the prover capacity.", load.queue_depth),
+            ("zk_prove_capacity", "Proofs this replica runs at once.", load.capacity),
+        ] {
+            writeln!(out, "# HELP {name} {help}\n# TYPE {name} gauge\n{name} {v}").ok();
+        }
+        out
+    }
+}
+
+fn escape(v: &str) -> String {
+    v.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Middleware: count every request by method, route template and status.
+pub async fn track<B>(State(state): State<Arc<AppState>>, req: Request<B>, next: Next<B>) -> Response {
+    let method = req.method().to_string();
+    let route = req.extensions().get::<MatchedPath>().map_or("unmatched", |p| p.as_str()).to_string();
+    let resp = next.run(req).await;
+    *state.metrics.requests.lock().unwrap().entry((method, route, resp.status().as_u16())).or_default() += 1;
+    resp
+}
+
+/// `GET /metrics`
+pub async fn handle_metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], state.metrics.render(&state))
+}
@@ -0,0 +1,188 @@
+//! NUMA-aware placement of subprocess prover workers (`ZK_NUMA_NODES`).
+//! MSM-heavy proving slows down measurably when its memory sits on another socket, so on
+//! multi-node hosts each worker is pinned to one node's CPUs and its allocations are bound to that
+//! node (`set_mempolicy(MPOL_BIND)`), both applied before the worker starts.  `ZK_NUMA_NODES=auto`
+//! uses every node under `/sys/devices/system/node`; a list such as `0,1` restricts placement to
+//! those nodes.  Each worker goes to the node with the fewest running workers.  Per-node running
+//! workers, completed proofs and worker CPU seconds are reported by `GET /admin/scaling`.
+//! Linux only, and only with `ZK_PROVER_MODE=subprocess`; inline proofs run on the runtime's
+//! blocking threads and are not pinned.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+#[derive(Debug)]
+struct Node {
+    id:        u32,
+    cpus:      Vec<usize>,
+    running:   AtomicUsize,
+    completed: AtomicU64,
+    cpu_ms:    AtomicU64,
+}
+
+#[derive(Debug)]
+pub struct Numa {
+    nodes: Vec<Node>,
+}
+
+#[derive(Serialize)]
+pub struct NodeReport {
+    pub node:      u32,
+    pub cpus:      usize,
+    pub running:   usize,
+    pub completed: u64,
+    pub cpu_sec:   f64,
+}
+
+/// CPUs and memory node for one worker, applied in the child before exec.
+#[derive(Clone, Copy)]
+pub struct Pin {
+    #[cfg(target_os = "linux")]
+    cpus: libc::cpu_set_t,
+    node: u32,
+}
+
+impl Pin {
+    /// Restrict the calling process to the node.  Only async-signal-safe calls, as this runs
+    /// between fork and exec.
+    #[cfg(target_os = "linux")]
+    pub fn apply(&self) -> std::io::Result<()> {
+        const MPOL_BIND: libc::c_long = 2;
+        unsafe {
+            if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &self.cpus) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            let mask: libc::c_ulong = 1 << self.node;
+            let maxnode = (self.node + 2) as libc::c_ulong;
+            if libc::syscall(libc::SYS_set_mempolicy, MPOL_BIND, &mask as *const libc::c_ulong, maxnode) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn apply(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A worker's slot on a node; leaves it when dropped.
+pub struct Placement<'a> {
+    node: &'a Node,
+}
+
+impl Placement<'_> {
+    pub fn pin(&self) -> Pin {
+        #[cfg(target_os = "linux")]
+        let cpus = {
+            let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+            for &cpu in &self.node.cpus {
+                unsafe { libc::CPU_SET(cpu, &mut set) };
+            }
+            set
+        };
+        Pin {
+            #[cfg(target_os = "linux")]
+            cpus,
+            node: self.node.id,
+        }
+    }
+
+    /// Count a finished proof and the worker's CPU time against the node.
+    pub fn record(&self, cpu_sec: f64) {
+        self.node.completed.fetch_add(1, Ordering::Relaxed);
+        self.node.cpu_ms.fetch_add((cpu_sec * 1000.0) as u64, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Placement<'_> {
+    fn drop(&mut self) {
+        self.node.running.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Numa {
+    /// `None` when `ZK_NUMA_NODES` is unset, or the host has a single node.
+    pub fn from_env() -> Result<Option<Self>> {
+        let raw = match std::env::var("ZK_NUMA_NODES") {
+            Ok(v) if !v.is_empty() => v,
+            _ => return Ok(None),
+        };
+        if !cfg!(target_os = "linux") {
+            bail!("ZK_NUMA_NODES is only supported on Linux");
+        }
+        let available = read_nodes().context("reading /sys/devices/system/node")?;
+        let wanted: Option<Vec<u32>> = match raw.trim() {
+            "auto" => None,
+            list => Some(list.split(',').map(|n| n.trim().parse().with_context(|| format!("bad NUMA node {n:?} in ZK_NUMA_NODES")))
+                .collect::<Result<_>>()?),
+        };
+        let mut nodes = Vec::new();
+        for (id, cpus) in available {
+            if wanted.as_ref().map_or(true, |w| w.contains(&id)) {
+                nodes.push(Node { id, cpus, running: AtomicUsize::new(0), completed: AtomicU64::new(0), cpu_ms: AtomicU64::new(0) });
+            }
+        }
+        if let Some(w) = &wanted {
+            if let Some(missing) = w.iter().find(|id| !nodes.iter().any(|n| n.id == **id)) {
+                bail!("ZK_NUMA_NODES names node {missing}, which this host does not have (or has no CPUs)");
+            }
+        }
+        if nodes.len() < 2 && wanted.is_none() {
+            tracing::info!("single NUMA node; worker placement disabled");
+            return Ok(None);
+        }
+        Ok(Some(Numa { nodes }))
+    }
+
+    /// Take a slot on the node with the fewest running workers.
+    pub fn place(&self) -> Placement<'_> {
+        let node = self.nodes.iter().min_by_key(|n| n.running.load(Ordering::Relaxed)).expect("at least one node");
+        node.running.fetch_add(1, Ordering::Relaxed);
+        Placement { node }
+    }
+
+    pub fn report(&self) -> Vec<NodeReport> {
+        self.nodes.iter().map(|n| NodeReport {
+            node:      n.id,
+            cpus:      n.cpus.len(),
+            running:   n.running.load(Ordering::Relaxed),
+            completed: n.completed.load(Ordering::Relaxed),
+            cpu_sec:   n.cpu_ms.load(Ordering::Relaxed) as f64 / 1000.0,
+        }).collect()
+    }
+}
+
+/// Nodes with CPUs, and their CPUs; node ids above 63 are skipped (the mempolicy mask is one word).
+fn read_nodes() -> Result<Vec<(u32, Vec<usize>)>> {
+    let mut nodes = Vec::new();
+    for entry in std::fs::read_dir("/sys/devices/system/node")? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let Some(id) = name.to_str().and_then(|n| n.strip_prefix("node")).and_then(|n| n.parse::<u32>().ok()) else { continue };
+        if id > 63 {
+            continue;
+        }
+        let list = std::fs::read_to_string(entry.path().join("cpulist"))?;
+        let cpus = parse_cpulist(list.trim()).with_context(|| format!("node{id}/cpulist"))?;
+        if !cpus.is_empty() {
+            nodes.push((id, cpus));
+        }
+    }
+    nodes.sort_by_key(|n| n.0);
+    Ok(nodes)
+}
+
+/// `0-3,8-11` → `[0, 1, 2, 3, 8, 9, 10, 11]`.
+fn parse_cpulist(list: &str) -> Result<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for part in list.split(',').filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((a, b)) => cpus.extend(a.parse::<usize>()?..=b.parse::<usize>()?),
+            None => cpus.push(part.parse()?),
+        }
+    }
+    Ok(cpus)
+}
@@ -0,0 +1,92 @@
+//! Pre-issuance hook backed by an Open Policy Agent sidecar.
+//! The Rego rule at `ZK_OPA_URL` (e.g. `http://127.0.0.1:8181/v1/data/kyc/issuance`)
+//! must evaluate to `{ "allow": bool, "reason"?: string }`.  An undefined result
+//! denies issuance (`403 POLICY_REJECTED`); an unreachable, slow or malformed
+//! sidecar fails closed too, as `503 POLICY_UNAVAILABLE`.  The sidecar gets
+//! `ZK_OPA_TIMEOUT_MS` (default 2000) per decision.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::{errors::{ErrorCode, WithCode}, policy::SubjectAttributes};
+
+/// Request context handed to Rego as `input`.
+#[derive(Serialize)]
+pub struct IssuanceInput<'a> {
+    /// Client IP, looking through trusted proxies.
+    pub caller:     String,
+    pub wallet:     &'a str,
+    pub kyc:        i32,
+    pub sig_valid:  i32,
+    pub step:       usize,
+    pub policy:     Option<&'a str>,
+    pub attributes: &'a SubjectAttributes,
+    pub screening:  Screening<'a>,
+}
+
+/// Outcome of the tenant's attestation source for the subject.
+#[derive(Serialize)]
+pub struct Screening<'a> {
+    /// Source name (`request`, `signed`, `circle`, `http`, `database`).
+    pub source:    &'a str,
+    /// Issuer that vouched for the outcome, if known.
+    pub issuer:    Option<&'a str>,
+    pub kyc:       i32,
+    pub sig_valid: i32,
+}
+
+#[derive(Deserialize)]
+struct Decision {
+    allow:  bool,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct OpaResponse {
+    result: Option<Decision>,
+}
+
+pub struct OpaHook {
+    url:    String,
+    client: reqwest::Client,
+}
+
+impl OpaHook {
+    /// Build from `ZK_OPA_URL`; `None` disables the hook.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(url) = std::env::var("ZK_OPA_URL") else { return Ok(None) };
+        let timeout = std::env::var("ZK_OPA_TIMEOUT_MS").ok()
+            .map(|v| v.parse().context("ZK_OPA_TIMEOUT_MS must be a number"))
+            .transpose()?
+            .map_or(Duration::from_secs(2), Duration::from_millis);
+        let client = reqwest::Client::builder()
+            .connect_timeout(timeout)
+            .timeout(timeout)
+            .build()?;
+        Ok(Some(Self { url, client }))
+    }
+
+    pub async fn check(&self, input: &IssuanceInput<'_>) -> Result<()> {
+        let resp: OpaResponse = async {
+            self.client
+                .post(&self.url)
+                .json(&serde_json::json!({ "input": input }))
+                .send().await
+                .context("issuance policy unreachable")?
+                .error_for_status()
+                .context("issuance policy error")?
+                .json().await
+                .context("issuance policy returned malformed decision")
+        }.await.with_code(ErrorCode::PolicyUnavailable)?;
+
+        match resp.result {
+            Some(Decision { allow: true, .. }) => Ok(()),
+            Some(Decision { reason, .. }) => {
+                bail!("Issuance denied: {}", reason.unwrap_or_else(|| "policy denied".into()))
+            }
+            None => bail!("Issuance denied: policy undefined"),
+        }
+    }
+}
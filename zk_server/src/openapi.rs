@@ -0,0 +1,121 @@
+//! OpenAPI 3 document of the client-facing API, served at `GET /openapi.json` for SDK
+//! generators.  It is built from the request and response structs and the `#[utoipa::path]`
+//! annotations on the handlers: proving (`/prove`, `/prove/batch`, `/jobs/{id}`), verifying
+//! (`/verify`, `/proofs/{id}/verify`) and `/status`.  Built with the `swagger-ui` feature, the
+//! server also serves Swagger UI at `/docs`.
+
+use axum::Json;
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi, ToSchema,
+};
+
+/// Failure body of the prove and verify routes (see errors.rs).
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct ErrorBody {
+    /// Stable code, e.g. `QUOTA_EXCEEDED`.
+    code:    String,
+    message: String,
+    /// `code` in lower case, for older clients.
+    error:   String,
+    #[schema(value_type = Option<Object>)]
+    details: Option<serde_json::Value>,
+}
+
+/// `202` answer of an asynchronous `/prove`.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct JobAccepted {
+    job_id:     String,
+    status_url: String,
+}
+
+/// Answer of `POST /verify`.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct VerifyResult {
+    valid:      bool,
+    step:       usize,
+    verify_sec: Option<f64>,
+    cached:     bool,
+    error:      Option<ErrorBody>,
+}
+
+/// Answer of `GET /proofs/{id}/verify`.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct StoredVerifyResult {
+    proof_id:         String,
+    /// The SNARK verifies and no check below failed.
+    valid:            bool,
+    proof_ok:         bool,
+    revoked:          bool,
+    expired:          bool,
+    expires_at:       Option<u64>,
+    /// Only with `?scope=`.
+    spent:            Option<bool>,
+    issuer:           Option<String>,
+    issuer_trusted:   bool,
+    /// Only with `?issuers=`.
+    issuer_accepted:  Option<bool>,
+    version_accepted: bool,
+    circuit:          String,
+    circuit_version:  String,
+    verify_sec:       Option<f64>,
+    cached:           bool,
+    error:            Option<ErrorBody>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "zk_server", description = "Zero-knowledge KYC proofs over HTTP."),
+    paths(
+        crate::handle_prove,
+        crate::batch::handle_batch,
+        crate::handle_job,
+        crate::handle_verify,
+        crate::handle_verify_stored,
+        crate::handle_status,
+    ),
+    components(schemas(
+        crate::request::ProveRequest,
+        crate::ProveResponse,
+        crate::ProofEncoding,
+        crate::compression::Compression,
+        crate::VerifyBody,
+        crate::batch::BatchResponse,
+        crate::batch::ItemResult,
+        crate::batch::BatchStats,
+        crate::jobs::Job,
+        crate::jobs::JobState,
+        crate::jobs::Phase,
+        crate::status::StatusSummary,
+        ErrorBody,
+        JobAccepted,
+        VerifyResult,
+        StoredVerifyResult,
+    )),
+    modifiers(&Credentials),
+    tags(
+        (name = "proving", description = "Issue KYC proofs"),
+        (name = "verifying", description = "Check proofs"),
+        (name = "status", description = "Service health"),
+    ),
+)]
+pub struct ApiDoc;
+
+/// Prove keys (`ZK_PROVE_KEYS_FILE`) and verifier API keys (`ZK_API_KEYS_FILE`).
+struct Credentials;
+
+impl Modify for Credentials {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme("prove_key", SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()));
+        components.add_security_scheme("api_key", SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("x-api-key"))));
+    }
+}
+
+pub async fn handle_openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
@@ -0,0 +1,81 @@
+//! Durable outbox for the side effects of a completed proof.
+//! Before a webhook event is queued for delivery, its intent is written to `ZK_OUTBOX_DIR`
+//! (default `$ZK_STORE_DIR/outbox`) as one JSON file named by the event id.  The file is removed
+//! only once the sender reaches a final outcome: delivered, or handed to the dead-letter store.
+//! At startup every intent still on disk is dispatched again; that is work a previous process
+//! queued and died holding.  Receivers deduplicate on the event id, so a crash between delivery
+//! and removal costs at most a duplicate that the receiver drops.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::{deadletter::Kind, store::{self, read_json, write_json}};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Intent {
+    /// Event id; also the file name.
+    pub id:         String,
+    pub kind:       Kind,
+    pub payload:    serde_json::Value,
+    pub created_at: u64,
+}
+
+pub struct Outbox {
+    dir: PathBuf,
+}
+
+impl Outbox {
+    /// Build from `ZK_OUTBOX_DIR` or `ZK_STORE_DIR`; `None` when neither is set (delivery is then
+    /// best-effort and in memory only).
+    pub fn from_env() -> Result<Option<Self>> {
+        let dir = match (std::env::var("ZK_OUTBOX_DIR"), std::env::var("ZK_STORE_DIR")) {
+            (Ok(d), _) if !d.is_empty() => PathBuf::from(d),
+            (_, Ok(s)) if !s.is_empty() => PathBuf::from(s).join("outbox"),
+            _ => return Ok(None),
+        };
+        std::fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+        Ok(Some(Outbox { dir }))
+    }
+
+    fn path(&self, id: &str) -> Result<PathBuf> {
+        match uuid::Uuid::parse_str(id) {
+            Ok(u)  => Ok(self.dir.join(format!("{u}.json"))),
+            Err(_) => bail!("outbox event id {id:?} is not a UUID"),
+        }
+    }
+
+    /// Persist an intent.  Recording the same id again overwrites it.
+    pub fn record(&self, kind: Kind, id: &str, payload: &impl Serialize) -> Result<()> {
+        let intent = Intent {
+            id:         id.to_string(),
+            kind,
+            payload:    serde_json::to_value(payload)?,
+            created_at: store::now(),
+        };
+        write_json(&self.path(id)?, &intent)
+    }
+
+    /// The sender is finished with `id`.
+    pub fn done(&self, id: &str) {
+        let res = self.path(id).and_then(|p| match std::fs::remove_file(p) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        });
+        if let Err(e) = res {
+            tracing::error!(event = %id, "clearing outbox entry: {e:#}");
+        }
+    }
+
+    /// Intents left by earlier processes, oldest first.
+    pub fn pending(&self) -> Result<Vec<Intent>> {
+        let mut out = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") { continue; }
+            out.extend(read_json::<Intent>(&path)?);
+        }
+        out.sort_by_key(|i| i.created_at);
+        Ok(out)
+    }
+}
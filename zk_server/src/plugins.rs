@@ -0,0 +1,200 @@
+//! Operator plugins: sandboxed WASM modules (wasmtime) run before and after proving.
+//!
+//! Host-call ABI (all JSON is UTF-8, passed through the plugin's linear memory):
+//!
+//! | export                              | purpose                                   |
+//! |-------------------------------------|-------------------------------------------|
+//! | `memory`                            | linear memory (required)                  |
+//! | `zk_alloc(len: i32) -> i32`         | reserve `len` bytes, return offset        |
+//! | `zk_pre_prove(ptr, len) -> i64`     | input enrichment / extra validation       |
+//! | `zk_post_prove(ptr, len) -> i64`    | response augmentation                     |
+//!
+//! Hooks return `(out_ptr << 32) | out_len` of a JSON result, or `0` for "no opinion".
+//! `zk_pre_prove` results: `{ "allow"?: bool, "reason"?: string, "attributes"?: {..} }`.
+//! `zk_post_prove` results: any JSON value, attached under the plugin's name.
+//!
+//! Imports under module `zkkyc`: `log(ptr: i32, len: i32)`.
+//!
+//! Each call gets a fresh store with a fuel budget and a memory cap, and hooks run on the blocking
+//! pool.  A result (or log message) must lie inside the plugin's memory and be at most
+//! `MAX_OUTPUT` (`MAX_LOG`) bytes; nothing is allocated before that is checked.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{path::Path, sync::Arc};
+use wasmtime::{Caller, Config, Engine, Instance, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::policy::SubjectAttributes;
+
+const FUEL:      u64   = 50_000_000;
+const MEM_LIMIT: usize = 16 << 20;
+/// Largest hook result read back.
+const MAX_OUTPUT: usize = 1 << 20;
+/// Largest `log` message read.
+const MAX_LOG:    usize = 4 << 10;
+
+/// What a pre-prove hook sees.
+#[derive(Serialize)]
+pub struct PreProveInput {
+    pub wallet:     String,
+    pub kyc:        i32,
+    pub sig_valid:  i32,
+    pub step:       usize,
+    pub policy:     Option<String>,
+    pub attributes: SubjectAttributes,
+}
+
+#[derive(Deserialize, Default)]
+pub struct PreProveOutput {
+    #[serde(default)]
+    pub allow:      Option<bool>,
+    #[serde(default)]
+    pub reason:     Option<String>,
+    #[serde(default)]
+    pub attributes: Option<SubjectAttributes>,
+}
+
+struct PluginCtx {
+    name:   String,
+    limits: StoreLimits,
+}
+
+pub struct Plugin {
+    name:   String,
+    module: Module,
+}
+
+pub struct PluginHost {
+    engine:  Engine,
+    linker:  Linker<PluginCtx>,
+    plugins: Vec<Plugin>,
+}
+
+impl PluginHost {
+    /// Load every `*.wasm` in `dir` (sorted by file name — that is the call order).
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut cfg = Config::new();
+        cfg.consume_fuel(true);
+        let engine = Engine::new(&cfg)?;
+
+        let mut linker = Linker::new(&engine);
+        linker.func_wrap("zkkyc", "log", |mut caller: Caller<'_, PluginCtx>, ptr: i32, len: i32| {
+            if let Ok(msg) = read_str(&mut caller, ptr, len) {
+                tracing::info!(plugin = %caller.data().name, "{msg}");
+            }
+        })?;
+
+        let mut paths: Vec<_> = std::fs::read_dir(dir)
+            .with_context(|| format!("reading plugin dir {}", dir.display()))?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().map_or(false, |x| x == "wasm"))
+            .collect();
+        paths.sort();
+
+        let mut plugins = Vec::with_capacity(paths.len());
+        for p in paths {
+            let name = p.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            let module = Module::from_file(&engine, &p)
+                .with_context(|| format!("compiling plugin {}", p.display()))?;
+            tracing::info!("loaded plugin {name}");
+            plugins.push(Plugin { name, module });
+        }
+        Ok(Self { engine, linker, plugins })
+    }
+
+    /// Build from `ZK_PLUGIN_DIR`; `None` when unset.
+    pub fn from_env() -> Result<Option<Self>> {
+        match std::env::var("ZK_PLUGIN_DIR") {
+            Ok(dir) => Self::load(dir.as_ref()).map(Some),
+            Err(_)  => Ok(None),
+        }
+    }
+
+    /// Run every `zk_pre_prove`; enrichments are applied in order, the first deny wins.  Returns
+    /// the enriched attributes.
+    pub async fn pre_prove(self: &Arc<Self>, mut input: PreProveInput) -> Result<SubjectAttributes> {
+        let host = self.clone();
+        tokio::task::spawn_blocking(move || {
+            for p in &host.plugins {
+                let payload = serde_json::to_vec(&input)?;
+                let Some(out) = host.call(p, "zk_pre_prove", &payload)? else { continue };
+                let out: PreProveOutput = serde_json::from_slice(&out)
+                    .with_context(|| format!("plugin {}: bad pre_prove result", p.name))?;
+                if out.allow == Some(false) {
+                    bail!("Rejected by plugin {}: {}", p.name, out.reason.unwrap_or_default());
+                }
+                if let Some(a) = out.attributes {
+                    input.attributes = a;
+                }
+            }
+            Ok(input.attributes)
+        }).await.context("pre_prove plugins panicked")?
+    }
+
+    /// Run every `zk_post_prove` over the serialized response; returns name → result.
+    pub async fn post_prove(self: &Arc<Self>, response: Value) -> Result<serde_json::Map<String, Value>> {
+        let host = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let payload = serde_json::to_vec(&response)?;
+            let mut extra = serde_json::Map::new();
+            for p in &host.plugins {
+                if let Some(out) = host.call(p, "zk_post_prove", &payload)? {
+                    let v: Value = serde_json::from_slice(&out)
+                        .with_context(|| format!("plugin {}: bad post_prove result", p.name))?;
+                    extra.insert(p.name.clone(), v);
+                }
+            }
+            Ok(extra)
+        }).await.context("post_prove plugins panicked")?
+    }
+
+    /// Invoke `hook` if exported; `Ok(None)` when absent or the plugin returned 0.
+    fn call(&self, p: &Plugin, hook: &str, input: &[u8]) -> Result<Option<Vec<u8>>> {
+        let ctx = PluginCtx {
+            name:   p.name.clone(),
+            limits: StoreLimitsBuilder::new().memory_size(MEM_LIMIT).build(),
+        };
+        let mut store = Store::new(&self.engine, ctx);
+        store.limiter(|c| &mut c.limits);
+        store.set_fuel(FUEL)?;
+
+        let inst: Instance = self.linker.instantiate(&mut store, &p.module)
+            .with_context(|| format!("instantiating plugin {}", p.name))?;
+        let Ok(func) = inst.get_typed_func::<(i32, i32), i64>(&mut store, hook) else {
+            return Ok(None);
+        };
+        let alloc  = inst.get_typed_func::<i32, i32>(&mut store, "zk_alloc")?;
+        let memory = inst.get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow!("plugin {} exports no memory", p.name))?;
+
+        let len = i32::try_from(input.len())?;
+        let ptr = alloc.call(&mut store, len)?;
+        memory.write(&mut store, ptr as u32 as usize, input)?;
+
+        let packed = func.call(&mut store, (ptr, len))
+            .with_context(|| format!("plugin {} trapped in {hook}", p.name))?;
+        if packed == 0 {
+            return Ok(None);
+        }
+        let (out_ptr, out_len) = ((packed as u64 >> 32) as usize, (packed as u64 & 0xffff_ffff) as usize);
+        if out_len > MAX_OUTPUT || out_ptr + out_len > memory.data_size(&store) {
+            bail!("plugin {}: {hook} result of {out_len} bytes at {out_ptr} is out of bounds", p.name);
+        }
+        let mut out = vec![0u8; out_len];
+        memory.read(&store, out_ptr, &mut out)?;
+        Ok(Some(out))
+    }
+}
+
+fn read_str(caller: &mut Caller<'_, PluginCtx>, ptr: i32, len: i32) -> Result<String> {
+    let mem = caller.get_export("memory").and_then(|e| e.into_memory())
+        .ok_or_else(|| anyhow!("no memory export"))?;
+    let (ptr, len) = (ptr as u32 as usize, usize::try_from(len)?);
+    if len > MAX_LOG || ptr + len > mem.data_size(&*caller) {
+        bail!("message of {len} bytes at {ptr} is out of bounds");
+    }
+    let mut buf = vec![0u8; len];
+    mem.read(&*caller, ptr, &mut buf)?;
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
@@ -0,0 +1,214 @@
+//! Named verifier policies.
+//! A policy is a list of requirements checked against the request, plus the
+//! guest export (circuit) that must serve it.  Loaded from a TOML file:
+//!
+//! ```toml
+//! [policies.us_retail]
+//! requirements = [
+//!     { type = "min_kyc_tier", min = 2 },
+//!     { type = "country_not_in", countries = ["IR", "KP", "CU"] },
+//!     { type = "not_sanctioned" },
+//! ]
+//!
+//! [circuits.check_kyc_full]
+//! commitment = "sha256"
+//! ```
+//!
+//! `check_kyc_full_keccak` computes the wallet's Keccak-256 commitment in the guest, from the raw
+//! address, so the proof binds the commitment to the wallet.  A policy selects it with
+//! `circuit = "check_kyc_full_keccak"`; it serves `evm_address` subjects only and always commits
+//! with Keccak-256.
+
+use anyhow::{anyhow, bail, Context, Result};
+use kyc_core::{public_inputs::Layout, HashScheme};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::Path};
+
+/// A guest export and the argument layout it expects.
+#[derive(Clone, Copy, Debug)]
+pub struct Circuit {
+    pub name:         &'static str,
+    /// Wallet digest limbs consumed (8 = full 256 bits, 5 = legacy 160 bits).
+    pub wallet_limbs: usize,
+    /// Whether 8 Travel Rule limbs follow the two flags.
+    pub travel_rule:  bool,
+    /// Whether 8 challenge-nonce limbs come last (see challenge.rs).
+    pub nonce:        bool,
+    /// Whether the 5 raw address words come first and the guest hashes them itself; they are
+    /// witness, not public inputs.
+    pub keccak_in_guest: bool,
+}
+
+impl Circuit {
+    /// Public-input layout for [`kyc_core::public_inputs::decode`].
+    pub fn layout(&self) -> Layout {
+        Layout { commitment_limbs: self.wallet_limbs, travel_rule: self.travel_rule, nonce: self.nonce }
+    }
+
+    /// The guest arguments a verifier sees: `args` without the leading witness words.
+    pub fn public_inputs(&self, args: &[String]) -> Vec<String> {
+        args[if self.keccak_in_guest { 5 } else { 0 }..].to_vec()
+    }
+
+    /// The export this one extends with a nonce, or itself; issuer trust and commitment settings
+    /// are configured under this name.
+    pub fn base(&self) -> &'static str {
+        self.name.strip_suffix("_nonce").unwrap_or(self.name)
+    }
+
+    /// The export taking the same inputs plus a challenge nonce, if the guest has one.
+    pub fn with_nonce(&self) -> Option<&'static Circuit> {
+        CIRCUITS.iter().find(|c| {
+            c.wallet_limbs == self.wallet_limbs && c.travel_rule == self.travel_rule
+                && c.keccak_in_guest == self.keccak_in_guest && c.nonce
+        })
+    }
+}
+
+/// Guest exports a policy may select.
+pub const CIRCUITS: &[Circuit] = &[
+    Circuit { name: "check_kyc_full",                   wallet_limbs: 8, travel_rule: false, nonce: false, keccak_in_guest: false },
+    Circuit { name: "check_kyc_full_travel_rule",       wallet_limbs: 8, travel_rule: true,  nonce: false, keccak_in_guest: false },
+    Circuit { name: "check_kyc",                        wallet_limbs: 5, travel_rule: false, nonce: false, keccak_in_guest: false },
+    Circuit { name: "check_kyc_travel_rule",            wallet_limbs: 5, travel_rule: true,  nonce: false, keccak_in_guest: false },
+    Circuit { name: "check_kyc_full_nonce",             wallet_limbs: 8, travel_rule: false, nonce: true,  keccak_in_guest: false },
+    Circuit { name: "check_kyc_full_travel_rule_nonce", wallet_limbs: 8, travel_rule: true,  nonce: true,  keccak_in_guest: false },
+    Circuit { name: "check_kyc_full_keccak",            wallet_limbs: 8, travel_rule: false, nonce: false, keccak_in_guest: true  },
+];
+
+/// Look up a guest export by name.
+pub fn circuit(name: &str) -> Option<&'static Circuit> {
+    CIRCUITS.iter().find(|c| c.name == name)
+}
+
+/// Circuit used when no policy names one.
+pub fn default_circuit(legacy_limbs: bool, travel_rule: bool) -> &'static Circuit {
+    let wallet_limbs = if legacy_limbs { 5 } else { 8 };
+    CIRCUITS
+        .iter()
+        .find(|c| c.wallet_limbs == wallet_limbs && c.travel_rule == travel_rule && !c.nonce && !c.keccak_in_guest)
+        .expect("every layout has a circuit")
+}
+
+/// Subject attributes a policy can reason about (all optional on the wire).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SubjectAttributes {
+    pub kyc_tier:   Option<u8>,
+    pub country:    Option<String>,
+    pub sanctioned: Option<bool>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Requirement {
+    MinKycTier   { min: u8 },
+    CountryNotIn { countries: Vec<String> },
+    NotSanctioned,
+    TravelRule,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Policy {
+    /// Guest export to invoke; derived from the request when absent.
+    #[serde(default)]
+    pub circuit:      Option<String>,
+    #[serde(default)]
+    pub requirements: Vec<Requirement>,
+}
+
+/// Per-circuit settings.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct CircuitConfig {
+    /// Hash used for the wallet commitment fed to this circuit.
+    #[serde(default)]
+    pub commitment: HashScheme,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct PolicySet {
+    #[serde(default)]
+    pub policies: HashMap<String, Policy>,
+    #[serde(default)]
+    pub circuits: HashMap<String, CircuitConfig>,
+}
+
+impl PolicySet {
+    /// Load from `path`; a missing file yields an empty set.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        let set: PolicySet = toml::from_str(&raw)
+            .with_context(|| format!("parsing {}", path.display()))?;
+        for (name, p) in &set.policies {
+            if let Some(c) = &p.circuit {
+                if circuit(c).is_none() {
+                    bail!("policy {name}: unknown circuit {c}");
+                }
+            }
+        }
+        for (c, cfg) in &set.circuits {
+            match circuit(c) {
+                None => bail!("unknown circuit {c}"),
+                Some(k) if k.keccak_in_guest && cfg.commitment != HashScheme::Keccak256 => {
+                    bail!("circuit {c} hashes in the guest and always commits with keccak256")
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(set)
+    }
+
+    /// Commitment hash for `circuit`: configured under its base name (Keccak-256 by default), and
+    /// always Keccak-256 when the guest hashes the wallet itself.
+    pub fn commitment_for(&self, circuit: &Circuit) -> HashScheme {
+        if circuit.keccak_in_guest {
+            return HashScheme::Keccak256;
+        }
+        self.circuits.get(circuit.base()).map(|c| c.commitment).unwrap_or_default()
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Policy> {
+        self.policies.get(name).ok_or_else(|| anyhow!("unknown policy: {name}"))
+    }
+}
+
+impl Policy {
+    /// Check every requirement; the first failure is returned as the error.
+    pub fn evaluate(&self, attrs: &SubjectAttributes, has_travel_rule: bool) -> Result<()> {
+        for r in &self.requirements {
+            match r {
+                Requirement::MinKycTier { min } => match attrs.kyc_tier {
+                    Some(t) if t >= *min => {}
+                    Some(t) => bail!("kyc tier {t} below required {min}"),
+                    None    => bail!("policy requires kyc_tier"),
+                },
+                Requirement::CountryNotIn { countries } => match &attrs.country {
+                    Some(c) if countries.iter().any(|b| b.eq_ignore_ascii_case(c)) => {
+                        bail!("country {c} is blocked")
+                    }
+                    Some(_) => {}
+                    None    => bail!("policy requires country"),
+                },
+                Requirement::NotSanctioned => match attrs.sanctioned {
+                    Some(false) => {}
+                    Some(true)  => bail!("subject is sanctioned"),
+                    None        => bail!("policy requires sanctioned flag"),
+                },
+                Requirement::TravelRule => {
+                    if !has_travel_rule {
+                        bail!("policy requires a travel_rule payload");
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Guest export serving this policy.
+    pub fn circuit(&self, default: &'static Circuit) -> &'static Circuit {
+        self.circuit.as_deref().and_then(circuit).unwrap_or(default)
+    }
+}
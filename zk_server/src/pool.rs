@@ -0,0 +1,105 @@
+//! Proving off the async runtime, with backpressure.
+//! A proof is minutes of CPU-bound work; run inline in a handler it would stall a tokio worker
+//! and with it `/status`, verification and admin traffic.  Jobs therefore run on the blocking
+//! thread pool, at most `capacity` at a time (`ZK_PROVE_CONCURRENCY`, default one per core).
+//! Up to `ZK_PROVE_QUEUE` further jobs (default 64) wait for a slot; past that, requests fail
+//! fast with [`Overloaded`] (HTTP 503 + `Retry-After`) instead of piling up.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
+use tokio::sync::Semaphore;
+
+/// Weight of the newest job duration in the moving average.
+const ALPHA: f64 = 0.2;
+
+pub struct ProvePool {
+    capacity:   usize,
+    max_queued: usize,
+    slots:      Arc<Semaphore>,
+    queued:     AtomicUsize,
+    avg_job:    Arc<Mutex<Option<f64>>>,
+}
+
+/// Returned (inside `anyhow::Error`) when every slot is busy and the queue is full.
+#[derive(Debug, Serialize)]
+pub struct Overloaded {
+    pub capacity:    usize,
+    pub queued:      usize,
+    /// Seconds until a slot is likely to free up.
+    pub retry_after: u64,
+}
+
+impl std::fmt::Display for Overloaded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "prover busy: {} proofs running and {} queued; retry in {}s",
+               self.capacity, self.queued, self.retry_after)
+    }
+}
+
+impl std::error::Error for Overloaded {}
+
+/// Leaves the queue when dropped, also when the waiting request is cancelled.
+struct Queued<'a>(&'a AtomicUsize);
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl ProvePool {
+    pub fn new(capacity: usize, max_queued: usize) -> Self {
+        ProvePool {
+            capacity,
+            max_queued,
+            slots:   Arc::new(Semaphore::new(capacity)),
+            queued:  AtomicUsize::new(0),
+            avg_job: Arc::default(),
+        }
+    }
+
+    /// Run `job` on the blocking pool once a slot is free, or fail with [`Overloaded`].
+    /// A started job keeps its slot until it finishes, even if the caller goes away.
+    pub async fn run<T: Send + 'static>(&self, job: impl FnOnce() -> T + Send + 'static) -> Result<T> {
+        let slot = match self.slots.clone().try_acquire_owned() {
+            Ok(slot) => slot,
+            Err(_) => {
+                let queued = self.queued.fetch_add(1, Ordering::Relaxed);
+                let _queued = Queued(&self.queued);
+                if queued >= self.max_queued {
+                    return Err(Overloaded { capacity: self.capacity, queued, retry_after: self.retry_after(queued) }.into());
+                }
+                self.slots.clone().acquire_owned().await?
+            }
+        };
+        let avg = self.avg_job.clone();
+        let out = tokio::task::spawn_blocking(move || {
+            let t0 = Instant::now();
+            let out = job();
+            let secs = t0.elapsed().as_secs_f64();
+            let mut a = avg.lock().unwrap();
+            *a = Some(a.map_or(secs, |a| a + ALPHA * (secs - a)));
+            drop(slot);
+            out
+        }).await?;
+        Ok(out)
+    }
+
+    /// Whether every slot is taken, i.e. a new job would have to queue.
+    pub fn busy(&self) -> bool {
+        self.slots.available_permits() == 0
+    }
+
+    /// Time for the jobs running and `queued` ahead to drain, from the average job length.
+    fn retry_after(&self, queued: usize) -> u64 {
+        let avg = self.avg_job.lock().unwrap().unwrap_or(1.0);
+        (avg * (queued / self.capacity + 1) as f64).ceil().max(1.0) as u64
+    }
+}
@@ -0,0 +1,302 @@
+//! Pre-issuance for subjects expected to transact soon.
+//! `PUT /admin/preissue` uploads a list of `{ tenant?, policy?, request }` entries, `request`
+//! being the `/prove` body the subject is expected to send.  During the off-peak hours
+//! `ZK_PREISSUE_HOURS` (UTC, `start-end`, default `1-5`), and only while no other proof is
+//! running, the SNARK for each entry is proven in the background and kept in memory for
+//! `ZK_PREISSUE_TTL_SECS` (default two days).  When the subject's `/prove` then arrives with the
+//! same guest arguments, step and guest build, the precomputed run is used instead of proving.
+//! Nothing else is skipped: attestation, consent, policy, quota, usage and storage all happen on
+//! that request, which gets its own proof id and `"preissued": true`.  Each run is used once.
+//! Lists are capped at `ZK_PREISSUE_MAX` entries (default 1000) and lost on restart.
+//!
+//! Challenge-bound proofs can't be pre-issued off-peak, since the nonce is one of their inputs.
+//! Instead, a relying party that knows the wallet asks for `GET /challenge?wallet=…[&policy=…]`,
+//! and the proof for that wallet and nonce is started speculatively right away, so it is likely
+//! done by the time the holder's `/prove` arrives with the challenge.  At most
+//! `ZK_SPECULATIVE_MAX` (default 0: off) such proofs run at once and further challenges are not
+//! speculated on; the result lives until the challenge expires and is used the same way.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{policy, prover::ProofRun, request, store, usage::{Cost, Meter}, worker::Priority, AppState};
+
+/// How often the background task looks for work.
+const TICK: Duration = Duration::from_secs(60);
+
+/// Identifies one proving run: guest build, export, step and guest arguments.
+pub type Key = [u8; 32];
+
+pub fn key(circuit_version: &str, circuit: &str, step: usize, args: &[String]) -> Key {
+    let step = step.to_string();
+    let mut h = Sha256::new();
+    for part in [circuit_version, circuit, &step].into_iter().chain(args.iter().map(String::as_str)) {
+        h.update((part.len() as u64).to_be_bytes());
+        h.update(part.as_bytes());
+    }
+    h.finalize().into()
+}
+
+fn default_tenant() -> String { "default".into() }
+
+/// One subject to pre-issue for.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Subject {
+    #[serde(default = "default_tenant")]
+    pub tenant:  String,
+    #[serde(default)]
+    pub policy:  Option<String>,
+    pub request: serde_json::Value,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "state")]
+pub enum EntryState {
+    Queued,
+    Ready { at: u64 },
+    Used,
+    Expired,
+    Failed { error: String },
+}
+
+struct Entry {
+    subject: Subject,
+    state:   EntryState,
+    key:     Option<Key>,
+    run:     Option<(ProofRun, Cost)>,
+}
+
+#[derive(Serialize)]
+pub struct EntryView {
+    pub tenant: String,
+    #[serde(flatten)]
+    pub state:  EntryState,
+}
+
+#[derive(Serialize)]
+pub struct PreissueReport {
+    pub hours:    String,
+    pub off_peak: bool,
+    pub hits:     u64,
+    pub entries:  Vec<EntryView>,
+}
+
+pub struct Preissue {
+    /// Off-peak window in UTC hours, `[start, end)`; wraps past midnight when `start > end`.
+    hours:   (u64, u64),
+    ttl:     u64,
+    max:     usize,
+    entries: Mutex<Vec<Entry>>,
+    hits:    AtomicU64,
+    /// `ZK_SPECULATIVE_MAX`: speculative proofs running at once.
+    speculative_max: usize,
+    speculating:     AtomicUsize,
+    /// Speculative runs by key, with the expiry of their challenge.
+    speculated:      Mutex<HashMap<Key, (ProofRun, Cost, u64)>>,
+}
+
+impl Preissue {
+    pub fn from_env() -> Result<Self> {
+        let num = |k: &str, default: u64| -> Result<u64> {
+            match std::env::var(k) {
+                Ok(v) if !v.is_empty() => v.parse().with_context(|| format!("{k} must be a number")),
+                _ => Ok(default),
+            }
+        };
+        let raw = std::env::var("ZK_PREISSUE_HOURS").unwrap_or_else(|_| "1-5".into());
+        let hours = raw.split_once('-')
+            .and_then(|(a, b)| Some((a.trim().parse().ok()?, b.trim().parse().ok()?)))
+            .filter(|&(a, b): &(u64, u64)| a < 24 && b <= 24 && a != b)
+            .with_context(|| format!("ZK_PREISSUE_HOURS must be start-end in UTC hours, got {raw:?}"))?;
+        Ok(Preissue {
+            hours,
+            ttl:     num("ZK_PREISSUE_TTL_SECS", 2 * 86_400)?,
+            max:     num("ZK_PREISSUE_MAX", 1000)? as usize,
+            entries: Mutex::default(),
+            hits:    AtomicU64::new(0),
+            speculative_max: num("ZK_SPECULATIVE_MAX", 0)? as usize,
+            speculating:     AtomicUsize::new(0),
+            speculated:      Mutex::default(),
+        })
+    }
+
+    fn off_peak(&self, now: u64) -> bool {
+        let hour = now / 3600 % 24;
+        let (start, end) = self.hours;
+        if start < end { (start..end).contains(&hour) } else { hour >= start || hour < end }
+    }
+
+    /// Replace the list; runs already precomputed for the old list are dropped.
+    pub fn set(&self, subjects: Vec<Subject>) -> Result<()> {
+        if subjects.len() > self.max {
+            bail!("at most {} subjects (ZK_PREISSUE_MAX)", self.max);
+        }
+        *self.entries.lock().unwrap() = subjects.into_iter()
+            .map(|subject| Entry { subject, state: EntryState::Queued, key: None, run: None })
+            .collect();
+        Ok(())
+    }
+
+    /// The precomputed run for `key`, if one is ready; it is not handed out again.
+    pub fn take(&self, key: &Key) -> Option<(ProofRun, Cost)> {
+        let now = store::now();
+        if let Some((run, cost, expires_at)) = self.speculated.lock().unwrap().remove(key) {
+            if expires_at > now {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Some((run, cost));
+            }
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let e = entries.iter_mut().find(|e| e.key.as_ref() == Some(key) && e.run.is_some())?;
+        if matches!(e.state, EntryState::Ready { at } if at + self.ttl <= now) {
+            return None;
+        }
+        e.state = EntryState::Used;
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        e.run.take()
+    }
+
+    pub fn report(&self) -> PreissueReport {
+        PreissueReport {
+            hours:    format!("{}-{}", self.hours.0, self.hours.1),
+            off_peak: self.off_peak(store::now()),
+            hits:     self.hits.load(Ordering::Relaxed),
+            entries:  self.entries.lock().unwrap().iter()
+                .map(|e| EntryView { tenant: e.subject.tenant.clone(), state: e.state.clone() })
+                .collect(),
+        }
+    }
+
+    /// Drop runs past their TTL, then return the next queued entry and its index.
+    fn next(&self, now: u64) -> Option<(usize, Subject)> {
+        let mut entries = self.entries.lock().unwrap();
+        for e in entries.iter_mut() {
+            if matches!(e.state, EntryState::Ready { at } if at + self.ttl <= now) {
+                e.state = EntryState::Expired;
+                e.run = None;
+            }
+        }
+        entries.iter().position(|e| e.state == EntryState::Queued).map(|i| (i, entries[i].subject.clone()))
+    }
+
+    fn finish(&self, i: usize, subject: &Subject, outcome: Result<(Key, ProofRun, Cost)>) {
+        let mut entries = self.entries.lock().unwrap();
+        // The list may have been replaced while proving.
+        let Some(e) = entries.get_mut(i).filter(|e| e.subject.request == subject.request && e.state == EntryState::Queued) else {
+            return;
+        };
+        match outcome {
+            Ok((key, run, cost)) => {
+                e.state = EntryState::Ready { at: store::now() };
+                e.key = Some(key);
+                e.run = Some((run, cost));
+            }
+            Err(err) => e.state = EntryState::Failed { error: format!("{err:#}") },
+        }
+    }
+}
+
+/// Start proving `wallet`'s request for challenge `nonce` (`0x`-hex, live until `expires_at`) in
+/// the background, unless the speculative budget is used up.
+pub fn speculate(state: Arc<AppState>, wallet: String, policy: Option<String>, nonce: String, expires_at: u64) {
+    let p = &state.preissue;
+    {
+        let mut ready = p.speculated.lock().unwrap();
+        let now = store::now();
+        ready.retain(|_, (_, _, exp)| *exp > now);
+        if ready.len() >= p.max {
+            return;
+        }
+    }
+    let claimed = p.speculating.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < p.speculative_max).then_some(n + 1));
+    if claimed.is_err() {
+        return;
+    }
+    tokio::spawn(async move {
+        let subject = Subject {
+            tenant:  default_tenant(),
+            policy,
+            request: serde_json::json!({ "wallet": wallet, "kyc": 1, "sig_valid": 1, "challenge": nonce }),
+        };
+        match precompute(&state, &subject).await {
+            Ok((key, run, cost)) if expires_at > store::now() => {
+                state.preissue.speculated.lock().unwrap().insert(key, (run, cost, expires_at));
+            }
+            Ok(_)  => {}
+            Err(e) => tracing::debug!("speculative proof skipped: {e:#}"),
+        }
+        state.preissue.speculating.fetch_sub(1, Ordering::SeqCst);
+    });
+}
+
+pub fn spawn(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(TICK);
+        loop {
+            tick.tick().await;
+            // Interactive proving always comes first; at most one pre-issue proof runs at a time.
+            while state.preissue.off_peak(store::now())
+                && state.load.hints().in_flight == 0
+                && state.maintenance.check().is_ok()
+                && state.kill_switch.check().is_ok()
+            {
+                let Some((i, subject)) = state.preissue.next(store::now()) else { break };
+                let outcome = precompute(&state, &subject).await;
+                if let Err(e) = &outcome {
+                    tracing::warn!(tenant = %subject.tenant, "pre-issuance failed: {e:#}");
+                }
+                state.preissue.finish(i, &subject, outcome);
+            }
+        }
+    });
+}
+
+/// Prove `subject`'s expected request the way `/prove` would, without issuing anything.  Only
+/// [`speculate`] sends a challenge.
+async fn precompute(state: &AppState, subject: &Subject) -> Result<(Key, ProofRun, Cost)> {
+    let mut req = request::parse_prove(subject.request.clone())?;
+    state.limits.check_step(&subject.tenant, req.step)?;
+    req.identifier_type.check_checksum(&req.wallet)?;
+    req.wallet = req.identifier_type.canonicalize(&req.wallet);
+    req.identifier_type.validate(&req.wallet)?;
+    if req.kyc != 1 || req.sig_valid != 1 {
+        bail!("only approved subjects (kyc = sig_valid = 1) can be pre-issued");
+    }
+    let default_circuit = policy::default_circuit(req.legacy_limbs, req.travel_rule.is_some());
+    let circuit = match &subject.policy {
+        Some(name) => state.policies.get(name)?.circuit(default_circuit),
+        None       => default_circuit,
+    };
+    let circuit = match &req.challenge {
+        Some(_) if !circuit.nonce => circuit.with_nonce().with_context(|| format!("circuit {} takes no challenge", circuit.name))?,
+        _ => circuit,
+    };
+    let (_, args, _) = crate::guest_inputs(state, circuit, &req)?;
+    let guest = state.rollout.select(&req.wallet);
+    let key = key(&guest.version, circuit.name, req.step, &args);
+
+    let (isolation, params) = (state.prover.clone(), state.params.clone());
+    let (wasm, invoke, step) = (guest.wasm.clone(), circuit.name, req.step);
+    let (run, mut cost) = state.pool.run(move || {
+        let meter = Meter::start();
+        let run = isolation.run(&params, &wasm, invoke, args, step, Priority::Background);
+        (run, meter.finish())
+    }).await?;
+    let run = run?;
+    if let Some((cpu_sec, peak_rss_mb)) = run.worker {
+        cost.cpu_sec     = cpu_sec;
+        cost.peak_rss_mb = peak_rss_mb;
+    }
+    state.load.record_prove(run.prove_sec);
+    state.metrics.proved(run.prove_sec, run.proof.len());
+    Ok((key, run, cost))
+}
@@ -0,0 +1,213 @@
+//! Wallet presentation flow over `zkkyc://present` deep links (`kyc_core::deeplink`).
+//!
+//! 1. The verifier calls `POST /presentations` with `{ scope, ttl_secs? }` and shows the returned
+//!    `deep_link` (QR code or same-device redirect).
+//! 2. The wallet POSTs `{ proof_id, nonce }` to the link's callback, `/presentations/:id`.  The
+//!    proof is accepted if it is stored here, verifies, is not revoked, runs on an accepted
+//!    circuit version, and its nullifier for `scope` was unspent — it is spent on acceptance, so
+//!    one proof is presented to one relying party once.
+//! 3. The verifier polls `GET /presentations/:id` until `status` leaves `pending`.
+//!
+//! Requests live in memory for `ZK_PRESENTATION_TTL_SECS` (default 300) and need the proof store
+//! and `ZK_PUBLIC_URL` (for the callback).  At most `MAX_OPEN` are kept at once.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use kyc_core::{deeplink::PresentationRequest, public_inputs::{self, PublicInputs}};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::{Arc, Mutex}};
+
+use crate::{policy, store, verify_cached, AppState};
+
+const MAX_OPEN: usize = 10_000;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Outcome {
+    Pending,
+    Accepted {
+        proof_id:      String,
+        circuit:       String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        public_inputs: Option<PublicInputs>,
+        presented_at:  u64,
+    },
+    Rejected {
+        proof_id: String,
+        reason:   String,
+    },
+}
+
+struct Open {
+    request: PresentationRequest,
+    outcome: Outcome,
+    /// A presentation is being checked; others are turned away so only one proof is spent.
+    busy:    bool,
+}
+
+pub struct Presentations {
+    ttl:  u64,
+    open: Mutex<HashMap<String, Open>>,
+}
+
+impl Presentations {
+    pub fn from_env() -> Self {
+        let ttl = std::env::var("ZK_PRESENTATION_TTL_SECS").ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300);
+        Presentations { ttl, open: Mutex::new(HashMap::new()) }
+    }
+
+    /// Drop requests past their expiry (answered ones are kept until then for polling).
+    fn prune(open: &mut HashMap<String, Open>, now: u64) {
+        open.retain(|_, o| o.request.expires_at > now);
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateBody {
+    scope:    String,
+    ttl_secs: Option<u64>,
+}
+
+/// `POST /presentations`: open a request and return its deep link.
+pub async fn handle_create(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<CreateBody>,
+) -> Response {
+    let (Some(_), Some(base)) = (&state.store, &state.public_url) else {
+        return (StatusCode::NOT_FOUND, Json("presentations need ZK_STORE_DIR and ZK_PUBLIC_URL")).into_response();
+    };
+    if body.scope.is_empty() {
+        return (StatusCode::BAD_REQUEST, Json("scope is required")).into_response();
+    }
+    let p = &state.presentations;
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = store::now();
+    let request = PresentationRequest {
+        request_id: id.clone(),
+        scope:      body.scope,
+        nonce:      hex::encode(rand::random::<[u8; 16]>()),
+        expires_at: now + body.ttl_secs.unwrap_or(p.ttl).min(p.ttl),
+        callback:   format!("{}/presentations/{id}", base.trim_end_matches('/')),
+    };
+    let mut open = p.open.lock().unwrap();
+    Presentations::prune(&mut open, now);
+    if open.len() >= MAX_OPEN {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json("too many open presentation requests")).into_response();
+    }
+    let resp = serde_json::json!({
+        "request_id": id,
+        "deep_link":  request.to_uri(),
+        "scope":      request.scope,
+        "expires_at": request.expires_at,
+    });
+    open.insert(id, Open { request, outcome: Outcome::Pending, busy: false });
+    (StatusCode::CREATED, Json(resp)).into_response()
+}
+
+/// `GET /presentations/:id`: the request's outcome so far.
+pub async fn handle_status(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response {
+    let mut open = state.presentations.open.lock().unwrap();
+    Presentations::prune(&mut open, store::now());
+    match open.get(&id) {
+        Some(o) => Json(serde_json::json!({
+            "request_id": id,
+            "scope":      o.request.scope,
+            "expires_at": o.request.expires_at,
+            "outcome":    o.outcome,
+        })).into_response(),
+        None => (StatusCode::NOT_FOUND, Json("unknown or expired presentation request")).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PresentBody {
+    proof_id: String,
+    nonce:    String,
+}
+
+/// `POST /presentations/:id`: a wallet presents a proof.
+pub async fn handle_present(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(body): Json<PresentBody>,
+) -> Response {
+    let Some(store) = &state.store else {
+        return (StatusCode::NOT_FOUND, Json("proof store disabled")).into_response();
+    };
+    let scope = {
+        let mut open = state.presentations.open.lock().unwrap();
+        Presentations::prune(&mut open, store::now());
+        let Some(o) = open.get_mut(&id) else {
+            return (StatusCode::NOT_FOUND, Json("unknown or expired presentation request")).into_response();
+        };
+        if !matches!(o.outcome, Outcome::Pending) || o.busy {
+            return (StatusCode::CONFLICT, Json("presentation request already answered")).into_response();
+        }
+        if o.request.nonce != body.nonce {
+            return (StatusCode::BAD_REQUEST, Json("nonce does not match the request")).into_response();
+        }
+        o.busy = true;
+        o.request.scope.clone()
+    };
+
+    let outcome = evaluate(&state, store, &body.proof_id, &scope).await;
+    let mut open = state.presentations.open.lock().unwrap();
+    let Some(o) = open.get_mut(&id) else {
+        return (StatusCode::GONE, Json("presentation request expired")).into_response();
+    };
+    o.busy = false;
+    o.outcome = outcome.clone();
+    let code = match outcome {
+        Outcome::Accepted { .. } => StatusCode::OK,
+        _                        => StatusCode::UNPROCESSABLE_ENTITY,
+    };
+    (code, Json(outcome)).into_response()
+}
+
+async fn evaluate(state: &AppState, store: &store::ProofStore, proof_id: &str, scope: &str) -> Outcome {
+    let reject = |reason: String| Outcome::Rejected { proof_id: proof_id.to_string(), reason };
+    let proof = match store.get_proof(proof_id) {
+        Ok(Some(p)) => p,
+        Ok(None)    => return reject("unknown proof".into()),
+        Err(e)      => return reject(e.to_string()),
+    };
+    match store.revocation(proof_id) {
+        Ok(None)    => {}
+        Ok(Some(r)) => return reject(format!("revoked: {}", r.reason)),
+        Err(e)      => return reject(e.to_string()),
+    }
+    if proof.expired(store::now()) {
+        return reject("proof has expired".into());
+    }
+    if let Some(i) = proof.issuer.as_deref().filter(|i| !state.issuers.trusts(i, &proof.circuit, proof.created_at)) {
+        return reject(format!("issuer {i} is no longer trusted"));
+    }
+    if !state.rollout.accepts(&proof.circuit_version) {
+        return reject(format!("circuit version {} is no longer accepted", proof.circuit_version));
+    }
+    if let (Err(e), _) = verify_cached(state, &proof).await {
+        return reject(format!("proof does not verify: {e}"));
+    }
+    match store.spend(proof_id, scope) {
+        Ok((_, true))  => {}
+        Ok((_, false)) => return reject("already presented to this scope".into()),
+        Err(e)         => return reject(e.to_string()),
+    }
+    Outcome::Accepted {
+        proof_id:      proof.id,
+        public_inputs: policy::circuit(&proof.circuit)
+            .and_then(|c| public_inputs::decode(c.layout(), &proof.public_inputs).ok())
+            .map(|p| PublicInputs { expires_at: proof.expires_at, ..p }),
+        circuit:       proof.circuit,
+        presented_at:  store::now(),
+    }
+}
@@ -0,0 +1,345 @@
+//! Static API keys for the prove routes (`/prove`, `/legacy/prove`, `/jobs/:id`) and the
+//! tenant-scoped proof routes (`/proofs…`, `POST /presentations`; see [`require_tenant`]).
+//! Enabled by `ZK_PROVE_KEYS_FILE`; without it the prove routes stay open as before, and the proof
+//! routes act for the `default` tenant only.  The file lists
+//! each key by the hex SHA-256 of its secret, never the secret itself, and binds it to a tenant:
+//!
+//! ```toml
+//! [keys.acme-backend]
+//! sha256   = "…64 hex chars…"   # printf %s "$KEY" | sha256sum
+//! tenant   = "acme"
+//! disabled = false               # optional
+//! rate_per_min = 30              # optional, proofs started per minute
+//! burst        = 5               # optional, default rate_per_min
+//! ```
+//!
+//! Callers send `Authorization: Bearer <key>`.  The key's tenant replaces `x-tenant-id` (a
+//! conflicting header is refused), and every log line of the request carries the key id.  The
+//! file is re-read when it changes, so keys are added, disabled or removed without a restart; a
+//! file that no longer parses is logged and the previous keys stay in force.
+//!
+//! Keys without their own `rate_per_min` / `burst` take `ZK_PROVE_KEY_RATE_PER_MIN` /
+//! `ZK_PROVE_KEY_BURST`; with neither a key is unlimited.  A key over its rate gets `429` with
+//! `Retry-After` on `POST` routes; polling `/jobs/:id` is never limited.  `/prove/batch` takes a
+//! token per item, all or none ([`ProveKeys::take_batch`]), and a batch larger than the key's
+//! burst is refused outright.  Buckets are kept by key id, so reloading the file doesn't refill
+//! them.
+
+use anyhow::{bail, Context, Result};
+use axum::{
+    extract::State,
+    http::{header, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant, SystemTime},
+};
+use tracing::Instrument;
+
+use crate::{errors::Refused, ratelimit::Bucket, AppState};
+
+/// How often the file's modification time is checked.
+const RELOAD_EVERY: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ProveKey {
+    #[serde(skip_deserializing)]
+    pub id:       String,
+    #[serde(skip_serializing)]
+    sha256:       String,
+    pub tenant:   String,
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_per_min: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst:        Option<u32>,
+}
+
+#[derive(Deserialize, Default)]
+struct ProveKeysFile {
+    #[serde(default)]
+    keys: HashMap<String, ProveKey>,
+}
+
+struct Loaded {
+    modified: Option<SystemTime>,
+    /// Keys by their hash.
+    by_hash:  HashMap<String, ProveKey>,
+}
+
+pub struct ProveKeys {
+    path:          PathBuf,
+    loaded:        RwLock<Loaded>,
+    checked:       Mutex<Instant>,
+    default_rate:  Option<u32>,
+    default_burst: Option<u32>,
+    buckets:       Mutex<HashMap<String, Bucket>>,
+}
+
+impl ProveKeys {
+    /// Build from `ZK_PROVE_KEYS_FILE`; `None` leaves the prove routes open.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(path) = std::env::var("ZK_PROVE_KEYS_FILE") else { return Ok(None) };
+        let path = PathBuf::from(path);
+        let num = |k: &str| -> Result<Option<u32>> {
+            match std::env::var(k) {
+                Ok(v) if !v.is_empty() => Ok(Some(v.parse().with_context(|| format!("{k} must be a number"))?)),
+                _ => Ok(None),
+            }
+        };
+        Self::open(path, num("ZK_PROVE_KEY_RATE_PER_MIN")?, num("ZK_PROVE_KEY_BURST")?).map(Some)
+    }
+
+    fn open(path: PathBuf, default_rate: Option<u32>, default_burst: Option<u32>) -> Result<Self> {
+        let loaded = Self::read(&path)?;
+        tracing::info!("loaded {} prove keys from {}", loaded.by_hash.len(), path.display());
+        Ok(ProveKeys {
+            path,
+            loaded:        RwLock::new(loaded),
+            checked:       Mutex::new(Instant::now()),
+            default_rate,
+            default_burst,
+            buckets:       Mutex::default(),
+        })
+    }
+
+    fn read(path: &PathBuf) -> Result<Loaded> {
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let raw = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let file: ProveKeysFile = toml::from_str(&raw).with_context(|| format!("parsing {}", path.display()))?;
+        let mut by_hash = HashMap::new();
+        for (id, mut key) in file.keys {
+            key.sha256 = key.sha256.trim().to_ascii_lowercase();
+            if key.sha256.len() != 64 || hex::decode(&key.sha256).is_err() {
+                bail!("prove key {id}: sha256 must be 64 hex chars");
+            }
+            if key.tenant.is_empty() {
+                bail!("prove key {id}: tenant must not be empty");
+            }
+            if key.rate_per_min == Some(0) || key.burst == Some(0) {
+                bail!("prove key {id}: rate_per_min and burst must be positive");
+            }
+            key.id = id;
+            if let Some(dup) = by_hash.insert(key.sha256.clone(), key) {
+                bail!("prove key {} shares its secret with another key", dup.id);
+            }
+        }
+        Ok(Loaded { modified, by_hash })
+    }
+
+    /// Re-read the file if it changed since it was last loaded.
+    fn refresh(&self) {
+        {
+            let mut checked = self.checked.lock().unwrap();
+            if checked.elapsed() < RELOAD_EVERY {
+                return;
+            }
+            *checked = Instant::now();
+        }
+        let modified = std::fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if modified == self.loaded.read().unwrap().modified {
+            return;
+        }
+        match Self::read(&self.path) {
+            Ok(l) => {
+                tracing::info!("reloaded {} prove keys from {}", l.by_hash.len(), self.path.display());
+                *self.loaded.write().unwrap() = l;
+            }
+            Err(e) => tracing::error!("keeping previous prove keys: {e:#}"),
+        }
+    }
+
+    /// The enabled key whose secret is `secret`.
+    fn lookup(&self, secret: &str) -> Option<ProveKey> {
+        self.refresh();
+        let hash = hex::encode(Sha256::digest(secret.as_bytes()));
+        self.loaded.read().unwrap().by_hash.get(&hash).filter(|k| !k.disabled).cloned()
+    }
+
+    /// `(rate_per_min, burst)` of `key`; `None` if it is unlimited.
+    fn limits(&self, key: &ProveKey) -> Option<(f64, f64)> {
+        let rate = key.rate_per_min.or(self.default_rate).map(|r| r.max(1) as f64)?;
+        Some((rate, key.burst.or(self.default_burst).map_or(rate, |b| b.max(1) as f64)))
+    }
+
+    /// Take `n` rate tokens for `key` at once, or none and return the seconds until they are available.
+    fn take(&self, key: &ProveKey, n: usize) -> Result<(), u64> {
+        let Some((rate, burst)) = self.limits(key) else { return Ok(()) };
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets.entry(key.id.clone()).or_insert_with(|| Bucket::full(burst, now)).take_n(n as f64, rate, burst, now)
+    }
+
+    /// Take rate tokens for a batch of `items` proofs, beyond the one [`require`] took for the
+    /// request.  A batch larger than the key's burst could never be admitted and is refused
+    /// without `Retry-After`.
+    pub fn take_batch(&self, key: &ProveKey, items: usize) -> Result<(), Refused> {
+        if self.limits(key).map_or(false, |(_, burst)| items as f64 > burst) {
+            return Err(Refused::new(StatusCode::TOO_MANY_REQUESTS, "batch is larger than the prove key's burst; split it up"));
+        }
+        self.take(key, items.saturating_sub(1)).map_err(|retry| Refused {
+            retry_after: Some(retry),
+            ..Refused::new(StatusCode::TOO_MANY_REQUESTS, "prove key rate limit exceeded")
+        })
+    }
+
+    /// The key for bearer `secret`, if it may act for the `claimed` tenant; `starts_proof` takes
+    /// a rate token.
+    pub fn authorize(&self, secret: Option<&str>, claimed: Option<&str>, starts_proof: bool) -> Result<ProveKey, Refused> {
+        let Some(key) = secret.and_then(|s| self.lookup(s.trim())) else {
+            return Err(Refused::new(StatusCode::UNAUTHORIZED, "missing, unknown or disabled prove key"));
+        };
+        if claimed.filter(|t| !t.is_empty()).map_or(false, |t| t != key.tenant) {
+            return Err(Refused::new(StatusCode::FORBIDDEN, "x-tenant-id does not match the prove key's tenant"));
+        }
+        if starts_proof {
+            if let Err(retry) = self.take(&key, 1) {
+                return Err(Refused { retry_after: Some(retry), ..Refused::new(StatusCode::TOO_MANY_REQUESTS, "prove key rate limit exceeded") });
+            }
+        }
+        Ok(key)
+    }
+
+    /// Every key, sorted by id.
+    pub fn list(&self) -> Vec<ProveKey> {
+        self.refresh();
+        let mut keys: Vec<_> = self.loaded.read().unwrap().by_hash.values().cloned().collect();
+        keys.sort_by(|a, b| a.id.cmp(&b.id));
+        keys
+    }
+}
+
+/// Middleware: require a prove key and pin the request to its tenant.  The key is left in the
+/// request's extensions for handlers that start more than one proof.
+pub async fn require<B>(State(state): State<Arc<AppState>>, req: Request<B>, next: Next<B>) -> Response {
+    let Some(keys) = &state.prove_keys else { return next.run(req).await };
+    let starts_proof = req.method() == Method::POST;
+    pin(keys, req, next, starts_proof).await
+}
+
+/// Middleware for the proof-ACL routes: as [`require`], without taking a rate token.  Without
+/// prove keys no tenant is authenticated, so `x-tenant-id` is not trusted: a request naming a
+/// tenant other than `default` is refused, and the rest act for `default`.
+pub async fn require_tenant<B>(State(state): State<Arc<AppState>>, req: Request<B>, next: Next<B>) -> Response {
+    let Some(keys) = &state.prove_keys else {
+        let claimed = req.headers().get("x-tenant-id").map(|v| v.to_str().unwrap_or("?"));
+        if claimed.map_or(false, |t| !t.is_empty() && t != DEFAULT_TENANT) {
+            return Refused::new(StatusCode::UNAUTHORIZED, "x-tenant-id needs a prove key (ZK_PROVE_KEYS_FILE)").into_response();
+        }
+        return next.run(req).await;
+    };
+    pin(keys, req, next, false).await
+}
+
+/// Tenant of requests made without prove keys.
+pub const DEFAULT_TENANT: &str = "default";
+
+async fn pin<B>(keys: &ProveKeys, mut req: Request<B>, next: Next<B>, starts_proof: bool) -> Response {
+    let secret = req.headers().get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let claimed = req.headers().get("x-tenant-id").and_then(|v| v.to_str().ok());
+    let key = match keys.authorize(secret, claimed, starts_proof) {
+        Ok(key) => key,
+        Err(refused) => return refused.into_response(),
+    };
+    let Ok(tenant) = HeaderValue::from_str(&key.tenant) else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json("prove key tenant is not a valid header value")).into_response();
+    };
+    req.headers_mut().insert("x-tenant-id", tenant);
+    req.extensions_mut().insert(key.clone());
+    let span = tracing::info_span!("prove_key", key = %key.id, tenant = %key.tenant);
+    next.run(req).instrument(span).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, secret: &str, extra: &str) -> String {
+        format!(
+            "[keys.{id}]\nsha256 = \"{}\"\ntenant = \"acme\"\n{extra}\n",
+            hex::encode(Sha256::digest(secret.as_bytes())),
+        )
+    }
+
+    fn keys_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("prove-keys-{name}-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// Make the next lookup re-read the file, whatever the file system's mtime resolution.
+    fn recheck(keys: &ProveKeys) {
+        *keys.checked.lock().unwrap() = Instant::now() - RELOAD_EVERY;
+        keys.loaded.write().unwrap().modified = None;
+    }
+
+    #[test]
+    fn reloads_changed_file() {
+        let path = keys_file("reload", &entry("a", "alpha", ""));
+        let keys = ProveKeys::open(path.clone(), None, None).unwrap();
+        assert_eq!(keys.authorize(Some("alpha"), None, false).unwrap().id, "a");
+        assert!(keys.authorize(Some("beta"), None, false).is_err());
+
+        std::fs::write(&path, entry("a", "alpha", "disabled = true") + &entry("b", "beta", "")).unwrap();
+        recheck(&keys);
+        assert_eq!(keys.authorize(Some("alpha"), None, false).unwrap_err().status, StatusCode::UNAUTHORIZED);
+        assert_eq!(keys.authorize(Some("beta"), None, false).unwrap().id, "b");
+
+        // A file that no longer parses leaves the previous keys in force.
+        std::fs::write(&path, "[keys.b\n").unwrap();
+        recheck(&keys);
+        assert_eq!(keys.authorize(Some("beta"), None, false).unwrap().id, "b");
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn refuses_other_tenant() {
+        let path = keys_file("tenant", &entry("a", "alpha", ""));
+        let keys = ProveKeys::open(path.clone(), None, None).unwrap();
+        assert_eq!(keys.authorize(Some("alpha"), Some("acme"), false).unwrap().tenant, "acme");
+        assert_eq!(keys.authorize(Some("alpha"), Some(""), false).unwrap().tenant, "acme");
+        assert_eq!(keys.authorize(Some("alpha"), Some("other"), false).unwrap_err().status, StatusCode::FORBIDDEN);
+        assert_eq!(keys.authorize(None, Some("acme"), false).unwrap_err().status, StatusCode::UNAUTHORIZED);
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn refuses_over_rate() {
+        let path = keys_file("rate", &entry("a", "alpha", "rate_per_min = 1\nburst = 1") + &entry("b", "beta", ""));
+        let keys = ProveKeys::open(path.clone(), Some(60), Some(2)).unwrap();
+        keys.authorize(Some("alpha"), None, true).unwrap();
+        let refused = keys.authorize(Some("alpha"), None, true).unwrap_err();
+        assert_eq!(refused.status, StatusCode::TOO_MANY_REQUESTS);
+        assert!(refused.retry_after.map_or(false, |s| s >= 1));
+        // Polling takes no token, and other keys have their own bucket (the defaults here).
+        keys.authorize(Some("alpha"), None, false).unwrap();
+        keys.authorize(Some("beta"), None, true).unwrap();
+        keys.authorize(Some("beta"), None, true).unwrap();
+        assert!(keys.authorize(Some("beta"), None, true).is_err());
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn batch_takes_a_token_per_item() {
+        let path = keys_file("batch", &entry("a", "alpha", "rate_per_min = 1\nburst = 3"));
+        let keys = ProveKeys::open(path.clone(), None, None).unwrap();
+        let key = keys.authorize(Some("alpha"), None, true).unwrap();
+        // Larger than the burst: never admitted, so no Retry-After.
+        assert_eq!(keys.take_batch(&key, 4).unwrap_err().retry_after, None);
+        // Two tokens are left after the request's own: a batch of 3 fits, then one of 2 doesn't.
+        keys.take_batch(&key, 3).unwrap();
+        assert!(keys.take_batch(&key, 2).unwrap_err().retry_after.is_some());
+        assert!(keys.authorize(Some("alpha"), None, true).is_err());
+        std::fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,238 @@
+//! Nova setup → prove → verify for one guest invocation.
+//! Setup is a pure function of the step size and dominates latency, so its output is kept in a
+//! [`ParamCache`] (held in `AppState`) and built once per step size.  With `ZK_PARAMS_DIR` set the
+//! parameters are also written there, as `pp-<setup key>.bin`, and read back instead of re-running
+//! setup, by later processes and by worker subprocesses alike.  The key ([`engine::setup_key`])
+//! covers the Nova backend and the step size, so a file from another build is never picked up; a
+//! file that fails to decode is regenerated.  Loaded parameters are named by their
+//! [`engine::pp_digest`], the SHA-256 of their bincode, which proof envelopes and `/vks` carry.  `ZK_PARAMS_PRELOAD` (comma-separated step sizes, default the default
+//! step 8; empty for none) loads or builds those parameters at startup, and `/readyz` waits for them.
+//! Both entry points run behind [`isolate`]: a panic inside zk_engine becomes a [`ProverPanic`]
+//! error for that job instead of unwinding into (and poisoning) the server.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    panic::AssertUnwindSafe,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    time::Instant,
+};
+use kyc_core::{engine::{self, KycProof, Params}, envelope};
+
+/// Nova public parameters and their digest.
+struct Loaded {
+    pp:     Arc<Params>,
+    digest: [u8; 32],
+}
+
+/// Nova public parameters by step size, set up (or read from disk) on first use and shared by
+/// later jobs.
+#[derive(Default)]
+pub struct ParamCache {
+    by_step: Mutex<HashMap<usize, Arc<OnceLock<Loaded>>>>,
+    dir:     Option<PathBuf>,
+}
+
+impl ParamCache {
+    pub fn from_env() -> Self {
+        let dir = std::env::var("ZK_PARAMS_DIR").ok().filter(|d| !d.is_empty()).map(PathBuf::from);
+        ParamCache { by_step: Mutex::default(), dir }
+    }
+
+    /// Step sizes named by `ZK_PARAMS_PRELOAD` (default `8`).
+    pub fn preload_steps() -> Vec<usize> {
+        std::env::var("ZK_PARAMS_PRELOAD").unwrap_or_else(|_| "8".into())
+            .split(',')
+            .filter_map(|s| s.trim().parse().ok())
+            .collect()
+    }
+
+    /// Parameters for `step`.  Concurrent first requests for a step wait on a single setup; if
+    /// setup panics the slot stays empty and the next request retries.
+    pub fn get(&self, step: usize) -> Arc<Params> {
+        self.load(step).0
+    }
+
+    /// [`engine::pp_digest`] of the parameters for `step`, loading them like [`get`](Self::get).
+    pub fn digest(&self, step: usize) -> [u8; 32] {
+        self.load(step).1
+    }
+
+    /// [`digest`](Self::digest) when the parameters for `step` are loaded already; never blocks
+    /// on setup.
+    pub fn loaded_digest(&self, step: usize) -> Option<[u8; 32]> {
+        self.by_step.lock().unwrap().get(&step).and_then(|c| c.get().map(|l| l.digest))
+    }
+
+    fn load(&self, step: usize) -> (Arc<Params>, [u8; 32]) {
+        let cell = self.by_step.lock().unwrap().entry(step).or_default().clone();
+        let loaded = cell.get_or_init(|| {
+            if let Some(loaded) = self.read(step) {
+                return loaded;
+            }
+            tracing::info!(step, "running Nova setup");
+            let pp = engine::setup(step);
+            let raw = bincode::serialize(&pp).expect("public parameters serialize");
+            self.write(step, &raw);
+            Loaded { pp: Arc::new(pp), digest: envelope::digest(&raw) }
+        });
+        (loaded.pp.clone(), loaded.digest)
+    }
+
+    /// Whether parameters for `step` are built (or loaded) already.
+    pub fn is_ready(&self, step: usize) -> bool {
+        self.loaded_digest(step).is_some()
+    }
+
+    fn file(&self, step: usize) -> Option<PathBuf> {
+        self.dir.as_ref().map(|d| d.join(format!("pp-{}.bin", hex::encode(engine::setup_key(step)))))
+    }
+
+    /// Parameters saved by an earlier setup; `None` (logged) when missing or unreadable.
+    fn read(&self, step: usize) -> Option<Loaded> {
+        let path = self.file(step)?;
+        let raw = match std::fs::read(&path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                tracing::warn!(step, "reading {}: {e}", path.display());
+                return None;
+            }
+        };
+        // The key is repeated inside the file, so a renamed or truncated file is rejected too.
+        let (key, body) = raw.split_at(32.min(raw.len()));
+        if key != engine::setup_key(step) {
+            tracing::warn!(step, "{} is for other parameters; regenerating", path.display());
+            return None;
+        }
+        match bincode::deserialize(body) {
+            Ok(pp) => {
+                tracing::info!(step, "loaded Nova parameters from {}", path.display());
+                // The body is the parameters' bincode, so this is their `pp_digest`.
+                Some(Loaded { pp: Arc::new(pp), digest: envelope::digest(body) })
+            }
+            Err(e) => {
+                tracing::warn!(step, "{} is unreadable ({e}); regenerating", path.display());
+                None
+            }
+        }
+    }
+
+    /// Save the bincode `pp` for later processes; failures are logged and otherwise ignored.
+    fn write(&self, step: usize, pp: &[u8]) {
+        let Some(path) = self.file(step) else { return };
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        let res = (|| -> Result<()> {
+            if let Some(d) = path.parent() {
+                std::fs::create_dir_all(d)?;
+            }
+            std::fs::write(&tmp, [&engine::setup_key(step)[..], pp].concat())?;
+            std::fs::rename(&tmp, &path)?;
+            Ok(())
+        })();
+        match res {
+            Ok(())  => tracing::info!(step, "saved Nova parameters to {}", path.display()),
+            Err(e)  => {
+                let _ = std::fs::remove_file(&tmp);
+                tracing::warn!(step, "saving Nova parameters to {}: {e:#}", path.display());
+            }
+        }
+    }
+}
+
+/// Timings and serialized proof of one run.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ProofRun {
+    pub setup_sec:  f64,
+    pub prove_sec:  f64,
+    pub verify_sec: f64,
+    pub proof:      Vec<u8>,
+    /// bincode of the instance the proof verifies against.
+    pub instance:   Vec<u8>,
+    /// [`engine::pp_digest`] of the parameters it was proven under.
+    #[serde(default)]
+    pub pp_digest:  [u8; 32],
+    /// CPU seconds and peak RSS (MB) of the worker process, when proven in a subprocess.
+    #[serde(skip)]
+    pub worker:     Option<(f64, f64)>,
+}
+
+impl ProofRun {
+    /// First and last 16 bytes, hex.
+    pub fn preview(&self) -> String {
+        let p = &self.proof;
+        format!("{}…{}", hex::encode(&p[..16.min(p.len())]), hex::encode(&p[p.len().saturating_sub(16)..]))
+    }
+}
+
+/// Returned (inside `anyhow::Error`) when the prover panicked during a job.
+#[derive(Debug)]
+pub struct ProverPanic {
+    pub message: String,
+}
+
+impl std::fmt::Display for ProverPanic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "prover panicked: {}", self.message)
+    }
+}
+
+impl std::error::Error for ProverPanic {}
+
+/// Run `job`, turning a panic into [`ProverPanic`].  Nothing outlives a job but its result, so
+/// observing its state after an unwind is not a concern.
+pub fn isolate<T>(job: impl FnOnce() -> Result<T>) -> Result<T> {
+    std::panic::catch_unwind(AssertUnwindSafe(job)).unwrap_or_else(|payload| {
+        let message = payload.downcast_ref::<&str>().map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "non-string panic payload".into());
+        tracing::error!("prover panicked: {message}");
+        Err(ProverPanic { message }.into())
+    })
+}
+
+/// Prove `invoke(args)` of the guest at `wasm` and verify it in-process.
+/// `setup_sec` is only non-zero for the job that built the parameters.
+pub fn run(params: &ParamCache, wasm: &Path, invoke: &str, args: Vec<String>, step: usize) -> Result<ProofRun> {
+    isolate(|| run_unisolated(params, wasm, invoke, args, step))
+}
+
+fn run_unisolated(params: &ParamCache, wasm: &Path, invoke: &str, args: Vec<String>, step: usize) -> Result<ProofRun> {
+    let t0    = Instant::now();
+    let pp    = params.get(step);
+    let setup = t0.elapsed().as_secs_f64();
+
+    let t1    = Instant::now();
+    let kp    = engine::prove_kyc(&pp, wasm, invoke, args, step)?;
+    let prove = t1.elapsed().as_secs_f64();
+
+    let t2    = Instant::now();
+    engine::verify_kyc(&pp, &kp)?;
+    let verify= t2.elapsed().as_secs_f64();
+
+    let (proof, instance) = kp.to_bytes()?;
+    Ok(ProofRun {
+        setup_sec:  setup,
+        prove_sec:  prove,
+        verify_sec: verify,
+        proof,
+        instance,
+        pp_digest:  params.digest(step),
+        worker:     None,
+    })
+}
+
+/// Verify a serialized proof/instance pair produced by [`run`] at `step`; returns verify seconds.
+pub fn verify(params: &ParamCache, proof: &[u8], instance: &[u8], step: usize) -> Result<f64> {
+    isolate(|| verify_unisolated(params, proof, instance, step))
+}
+
+fn verify_unisolated(params: &ParamCache, proof: &[u8], instance: &[u8], step: usize) -> Result<f64> {
+    let kp    = KycProof::from_bytes(proof, instance)?;
+    let pp    = params.get(step);
+    let t0    = Instant::now();
+    engine::verify_kyc(&pp, &kp)?;
+    Ok(t0.elapsed().as_secs_f64())
+}
@@ -0,0 +1,197 @@
+//! Per-tenant monthly proof quotas.
+//! Counted per UTC calendar month from the usage log (so restarts keep the count) plus proofs
+//! completed since.  Past `soft_percent` of the limit responses carry a `Warning` header; past the
+//! limit a tenant may still use its `grace` allowance (with warnings), then gets HTTP 429
+//! `quota_exceeded` with the reset time.
+//!
+//! ```toml
+//! default_monthly = 0      # 0 = unlimited
+//! soft_percent    = 80.0
+//!
+//! [tenants.acme]
+//! monthly = 10000
+//! grace   = 100
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::usage::UsageLog;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub struct TenantQuota {
+    #[serde(default)]
+    pub monthly: u64,
+    #[serde(default)]
+    pub grace:   u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct QuotaFile {
+    #[serde(default)]
+    default_monthly: u64,
+    #[serde(default = "default_soft")]
+    soft_percent:    f64,
+    #[serde(default)]
+    tenants:         HashMap<String, TenantQuota>,
+}
+fn default_soft() -> f64 { 80.0 }
+
+impl Default for QuotaFile {
+    fn default() -> Self {
+        QuotaFile { default_monthly: 0, soft_percent: default_soft(), tenants: HashMap::new() }
+    }
+}
+
+/// Where a tenant stands this month.
+#[derive(Clone, Debug, Serialize)]
+pub struct QuotaStatus {
+    pub limit:     u64,
+    pub used:      u64,
+    pub remaining: u64,
+    /// Unix seconds when the count resets (start of next UTC month).
+    pub resets_at: u64,
+    /// Over the soft threshold or into grace.
+    #[serde(skip)]
+    pub warning:   Option<String>,
+}
+
+impl QuotaStatus {
+    /// The status once the proof it admitted has been counted.
+    pub fn consumed(self) -> Self {
+        QuotaStatus { used: self.used + 1, remaining: self.remaining.saturating_sub(1), ..self }
+    }
+}
+
+/// Returned (inside `anyhow::Error`) when a tenant is out of quota and grace.
+#[derive(Debug, Serialize)]
+pub struct QuotaExceeded {
+    pub tenant:    String,
+    pub limit:     u64,
+    pub used:      u64,
+    pub resets_at: u64,
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "monthly proof quota of {} exhausted for tenant {}; resets at {}",
+               self.limit, self.tenant, self.resets_at)
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+pub struct Quotas {
+    cfg:  QuotaFile,
+    /// (tenant → used) for `month`.
+    used: Mutex<(u64, HashMap<String, u64>)>,
+}
+
+impl Quotas {
+    /// Load from `path` (missing file: unlimited) and seed this month's counts from `usage`.
+    pub fn load(path: &Path, usage: &UsageLog) -> Result<Self> {
+        let cfg = if path.exists() {
+            let raw = std::fs::read_to_string(path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            toml::from_str(&raw).with_context(|| format!("parsing {}", path.display()))?
+        } else {
+            QuotaFile::default()
+        };
+        let (start, end) = month_bounds(now());
+        let mut counts = HashMap::new();
+        for r in usage.records(start, end, None)? {
+            *counts.entry(r.tenant).or_insert(0) += 1;
+        }
+        Ok(Self { cfg, used: Mutex::new((start, counts)) })
+    }
+
+    fn quota(&self, tenant: &str) -> TenantQuota {
+        self.cfg.tenants.get(tenant).copied()
+            .unwrap_or(TenantQuota { monthly: self.cfg.default_monthly, grace: 0 })
+    }
+
+    fn used(&self, tenant: &str, now: u64) -> u64 {
+        let mut used = self.used.lock().unwrap();
+        let (start, _) = month_bounds(now);
+        if used.0 != start {
+            *used = (start, HashMap::new());
+        }
+        used.1.get(tenant).copied().unwrap_or(0)
+    }
+
+    /// Admit one more proof for `tenant`, or fail with [`QuotaExceeded`].
+    /// `None` means the tenant is unlimited.
+    pub fn check(&self, tenant: &str) -> Result<Option<QuotaStatus>> {
+        let q = self.quota(tenant);
+        if q.monthly == 0 {
+            return Ok(None);
+        }
+        let now  = now();
+        let used = self.used(tenant, now);
+        let (_, resets_at) = month_bounds(now);
+        if used >= q.monthly + q.grace {
+            return Err(QuotaExceeded { tenant: tenant.to_string(), limit: q.monthly, used, resets_at }.into());
+        }
+        let warning = if used >= q.monthly {
+            Some(format!("monthly quota of {} exceeded; {} grace proofs left", q.monthly, q.monthly + q.grace - used))
+        } else if used as f64 >= q.monthly as f64 * self.cfg.soft_percent / 100.0 {
+            Some(format!("{used} of {} monthly proofs used", q.monthly))
+        } else {
+            None
+        };
+        Ok(Some(QuotaStatus {
+            limit: q.monthly,
+            used,
+            remaining: q.monthly.saturating_sub(used),
+            resets_at,
+            warning,
+        }))
+    }
+
+    /// Count a completed proof.
+    pub fn consume(&self, tenant: &str) {
+        self.used(tenant, now());
+        *self.used.lock().unwrap().1.entry(tenant.to_string()).or_insert(0) += 1;
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/* ---------- UTC calendar months ----------------------------------- */
+/// `[start, end)` in unix seconds of the UTC month containing `t`.
+fn month_bounds(t: u64) -> (u64, u64) {
+    let (y, m, _) = civil_from_days((t / 86_400) as i64);
+    let (ny, nm) = if m == 12 { (y + 1, 1) } else { (y, m + 1) };
+    (days_from_civil(y, m, 1) as u64 * 86_400, days_from_civil(ny, nm, 1) as u64 * 86_400)
+}
+
+// Howard Hinnant's algorithms, proleptic Gregorian.
+pub(crate) fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp  = (m as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+pub(crate) fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z   = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp  = (5 * doy + 2) / 153;
+    let d   = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m   = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (yoe + era * 400 + if m <= 2 { 1 } else { 0 }, m, d)
+}
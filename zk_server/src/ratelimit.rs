@@ -0,0 +1,45 @@
+//! Token buckets, shared by the per-IP, prove-key and verifier-key limits.
+//! A bucket holds up to `burst` tokens and refills at `rate_per_min`; each request takes one.
+
+use std::time::Instant;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Bucket {
+    tokens: f64,
+    at:     Instant,
+}
+
+impl Bucket {
+    /// A full bucket.
+    pub fn full(burst: f64, now: Instant) -> Self {
+        Bucket { tokens: burst, at: now }
+    }
+
+    fn refill(&mut self, rate_per_min: f64, burst: f64, now: Instant) {
+        self.tokens = (self.tokens + now.duration_since(self.at).as_secs_f64() * rate_per_min / 60.0).min(burst);
+        self.at = now;
+    }
+
+    /// Take a token, or return the seconds until one is available.
+    pub fn take(&mut self, rate_per_min: f64, burst: f64, now: Instant) -> Result<(), u64> {
+        self.take_n(1.0, rate_per_min, burst, now)
+    }
+
+    /// Take `n` tokens at once (`n <= burst`), or none and return the seconds until they are available.
+    pub fn take_n(&mut self, n: f64, rate_per_min: f64, burst: f64, now: Instant) -> Result<(), u64> {
+        self.refill(rate_per_min, burst, now);
+        if self.tokens < n {
+            return Err((((n - self.tokens) * 60.0 / rate_per_min).ceil() as u64).max(1));
+        }
+        self.tokens -= n;
+        Ok(())
+    }
+
+    /// Whether the bucket has refilled completely, so forgetting it changes nothing.
+    pub fn is_full(&self, rate_per_min: f64, burst: f64, now: Instant) -> bool {
+        let mut b = *self;
+        b.refill(rate_per_min, burst, now);
+        b.tokens >= burst
+    }
+}
+
@@ -0,0 +1,197 @@
+//! Redaction of subject data and secrets from logs and error output.
+//! Everything the server writes to its log, every error response (status ≥ 400) and every
+//! dead letter's error text passes through [`scrub`], which replaces:
+//!
+//! - `wallet`: 40-hex-digit runs, with or without `0x` (EVM addresses);
+//! - `commitment`: hex runs of 64 digits or more (commitments, digests, HMAC tokens);
+//! - `token`: API keys (`zkv_…`), `Bearer …` credentials and `token=…` query values.
+//!
+//! Nothing is exempt by default.  Debugging environments can let classes through with
+//! `ZK_REDACT_ALLOW` (comma-separated class names, or `all`); the server warns at startup when
+//! it does.  Successful responses are never touched: a proof's public inputs are its payload.
+
+use anyhow::{bail, Result};
+use axum::{
+    body::{self, Full, HttpBody},
+    http::{header, Request},
+    middleware::Next,
+    response::Response,
+};
+use std::{io::Write, sync::OnceLock};
+use tracing_subscriber::fmt::MakeWriter;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Class {
+    Wallet,
+    Commitment,
+    Token,
+}
+
+impl Class {
+    const ALL: [Class; 3] = [Class::Wallet, Class::Commitment, Class::Token];
+
+    fn name(self) -> &'static str {
+        match self {
+            Class::Wallet     => "wallet",
+            Class::Commitment => "commitment",
+            Class::Token      => "token",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Redactor {
+    allow: Vec<Class>,
+}
+
+static ACTIVE: OnceLock<Redactor> = OnceLock::new();
+
+impl Redactor {
+    /// Build from `ZK_REDACT_ALLOW`; unset redacts every class.
+    pub fn from_env() -> Result<Self> {
+        let mut allow = Vec::new();
+        for name in std::env::var("ZK_REDACT_ALLOW").unwrap_or_default().split(',').map(str::trim) {
+            match name {
+                ""    => {}
+                "all" => allow = Class::ALL.to_vec(),
+                n     => match Class::ALL.into_iter().find(|c| c.name() == n) {
+                    Some(c) if !allow.contains(&c) => allow.push(c),
+                    Some(_) => {}
+                    None    => bail!("ZK_REDACT_ALLOW: unknown class {n:?} (wallet, commitment, token or all)"),
+                },
+            }
+        }
+        Ok(Redactor { allow })
+    }
+
+    /// Class names let through unredacted.
+    pub fn allowed(&self) -> Vec<&'static str> {
+        self.allow.iter().map(|c| c.name()).collect()
+    }
+
+    fn masks(&self, c: Class) -> bool {
+        !self.allow.contains(&c)
+    }
+
+    pub fn scrub(&self, s: &str) -> String {
+        let b = s.as_bytes();
+        let mut out = String::with_capacity(s.len());
+        let mut i = 0;
+        let mut copied = 0;
+        while i < b.len() {
+            let boundary = s.is_char_boundary(i) && (i == 0 || !is_word(b[i - 1]));
+            if !boundary {
+                i += 1;
+                continue;
+            }
+            let rest = &s[i..];
+            let replaced = if let Some(n) = secret_len(rest).filter(|_| self.masks(Class::Token)) {
+                Some((n, "[redacted token]"))
+            } else {
+                let prefix = if rest.starts_with("0x") || rest.starts_with("0X") { 2 } else { 0 };
+                let run = b[i + prefix..].iter().take_while(|c| c.is_ascii_hexdigit()).count();
+                let ends = b.get(i + prefix + run).map_or(true, |c| !is_word(*c));
+                match run {
+                    40 if ends && self.masks(Class::Wallet)             => Some((prefix + run, "[redacted wallet]")),
+                    n if n >= 64 && ends && self.masks(Class::Commitment) => Some((prefix + run, "[redacted commitment]")),
+                    _ => None,
+                }
+            };
+            match replaced {
+                Some((n, with)) => {
+                    out.push_str(&s[copied..i]);
+                    out.push_str(with);
+                    i += n;
+                    copied = i;
+                }
+                None => i += 1,
+            }
+        }
+        out.push_str(&s[copied..]);
+        out
+    }
+}
+
+fn is_word(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
+
+/// Length of a credential starting `s`, including its marker.
+fn secret_len(s: &str) -> Option<usize> {
+    let value = |marker: usize| {
+        let n = s[marker..].bytes().take_while(|c| !matches!(c, b' ' | b'&' | b'"' | b'\'' | b',' | b'\n')).count();
+        (n > 0).then_some(marker + n)
+    };
+    if s.starts_with("zkv_") {
+        value(4)
+    } else if s.starts_with("Bearer ") {
+        value(7)
+    } else if s.starts_with("token=") {
+        value(6)
+    } else {
+        None
+    }
+}
+
+/// Make `r` the process-wide redactor.  Call before logging starts; later calls are ignored.
+pub fn install(r: Redactor) {
+    ACTIVE.set(r).ok();
+}
+
+/// [`Redactor::scrub`] with the installed redactor (or the default, which redacts everything).
+pub fn scrub(s: &str) -> String {
+    ACTIVE.get_or_init(Redactor::default).scrub(s)
+}
+
+/// Log writer: each formatted event is scrubbed whole before it reaches stdout.
+pub struct Stdout;
+
+pub struct EventWriter(Vec<u8>);
+
+impl Write for EventWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for EventWriter {
+    fn drop(&mut self) {
+        let line = scrub(&String::from_utf8_lossy(&self.0));
+        std::io::stdout().lock().write_all(line.as_bytes()).ok();
+    }
+}
+
+impl<'a> MakeWriter<'a> for Stdout {
+    type Writer = EventWriter;
+
+    fn make_writer(&'a self) -> EventWriter {
+        EventWriter(Vec::new())
+    }
+}
+
+/// Middleware: scrub the body of every JSON or text error response.
+pub async fn errors<B>(req: Request<B>, next: Next<B>) -> Response {
+    let resp = next.run(req).await;
+    let textual = resp.headers().get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |ct| ct.starts_with("application/json") || ct.starts_with("text/"));
+    if !(resp.status().is_client_error() || resp.status().is_server_error()) || !textual {
+        return resp;
+    }
+    let (mut parts, mut inner) = resp.into_parts();
+    let mut raw = Vec::new();
+    while let Some(chunk) = inner.data().await {
+        match chunk {
+            Ok(c)  => raw.extend_from_slice(&c),
+            Err(_) => break,
+        }
+    }
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let scrubbed = scrub(&String::from_utf8_lossy(&raw));
+    Response::from_parts(parts, body::boxed(Full::from(scrubbed)))
+}
@@ -0,0 +1,207 @@
+//! Relying-party acceptance policies, and a dry run of them against a proof envelope.
+//! `relying_parties.toml` (`ZK_RELYING_PARTIES_FILE`) says what each integrator accepts:
+//!
+//! ```toml
+//! [relying_parties.exchange-eu]
+//! audience     = "exchange-eu"              # scope proofs are presented to (default: the name)
+//! issuers      = ["acme-kyc", "did:web:kyc.bank.example"]   # optional; absent = any trusted issuer
+//! circuits     = ["check_kyc_full"]         # optional; absent = any circuit
+//! max_age_secs = 2592000                    # optional; proofs older than this are refused
+//! ```
+//!
+//! `POST /envelopes/check` takes an envelope — a stored `proof_id`, or the metadata a holder
+//! carries (`circuit`, `issuer`, `issued_at`, `expires_at`, `audience`) — and reports, per relying
+//! party, which checks pass.  Nothing is verified cryptographically or spent; this answers "why
+//! would this be refused?", and `GET /proofs/:id/verify` remains the acceptance check.
+
+use anyhow::{bail, Context, Result};
+use axum::{extract::State, http::StatusCode, response::{IntoResponse, Response}, Json};
+use serde::{Deserialize, Serialize};
+use std::{collections::{BTreeMap, HashMap}, path::Path, sync::Arc};
+
+use crate::{store, AppState};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RelyingParty {
+    #[serde(skip)]
+    pub name:         String,
+    #[serde(default)]
+    pub audience:     String,
+    #[serde(default)]
+    pub issuers:      Vec<String>,
+    #[serde(default)]
+    pub circuits:     Vec<String>,
+    pub max_age_secs: Option<u64>,
+}
+
+#[derive(Deserialize, Default)]
+struct RelyingPartiesFile {
+    #[serde(default)]
+    relying_parties: HashMap<String, RelyingParty>,
+}
+
+#[derive(Default)]
+pub struct RelyingParties {
+    parties: Vec<RelyingParty>,
+}
+
+impl RelyingParties {
+    pub fn load(path: &Path) -> Result<Self> {
+        let file: RelyingPartiesFile = if path.exists() {
+            let raw = std::fs::read_to_string(path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            toml::from_str(&raw).with_context(|| format!("parsing {}", path.display()))?
+        } else {
+            RelyingPartiesFile::default()
+        };
+        let mut parties: Vec<_> = file.relying_parties.into_iter().map(|(name, mut rp)| {
+            if rp.audience.is_empty() {
+                rp.audience = name.clone();
+            }
+            rp.name = name;
+            rp
+        }).collect();
+        parties.sort_by(|a, b| a.name.cmp(&b.name));
+        tracing::info!("loaded {} relying-party policies from {}", parties.len(), path.display());
+        Ok(Self { parties })
+    }
+}
+
+/// What a holder presents.  With `proof_id` the other fields come from the proof store.
+#[derive(Debug, Default, Deserialize)]
+pub struct Envelope {
+    proof_id:   Option<String>,
+    circuit:    Option<String>,
+    issuer:     Option<String>,
+    issued_at:  Option<u64>,
+    expires_at: Option<u64>,
+    /// Relying party the envelope was issued or presented for.
+    audience:   Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CheckBody {
+    envelope:        Envelope,
+    /// Relying parties to check against; absent = all configured.
+    #[serde(default)]
+    relying_parties: Option<Vec<String>>,
+}
+
+/// One check's outcome; `pass: None` means the envelope didn't carry what the check needs.
+#[derive(Serialize)]
+struct Check {
+    pass:   Option<bool>,
+    detail: String,
+}
+
+impl Check {
+    fn pass(detail: impl Into<String>) -> Self { Check { pass: Some(true), detail: detail.into() } }
+    fn fail(detail: impl Into<String>) -> Self { Check { pass: Some(false), detail: detail.into() } }
+    fn unknown(detail: impl Into<String>) -> Self { Check { pass: None, detail: detail.into() } }
+}
+
+#[derive(Serialize)]
+struct Verdict {
+    relying_party: String,
+    /// Every check passed; a check that couldn't run counts as not passed.
+    accepted:      bool,
+    checks:        BTreeMap<&'static str, Check>,
+}
+
+/// `POST /envelopes/check`: which relying parties would accept this envelope, and why not.
+pub async fn handle_check(State(state): State<Arc<AppState>>, Json(body): Json<CheckBody>) -> Response {
+    let (env, revoked) = match resolve(&state, body.envelope) {
+        Ok(r)  => r,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(e.to_string())).into_response(),
+    };
+    let wanted = body.relying_parties;
+    let parties: Vec<_> = state.relying_parties.parties.iter()
+        .filter(|rp| wanted.as_ref().map_or(true, |w| w.contains(&rp.name)))
+        .collect();
+    if let Some(unknown) = wanted.iter().flatten().find(|w| !parties.iter().any(|rp| &rp.name == *w)) {
+        return (StatusCode::NOT_FOUND, Json(format!("unknown relying party {unknown}"))).into_response();
+    }
+    let now = store::now();
+    let verdicts: Vec<_> = parties.into_iter().map(|rp| evaluate(&state, rp, &env, revoked, now)).collect();
+    Json(serde_json::json!({
+        "proof_id":        env.proof_id,
+        "revoked":         revoked,
+        "relying_parties": verdicts,
+    })).into_response()
+}
+
+/// Fill the envelope from the store when it names a proof; returns it and the revocation state.
+fn resolve(state: &AppState, env: Envelope) -> Result<(Envelope, Option<bool>)> {
+    let Some(id) = env.proof_id.clone() else {
+        if env.circuit.is_none() {
+            bail!("envelope needs a proof_id or at least a circuit");
+        }
+        return Ok((env, None));
+    };
+    let Some(store) = &state.store else { bail!("proof store disabled; send the envelope fields instead") };
+    let Some(p) = store.get_proof(&id)? else { bail!("unknown proof {id}") };
+    let revoked = store.revocation(&id)?.is_some();
+    Ok((Envelope {
+        proof_id:   Some(id),
+        circuit:    Some(p.circuit),
+        issuer:     p.issuer,
+        issued_at:  Some(p.created_at),
+        expires_at: p.expires_at,
+        audience:   env.audience,
+    }, Some(revoked)))
+}
+
+fn evaluate(state: &AppState, rp: &RelyingParty, env: &Envelope, revoked: Option<bool>, now: u64) -> Verdict {
+    let mut checks = BTreeMap::new();
+    let circuit = env.circuit.as_deref().unwrap_or_default();
+    let issuer = env.issuer.as_deref().and_then(|i| state.issuers.list().into_iter().find(|t| t.is(i)));
+
+    checks.insert("issuer_trusted", match (&env.issuer, issuer) {
+        (None, _)          => Check::pass("no issuer recorded; nothing to distrust"),
+        (Some(i), None)    => Check::fail(format!("issuer {i} is not in the trust store")),
+        (Some(_), Some(t)) => match state.issuers.admit(&t.id, circuit, env.issued_at.unwrap_or(now)) {
+            Ok(())  => Check::pass(format!("issuer {} is trusted for {circuit}", t.id)),
+            Err(e)  => Check::fail(e.to_string()),
+        },
+    });
+    checks.insert("issuer_accepted", match (&env.issuer, rp.issuers.is_empty()) {
+        (_, true)       => Check::pass("relying party accepts any trusted issuer"),
+        (None, false)   => Check::fail(format!("relying party requires one of {:?}; envelope names no issuer", rp.issuers)),
+        (Some(i), false) => {
+            let ok = rp.issuers.iter().any(|a| a == i || issuer.map_or(false, |t| t.is(a)));
+            let detail = format!("issuer {i}; relying party accepts {:?}", rp.issuers);
+            if ok { Check::pass(detail) } else { Check::fail(detail) }
+        }
+    });
+    checks.insert("circuit_accepted", if rp.circuits.is_empty() || rp.circuits.iter().any(|c| c == circuit) {
+        Check::pass(format!("circuit {circuit}"))
+    } else {
+        Check::fail(format!("circuit {circuit}; relying party accepts {:?}", rp.circuits))
+    });
+    checks.insert("unexpired", match env.expires_at {
+        None                 => Check::pass("proof does not expire"),
+        Some(e) if now < e   => Check::pass(format!("expires at {e}")),
+        Some(e)              => Check::fail(format!("expired at {e}")),
+    });
+    checks.insert("fresh", match (rp.max_age_secs, env.issued_at) {
+        (None, _)              => Check::pass("relying party sets no maximum age"),
+        (Some(_), None)        => Check::unknown("envelope has no issued_at"),
+        (Some(max), Some(at))  => {
+            let detail = format!("issued {}s ago; maximum {max}s", now.saturating_sub(at));
+            if now.saturating_sub(at) <= max { Check::pass(detail) } else { Check::fail(detail) }
+        }
+    });
+    checks.insert("audience", match env.audience.as_deref() {
+        None                       => Check::unknown(format!("envelope has no audience; relying party expects {}", rp.audience)),
+        Some(a) if a == rp.audience => Check::pass(format!("audience {a}")),
+        Some(a)                    => Check::fail(format!("audience {a}; relying party expects {}", rp.audience)),
+    });
+    if let Some(r) = revoked {
+        checks.insert("not_revoked", if r { Check::fail("proof is revoked") } else { Check::pass("proof is not revoked") });
+    }
+    Verdict {
+        relying_party: rp.name.clone(),
+        accepted:      checks.values().all(|c| c.pass == Some(true)),
+        checks,
+    }
+}
@@ -0,0 +1,153 @@
+//! Asynchronous cross-region replication of the proof store.
+//! Local writes are queued and POSTed, in order, to each peer in `ZK_REPLICA_URLS`
+//! (comma-separated base URLs) at `/replication/apply`, authenticated with
+//! `Bearer $ZK_REPLICATION_TOKEN`.  A peer that is down is retried with capped backoff, so
+//! its copy catches up when the region returns; the peer resolves conflicts (see `store.rs`).
+
+use anyhow::{bail, Result};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::Notify;
+
+use crate::{store::Change, AppState};
+
+/// Changes buffered per peer before the oldest are dropped.
+const BACKLOG:  usize = 100_000;
+const BATCH:    usize = 100;
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+pub struct Replicator {
+    peers: Vec<Arc<Peer>>,
+}
+
+struct Peer {
+    url:     String,
+    token:   String,
+    client:  reqwest::Client,
+    /// (sequence number, change), oldest first.
+    backlog: Mutex<(u64, VecDeque<(u64, Change)>)>,
+    wake:    Notify,
+}
+
+impl Replicator {
+    /// Build from `ZK_REPLICA_URLS` / `ZK_REPLICATION_TOKEN`; `None` disables replication.
+    pub fn from_env() -> Result<Option<Self>> {
+        let urls: Vec<String> = std::env::var("ZK_REPLICA_URLS").unwrap_or_default()
+            .split(',').map(str::trim).filter(|u| !u.is_empty()).map(String::from).collect();
+        if urls.is_empty() {
+            return Ok(None);
+        }
+        let token = std::env::var("ZK_REPLICATION_TOKEN").unwrap_or_default();
+        if token.is_empty() {
+            bail!("ZK_REPLICA_URLS is set but ZK_REPLICATION_TOKEN is not");
+        }
+        let client = reqwest::Client::builder().timeout(Duration::from_secs(30)).build()?;
+        let peers = urls.into_iter().map(|url| {
+            let peer = Arc::new(Peer {
+                url:     format!("{}/replication/apply", url.trim_end_matches('/')),
+                token:   token.clone(),
+                client:  client.clone(),
+                backlog: Mutex::new((0, VecDeque::new())),
+                wake:    Notify::new(),
+            });
+            tokio::spawn(peer.clone().run());
+            peer
+        }).collect();
+        Ok(Some(Self { peers }))
+    }
+
+    /// Queue a local write for every peer; never blocks on the network.
+    pub fn push(&self, change: Change) {
+        for p in &self.peers {
+            let mut b = p.backlog.lock().unwrap();
+            if b.1.len() == BACKLOG {
+                b.1.pop_front();
+                tracing::error!(peer = %p.url, "replication backlog full; dropped oldest change");
+            }
+            b.0 += 1;
+            let seq = b.0;
+            b.1.push_back((seq, change.clone()));
+            drop(b);
+            p.wake.notify_one();
+        }
+    }
+}
+
+impl Peer {
+    async fn run(self: Arc<Self>) {
+        let mut wait = Duration::from_secs(1);
+        loop {
+            let (last, batch): (u64, Vec<Change>) = {
+                let b = self.backlog.lock().unwrap();
+                let items: Vec<_> = b.1.iter().take(BATCH).cloned().collect();
+                (items.last().map_or(0, |(s, _)| *s), items.into_iter().map(|(_, c)| c).collect())
+            };
+            if batch.is_empty() {
+                self.wake.notified().await;
+                continue;
+            }
+            match self.send(&batch).await {
+                Ok(()) => {
+                    let mut b = self.backlog.lock().unwrap();
+                    while b.1.front().map_or(false, |(s, _)| *s <= last) {
+                        b.1.pop_front();
+                    }
+                    wait = Duration::from_secs(1);
+                }
+                Err(e) => {
+                    tracing::warn!(peer = %self.url, pending = batch.len(), "replication failed: {e:#}");
+                    tokio::time::sleep(wait).await;
+                    wait = (wait * 2).min(MAX_WAIT);
+                }
+            }
+        }
+    }
+
+    async fn send(&self, batch: &[Change]) -> Result<()> {
+        self.client.post(&self.url)
+            .bearer_auth(&self.token)
+            .json(batch)
+            .send().await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/* ---------- receiving side: POST /replication/apply --------------- */
+pub async fn handle_apply(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(batch): Json<Vec<Change>>,
+) -> Response {
+    let Some(expected) = state.replication_token.as_deref() else {
+        return (StatusCode::FORBIDDEN, Json("replication disabled (set ZK_REPLICATION_TOKEN)")).into_response();
+    };
+    let given = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if given != Some(expected) {
+        return (StatusCode::UNAUTHORIZED, Json("bad replication token")).into_response();
+    }
+    let Some(store) = &state.store else {
+        return (StatusCode::CONFLICT, Json("no proof store configured (set ZK_STORE_DIR)")).into_response();
+    };
+    let mut applied = 0;
+    for change in batch {
+        match store.apply(change) {
+            Ok(true)  => applied += 1,
+            Ok(false) => {}
+            Err(e)    => return (StatusCode::INTERNAL_SERVER_ERROR, Json(format!("{e:#}"))).into_response(),
+        }
+    }
+    Json(serde_json::json!({ "applied": applied })).into_response()
+}
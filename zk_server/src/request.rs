@@ -0,0 +1,138 @@
+//! Versioned /prove request bodies.
+//! Every body carries an optional `schema_version` (absent = 1).  Each version keeps its own
+//! deserializer and is migrated step by step into [`ProveRequest`], the internal model.
+
+use anyhow::{bail, Context, Result};
+use kyc_core::IdentifierType;
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::OnceLock;
+use utoipa::ToSchema;
+
+use crate::{consent::Consent, issuers::SignedAttestation, policy::SubjectAttributes, siwe::SignedMessage, travel_rule::Ivms101Payload};
+
+/// Version new clients should send.
+pub const CURRENT_SCHEMA: u64 = 2;
+
+/// `step` of requests that leave it out; set once at startup from the server config.
+static DEFAULT_STEP: OnceLock<usize> = OnceLock::new();
+
+pub fn set_default_step(step: usize) {
+    DEFAULT_STEP.set(step).ok();
+}
+
+fn default_step() -> usize { DEFAULT_STEP.get().copied().unwrap_or(8) }
+
+/* ---------- current model (schema 2) ----------------------------- */
+#[derive(Deserialize, ToSchema)]
+pub struct ProveRequest {
+    /// Subject identifier; a wallet address unless `identifier_type` says otherwise.
+    #[serde(alias = "subject")]
+    pub wallet:    String,
+    #[serde(default)]
+    #[schema(value_type = String, example = "evm_address")]
+    pub identifier_type: IdentifierType,
+    pub kyc:       i32,
+    pub sig_valid: i32,
+    #[serde(default = "default_step")]
+    pub step:      usize,
+    /// Optional IVMS101 originator/beneficiary payload to commit to.
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub travel_rule: Option<Ivms101Payload>,
+    /// Compatibility: commit only the first 5 limbs (160 bits) via the legacy guest exports.
+    #[serde(default)]
+    pub legacy_limbs: bool,
+    /// Refused: `?policy=` attributes come from the tenant's attestation source (attestation.rs).
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub attributes: Option<SubjectAttributes>,
+    /// Subject's signed consent to processing, stored with the proof.
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub consent: Option<Consent>,
+    /// Issuer-signed flags, for tenants whose source is `signed`.
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub attestation: Option<SignedAttestation>,
+    /// Relying party's nonce from `GET /challenge`, proven as a public input (see challenge.rs).
+    #[serde(default)]
+    pub challenge: Option<String>,
+    /// SIWE message from `POST /siwe/message` and the wallet's signature over it (see siwe.rs).
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub siwe: Option<SignedMessage>,
+}
+
+/* ---------- schema 1: the original demo contract ----------------- */
+/// Fields beyond these are ignored, as they always were, and logged (see [`warn_unknown_v1`]).
+#[derive(Deserialize)]
+struct ProveRequestV1 {
+    wallet:    String,
+    kyc:       i32,
+    sig_valid: i32,
+    #[serde(default = "default_step")]
+    step:      usize,
+    #[serde(default)]
+    #[allow(dead_code)]
+    schema_version: Option<u64>,
+}
+
+impl From<ProveRequestV1> for ProveRequest {
+    fn from(v1: ProveRequestV1) -> Self {
+        ProveRequest {
+            wallet:          v1.wallet,
+            identifier_type: IdentifierType::EvmAddress,
+            kyc:             v1.kyc,
+            sig_valid:       v1.sig_valid,
+            step:            v1.step,
+            travel_rule:     None,
+            legacy_limbs:    false,
+            attributes:      None,
+            consent:         None,
+            attestation:     None,
+            challenge:       None,
+            siwe:            None,
+        }
+    }
+}
+
+const V1_FIELDS: [&str; 5] = ["wallet", "kyc", "sig_valid", "step", "schema_version"];
+
+/// Log the fields of a schema 1 body that it ignores, such as newer fields sent without
+/// `"schema_version": 2`.
+fn warn_unknown_v1(body: &Value) {
+    let Some(fields) = body.as_object() else { return };
+    let unknown: Vec<&str> = fields.keys().map(String::as_str).filter(|k| !V1_FIELDS.contains(k)).collect();
+    if !unknown.is_empty() {
+        tracing::warn!(
+            ?unknown,
+            "ignoring fields of a schema_version 1 /prove body (newer fields need \"schema_version\": {CURRENT_SCHEMA})"
+        );
+    }
+}
+
+/// Body of `/legacy/prove`: schema 1 only, with the original 5-limb commitment.
+pub fn parse_legacy(body: Value) -> Result<ProveRequest> {
+    warn_unknown_v1(&body);
+    let v1: ProveRequestV1 = serde_json::from_value(body).context("invalid legacy /prove body")?;
+    Ok(ProveRequest { legacy_limbs: true, ..v1.into() })
+}
+
+/* ---------- dispatch --------------------------------------------- */
+/// Read `schema_version`, deserialize with that version's shape, migrate to current.
+pub fn parse_prove(body: Value) -> Result<ProveRequest> {
+    let version = match body.get("schema_version") {
+        None    => 1,
+        Some(v) => v.as_u64().context("schema_version must be a positive integer")?,
+    };
+    match version {
+        1 => {
+            warn_unknown_v1(&body);
+            let v1: ProveRequestV1 = serde_json::from_value(body).context("invalid schema_version 1 body")?;
+            Ok(v1.into())
+        }
+        2 => serde_json::from_value(body).context("invalid schema_version 2 body"),
+        v => bail!("unsupported schema_version {v} (this server speaks 1..={CURRENT_SCHEMA})"),
+    }
+}
@@ -0,0 +1,87 @@
+//! Scheduled re-screening of issued proofs' subjects.
+//! A proof attests to KYC status at issuance; status can degrade afterwards (a wallet is
+//! sanctioned, an account closed).  Every `ZK_RESCREEN_SECS` (unset: disabled) the subjects of
+//! unrevoked proofs are checked again against their tenant's attestation source.  A subject that
+//! no longer passes has its proof revoked, and a `proof.revoked` event is sent to the lifecycle
+//! webhook (`ZK_EVENTS_URL`).  A source that is unreachable or errors leaves the proof alone
+//! until the next pass: outages must not mass-revoke.
+//!
+//! Only tenants with an external source are tracked; `request` flags and signed attestations say
+//! nothing new on a re-check.
+
+use anyhow::Result;
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    attestation::Subject,
+    store::{self, ProofStore, ScreenedSubject},
+    webhook::Event,
+    AppState,
+};
+
+/// Interval from `ZK_RESCREEN_SECS`; `None` disables re-screening.
+pub fn interval_from_env() -> Option<Duration> {
+    std::env::var("ZK_RESCREEN_SECS").ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&s: &u64| s > 0)
+        .map(Duration::from_secs)
+}
+
+/// Whether proofs issued for `tenant` should be re-screened.
+pub fn tracks(state: &AppState, tenant: &str) -> bool {
+    !matches!(state.attest.for_tenant(tenant).name(), "request" | "signed")
+}
+
+pub fn spawn(state: Arc<AppState>, every: Duration) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(every);
+        tick.tick().await;
+        loop {
+            tick.tick().await;
+            let Some(store) = &state.store else { return };
+            match pass(&state, store).await {
+                Ok((checked, revoked)) => tracing::info!(checked, revoked, "re-screening pass done"),
+                Err(e)                 => tracing::error!("re-screening pass failed: {e:#}"),
+            }
+        }
+    });
+}
+
+/// One pass over every tracked subject; returns (checked, revoked).
+async fn pass(state: &AppState, store: &ProofStore) -> Result<(usize, usize)> {
+    let (mut checked, mut revoked) = (0, 0);
+    for subject in store.subjects()? {
+        if store.revocation(&subject.proof_id)?.is_some() {
+            store.drop_subject(&subject.proof_id)?;
+            continue;
+        }
+        let source = state.attest.for_tenant(&subject.tenant);
+        let att = match source.attest(&Subject { wallet: &subject.wallet, kyc: 0, sig_valid: 0, signed: None }).await {
+            Ok(a)  => a,
+            Err(e) => {
+                tracing::warn!(proof = %subject.proof_id, source = source.name(), "re-screening skipped: {e:#}");
+                continue;
+            }
+        };
+        checked += 1;
+        if att.kyc == 1 && att.sig_valid == 1 {
+            store.put_subject(&ScreenedSubject { screened_at: store::now(), ..subject })?;
+            continue;
+        }
+        let reason = format!("re-screening: subject no longer passes {} (kyc={}, sig={})", source.name(), att.kyc, att.sig_valid);
+        let r = store.revoke(&subject.proof_id, &reason)?;
+        store.drop_subject(&subject.proof_id)?;
+        revoked += 1;
+        tracing::warn!(proof = %subject.proof_id, tenant = %subject.tenant, "proof revoked: {reason}");
+        if let Some(events) = &state.events {
+            events.emit(Event::new("proof.revoked", "revocation", serde_json::json!({
+                "proof_id":   r.proof_id,
+                "tenant":     subject.tenant,
+                "reason":     r.reason,
+                "revoked_at": r.revoked_at,
+                "source":     source.name(),
+            })));
+        }
+    }
+    Ok((checked, revoked))
+}
@@ -0,0 +1,217 @@
+//! Blue/green guest rollout.
+//! Two builds of the KYC guest can be registered at once; `green_percent` of subjects (stable per
+//! subject) are proven against green, the rest against blue.  Every proof records the version that
+//! served it, and both versions stay acceptable for verification while the rollout is open.
+//! Builds replaced by a swap (see swap.rs) are listed under `retiring` and stay acceptable until
+//! their `until` (unix seconds).
+//!
+//! ```toml
+//! green_percent = 10.0
+//! canary_percent = 1.0       # also prove on the other build and compare (see canary.rs)
+//! canary_max_slowdown = 2.0  # candidate/baseline timing ratio reported as a divergence
+//!
+//! [blue]
+//! version = "2024.10"
+//! wasm    = "examples/kyc_wasm.wasm"
+//!
+//! [green]
+//! version = "2024.11"
+//! wasm    = "examples/kyc_wasm_v2.wasm"
+//! ```
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use crate::store;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GuestVersion {
+    pub version: String,
+    pub wasm:    PathBuf,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RolloutConfig {
+    pub blue:  GuestVersion,
+    #[serde(default)]
+    pub green: Option<GuestVersion>,
+    #[serde(default)]
+    pub green_percent: f64,
+    #[serde(default)]
+    pub canary_percent: f64,
+    #[serde(default = "default_slowdown")]
+    pub canary_max_slowdown: f64,
+    #[serde(default)]
+    pub retiring: Vec<Retiring>,
+}
+fn default_slowdown() -> f64 { 2.0 }
+
+/// A build taken out of service, still accepted for verification until `until`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Retiring {
+    #[serde(flatten)]
+    pub guest: GuestVersion,
+    pub until: u64,
+}
+
+impl Default for RolloutConfig {
+    fn default() -> Self {
+        RolloutConfig {
+            blue: GuestVersion { version: "v1".into(), wasm: "examples/kyc_wasm.wasm".into() },
+            green: None,
+            green_percent: 0.0,
+            canary_percent: 0.0,
+            canary_max_slowdown: default_slowdown(),
+            retiring: Vec::new(),
+        }
+    }
+}
+
+impl RolloutConfig {
+    fn check(&self) -> Result<()> {
+        if !(0.0..=100.0).contains(&self.green_percent) {
+            bail!("green_percent must be within 0..=100");
+        }
+        if !(0.0..=100.0).contains(&self.canary_percent) {
+            bail!("canary_percent must be within 0..=100");
+        }
+        if self.canary_max_slowdown <= 0.0 {
+            bail!("canary_max_slowdown must be positive");
+        }
+        if let Some(g) = &self.green {
+            if g.version == self.blue.version {
+                bail!("blue and green must have distinct versions");
+            }
+        }
+        let serving = |v: &str| v == self.blue.version || self.green.as_ref().map_or(false, |g| g.version == v);
+        if let Some(r) = self.retiring.iter().find(|r| serving(&r.guest.version)) {
+            bail!("{} is serving and cannot also be retiring", r.guest.version);
+        }
+        Ok(())
+    }
+}
+
+pub struct Rollout {
+    cfg: RwLock<RolloutConfig>,
+}
+
+impl Rollout {
+    /// Load from `path`; a missing file serves `wasm` as `v1`.
+    pub fn load(path: &Path, wasm: &Path) -> Result<Self> {
+        let cfg = if path.exists() {
+            let raw = std::fs::read_to_string(path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            toml::from_str(&raw).with_context(|| format!("parsing {}", path.display()))?
+        } else {
+            RolloutConfig {
+                blue: GuestVersion { version: "v1".into(), wasm: wasm.to_path_buf() },
+                ..RolloutConfig::default()
+            }
+        };
+        cfg.check()?;
+        Ok(Self { cfg: RwLock::new(cfg) })
+    }
+
+    /// Guest build serving `subject`.
+    pub fn select(&self, subject: &str) -> GuestVersion {
+        let cfg = self.cfg.read().unwrap();
+        match &cfg.green {
+            Some(g) if bucket(subject) < cfg.green_percent => g.clone(),
+            _ => cfg.blue.clone(),
+        }
+    }
+
+    /// Build to dual-prove this request against, if it was drawn for the canary.
+    /// The candidate is whichever of blue/green did not serve it.
+    pub fn canary(&self, served: &str) -> Option<(GuestVersion, f64)> {
+        let cfg = self.cfg.read().unwrap();
+        let green = cfg.green.as_ref()?;
+        if rand::random::<f64>() * 100.0 >= cfg.canary_percent {
+            return None;
+        }
+        let other = if served == green.version { &cfg.blue } else { green };
+        Some((other.clone(), cfg.canary_max_slowdown))
+    }
+
+    /// Versions a verifier must accept right now.
+    pub fn accepted_versions(&self) -> Vec<String> {
+        self.accepted().into_iter().map(|(v, _)| v).collect()
+    }
+
+    /// Accepted versions with, for retiring builds, the time they stop being accepted.
+    pub fn accepted(&self) -> Vec<(String, Option<u64>)> {
+        let cfg = self.cfg.read().unwrap();
+        let now = store::now();
+        std::iter::once((cfg.blue.version.clone(), None))
+            .chain(cfg.green.as_ref().map(|g| (g.version.clone(), None)))
+            .chain(cfg.retiring.iter().filter(|r| r.until > now).map(|r| (r.guest.version.clone(), Some(r.until))))
+            .collect()
+    }
+
+    pub fn accepts(&self, version: &str) -> bool {
+        self.accepted_versions().iter().any(|v| v == version)
+    }
+
+    /// Every registered guest build: blue, then green.
+    pub fn guests(&self) -> Vec<GuestVersion> {
+        let cfg = self.cfg.read().unwrap();
+        std::iter::once(cfg.blue.clone()).chain(cfg.green.clone()).collect()
+    }
+
+    pub fn config(&self) -> RolloutConfig {
+        self.cfg.read().unwrap().clone()
+    }
+
+    /// Serve `next` to every subject from now on, closing any blue/green split.  The builds it
+    /// replaces are moved to `retiring` for `overlap_secs`; proofs already running on them are
+    /// unaffected, as each job holds the build it was started with.
+    pub fn promote(&self, next: GuestVersion, overlap_secs: u64) -> Result<RolloutConfig> {
+        let mut cfg = self.cfg.write().unwrap();
+        let now = store::now();
+        let mut draft = cfg.clone();
+        draft.retiring.retain(|r| r.until > now && r.guest.version != next.version);
+        for old in std::iter::once(draft.blue.clone()).chain(draft.green.take()) {
+            if old.version != next.version {
+                draft.retiring.push(Retiring { guest: old, until: now + overlap_secs });
+            }
+        }
+        draft.blue = next;
+        draft.green_percent = 0.0;
+        draft.check()?;
+        *cfg = draft.clone();
+        Ok(draft)
+    }
+
+    /// Replace the rollout (e.g. bump the split, promote green to blue, close the window).
+    pub fn set(&self, cfg: RolloutConfig) -> Result<()> {
+        cfg.check()?;
+        *self.cfg.write().unwrap() = cfg;
+        Ok(())
+    }
+}
+
+impl GuestVersion {
+    /// Check the guest file is a valid module exporting `check_kyc_full`.
+    pub fn validate(&self) -> Result<()> {
+        let engine = wasmtime::Engine::default();
+        let module = wasmtime::Module::from_file(&engine, &self.wasm)
+            .with_context(|| format!("guest {} ({})", self.version, self.wasm.display()))?;
+        if module.get_export("check_kyc_full").is_none() {
+            bail!("guest {} ({}) does not export check_kyc_full", self.version, self.wasm.display());
+        }
+        Ok(())
+    }
+}
+
+/// Stable bucket in `[0, 100)` for a subject.
+fn bucket(subject: &str) -> f64 {
+    let mut h = DefaultHasher::new();
+    ("rollout", subject).hash(&mut h);
+    (h.finish() % 10_000) as f64 / 100.0
+}
@@ -0,0 +1,126 @@
+//! Load figures for an external autoscaler (`GET /admin/scaling`).
+//! Proving is CPU-bound, so a replica can usefully run about one proof per core
+//! (`ZK_PROVE_CONCURRENCY` overrides); anything beyond that counts as queued.  The hint sizes the
+//! fleet so this replica's in-flight work would fit that capacity; autoscalers should average
+//! it across replicas.
+
+use serde::Serialize;
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Mutex,
+};
+
+/// Weight of the newest sample in the moving average.
+const ALPHA: f64 = 0.2;
+
+pub struct Load {
+    capacity:  usize,
+    in_flight: AtomicUsize,
+    completed: AtomicU64,
+    avg_prove: Mutex<Option<f64>>,
+}
+
+/// Counts a request in-flight until dropped.
+pub struct InFlight<'a>(&'a Load);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Serialize)]
+pub struct ScalingHints {
+    pub capacity:           usize,
+    pub in_flight:          usize,
+    pub queue_depth:        usize,
+    pub completed:          u64,
+    pub avg_prove_sec:      Option<f64>,
+    pub rss_mb:             Option<f64>,
+    pub memory_limit_mb:    Option<f64>,
+    pub memory_headroom_mb: Option<f64>,
+    pub suggested_replicas: usize,
+}
+
+impl Load {
+    /// A replica running `capacity` proofs at once.
+    pub fn new(capacity: usize) -> Self {
+        Load {
+            capacity,
+            in_flight: AtomicUsize::new(0),
+            completed: AtomicU64::new(0),
+            avg_prove: Mutex::new(None),
+        }
+    }
+
+    /// Proofs this replica runs at once.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn enter(&self) -> InFlight<'_> {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlight(self)
+    }
+
+    pub fn record_prove(&self, secs: f64) {
+        self.completed.fetch_add(1, Ordering::Relaxed);
+        let mut avg = self.avg_prove.lock().unwrap();
+        *avg = Some(avg.map_or(secs, |a| a + ALPHA * (secs - a)));
+    }
+
+    pub fn hints(&self) -> ScalingHints {
+        let in_flight = self.in_flight.load(Ordering::Relaxed);
+        let rss   = rss_mb();
+        let limit = memory_limit_mb();
+        let headroom = limit.zip(rss).map(|(l, r)| (l - r).max(0.0));
+
+        // One replica per `capacity` proofs in flight; one more when memory is nearly exhausted.
+        let mut replicas = ((in_flight + self.capacity - 1) / self.capacity).max(1);
+        if let (Some(h), Some(l)) = (headroom, limit) {
+            if h < 0.1 * l {
+                replicas += 1;
+            }
+        }
+        ScalingHints {
+            capacity: self.capacity,
+            in_flight,
+            queue_depth: in_flight.saturating_sub(self.capacity),
+            completed: self.completed.load(Ordering::Relaxed),
+            avg_prove_sec: *self.avg_prove.lock().unwrap(),
+            rss_mb: rss,
+            memory_limit_mb: limit,
+            memory_headroom_mb: headroom,
+            suggested_replicas: replicas,
+        }
+    }
+}
+
+/* ---------- memory (Linux; None elsewhere) ------------------------ */
+pub fn rss_mb() -> Option<f64> {
+    proc_rss_mb("self")
+}
+
+/// Resident set of process `pid` (or `self`).
+pub fn proc_rss_mb(pid: &str) -> Option<f64> {
+    let status = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    meminfo_kb(&status, "VmRSS:").map(|kb| kb / 1024.0)
+}
+
+/// cgroup v2 limit when set, otherwise the host's total memory.
+fn memory_limit_mb() -> Option<f64> {
+    if let Ok(raw) = std::fs::read_to_string("/sys/fs/cgroup/memory.max") {
+        if let Ok(bytes) = raw.trim().parse::<f64>() {
+            return Some(bytes / (1024.0 * 1024.0));
+        }
+    }
+    let info = std::fs::read_to_string("/proc/meminfo").ok()?;
+    meminfo_kb(&info, "MemTotal:").map(|kb| kb / 1024.0)
+}
+
+fn meminfo_kb(text: &str, key: &str) -> Option<f64> {
+    text.lines()
+        .find_map(|l| l.strip_prefix(key))
+        .and_then(|v| v.split_whitespace().next())
+        .and_then(|v| v.parse().ok())
+}
@@ -0,0 +1,93 @@
+//! Known-answer self-test for validating an instance after a deploy.
+//! `POST /admin/selftest` builds the guest call for a fixed approved subject, checks its public
+//! inputs against the ones pinned in [`EXPECTED_INPUTS`], then proves it at [`STEP`] on every
+//! serving guest build (blue, and green when set) and verifies each proof again from its bytes.
+//! Proofs run on the prove pool with the server's usual isolation, so the check covers hashing,
+//! encoding, parameters, workers and the guests themselves.  Nothing is stored, billed or counted
+//! against a tenant, and no real subject is involved.
+
+use anyhow::{bail, Result};
+use kyc_core::{engine, HashScheme, IdentifierType};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::{prover, rollout::GuestVersion, store, worker::Priority, AppState};
+
+/// Subject of the test vector.
+const SUBJECT: &str = "0x742d35cc6634c0532925a3b844bc454e4438f44e";
+/// Small step size, so parameters are quick to build on a fresh instance.
+const STEP: usize = 2;
+/// `check_kyc_full` arguments for [`SUBJECT`]: its Keccak-256 commitment limbs, then the flags.
+const EXPECTED_INPUTS: [&str; 10] = [
+    "2745925684", "3307035469", "639816214", "3971441388",
+    "2054442767", "1242371082", "3438250909", "2759369419",
+    "1", "1",
+];
+
+#[derive(Serialize)]
+pub struct Report {
+    pub passed:        bool,
+    /// Unix seconds.
+    pub checked_at:    u64,
+    pub circuit:       &'static str,
+    pub step:          usize,
+    /// The vector's public inputs came out as pinned.
+    pub inputs_match:  bool,
+    pub public_inputs: Vec<String>,
+    pub guests:        Vec<GuestResult>,
+}
+
+#[derive(Serialize)]
+pub struct GuestResult {
+    pub version:    String,
+    pub passed:     bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prove_sec:  Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_sec: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error:      Option<String>,
+}
+
+/// Run the self-test.  Refused on a read replica, which has no guests to prove with.
+pub async fn run(state: &Arc<AppState>) -> Result<Report> {
+    if state.read_only {
+        bail!("read replica: run the self-test on a proving replica");
+    }
+    let call = engine::kyc_call(HashScheme::Keccak256, IdentifierType::EvmAddress, SUBJECT, 1, 1, false)?;
+    let inputs_match = call.args == EXPECTED_INPUTS;
+    let mut guests = Vec::new();
+    for guest in state.rollout.guests() {
+        let version = guest.version.clone();
+        let outcome = prove_and_verify(state, guest, call.invoke, call.args.clone()).await;
+        if let Err(e) = &outcome {
+            tracing::error!(version = %version, "self-test failed: {e:#}");
+        }
+        guests.push(match outcome {
+            Ok((prove_sec, verify_sec)) => GuestResult {
+                version, passed: true, prove_sec: Some(prove_sec), verify_sec: Some(verify_sec), error: None,
+            },
+            Err(e) => GuestResult {
+                version, passed: false, prove_sec: None, verify_sec: None, error: Some(format!("{e:#}")),
+            },
+        });
+    }
+    Ok(Report {
+        passed:        inputs_match && guests.iter().all(|g| g.passed),
+        checked_at:    store::now(),
+        circuit:       call.invoke,
+        step:          STEP,
+        inputs_match,
+        public_inputs: call.args,
+        guests,
+    })
+}
+
+/// Prove the vector on `guest`, then verify the proof from its serialized bytes.
+async fn prove_and_verify(state: &AppState, guest: GuestVersion, invoke: &'static str, args: Vec<String>) -> Result<(f64, f64)> {
+    let (isolation, params) = (state.prover.clone(), state.params.clone());
+    let run = state.pool.run(move || isolation.run(&params, &guest.wasm, invoke, args, STEP, Priority::Background)).await??;
+    let (prove_sec, params) = (run.prove_sec, state.params.clone());
+    let verify_sec = state.pool.run(move || prover::verify(&params, &run.proof, &run.instance, STEP)).await??;
+    Ok((prove_sec, verify_sec))
+}
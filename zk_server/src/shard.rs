@@ -0,0 +1,143 @@
+//! Consistent-hash partitioning of the revocation / nullifier keyspace.
+//! Keys are placed on a ring of `VNODES` points per shard (SHA-256 of the shard index and point),
+//! so each lookup touches exactly one shard and one file regardless of how many keys exist, and
+//! adding a shard moves only ~1/N of the keys.  Shards are directories (`ZK_STORE_SHARDS`,
+//! comma-separated; default `$ZK_STORE_DIR/keys`), typically on separate volumes.
+//!
+//! The shard list is recorded in `ring.json` of the first shard; when it changes, keys not on
+//! their new owner are moved at startup before serving.
+
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::store::{read_json, write_json};
+
+const VNODES: u32 = 128;
+
+pub struct ShardedKeys {
+    shards: Vec<PathBuf>,
+    ring:   Vec<(u64, usize)>,
+}
+
+fn point(bytes: &[u8]) -> u64 {
+    let h = Sha256::digest(bytes);
+    u64::from_be_bytes(h[..8].try_into().unwrap())
+}
+
+impl ShardedKeys {
+    /// Open `shards`, rebalancing if the shard list differs from the recorded one.
+    pub fn open(shards: Vec<PathBuf>, namespaces: &[&str]) -> Result<Self> {
+        anyhow::ensure!(!shards.is_empty(), "at least one key shard is required");
+        for dir in &shards {
+            for ns in namespaces {
+                std::fs::create_dir_all(dir.join(ns))
+                    .with_context(|| format!("creating {}", dir.join(ns).display()))?;
+            }
+        }
+        let mut ring: Vec<(u64, usize)> = (0..shards.len())
+            .flat_map(|i| (0..VNODES).map(move |v| (point(format!("shard-{i}-{v}").as_bytes()), i)))
+            .collect();
+        ring.sort_unstable();
+        let keys = Self { shards, ring };
+
+        let marker = keys.shards[0].join("ring.json");
+        let recorded: Option<Vec<PathBuf>> = read_json(&marker)?;
+        if recorded.as_ref() != Some(&keys.shards) {
+            let moved = keys.rebalance(recorded.as_deref().unwrap_or(&[]), namespaces)?;
+            if moved > 0 {
+                tracing::warn!("key shards changed; moved {moved} keys to their new owners");
+            }
+            write_json(&marker, &keys.shards)?;
+        }
+        Ok(keys)
+    }
+
+    fn owner(&self, key: &str) -> usize {
+        let p = point(key.as_bytes());
+        let i = self.ring.partition_point(|&(h, _)| h < p);
+        self.ring[i % self.ring.len()].1
+    }
+
+    /// `<shard>/<ns>/<first two chars>/<key>.json`.
+    fn path(&self, ns: &str, key: &str) -> PathBuf {
+        let fan = key.get(..2).unwrap_or("__");
+        self.shards[self.owner(key)].join(ns).join(fan).join(format!("{key}.json"))
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, ns: &str, key: &str) -> Result<Option<T>> {
+        read_json(&self.path(ns, key))
+    }
+
+    pub fn put<T: Serialize>(&self, ns: &str, key: &str, v: &T) -> Result<()> {
+        let path = self.path(ns, key);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        write_json(&path, v)
+    }
+
+    /// Create `key` only if absent; returns whether this call created it.
+    pub fn insert_new<T: Serialize>(&self, ns: &str, key: &str, v: &T) -> Result<bool> {
+        let path = self.path(ns, key);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut f) => {
+                f.write_all(&serde_json::to_vec(v)?)?;
+                Ok(true)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(e).with_context(|| format!("creating {}", path.display())),
+        }
+    }
+
+    /// Call `f` with every key of `ns`, across all shards.
+    pub fn for_each_key(&self, ns: &str, f: &mut dyn FnMut(&str)) -> Result<()> {
+        for dir in &self.shards {
+            let Ok(fans) = std::fs::read_dir(dir.join(ns)) else { continue };
+            for fan in fans.flatten() {
+                for entry in std::fs::read_dir(fan.path())?.flatten() {
+                    let p = entry.path();
+                    if p.extension().and_then(|e| e.to_str()) != Some("json") { continue; }
+                    if let Some(key) = p.file_stem().and_then(|k| k.to_str()) {
+                        f(key);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Move every key found in `previous` or current shards that isn't on its owner.
+    fn rebalance(&self, previous: &[PathBuf], namespaces: &[&str]) -> Result<usize> {
+        let mut dirs: Vec<&Path> = self.shards.iter().map(PathBuf::as_path).collect();
+        dirs.extend(previous.iter().map(PathBuf::as_path).filter(|p| !self.shards.iter().any(|s| s == p)));
+        let mut moved = 0;
+        for dir in dirs {
+            for ns in namespaces {
+                let Ok(fans) = std::fs::read_dir(dir.join(ns)) else { continue };
+                for fan in fans.flatten() {
+                    for entry in std::fs::read_dir(fan.path())?.flatten() {
+                        let from = entry.path();
+                        let Some(key) = from.file_stem().and_then(|k| k.to_str()) else { continue };
+                        if from.extension().and_then(|e| e.to_str()) != Some("json") {
+                            continue;
+                        }
+                        let to = self.path(ns, key);
+                        if to != from {
+                            std::fs::create_dir_all(to.parent().unwrap())?;
+                            std::fs::rename(&from, &to)
+                                .or_else(|_| std::fs::copy(&from, &to).and_then(|_| std::fs::remove_file(&from)))
+                                .with_context(|| format!("moving {}", from.display()))?;
+                            moved += 1;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(moved)
+    }
+}
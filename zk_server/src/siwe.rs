@@ -0,0 +1,230 @@
+//! Wallet ownership by Sign-In-With-Ethereum (EIP-4361), checked before proving.
+//!
+//! Without it, anyone can request a proof for any wallet string.
+//!
+//! 1. The caller posts `POST /siwe/message` with `{ address, chain_id? }` and gets
+//!    `{ message, nonce, expires_at }`: an EIP-4361 message for `ZK_SIWE_DOMAIN` naming the
+//!    (EIP-55) address, with a fresh nonce, live for `ZK_SIWE_TTL_SECS` (default 300).
+//! 2. The wallet signs the message with `personal_sign`.
+//! 3. The caller sends `siwe: { message, signature }` in the `/prove` body.  Proving proceeds only
+//!    if the message is one this instance issued, unchanged, unexpired and unused, it names the
+//!    request's wallet, and the signature recovers to that wallet.  The check spends the message.
+//!
+//! The message and signature are stored with the proof and recorded in its envelope
+//! ([`kyc_core::envelope::Ownership`]), so whoever holds the envelope can check them again.
+//! `ZK_REQUIRE_SIWE=true` refuses `evm_address` requests without one; other identifier types have
+//! no wallet key and can't send one.  Messages live in memory on the instance that issued them.
+//! At most `MAX_OPEN` are kept at once.
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use axum::{extract::State, http::StatusCode, response::{IntoResponse, Response}, Json};
+use kyc_core::{envelope::Ownership, identifier, IdentifierType};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::{Arc, Mutex}};
+
+use crate::{consent::recover_personal_sign, quota::civil_from_days, store, AppState};
+
+const MAX_OPEN: usize = 100_000;
+
+pub struct Siwe {
+    /// `ZK_SIWE_DOMAIN`, else the host of `ZK_PUBLIC_URL`.
+    domain:       String,
+    uri:          String,
+    ttl:          u64,
+    /// `ZK_REQUIRE_SIWE`: `evm_address` proofs need a signed message.
+    pub required: bool,
+    /// Issued messages and their expiry, by nonce.
+    open:         Mutex<HashMap<String, (String, u64)>>,
+}
+
+/// A signed message as sent in the `/prove` body.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SignedMessage {
+    /// The message from `POST /siwe/message`, unchanged.
+    pub message:   String,
+    /// `0x`-hex `personal_sign` signature (65 bytes, r‖s‖v).
+    pub signature: String,
+}
+
+/// Ownership check as stored with a proof.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OwnershipRecord {
+    pub message:    String,
+    pub signature:  String,
+    /// Unix seconds the server checked it.
+    pub checked_at: u64,
+}
+
+impl OwnershipRecord {
+    /// The record as carried in a proof envelope.
+    pub fn to_envelope(&self) -> Result<Ownership> {
+        let sig = hex::decode(self.signature.trim_start_matches("0x")).context("ownership signature is not hex")?;
+        let signature = sig.try_into().map_err(|_| anyhow!("ownership signature must be 65 bytes"))?;
+        Ok(Ownership { message: self.message.clone(), signature })
+    }
+}
+
+impl Siwe {
+    pub fn from_env(public_url: Option<&str>) -> Self {
+        let host = public_url.map(|u| {
+            let rest = u.split_once("://").map_or(u, |(_, r)| r);
+            rest.split('/').next().unwrap_or(rest).to_string()
+        });
+        let domain = std::env::var("ZK_SIWE_DOMAIN").ok().filter(|d| !d.is_empty())
+            .or(host)
+            .unwrap_or_else(|| "localhost".into());
+        let uri = public_url.map(|u| u.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| format!("https://{domain}"));
+        let ttl = std::env::var("ZK_SIWE_TTL_SECS").ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300);
+        let required = matches!(std::env::var("ZK_REQUIRE_SIWE").as_deref(), Ok("1" | "true"));
+        Siwe { domain, uri, ttl, required, open: Mutex::new(HashMap::new()) }
+    }
+
+    /// A new message for `address` (EIP-55) on `chain_id`, its nonce and expiry; `None` when too
+    /// many are open.
+    fn issue(&self, address: &str, chain_id: u64) -> Option<(String, String, u64)> {
+        let now = store::now();
+        let mut open = self.open.lock().unwrap();
+        open.retain(|_, (_, exp)| *exp > now);
+        if open.len() >= MAX_OPEN {
+            return None;
+        }
+        let (nonce, expires_at) = (hex::encode(rand::random::<[u8; 16]>()), now + self.ttl);
+        let message = format!(
+            "{} wants you to sign in with your Ethereum account:\n{address}\n\n\
+             Prove KYC approval for this wallet with zkKYC.\n\n\
+             URI: {}\nVersion: 1\nChain ID: {chain_id}\nNonce: {nonce}\nIssued At: {}\nExpiration Time: {}",
+            self.domain, self.uri, timestamp(now), timestamp(expires_at),
+        );
+        open.insert(nonce.clone(), (message.clone(), expires_at));
+        Some((message, nonce, expires_at))
+    }
+
+    /// Check `signed` for the (canonical) `wallet` and spend its message.
+    pub fn check(&self, signed: SignedMessage, wallet: &str, now: u64) -> Result<OwnershipRecord> {
+        let nonce = signed.message.lines()
+            .find_map(|l| l.strip_prefix("Nonce: "))
+            .context("siwe.message has no nonce")?;
+        let sig = hex::decode(signed.signature.trim_start_matches("0x")).context("siwe.signature is not hex")?;
+        let mut open = self.open.lock().unwrap();
+        let Some((issued, expires_at)) = open.get(nonce).cloned() else {
+            bail!("unknown or already used siwe message");
+        };
+        ensure!(issued == signed.message, "siwe.message differs from the one issued");
+        if expires_at <= now {
+            open.remove(nonce);
+            bail!("siwe message expired");
+        }
+        let claimed = signed.message.lines().nth(1).unwrap_or_default();
+        ensure!(claimed.eq_ignore_ascii_case(wallet), "siwe message is for {claimed}, not the subject");
+        let signer = recover_personal_sign(signed.message.as_bytes(), &sig)?;
+        ensure!(signer.eq_ignore_ascii_case(wallet), "siwe signature is from {signer}, not the subject");
+        open.remove(nonce);
+        Ok(OwnershipRecord { message: signed.message, signature: signed.signature.to_lowercase(), checked_at: now })
+    }
+}
+
+/// RFC 3339 UTC time of unix seconds `t`, as EIP-4361 writes it.
+fn timestamp(t: u64) -> String {
+    let (y, m, d) = civil_from_days((t / 86_400) as i64);
+    let s = t % 86_400;
+    format!("{y:04}-{m:02}-{d:02}T{:02}:{:02}:{:02}Z", s / 3600, s / 60 % 60, s % 60)
+}
+
+#[derive(Deserialize)]
+pub struct MessageBody {
+    address:  String,
+    chain_id: Option<u64>,
+}
+
+/// `POST /siwe/message`: a message for the wallet to sign before `/prove`.
+pub async fn handle_message(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<MessageBody>,
+) -> Response {
+    let id = IdentifierType::EvmAddress;
+    let address = id.check_checksum(&body.address)
+        .map(|_| id.canonicalize(&body.address))
+        .and_then(|a| id.validate(&a).map(|_| a));
+    let Some(address) = address.ok().and_then(|a| identifier::checksummed(&a)) else {
+        return (StatusCode::BAD_REQUEST, Json("address is not an EVM address")).into_response();
+    };
+    match state.siwe.issue(&address, body.chain_id.unwrap_or(1)) {
+        Some((message, nonce, expires_at)) => Json(serde_json::json!({
+            "message":    message,
+            "nonce":      nonce,
+            "expires_at": expires_at,
+        })).into_response(),
+        None => (StatusCode::SERVICE_UNAVAILABLE, Json("too many open siwe messages")).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::SigningKey;
+    use kyc_core::HashScheme;
+
+    fn siwe(ttl: u64) -> Siwe {
+        Siwe {
+            domain:   "kyc.example".into(),
+            uri:      "https://kyc.example".into(),
+            ttl,
+            required: false,
+            open:     Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// A wallet key and its lowercase address.
+    fn wallet(seed: u8) -> (SigningKey, String) {
+        let key = SigningKey::from_bytes(&[seed; 32].into()).unwrap();
+        let point = key.verifying_key().to_encoded_point(false);
+        let h = HashScheme::Keccak256.digest(&point.as_bytes()[1..]).unwrap();
+        (key, format!("0x{}", hex::encode(&h[12..])))
+    }
+
+    fn sign(key: &SigningKey, message: &str) -> SignedMessage {
+        let mut pre = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+        pre.extend_from_slice(message.as_bytes());
+        let digest = HashScheme::Keccak256.digest(&pre).unwrap();
+        let (sig, id) = key.sign_prehash_recoverable(&digest).unwrap();
+        let mut bytes = sig.to_bytes().to_vec();
+        bytes.push(27 + id.to_byte());
+        SignedMessage { message: message.into(), signature: format!("0x{}", hex::encode(bytes)) }
+    }
+
+    #[test]
+    fn message_is_single_use() {
+        let s = siwe(300);
+        let (key, address) = wallet(7);
+        let (message, _, _) = s.issue(&identifier::checksummed(&address).unwrap(), 1).unwrap();
+        let signed = sign(&key, &message);
+        let record = s.check(signed.clone(), &address, store::now()).unwrap();
+        assert_eq!(record.message, message);
+        assert!(s.check(signed, &address, store::now()).is_err());
+    }
+
+    #[test]
+    fn message_expires() {
+        let s = siwe(300);
+        let (key, address) = wallet(7);
+        let (message, _, expires_at) = s.issue(&address, 1).unwrap();
+        let signed = sign(&key, &message);
+        assert!(s.check(signed.clone(), &address, expires_at).is_err());
+        // An expired message is dropped, not left to be retried.
+        assert!(s.check(signed, &address, expires_at - 1).is_err());
+    }
+
+    #[test]
+    fn refuses_other_signer() {
+        let s = siwe(300);
+        let (_, address) = wallet(7);
+        let (other, _) = wallet(8);
+        let (message, _, _) = s.issue(&address, 1).unwrap();
+        assert!(s.check(sign(&other, &message), &address, store::now()).is_err());
+        // A failed signature check doesn't spend the message.
+        assert!(s.open.lock().unwrap().values().any(|(m, _)| *m == message));
+    }
+}
@@ -0,0 +1,159 @@
+//! Per-tenant SLO tracking.
+//! Each proving attempt is scored against its tenant's objectives — success, and completion within
+//! `latency_sec` — in per-minute buckets covering the last 24h.  `GET /admin/sla` reports, per
+//! tenant and window, the attainment and burn rate (error rate ÷ error budget; 1.0 spends the
+//! budget exactly over the SLO period).  A tenant is flagged when the 1h burn exceeds 14.4 or the
+//! 6h burn exceeds 6, the usual fast/slow multi-window thresholds.
+//!
+//! ```toml
+//! [default]
+//! success_target = 0.99
+//! latency_sec    = 30.0
+//! latency_target = 0.95
+//!
+//! [tenants.acme]
+//! latency_sec    = 10.0
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Windows reported, in minutes.
+const WINDOWS: [(&str, u64); 3] = [("1h", 60), ("6h", 360), ("24h", 1440)];
+const FAST_BURN: f64 = 14.4;
+const SLOW_BURN: f64 = 6.0;
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct Slo {
+    #[serde(default = "default_success")]
+    pub success_target: f64,
+    #[serde(default = "default_latency")]
+    pub latency_sec:    f64,
+    #[serde(default = "default_latency_target")]
+    pub latency_target: f64,
+}
+fn default_success() -> f64 { 0.99 }
+fn default_latency() -> f64 { 30.0 }
+fn default_latency_target() -> f64 { 0.95 }
+
+impl Default for Slo {
+    fn default() -> Self {
+        Slo { success_target: default_success(), latency_sec: default_latency(), latency_target: default_latency_target() }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SloFile {
+    #[serde(default)]
+    default: Slo,
+    #[serde(default)]
+    tenants: HashMap<String, Slo>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Bucket {
+    minute: u64,
+    total:  u64,
+    ok:     u64,
+    fast:   u64,
+}
+
+#[derive(Serialize)]
+pub struct WindowReport {
+    pub attempts:       u64,
+    pub success_rate:   Option<f64>,
+    pub within_latency: Option<f64>,
+    pub success_burn:   f64,
+    pub latency_burn:   f64,
+}
+
+#[derive(Serialize)]
+pub struct TenantReport {
+    pub slo:      Slo,
+    pub affected: bool,
+    pub windows:  BTreeMap<&'static str, WindowReport>,
+}
+
+pub struct SlaTracker {
+    cfg:     SloFile,
+    buckets: Mutex<HashMap<String, VecDeque<Bucket>>>,
+}
+
+impl SlaTracker {
+    /// Load from `path`; a missing file applies the defaults to every tenant.
+    pub fn load(path: &Path) -> Result<Self> {
+        let cfg = if path.exists() {
+            let raw = std::fs::read_to_string(path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            toml::from_str(&raw).with_context(|| format!("parsing {}", path.display()))?
+        } else {
+            SloFile::default()
+        };
+        Ok(Self { cfg, buckets: Mutex::new(HashMap::new()) })
+    }
+
+    fn slo(&self, tenant: &str) -> Slo {
+        self.cfg.tenants.get(tenant).copied().unwrap_or(self.cfg.default)
+    }
+
+    pub fn record(&self, tenant: &str, ok: bool, wall_sec: f64) {
+        let minute = now_minute();
+        let fast = ok && wall_sec <= self.slo(tenant).latency_sec;
+        let mut all = self.buckets.lock().unwrap();
+        let q = all.entry(tenant.to_string()).or_default();
+        if q.back().map_or(true, |b| b.minute != minute) {
+            q.push_back(Bucket { minute, ..Bucket::default() });
+        }
+        while q.front().map_or(false, |b| b.minute + WINDOWS[2].1 <= minute) {
+            q.pop_front();
+        }
+        let b = q.back_mut().unwrap();
+        b.total += 1;
+        b.ok    += ok as u64;
+        b.fast  += fast as u64;
+    }
+
+    pub fn report(&self) -> BTreeMap<String, TenantReport> {
+        let minute = now_minute();
+        let all = self.buckets.lock().unwrap();
+        all.iter().map(|(tenant, q)| {
+            let slo = self.slo(tenant);
+            let windows: BTreeMap<_, _> = WINDOWS.iter().map(|&(name, mins)| {
+                let (mut total, mut ok, mut fast) = (0, 0, 0);
+                for b in q.iter().filter(|b| b.minute + mins > minute) {
+                    total += b.total; ok += b.ok; fast += b.fast;
+                }
+                (name, window(slo, total, ok, fast))
+            }).collect();
+            let affected = windows["1h"].success_burn > FAST_BURN || windows["1h"].latency_burn > FAST_BURN
+                || windows["6h"].success_burn > SLOW_BURN || windows["6h"].latency_burn > SLOW_BURN;
+            (tenant.clone(), TenantReport { slo, affected, windows })
+        }).collect()
+    }
+}
+
+fn window(slo: Slo, total: u64, ok: u64, fast: u64) -> WindowReport {
+    let rate = |n: u64| (total > 0).then(|| n as f64 / total as f64);
+    let burn = |attained: Option<f64>, target: f64| match attained {
+        Some(a) if target < 1.0 => (1.0 - a) / (1.0 - target),
+        _ => 0.0,
+    };
+    let (success, within) = (rate(ok), rate(fast));
+    WindowReport {
+        attempts:       total,
+        success_rate:   success,
+        within_latency: within,
+        success_burn:   burn(success, slo.success_target),
+        latency_burn:   burn(within, slo.latency_target),
+    }
+}
+
+fn now_minute() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() / 60).unwrap_or(0)
+}
@@ -0,0 +1,182 @@
+//! Differentially private proof statistics, safe to publish.
+//! Each issued proof appends `{ day, bucket }` to `$ZK_STATS_FILE` (default `stats.jsonl`): the
+//! UTC day and the subject's country bucket, nothing else.  Buckets are the country codes listed in
+//! `ZK_DP_BUCKETS` (comma-separated), `other` for the rest, and `unknown` when no country was sent;
+//! the list is fixed so a bucket appearing says nothing about who was proven.
+//!
+//! `GET /stats/proofs` releases per-day, per-bucket counts for completed UTC days only, each with
+//! Laplace noise of scale `ZK_DP_SENSITIVITY / ZK_DP_EPSILON` (defaults 1 and 1.0), rounded and
+//! clamped at zero.  A proof lands in exactly one cell, so every release is ε-DP per proof;
+//! raise the sensitivity to the number of proofs one subject can get in a day.  Each day is noised
+//! once and the result kept in `<ZK_STATS_FILE>.released.json`: asking again returns the same
+//! numbers, so noise can't be averaged away by repeated queries.  Counts are per instance.
+
+use anyhow::{bail, Context, Result};
+use axum::{extract::{Query, State}, http::StatusCode, response::{IntoResponse, Response}, Json};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use crate::{quota::{civil_from_days, days_from_civil}, store, AppState};
+
+const DAY: u64 = 86_400;
+/// Longest range one request may cover.
+const MAX_DAYS: i64 = 366;
+
+#[derive(Deserialize, Serialize)]
+struct Entry {
+    day:    i64,
+    bucket: String,
+}
+
+/// Released (noised) counts by day, then bucket.
+type Released = BTreeMap<i64, BTreeMap<String, u64>>;
+
+pub struct Stats {
+    path:        PathBuf,
+    released_at: PathBuf,
+    epsilon:     f64,
+    sensitivity: f64,
+    buckets:     Vec<String>,
+    /// Guards both files; holds the released counts.
+    released:    Mutex<Released>,
+}
+
+impl Stats {
+    pub fn from_env() -> Result<Self> {
+        let path = PathBuf::from(std::env::var("ZK_STATS_FILE").unwrap_or_else(|_| "stats.jsonl".into()));
+        let released_at = path.with_extension("released.json");
+        let num = |k: &str, default: f64| -> Result<f64> {
+            match std::env::var(k) {
+                Ok(v) => v.parse().with_context(|| format!("{k} must be a number")),
+                Err(_) => Ok(default),
+            }
+        };
+        let (epsilon, sensitivity) = (num("ZK_DP_EPSILON", 1.0)?, num("ZK_DP_SENSITIVITY", 1.0)?);
+        if epsilon <= 0.0 || sensitivity <= 0.0 {
+            bail!("ZK_DP_EPSILON and ZK_DP_SENSITIVITY must be positive");
+        }
+        let mut buckets: Vec<String> = std::env::var("ZK_DP_BUCKETS").unwrap_or_default()
+            .split(',').map(|c| c.trim().to_ascii_uppercase()).filter(|c| !c.is_empty())
+            .collect();
+        buckets.sort();
+        buckets.dedup();
+        buckets.extend(["other".to_string(), "unknown".to_string()]);
+        let released = store::read_json(&released_at)?.unwrap_or_default();
+        Ok(Stats { path, released_at, epsilon, sensitivity, buckets, released: Mutex::new(released) })
+    }
+
+    fn bucket(&self, country: Option<&str>) -> String {
+        match country.map(|c| c.trim().to_ascii_uppercase()) {
+            None                                => "unknown".into(),
+            Some(c) if self.buckets.contains(&c) => c,
+            Some(_)                             => "other".into(),
+        }
+    }
+
+    /// Count one issued proof for a subject in `country`.
+    pub fn record(&self, country: Option<&str>) -> Result<()> {
+        let e = Entry { day: (store::now() / DAY) as i64, bucket: self.bucket(country) };
+        let _guard = self.released.lock().unwrap();
+        let mut f = OpenOptions::new().create(true).append(true).open(&self.path)
+            .with_context(|| format!("opening {}", self.path.display()))?;
+        writeln!(f, "{}", serde_json::to_string(&e)?)?;
+        Ok(())
+    }
+
+    /// Noised counts for days `from..=to`, releasing (and persisting) days not released before.
+    fn release(&self, from: i64, to: i64) -> Result<Released> {
+        let mut released = self.released.lock().unwrap();
+        let missing: BTreeSet<i64> = (from..=to).filter(|d| !released.contains_key(d)).collect();
+        if !missing.is_empty() {
+            let mut counts: BTreeMap<i64, BTreeMap<String, u64>> = missing.iter()
+                .map(|&d| (d, self.buckets.iter().map(|b| (b.clone(), 0)).collect()))
+                .collect();
+            match std::fs::File::open(&self.path) {
+                Ok(f) => for line in BufReader::new(f).lines() {
+                    let line = line?;
+                    if line.trim().is_empty() { continue; }
+                    let e: Entry = serde_json::from_str(&line)?;
+                    if let Some(c) = counts.get_mut(&e.day).and_then(|day| day.get_mut(&e.bucket)) {
+                        *c += 1;
+                    }
+                },
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e).with_context(|| format!("opening {}", self.path.display())),
+            }
+            let scale = self.sensitivity / self.epsilon;
+            for (day, buckets) in counts {
+                let noised = buckets.into_iter()
+                    .map(|(b, n)| (b, (n as f64 + laplace(scale)).round().max(0.0) as u64))
+                    .collect();
+                released.insert(day, noised);
+            }
+            store::write_json(&self.released_at, &*released)?;
+        }
+        Ok(released.range(from..=to).map(|(d, b)| (*d, b.clone())).collect())
+    }
+}
+
+/// One draw from Laplace(0, `scale`) by inverse transform.
+fn laplace(scale: f64) -> f64 {
+    let u: f64 = rand::random::<f64>() - 0.5;
+    -scale * u.signum() * (1.0 - 2.0 * u.abs()).max(f64::MIN_POSITIVE).ln()
+}
+
+fn parse_day(s: &str) -> Result<i64> {
+    let parts: Vec<_> = s.split('-').collect();
+    let [y, m, d] = parts[..] else { bail!("dates are YYYY-MM-DD, got {s:?}") };
+    let (y, m, d) = (y.parse()?, m.parse()?, d.parse()?);
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        bail!("invalid date {s:?}");
+    }
+    Ok(days_from_civil(y, m, d))
+}
+
+fn format_day(day: i64) -> String {
+    let (y, m, d) = civil_from_days(day);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+#[derive(Deserialize)]
+pub struct StatsQuery {
+    /// First day, `YYYY-MM-DD` (default: 30 days before `to`).
+    from: Option<String>,
+    /// Last day, inclusive (default and maximum: yesterday, UTC).
+    to:   Option<String>,
+}
+
+/// `GET /stats/proofs?from&to`: noised proofs per day and country bucket.
+pub async fn handle_proofs(State(state): State<Arc<AppState>>, Query(q): Query<StatsQuery>) -> Response {
+    let yesterday = (store::now() / DAY) as i64 - 1;
+    let range = (|| -> Result<(i64, i64)> {
+        let to = q.to.as_deref().map(parse_day).transpose()?.unwrap_or(yesterday).min(yesterday);
+        let from = q.from.as_deref().map(parse_day).transpose()?.unwrap_or(to - 29);
+        if from > to || to - from >= MAX_DAYS {
+            bail!("from must not be after to, and the range is at most {MAX_DAYS} days of completed days");
+        }
+        Ok((from, to))
+    })();
+    let (from, to) = match range {
+        Ok(r)  => r,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(e.to_string())).into_response(),
+    };
+    let stats = &state.stats;
+    match stats.release(from, to) {
+        Ok(released) => Json(serde_json::json!({
+            "epsilon":     stats.epsilon,
+            "sensitivity": stats.sensitivity,
+            "days": released.into_iter().map(|(day, buckets)| serde_json::json!({
+                "day":     format_day(day),
+                "total":   buckets.values().sum::<u64>(),
+                "buckets": buckets,
+            })).collect::<Vec<_>>(),
+        })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(e.to_string())).into_response(),
+    }
+}
@@ -0,0 +1,113 @@
+//! Public health summary (`GET /status`).
+//! Only fleet-wide aggregates over the last hour of proving attempts — no tenant, subject or
+//! circuit data — so it can feed a public status page directly.  Rejected requests (bad input,
+//! KYC denied, quota) are not proving attempts and don't count against the success rate.
+//!
+//! Probes for load balancers live here too: `GET /healthz` answers while the process is up, and
+//! `GET /readyz` answers `503` until the warm-up step sizes' public parameters are built or loaded
+//! and every rollout guest has been validated, so cold instances get no traffic.
+
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::prover::ParamCache;
+
+const WINDOW: Duration = Duration::from_secs(3600);
+
+pub struct Health {
+    started:  Instant,
+    attempts: Mutex<VecDeque<Attempt>>,
+}
+
+struct Attempt {
+    at:       Instant,
+    ok:       bool,
+    wall_sec: f64,
+}
+
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct StatusSummary {
+    pub status:               &'static str,
+    pub uptime_sec:           u64,
+    pub proofs_1h:            usize,
+    pub success_rate_1h:      Option<f64>,
+    pub avg_prove_latency_1h: Option<f64>,
+}
+
+impl Health {
+    pub fn new() -> Self {
+        Health { started: Instant::now(), attempts: Mutex::new(VecDeque::new()) }
+    }
+
+    /// One proving attempt finished after `wall_sec`.
+    pub fn record(&self, ok: bool, wall_sec: f64) {
+        let now = Instant::now();
+        let mut a = self.attempts.lock().unwrap();
+        prune(&mut a, now);
+        a.push_back(Attempt { at: now, ok, wall_sec });
+    }
+
+    pub fn summary(&self) -> StatusSummary {
+        let mut a = self.attempts.lock().unwrap();
+        prune(&mut a, Instant::now());
+        let ok: Vec<f64> = a.iter().filter(|x| x.ok).map(|x| x.wall_sec).collect();
+        let success = (!a.is_empty()).then(|| ok.len() as f64 / a.len() as f64);
+        StatusSummary {
+            status: match success {
+                Some(r) if r < 0.5  => "major_outage",
+                Some(r) if r < 0.95 => "degraded",
+                _                   => "operational",
+            },
+            uptime_sec: self.started.elapsed().as_secs(),
+            proofs_1h: a.len(),
+            success_rate_1h: success,
+            avg_prove_latency_1h: (!ok.is_empty()).then(|| ok.iter().sum::<f64>() / ok.len() as f64),
+        }
+    }
+}
+
+/// Warm-up state behind `GET /readyz`.
+pub struct Readiness {
+    steps:  Vec<usize>,
+    /// Guest validation outcome; `None` while it runs.
+    guests: Mutex<Option<Result<(), String>>>,
+}
+
+#[derive(Serialize)]
+pub struct ReadyReport {
+    pub ready:  bool,
+    /// Warm-up step sizes and whether their parameters are in memory.
+    pub params: BTreeMap<usize, bool>,
+    /// `pending`, `ok`, or why a guest was rejected.
+    pub guests: String,
+}
+
+impl Readiness {
+    pub fn new(steps: Vec<usize>) -> Self {
+        Readiness { steps, guests: Mutex::new(None) }
+    }
+
+    pub fn set_guests(&self, outcome: anyhow::Result<()>) {
+        *self.guests.lock().unwrap() = Some(outcome.map_err(|e| format!("{e:#}")));
+    }
+
+    pub fn report(&self, params: &ParamCache) -> ReadyReport {
+        let params: BTreeMap<_, _> = self.steps.iter().map(|&s| (s, params.is_ready(s))).collect();
+        let (guests_ok, guests) = match &*self.guests.lock().unwrap() {
+            None         => (false, "pending".to_string()),
+            Some(Ok(())) => (true, "ok".to_string()),
+            Some(Err(e)) => (false, e.clone()),
+        };
+        ReadyReport { ready: guests_ok && params.values().all(|r| *r), params, guests }
+    }
+}
+
+fn prune(a: &mut VecDeque<Attempt>, now: Instant) {
+    while a.front().map_or(false, |x| now.duration_since(x.at) > WINDOW) {
+        a.pop_front();
+    }
+}
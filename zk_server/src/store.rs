@@ -0,0 +1,397 @@
+//! Issued proofs, their revocation state, and spent nullifiers.
+//! Enabled by `ZK_STORE_DIR`; each proof is one JSON file under `proofs/`.  Revocations and
+//! nullifiers — the keys checked on every verification — live in a [`ShardedKeys`] set.
+//!
+//! Both key sets are fronted by Bloom filters (`bloom.rs`), so lookups of keys that were never
+//! written don't touch the shards.
+//!
+//! A nullifier is `keccak256("zkkyc-nullifier" || proof_id || 0x00 || scope)`: spending it lets a
+//! relying party (the scope) accept a proof once, and a second presentation is detected in O(1).
+//!
+//! Each proof is also indexed by the SHA-256 of its instance under `instances/`, so a proof posted
+//! back by a relying party can be matched to the inputs this server proved it over
+//! ([`ProofStore::proof_by_instance`]).  The SNARK instance doesn't expose those inputs itself.
+//!
+//! Writes made here are handed to the [`Replicator`] (if configured);
+//! writes arriving from another region go through [`ProofStore::apply`], which resolves
+//! conflicts:
+//!
+//! * proofs — the copy with the later `updated_at` wins (ties: keep local);
+//! * revocations — revoked always beats not-revoked, and the earliest `revoked_at` is kept, so a
+//!   revocation can never be undone by replication;
+//! * nullifiers — spent beats unspent; the first spend is kept.
+
+use anyhow::{bail, Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use kyc_core::{envelope, HashScheme};
+
+use crate::{bloom::LiveBloom, consent::ConsentRecord, replication::Replicator, shard::ShardedKeys, siwe::OwnershipRecord};
+
+const REVOCATIONS: &str = "revocations";
+const NULLIFIERS:  &str = "nullifiers";
+const SUBJECTS:    &str = "subjects";
+const INSTANCES:   &str = "instances";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StoredProof {
+    pub id:              String,
+    pub tenant:          String,
+    pub circuit:         String,
+    pub circuit_version: String,
+    pub step:            usize,
+    pub public_inputs:   Vec<String>,
+    /// Hex bincode of the SNARK.
+    pub proof:           String,
+    /// Hex bincode of the instance it verifies against.
+    pub instance:        String,
+    /// Unix seconds.
+    pub created_at:      u64,
+    pub updated_at:      u64,
+    /// Tenants other than `tenant` (the owner) granted read access.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shared_with:     Vec<String>,
+    /// Subject consent recorded at issuance.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consent:         Option<ConsentRecord>,
+    /// Wallet-ownership (SIWE) check made before proving.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ownership:       Option<OwnershipRecord>,
+    /// Hash scheme of the wallet commitment limbs; `None` on proofs stored before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commitment_scheme: Option<HashScheme>,
+    /// Policy the proof was issued under, if any; a renewal is held to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy:          Option<String>,
+    /// Trusted issuer that backed the attestation (`issuers.rs`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer:          Option<String>,
+    /// Unix seconds past which the proof no longer verifies as valid (`ZK_PROOF_TTL_DAYS`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at:      Option<u64>,
+    /// Expiry reminder thresholds (days) already sent.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reminded:        Vec<u64>,
+    /// Proof issued through this proof's renewal link.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub renewed_by:      Option<String>,
+}
+
+impl StoredProof {
+    /// Whether `tenant` may list and download this proof.
+    pub fn readable_by(&self, tenant: &str) -> bool {
+        self.tenant == tenant || self.shared_with.iter().any(|t| t == tenant)
+    }
+
+    pub fn expired(&self, now: u64) -> bool {
+        self.expires_at.map_or(false, |e| now >= e)
+    }
+}
+
+/// Subject behind an issued proof, kept so it can be re-screened.  Local to this store: never
+/// replicated or served.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ScreenedSubject {
+    pub proof_id:    String,
+    pub tenant:      String,
+    /// Canonical identifier, as screened at issuance.
+    pub wallet:      String,
+    /// Unix seconds of the last screening.
+    pub screened_at: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Revocation {
+    pub proof_id:   String,
+    pub reason:     String,
+    pub revoked_at: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Spend {
+    pub nullifier: String,
+    pub proof_id:  String,
+    pub scope:     String,
+    pub spent_at:  u64,
+}
+
+/// Entry of the instance index.
+#[derive(Deserialize, Serialize)]
+struct InstanceRef {
+    proof_id: String,
+}
+
+/// One replicated write.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    Proof(StoredProof),
+    Revocation(Revocation),
+    Nullifier(Spend),
+}
+
+pub struct ProofStore {
+    dir:        PathBuf,
+    keys:       ShardedKeys,
+    revoked:    LiveBloom,
+    spent:      LiveBloom,
+    replicator: Option<Replicator>,
+}
+
+pub fn nullifier(proof_id: &str, scope: &str) -> String {
+    let mut pre = b"zkkyc-nullifier".to_vec();
+    pre.extend_from_slice(proof_id.as_bytes());
+    pre.push(0);
+    pre.extend_from_slice(scope.as_bytes());
+    hex::encode(HashScheme::Keccak256.digest(&pre).expect("keccak accepts any input"))
+}
+
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+impl ProofStore {
+    /// Build from `ZK_STORE_DIR` (and `ZK_STORE_SHARDS`); `None` disables storage (proofs are only returned).
+    pub fn from_env(replicator: Option<Replicator>) -> Result<Option<Self>> {
+        let Ok(dir) = std::env::var("ZK_STORE_DIR") else { return Ok(None) };
+        let dir = PathBuf::from(dir);
+        for kind in ["proofs", SUBJECTS, INSTANCES] {
+            std::fs::create_dir_all(dir.join(kind))
+                .with_context(|| format!("creating {}", dir.join(kind).display()))?;
+        }
+        let shards: Vec<PathBuf> = match std::env::var("ZK_STORE_SHARDS") {
+            Ok(list) => list.split(',').map(str::trim).filter(|s| !s.is_empty()).map(PathBuf::from).collect(),
+            Err(_)   => vec![dir.join("keys")],
+        };
+        let keys = ShardedKeys::open(shards, &[REVOCATIONS, NULLIFIERS])?;
+        let capacity = std::env::var("ZK_BLOOM_CAPACITY").ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1_000_000);
+        Ok(Some(Self {
+            dir, keys,
+            revoked: LiveBloom::new(capacity),
+            spent:   LiveBloom::new(capacity),
+            replicator,
+        }))
+    }
+
+    /// Rebuild both Bloom filters from the shards; returns (revocations, nullifiers) seen.
+    /// Must run once after [`ProofStore::from_env`] — until then every lookup misses.
+    pub fn rebuild_filters(&self) -> Result<(usize, usize)> {
+        let r = self.revoked.rebuild(|f| self.keys.for_each_key(REVOCATIONS, f))?;
+        let n = self.spent.rebuild(|f| self.keys.for_each_key(NULLIFIERS, f))?;
+        Ok((r, n))
+    }
+
+    fn check_id(id: &str) -> Result<&str> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            bail!("invalid id {id:?}");
+        }
+        Ok(id)
+    }
+
+    fn path(&self, kind: &str, id: &str) -> Result<PathBuf> {
+        Ok(self.dir.join(kind).join(format!("{}.json", Self::check_id(id)?)))
+    }
+
+    pub fn put_proof(&self, p: StoredProof) -> Result<()> {
+        self.write_proof(&p)?;
+        self.replicate(Change::Proof(p));
+        Ok(())
+    }
+
+    fn write_proof(&self, p: &StoredProof) -> Result<()> {
+        write_json(&self.path("proofs", &p.id)?, p)?;
+        let instance = hex::decode(&p.instance).context("stored instance is not hex")?;
+        write_json(&self.path(INSTANCES, &instance_key(&instance))?, &InstanceRef { proof_id: p.id.clone() })
+    }
+
+    pub fn get_proof(&self, id: &str) -> Result<Option<StoredProof>> {
+        read_json(&self.path("proofs", id)?)
+    }
+
+    /// The proof this server issued with `instance` (bincode), if any.
+    pub fn proof_by_instance(&self, instance: &[u8]) -> Result<Option<StoredProof>> {
+        let Some(r) = read_json::<InstanceRef>(&self.path(INSTANCES, &instance_key(instance))?)? else {
+            return Ok(None);
+        };
+        Ok(self.get_proof(&r.proof_id)?.filter(|p| p.instance == hex::encode(instance)))
+    }
+
+    /// Proofs `tenant` may read (owned or shared with it), oldest first, without proof bytes.
+    pub fn list_proofs(&self, tenant: &str) -> Result<Vec<StoredProof>> {
+        self.scan(|p| p.readable_by(tenant))
+    }
+
+    /// Every stored proof matching `keep`, oldest first, without proof bytes.
+    pub fn scan(&self, keep: impl Fn(&StoredProof) -> bool) -> Result<Vec<StoredProof>> {
+        let mut out = Vec::new();
+        for entry in std::fs::read_dir(self.dir.join("proofs"))? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") { continue; }
+            if let Some(p) = read_json::<StoredProof>(&path)?.filter(|p| keep(p)) {
+                out.push(StoredProof { proof: String::new(), instance: String::new(), ..p });
+            }
+        }
+        out.sort_by_key(|p| p.created_at);
+        Ok(out)
+    }
+
+    pub fn put_subject(&self, s: &ScreenedSubject) -> Result<()> {
+        write_json(&self.path(SUBJECTS, &s.proof_id)?, s)
+    }
+
+    /// Stop re-screening `proof_id`'s subject.
+    pub fn drop_subject(&self, proof_id: &str) -> Result<()> {
+        match std::fs::remove_file(self.path(SUBJECTS, proof_id)?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Subjects due for re-screening, least recently screened first.
+    pub fn subjects(&self) -> Result<Vec<ScreenedSubject>> {
+        let mut out = Vec::new();
+        for entry in std::fs::read_dir(self.dir.join(SUBJECTS))? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") { continue; }
+            out.extend(read_json::<ScreenedSubject>(&path)?);
+        }
+        out.sort_by_key(|s| s.screened_at);
+        Ok(out)
+    }
+
+    /// Grant (`grant = true`) or withdraw `grantee`'s read access to `id`; only the owner may.
+    /// `None` when the proof doesn't exist or isn't `owner`'s.
+    pub fn share(&self, id: &str, owner: &str, grantee: &str, grant: bool) -> Result<Option<StoredProof>> {
+        let Some(mut p) = self.get_proof(id)?.filter(|p| p.tenant == owner) else { return Ok(None) };
+        let had = p.shared_with.iter().any(|t| t == grantee);
+        if grant == had || grantee == owner {
+            return Ok(Some(p));
+        }
+        if grant {
+            p.shared_with.push(grantee.to_string());
+        } else {
+            p.shared_with.retain(|t| t != grantee);
+        }
+        p.updated_at = now();
+        self.put_proof(p.clone())?;
+        Ok(Some(p))
+    }
+
+    /// Apply `f` to stored proof `id` and write it back (replicated, as a newer copy).
+    pub fn update(&self, id: &str, f: impl FnOnce(&mut StoredProof)) -> Result<()> {
+        let Some(mut p) = self.get_proof(id)? else { bail!("unknown proof {id}") };
+        f(&mut p);
+        p.updated_at = now();
+        self.put_proof(p)
+    }
+
+    /// Revoke `proof_id`; revoking twice keeps the first revocation.
+    pub fn revoke(&self, proof_id: &str, reason: &str) -> Result<Revocation> {
+        if let Some(r) = self.revocation(proof_id)? {
+            return Ok(r);
+        }
+        if self.get_proof(proof_id)?.is_none() {
+            bail!("unknown proof {proof_id}");
+        }
+        let r = Revocation { proof_id: proof_id.to_string(), reason: reason.to_string(), revoked_at: now() };
+        self.keys.put(REVOCATIONS, proof_id, &r)?;
+        self.revoked.insert(proof_id);
+        self.replicate(Change::Revocation(r.clone()));
+        Ok(r)
+    }
+
+    pub fn revocation(&self, proof_id: &str) -> Result<Option<Revocation>> {
+        if !self.revoked.may_contain(Self::check_id(proof_id)?) {
+            return Ok(None);
+        }
+        self.keys.get(REVOCATIONS, proof_id)
+    }
+
+    /// Spend `proof_id`'s nullifier for `scope`.  Returns the spend record and whether this call
+    /// spent it (`false`: it was already spent — a replayed presentation).
+    pub fn spend(&self, proof_id: &str, scope: &str) -> Result<(Spend, bool)> {
+        if self.get_proof(proof_id)?.is_none() {
+            bail!("unknown proof {proof_id}");
+        }
+        let n = nullifier(proof_id, scope);
+        let s = Spend { nullifier: n.clone(), proof_id: proof_id.to_string(), scope: scope.to_string(), spent_at: now() };
+        if self.keys.insert_new(NULLIFIERS, &n, &s)? {
+            self.spent.insert(&n);
+            self.replicate(Change::Nullifier(s.clone()));
+            return Ok((s, true));
+        }
+        let prior = self.keys.get(NULLIFIERS, &n)?.unwrap_or(s);
+        Ok((prior, false))
+    }
+
+    pub fn is_spent(&self, proof_id: &str, scope: &str) -> Result<bool> {
+        let n = nullifier(proof_id, scope);
+        if !self.spent.may_contain(&n) {
+            return Ok(false);
+        }
+        Ok(self.keys.get::<Spend>(NULLIFIERS, &n)?.is_some())
+    }
+
+    /// Merge a write replicated from another region (not re-replicated).
+    pub fn apply(&self, change: Change) -> Result<bool> {
+        match change {
+            Change::Proof(p) => {
+                let newer = self.get_proof(&p.id)?.map_or(true, |cur| p.updated_at > cur.updated_at);
+                if newer {
+                    self.write_proof(&p)?;
+                }
+                Ok(newer)
+            }
+            Change::Revocation(r) => {
+                let earlier = self.revocation(&r.proof_id)?.map_or(true, |cur| r.revoked_at < cur.revoked_at);
+                if earlier {
+                    self.keys.put(REVOCATIONS, Self::check_id(&r.proof_id)?, &r)?;
+                    self.revoked.insert(&r.proof_id);
+                }
+                Ok(earlier)
+            }
+            Change::Nullifier(n) => {
+                if n.nullifier.len() != 64 || !n.nullifier.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    bail!("invalid nullifier {:?}", n.nullifier);
+                }
+                self.spent.insert(&n.nullifier);
+                self.keys.insert_new(NULLIFIERS, &n.nullifier, &n)
+            }
+        }
+    }
+
+    fn replicate(&self, change: Change) {
+        if let Some(r) = &self.replicator {
+            r.push(change);
+        }
+    }
+}
+
+/// Name of `instance`'s entry in the instance index.
+fn instance_key(instance: &[u8]) -> String {
+    hex::encode(envelope::digest(instance))
+}
+
+/// Write via a temp file + rename so readers never see a partial record.
+pub fn write_json<T: Serialize>(path: &Path, v: &T) -> Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec(v)?)
+        .with_context(|| format!("writing {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("renaming to {}", path.display()))?;
+    Ok(())
+}
+
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    match std::fs::read(path) {
+        Ok(raw) => Ok(Some(serde_json::from_slice(&raw)
+            .with_context(|| format!("parsing {}", path.display()))?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("reading {}", path.display())),
+    }
+}
@@ -0,0 +1,132 @@
+//! Zero-downtime guest swaps.
+//! `POST /admin/rollout/swap { version, wasm, steps? }` prepares a new guest build while the
+//! current one keeps serving: the module is validated and the Nova parameters for `steps`
+//! (default `ZK_PARAMS_PRELOAD`) are loaded or built in the background.  Once both succeed every
+//! new prove request is switched to the build in one step (it becomes blue; any green split is
+//! closed).  Proofs already running finish on the build they started with, and the replaced builds
+//! stay accepted for verification for `ZK_SWAP_OVERLAP_SECS` (default one day).  `GET /vks` lists
+//! every accepted build with its verifying-key digests, so verifiers can follow the overlap.
+//! A failed preparation leaves the rollout untouched; `GET /admin/rollout/swap` reports progress.
+
+use anyhow::{anyhow, bail, Context, Result};
+use axum::{extract::State, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::{Arc, Mutex}};
+
+use crate::{prover::{self, ParamCache}, rollout::GuestVersion, store, AppState};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Preparing,
+    Swapped,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SwapStatus {
+    pub version:  String,
+    pub phase:    Phase,
+    pub started:  u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error:    Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SwapRequest {
+    #[serde(flatten)]
+    pub guest: GuestVersion,
+    /// Step sizes to have parameters for before switching; empty means `ZK_PARAMS_PRELOAD`.
+    #[serde(default)]
+    pub steps: Vec<usize>,
+}
+
+pub struct Swaps {
+    overlap_secs: u64,
+    /// The latest swap, running or finished.
+    last:         Mutex<Option<SwapStatus>>,
+}
+
+impl Swaps {
+    pub fn from_env() -> Result<Self> {
+        let overlap_secs = match std::env::var("ZK_SWAP_OVERLAP_SECS") {
+            Ok(v) if !v.is_empty() => v.parse().context("ZK_SWAP_OVERLAP_SECS must be a number")?,
+            _ => 86_400,
+        };
+        Ok(Swaps { overlap_secs, last: Mutex::new(None) })
+    }
+
+    pub fn status(&self) -> Option<SwapStatus> {
+        self.last.lock().unwrap().clone()
+    }
+
+    fn finish(&self, outcome: &Result<()>) {
+        if let Some(s) = self.last.lock().unwrap().as_mut() {
+            s.phase = if outcome.is_ok() { Phase::Swapped } else { Phase::Failed };
+            s.finished = Some(store::now());
+            s.error = outcome.as_ref().err().map(|e| format!("{e:#}"));
+        }
+    }
+}
+
+/// Begin preparing `req.guest` in the background.  Refused while another swap is preparing or
+/// when the build is already blue.
+pub fn start(state: Arc<AppState>, req: SwapRequest) -> Result<SwapStatus> {
+    let status = {
+        let mut last = state.swaps.last.lock().unwrap();
+        if last.as_ref().map_or(false, |s| s.phase == Phase::Preparing) {
+            bail!("a swap is already being prepared");
+        }
+        if state.rollout.config().blue.version == req.guest.version {
+            bail!("{} is already serving", req.guest.version);
+        }
+        let status = SwapStatus {
+            version:  req.guest.version.clone(),
+            phase:    Phase::Preparing,
+            started:  store::now(),
+            finished: None,
+            error:    None,
+        };
+        *last = Some(status.clone());
+        status
+    };
+    tracing::warn!("preparing swap to guest {} ({})", req.guest.version, req.guest.wasm.display());
+    tokio::spawn(prepare(state, req));
+    Ok(status)
+}
+
+async fn prepare(state: Arc<AppState>, req: SwapRequest) {
+    let steps = if req.steps.is_empty() { ParamCache::preload_steps() } else { req.steps };
+    let (guest, params) = (req.guest.clone(), state.params.clone());
+    let ready = tokio::task::spawn_blocking(move || prover::isolate(|| {
+        guest.validate()?;
+        for step in steps {
+            params.get(step);
+        }
+        Ok(())
+    }))
+    .await
+    .unwrap_or_else(|e| Err(anyhow!("swap task failed: {e}")));
+    let outcome = ready.and_then(|()| state.rollout.promote(req.guest.clone(), state.swaps.overlap_secs).map(drop));
+    match &outcome {
+        Ok(())  => tracing::warn!("swapped to guest {}: {:?}", req.guest.version, state.rollout.config()),
+        Err(e)  => tracing::error!("swap to guest {} failed: {e:#}", req.guest.version),
+    }
+    state.swaps.finish(&outcome);
+}
+
+/// `GET /vks`: accepted guest builds, with the digest of the public parameters
+/// ([`kyc_core::engine::pp_digest`]) for each preloaded step size that has finished loading.
+pub async fn handle_vks(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let vk_digests: BTreeMap<String, String> = ParamCache::preload_steps().into_iter()
+        .filter_map(|step| Some((step.to_string(), hex::encode(state.params.loaded_digest(step)?))))
+        .collect();
+    let builds: Vec<_> = state.rollout.accepted().into_iter().map(|(version, until)| serde_json::json!({
+        "circuit_version": version,
+        "accepted_until":  until,
+        "vk_digests":      vk_digests,
+    })).collect();
+    Json(builds)
+}
@@ -0,0 +1,229 @@
+//! Step-size sweeps for unfamiliar guests.
+//! `POST /admin/step-sweep { wasm?, invoke?, args?, start?, min_step?, max_step?, budget_secs? }`
+//! proves one guest call at successive step sizes, each in its own worker subprocess, and reports
+//! the step with the shortest total (setup + prove + verify).  The sweep starts at `start`
+//! (default `min_step`, 1) and doubles the step, then halves below `start`, moving on in a
+//! direction only while no trial has finished yet or the last one was the fastest so far.
+//! Trials are aborted early: a worker is killed once it has run for `budget_secs` (default 300)
+//! or for as long as the best finished trial, as it can no longer win.  Without `invoke` the
+//! serving guest is swept with a sample approved subject.  The outcome is advisory; adopt it
+//! through `default_step`.  `GET /admin/step-sweep` reports the trials.
+
+use anyhow::{bail, Result};
+use kyc_core::{engine, HashScheme, IdentifierType};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{store, worker::{Priority, WorkerKilled}, AppState};
+
+/// Subject proven when the request names no guest call.
+const SAMPLE_SUBJECT: &str = "0x742d35cc6634c0532925a3b844bc454e4438f44e";
+
+fn default_min() -> usize { 1 }
+fn default_max() -> usize { 64 }
+fn default_budget() -> u64 { 300 }
+
+#[derive(Deserialize)]
+pub struct SweepRequest {
+    /// Guest to sweep; the serving (blue) build when absent.
+    #[serde(default)]
+    pub wasm:        Option<PathBuf>,
+    /// Export to call; the KYC check of a sample subject when absent.
+    #[serde(default)]
+    pub invoke:      Option<String>,
+    #[serde(default)]
+    pub args:        Vec<String>,
+    #[serde(default)]
+    pub start:       Option<usize>,
+    #[serde(default = "default_min")]
+    pub min_step:    usize,
+    #[serde(default = "default_max")]
+    pub max_step:    usize,
+    #[serde(default = "default_budget")]
+    pub budget_secs: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "snake_case", tag = "outcome")]
+pub enum TrialOutcome {
+    Proved { setup_sec: f64, prove_sec: f64, verify_sec: f64, total_sec: f64 },
+    /// Killed at `after_sec`, past the budget or the best trial.
+    Aborted { after_sec: f64 },
+    Failed { error: String },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Trial {
+    pub step: usize,
+    #[serde(flatten)]
+    pub outcome: TrialOutcome,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SweepStatus {
+    pub wasm:      PathBuf,
+    pub invoke:    String,
+    pub phase:     Phase,
+    pub started:   u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished:  Option<u64>,
+    pub trials:    Vec<Trial>,
+    /// Fastest finished trial so far.
+    pub best_step: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error:     Option<String>,
+}
+
+/// The latest sweep, running or finished.
+#[derive(Default)]
+pub struct Sweeps {
+    last: Mutex<Option<SweepStatus>>,
+}
+
+impl Sweeps {
+    pub fn status(&self) -> Option<SweepStatus> {
+        self.last.lock().unwrap().clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut SweepStatus)) {
+        if let Some(s) = self.last.lock().unwrap().as_mut() {
+            f(s);
+        }
+    }
+}
+
+struct Job {
+    wasm:   PathBuf,
+    invoke: String,
+    args:   Vec<String>,
+    start:  usize,
+    min:    usize,
+    max:    usize,
+    budget: Duration,
+}
+
+/// Begin sweeping in the background.  Refused on a read replica and while another sweep runs.
+pub fn start(state: Arc<AppState>, req: SweepRequest) -> Result<SweepStatus> {
+    if state.read_only {
+        bail!("read replica: sweep on a proving replica");
+    }
+    let start = req.start.unwrap_or(req.min_step);
+    if req.min_step == 0 || req.min_step > req.max_step || !(req.min_step..=req.max_step).contains(&start) {
+        bail!("need 1 <= min_step <= start <= max_step");
+    }
+    if req.budget_secs == 0 {
+        bail!("budget_secs must be at least 1");
+    }
+    let (invoke, args) = match req.invoke {
+        Some(invoke) => (invoke, req.args),
+        None => {
+            let call = engine::kyc_call(HashScheme::default(), IdentifierType::EvmAddress, SAMPLE_SUBJECT, 1, 1, false)?;
+            (call.invoke.to_string(), call.args)
+        }
+    };
+    let job = Job {
+        wasm:   req.wasm.unwrap_or_else(|| state.rollout.config().blue.wasm),
+        invoke,
+        args,
+        start,
+        min:    req.min_step,
+        max:    req.max_step,
+        budget: Duration::from_secs(req.budget_secs),
+    };
+    let status = {
+        let mut last = state.sweeps.last.lock().unwrap();
+        if last.as_ref().map_or(false, |s| s.phase == Phase::Running) {
+            bail!("a sweep is already running");
+        }
+        let status = SweepStatus {
+            wasm:      job.wasm.clone(),
+            invoke:    job.invoke.clone(),
+            phase:     Phase::Running,
+            started:   store::now(),
+            finished:  None,
+            trials:    Vec::new(),
+            best_step: None,
+            error:     None,
+        };
+        *last = Some(status.clone());
+        status
+    };
+    tracing::warn!("sweeping step sizes {}..={} of {} from {start}", job.min, job.max, job.wasm.display());
+    tokio::spawn(async move {
+        let outcome = sweep(&state, &job).await;
+        if let Err(e) = &outcome {
+            tracing::error!("step sweep failed: {e:#}");
+        }
+        state.sweeps.update(|s| {
+            s.phase = if outcome.is_ok() { Phase::Done } else { Phase::Failed };
+            s.finished = Some(store::now());
+            s.error = outcome.err().map(|e| format!("{e:#}"));
+        });
+    });
+    Ok(status)
+}
+
+async fn sweep(state: &Arc<AppState>, job: &Job) -> Result<()> {
+    let mut best: Option<(usize, f64)> = None;
+    for up in [true, false] {
+        let mut step = if up { Some(job.start) } else { (job.start > 1).then_some(job.start / 2) };
+        while let Some(s) = step.filter(|s| (job.min..=job.max).contains(s)) {
+            let limit = best.map_or(job.budget, |(_, t)| job.budget.min(Duration::from_secs_f64(t)));
+            let outcome = trial(state, job, s, limit).await?;
+            tracing::info!(step = s, "step sweep trial: {outcome:?}");
+            let improved = match &outcome {
+                TrialOutcome::Proved { total_sec, .. } if best.map_or(true, |(_, t)| *total_sec < t) => {
+                    best = Some((s, *total_sec));
+                    true
+                }
+                _ => false,
+            };
+            let failed = matches!(outcome, TrialOutcome::Failed { .. });
+            state.sweeps.update(|st| {
+                st.trials.push(Trial { step: s, outcome });
+                st.best_step = best.map(|(s, _)| s);
+            });
+            // Past the optimum, or the guest cannot be proven at this size.
+            if failed || (best.is_some() && !improved) {
+                break;
+            }
+            step = if up { s.checked_mul(2) } else { (s > 1).then_some(s / 2) };
+        }
+    }
+    if best.is_none() {
+        bail!("no step size in {}..={} proved within {}s", job.min, job.max, job.budget.as_secs());
+    }
+    Ok(())
+}
+
+/// Prove `job` at `step` in a worker killed after `limit`.
+async fn trial(state: &AppState, job: &Job, step: usize, limit: Duration) -> Result<TrialOutcome> {
+    let isolation = state.prover.subprocess_with_timeout(limit);
+    let (params, wasm, invoke, args) = (state.params.clone(), job.wasm.clone(), job.invoke.clone(), job.args.clone());
+    let t0 = Instant::now();
+    let run = state.pool.run(move || isolation.run(&params, &wasm, &invoke, args, step, Priority::Background)).await?;
+    Ok(match run {
+        Ok(r) => TrialOutcome::Proved {
+            setup_sec:  r.setup_sec,
+            prove_sec:  r.prove_sec,
+            verify_sec: r.verify_sec,
+            total_sec:  r.setup_sec + r.prove_sec + r.verify_sec,
+        },
+        Err(e) if e.downcast_ref::<WorkerKilled>().map_or(false, |k| k.timed_out) => {
+            TrialOutcome::Aborted { after_sec: t0.elapsed().as_secs_f64() }
+        }
+        Err(e) => TrialOutcome::Failed { error: format!("{e:#}") },
+    })
+}
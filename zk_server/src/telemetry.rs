@@ -0,0 +1,110 @@
+//! Logging/tracing setup with per-endpoint and per-tenant sampling.
+//!
+//! INFO and above go through the usual `RUST_LOG` filter, which can be swapped at runtime via
+//! [`LogHandle`] (`PUT /admin/loglevel`).  DEBUG/TRACE output — including
+//! zkEngine's folding-step spans — is only emitted inside a request span that was sampled.
+//! Every event is scrubbed by [`crate::redact`] on its way out.
+//! Rates are read from a TOML file (`ZK_TELEMETRY_FILE`, default `telemetry.toml`):
+//!
+//! ```toml
+//! default_rate = 0.0
+//!
+//! [endpoints]
+//! "/prove" = 0.01
+//!
+//! [tenants]
+//! acme = 1.0          # tenant rates take precedence over endpoint rates
+//! ```
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{collections::HashMap, path::Path, sync::Mutex};
+use tracing::{Level, Span};
+use tracing_subscriber::{
+    filter::{dynamic_filter_fn, EnvFilter, FilterExt},
+    layer::SubscriberExt,
+    reload,
+    util::SubscriberInitExt,
+    Layer, Registry,
+};
+
+/// Name of the span that switches on debug-level output for everything beneath it.
+pub const SAMPLED_SPAN: &str = "sampled_request";
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct SamplingConfig {
+    #[serde(default)]
+    pub default_rate: f64,
+    #[serde(default)]
+    pub endpoints:    HashMap<String, f64>,
+    #[serde(default)]
+    pub tenants:      HashMap<String, f64>,
+}
+
+impl SamplingConfig {
+    /// Load from `path`; a missing file samples nothing.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        toml::from_str(&raw).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Effective rate: tenant, else endpoint, else default.
+    pub fn rate(&self, endpoint: &str, tenant: &str) -> f64 {
+        self.tenants.get(tenant)
+            .or_else(|| self.endpoints.get(endpoint))
+            .copied()
+            .unwrap_or(self.default_rate)
+    }
+
+    /// Span to run a request in; debug internals are recorded only if it is sampled.
+    pub fn request_span(&self, endpoint: &str, tenant: &str) -> Span {
+        let rate = self.rate(endpoint, tenant).clamp(0.0, 1.0);
+        if rate > 0.0 && rand::random::<f64>() < rate {
+            tracing::info_span!(SAMPLED_SPAN, endpoint, tenant)
+        } else {
+            tracing::info_span!("request", endpoint, tenant)
+        }
+    }
+}
+
+/// Runtime handle on the base `RUST_LOG`-style filter.
+pub struct LogHandle {
+    handle:  reload::Handle<EnvFilter, Registry>,
+    current: Mutex<String>,
+}
+
+impl LogHandle {
+    /// Directive string currently in force.
+    pub fn current(&self) -> String {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Replace the filter with `directives` (e.g. `info,zk_engine=debug`).
+    pub fn set(&self, directives: &str) -> Result<()> {
+        let filter = EnvFilter::try_new(directives)
+            .with_context(|| format!("bad filter directives: {directives}"))?;
+        self.handle.reload(filter).context("reloading log filter")?;
+        *self.current.lock().unwrap() = directives.to_string();
+        Ok(())
+    }
+}
+
+/// Install the global subscriber.
+pub fn init() -> LogHandle {
+    let directives = std::env::var("RUST_LOG").unwrap_or_else(|_| "info".into());
+    let base = EnvFilter::try_new(&directives).unwrap_or_else(|_| EnvFilter::new("info"));
+    let (base, handle) = reload::Layer::new(base);
+    let sampled = dynamic_filter_fn(|meta, cx| {
+        *meta.level() > Level::INFO
+            && cx.lookup_current()
+                .map_or(false, |s| s.scope().any(|s| s.name() == SAMPLED_SPAN))
+    });
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_writer(crate::redact::Stdout).with_filter(base.or(sampled)))
+        .init();
+    LogHandle { handle, current: Mutex::new(directives) }
+}
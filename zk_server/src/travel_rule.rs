@@ -0,0 +1,87 @@
+//! IVMS101 Travel Rule payload commitment.
+//! The payload itself never reaches the prover — only its Keccak-256 digest,
+//! split into 8 canonical limbs (`kyc_core::encoding`), is fed to the guest.
+
+use serde::{Deserialize, Serialize};
+use kyc_core::HashScheme;
+use serde_json::Value;
+
+/// Originator / beneficiary block of an IVMS101 message.
+/// Person and VASP objects are kept opaque; only their canonical bytes matter.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Ivms101Payload {
+    pub originator:  Value,
+    pub beneficiary: Value,
+    #[serde(rename = "originatingVASP", default, skip_serializing_if = "Option::is_none")]
+    pub originating_vasp: Option<Value>,
+    #[serde(rename = "beneficiaryVASP", default, skip_serializing_if = "Option::is_none")]
+    pub beneficiary_vasp: Option<Value>,
+}
+
+impl Ivms101Payload {
+    /// Canonical encoding: compact JSON with object keys sorted at every level.
+    /// Keys are sorted here rather than relying on `Map`'s iteration order, which
+    /// serde_json's `preserve_order` feature turns into insertion order.
+    pub fn canonical_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        write_canonical(&serde_json::to_value(self)?, &mut out)?;
+        Ok(out)
+    }
+
+    /// Keccak-256 of the canonical encoding.
+    pub fn commitment(&self) -> anyhow::Result<[u8; 32]> {
+        Ok(HashScheme::Keccak256.digest(&self.canonical_bytes()?)?)
+    }
+}
+
+fn write_canonical(v: &Value, out: &mut Vec<u8>) -> anyhow::Result<()> {
+    match v {
+        Value::Object(m) => {
+            let mut entries: Vec<_> = m.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            out.push(b'{');
+            for (i, (k, v)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                serde_json::to_writer(&mut *out, k)?;
+                out.push(b':');
+                write_canonical(v, out)?;
+            }
+            out.push(b'}');
+        }
+        Value::Array(a) => {
+            out.push(b'[');
+            for (i, v) in a.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_canonical(v, out)?;
+            }
+            out.push(b']');
+        }
+        scalar => serde_json::to_writer(&mut *out, scalar)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_sorted_whatever_the_input_order() {
+        let payload: Ivms101Payload = serde_json::from_str(
+            r#"{"beneficiary":{"z":1,"a":[{"y":true,"b":null}]},"originator":{"name":"Ann","id":"7"}}"#,
+        ).unwrap();
+        let bytes = payload.canonical_bytes().unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            r#"{"beneficiary":{"a":[{"b":null,"y":true}],"z":1},"originator":{"id":"7","name":"Ann"}}"#,
+        );
+        let sorted: Ivms101Payload = serde_json::from_str(
+            r#"{"originator":{"id":"7","name":"Ann"},"beneficiary":{"a":[{"b":null,"y":true}],"z":1}}"#,
+        ).unwrap();
+        assert_eq!(payload.commitment().unwrap(), sorted.commitment().unwrap());
+    }
+}
@@ -0,0 +1,264 @@
+//! Per-proof compute cost.
+//! Each proving job is metered for thread CPU time and peak resident memory, attributed to its
+//! tenant and circuit, and appended as one JSON line to `$ZK_USAGE_FILE` (default `usage.jsonl`).
+//! `GET /admin/usage` aggregates that log.  A proof is charged (usage and monthly quota) only once
+//! it is known to be issued, see [`charge`].
+//!
+//! Peak memory is the process RSS high-water mark above the job's starting RSS, sampled while the
+//! job runs; with several jobs in flight it over-attributes to each of them.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::OpenOptions,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{killswitch::{IssuanceHalted, KillSwitch}, quota::Quotas};
+
+/// How often RSS is sampled during a job.
+const SAMPLE_EVERY: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UsageRecord {
+    pub id:              String,
+    /// Unix seconds at completion.
+    pub at:              u64,
+    pub tenant:          String,
+    pub circuit:         String,
+    pub circuit_version: String,
+    pub step:            usize,
+    pub wall_sec:        f64,
+    pub cpu_sec:         f64,
+    pub peak_rss_mb:     f64,
+}
+
+/* ---------- metering ---------------------------------------------- */
+/// Cost of one job, see [`Meter`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Cost {
+    pub wall_sec:    f64,
+    pub cpu_sec:     f64,
+    pub peak_rss_mb: f64,
+}
+
+/// Started on the thread that runs the job; `finish` on the same thread.
+pub struct Meter {
+    started: Instant,
+    cpu0:    f64,
+    rss0:    f64,
+    peak:    Arc<Mutex<f64>>,
+    stop:    Arc<AtomicBool>,
+    sampler: Option<JoinHandle<()>>,
+}
+
+impl Meter {
+    pub fn start() -> Self {
+        let rss0 = rss_mb().unwrap_or(0.0);
+        let peak = Arc::new(Mutex::new(rss0));
+        let stop = Arc::new(AtomicBool::new(false));
+        let sampler = {
+            let (peak, stop) = (peak.clone(), stop.clone());
+            std::thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    if let Some(r) = rss_mb() {
+                        let mut p = peak.lock().unwrap();
+                        *p = p.max(r);
+                    }
+                    std::thread::sleep(SAMPLE_EVERY);
+                }
+            })
+        };
+        Meter { started: Instant::now(), cpu0: thread_cpu_sec(), rss0, peak, stop, sampler: Some(sampler) }
+    }
+
+    pub fn finish(mut self) -> Cost {
+        let cpu_sec = thread_cpu_sec() - self.cpu0;
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(h) = self.sampler.take() {
+            h.join().ok();
+        }
+        let sampled = *self.peak.lock().unwrap();
+        let peak = rss_mb().map_or(sampled, |r| r.max(sampled));
+        Cost {
+            wall_sec:    self.started.elapsed().as_secs_f64(),
+            cpu_sec,
+            peak_rss_mb: (peak - self.rss0).max(0.0),
+        }
+    }
+}
+
+/// CPU time consumed by the calling thread.
+#[cfg(target_os = "linux")]
+fn thread_cpu_sec() -> f64 {
+    let mut ru: libc::rusage = unsafe { core::mem::zeroed() };
+    unsafe { libc::getrusage(libc::RUSAGE_THREAD, &mut ru) };
+    let secs = |t: libc::timeval| t.tv_sec as f64 + t.tv_usec as f64 / 1e6;
+    secs(ru.ru_utime) + secs(ru.ru_stime)
+}
+#[cfg(not(target_os = "linux"))]
+fn thread_cpu_sec() -> f64 { 0.0 }
+
+fn rss_mb() -> Option<f64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status.lines()
+        .find_map(|l| l.strip_prefix("VmRSS:"))
+        .and_then(|v| v.split_whitespace().next())
+        .and_then(|v| v.parse::<f64>().ok())
+        .map(|kb| kb / 1024.0)
+}
+
+/* ---------- persistence ------------------------------------------- */
+pub struct UsageLog {
+    path: PathBuf,
+    file: Mutex<()>,
+}
+
+/// Totals for one (tenant, circuit).
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct UsageTotals {
+    pub proofs:     u64,
+    pub cpu_sec:    f64,
+    pub wall_sec:   f64,
+    pub max_rss_mb: f64,
+    /// Σ peak_rss_mb × wall_sec, for memory-time pricing.
+    pub rss_mb_sec: f64,
+}
+
+impl UsageLog {
+    pub fn from_env() -> Self {
+        let path = std::env::var("ZK_USAGE_FILE").unwrap_or_else(|_| "usage.jsonl".into());
+        Self::open(Path::new(&path))
+    }
+
+    pub fn open(path: &Path) -> Self {
+        UsageLog { path: path.to_path_buf(), file: Mutex::new(()) }
+    }
+
+    /// Append the cost of proof `id`.
+    pub fn record(&self, id: &str, tenant: &str, circuit: &str, circuit_version: &str, step: usize, cost: Cost)
+        -> Result<UsageRecord>
+    {
+        let rec = UsageRecord {
+            id:              id.to_string(),
+            at:              SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            tenant:          tenant.to_string(),
+            circuit:         circuit.to_string(),
+            circuit_version: circuit_version.to_string(),
+            step,
+            wall_sec:        cost.wall_sec,
+            cpu_sec:         cost.cpu_sec,
+            peak_rss_mb:     cost.peak_rss_mb,
+        };
+        let _guard = self.file.lock().unwrap();
+        let mut f = OpenOptions::new().create(true).append(true).open(&self.path)
+            .with_context(|| format!("opening {}", self.path.display()))?;
+        writeln!(f, "{}", serde_json::to_string(&rec)?)?;
+        Ok(rec)
+    }
+
+    /// Records in `[from, to)` (unix seconds), optionally for one tenant.
+    pub fn records(&self, from: u64, to: u64, tenant: Option<&str>) -> Result<Vec<UsageRecord>> {
+        let _guard = self.file.lock().unwrap();
+        let f = match std::fs::File::open(&self.path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("opening {}", self.path.display())),
+        };
+        let mut out = Vec::new();
+        for line in BufReader::new(f).lines() {
+            let line = line?;
+            if line.trim().is_empty() { continue; }
+            let rec: UsageRecord = serde_json::from_str(&line)?;
+            if rec.at >= from && rec.at < to && tenant.map_or(true, |t| t == rec.tenant) {
+                out.push(rec);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Totals per tenant, then per circuit.
+    pub fn totals(records: &[UsageRecord]) -> BTreeMap<String, BTreeMap<String, UsageTotals>> {
+        let mut out: BTreeMap<String, BTreeMap<String, UsageTotals>> = BTreeMap::new();
+        for r in records {
+            let t = out.entry(r.tenant.clone()).or_default().entry(r.circuit.clone()).or_default();
+            t.proofs     += 1;
+            t.cpu_sec    += r.cpu_sec;
+            t.wall_sec   += r.wall_sec;
+            t.max_rss_mb  = t.max_rss_mb.max(r.peak_rss_mb);
+            t.rss_mb_sec += r.peak_rss_mb * r.wall_sec;
+        }
+        out
+    }
+}
+
+/// A finished proof, as it is charged to its tenant.
+pub struct Charge<'a> {
+    pub proof_id:        &'a str,
+    pub tenant:          &'a str,
+    pub circuit:         &'a str,
+    pub circuit_version: &'a str,
+    pub step:            usize,
+    pub cost:            Cost,
+}
+
+/// Count a finished proof against its tenant's quota and log its usage, unless issuance was
+/// halted while it ran: the proof is then dropped, so nothing is charged.  `Ok(None)` if the usage
+/// line couldn't be written (logged; the quota still counts it).
+pub fn charge(kill_switch: &KillSwitch, quotas: &Quotas, usage: &UsageLog, c: Charge)
+    -> Result<Option<UsageRecord>, IssuanceHalted>
+{
+    kill_switch.check()?;
+    quotas.consume(c.tenant);
+    match usage.record(c.proof_id, c.tenant, c.circuit, c.circuit_version, c.step, c.cost) {
+        Ok(rec) => Ok(Some(rec)),
+        Err(e)  => {
+            tracing::error!("usage record lost: {e:#}");
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::killswitch::Halt;
+
+    fn temp(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("usage-{name}-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn halted_proof_is_not_charged() {
+        let (quota_file, usage_file) = (temp("quotas.toml"), temp("usage.jsonl"));
+        std::fs::write(&quota_file, "[tenants.acme]\nmonthly = 10\n").unwrap();
+        let usage = UsageLog::open(&usage_file);
+        let quotas = Quotas::load(&quota_file, &usage).unwrap();
+        let kill_switch = KillSwitch::open(temp("halt.json"), temp("audit.jsonl")).unwrap();
+        let proof = |id| Charge {
+            proof_id: id, tenant: "acme", circuit: "check_kyc_full", circuit_version: "v1", step: 8,
+            cost: Cost::default(),
+        };
+
+        charge(&kill_switch, &quotas, &usage, proof("a")).unwrap().unwrap();
+        assert_eq!(quotas.check("acme").unwrap().unwrap().used, 1);
+
+        // Raised while the next proof ran.
+        kill_switch.halt(Halt { reason: "drill".into(), actor: "ops".into() }).unwrap();
+        assert!(charge(&kill_switch, &quotas, &usage, proof("b")).is_err());
+        assert_eq!(quotas.check("acme").unwrap().unwrap().used, 1);
+        let logged = usage.records(0, u64::MAX, Some("acme")).unwrap();
+        assert_eq!(logged.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), ["a"]);
+        for f in [quota_file, usage_file] {
+            std::fs::remove_file(f).ok();
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! LRU cache of SNARK verification outcomes for `GET /proofs/:id/verify`.
+//! Relying parties tend to re-check the same envelope on every call; a full Nova verify (setup
+//! included) is seconds of CPU, so outcomes are kept for `ZK_VERIFY_CACHE_TTL_SECS` (default 300)
+//! in up to `ZK_VERIFY_CACHE_SIZE` entries (default 10000; 0 disables).
+//!
+//! Entries are keyed by SHA-256 of the proof and instance bytes together with the digest of the
+//! public parameters ([`crate::prover::ParamCache::digest`]), so different parameters never reuse
+//! an outcome.  Only the SNARK check is cached — revocation, spent and rollout state are
+//! still read on every request.
+
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+pub type Key = [u8; 32];
+
+/// Outcome of one verification: verify seconds, or the verifier's error.
+pub type Verdict = Result<f64, String>;
+
+pub struct VerifyCache {
+    capacity: usize,
+    ttl:      Duration,
+    inner:    Mutex<Lru>,
+}
+
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<Key, Entry>,
+    /// Last-use tick → key, oldest first.
+    order:   BTreeMap<u64, Key>,
+    tick:    u64,
+}
+
+struct Entry {
+    verdict: Verdict,
+    at:      Instant,
+    used:    u64,
+}
+
+pub fn key(proof: &[u8], instance: &[u8], vk_digest: &[u8; 32]) -> Key {
+    let mut h = Sha256::new();
+    h.update(vk_digest);
+    h.update((proof.len() as u64).to_be_bytes());
+    h.update(proof);
+    h.update(instance);
+    h.finalize().into()
+}
+
+impl VerifyCache {
+    pub fn from_env() -> Self {
+        let capacity = std::env::var("ZK_VERIFY_CACHE_SIZE").ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10_000);
+        let ttl = std::env::var("ZK_VERIFY_CACHE_TTL_SECS").ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300);
+        Self::new(capacity, Duration::from_secs(ttl))
+    }
+
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        VerifyCache { capacity, ttl, inner: Mutex::new(Lru::default()) }
+    }
+
+    /// The cached verdict for `key`, if present and younger than the TTL.
+    pub fn get(&self, key: &Key) -> Option<Verdict> {
+        let mut lru = self.inner.lock().unwrap();
+        let fresh = lru.entries.get(key).map(|e| e.at.elapsed() <= self.ttl)?;
+        if !fresh {
+            lru.remove(key);
+            return None;
+        }
+        lru.touch(key);
+        lru.entries.get(key).map(|e| e.verdict.clone())
+    }
+
+    pub fn insert(&self, key: Key, verdict: Verdict) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut lru = self.inner.lock().unwrap();
+        lru.remove(&key);
+        while lru.entries.len() >= self.capacity {
+            let Some((_, oldest)) = lru.order.pop_first() else { break };
+            lru.entries.remove(&oldest);
+        }
+        lru.tick += 1;
+        let used = lru.tick;
+        lru.order.insert(used, key);
+        lru.entries.insert(key, Entry { verdict, at: Instant::now(), used });
+    }
+}
+
+impl Lru {
+    fn touch(&mut self, key: &Key) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(e) = self.entries.get_mut(key) {
+            self.order.remove(&e.used);
+            e.used = tick;
+            self.order.insert(tick, *key);
+        }
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(e) = self.entries.remove(key) {
+            self.order.remove(&e.used);
+        }
+    }
+}
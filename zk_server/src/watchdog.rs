@@ -0,0 +1,141 @@
+//! Memory watchdog.
+//! With `ZK_MEMORY_CEILING_MB` set, the resident memory of the server plus its prover workers is
+//! sampled every `ZK_MEMORY_CHECK_MS` (default 500).  Once it reaches `ZK_MEMORY_ABORT_PERCENT`
+//! (default 90) of the ceiling, one running worker is killed per sample — background work
+//! (canary, pre-issuance, sweeps) before client requests, the largest first — and its job fails
+//! with [`ResourceExceeded`] (HTTP 503 `RESOURCE_EXCEEDED`) instead of the OOM killer taking the
+//! whole server.  While above the mark new proofs are refused the same way.  Only subprocess
+//! workers (`ZK_PROVER_MODE=subprocess`) can be aborted; inline proofs share the server's memory
+//! and are only refused.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{scaling, worker};
+
+/// Seconds a refused or aborted client is told to wait.
+const RETRY_AFTER: u64 = 30;
+
+/// Returned (inside `anyhow::Error`) when a proof was aborted or refused for memory.
+#[derive(Debug, Serialize)]
+pub struct ResourceExceeded {
+    pub reason:      String,
+    pub retry_after: u64,
+}
+
+impl ResourceExceeded {
+    pub fn aborted() -> Self {
+        ResourceExceeded {
+            reason:      "proof aborted by the memory watchdog (ZK_MEMORY_CEILING_MB)".into(),
+            retry_after: RETRY_AFTER,
+        }
+    }
+}
+
+impl std::fmt::Display for ResourceExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}; retry in {}s", self.reason, self.retry_after)
+    }
+}
+
+impl std::error::Error for ResourceExceeded {}
+
+#[derive(Serialize)]
+pub struct WatchdogReport {
+    pub ceiling_mb: f64,
+    pub mark_mb:    f64,
+    pub over:       bool,
+    pub aborted:    u64,
+}
+
+pub struct Watchdog {
+    ceiling_mb: f64,
+    mark_mb:    f64,
+    every:      Duration,
+    /// Memory was at or above the mark on the last sample.
+    over:       AtomicBool,
+    /// Workers killed so far.
+    aborted:    AtomicU64,
+}
+
+impl Watchdog {
+    /// `None` unless `ZK_MEMORY_CEILING_MB` is set.
+    pub fn from_env() -> Result<Option<Self>> {
+        let num = |k: &str| -> Result<Option<u64>> {
+            match std::env::var(k) {
+                Ok(v) if !v.is_empty() => Ok(Some(v.parse().with_context(|| format!("{k} must be a number"))?)),
+                _ => Ok(None),
+            }
+        };
+        let Some(ceiling) = num("ZK_MEMORY_CEILING_MB")? else { return Ok(None) };
+        let percent = num("ZK_MEMORY_ABORT_PERCENT")?.unwrap_or(90).clamp(1, 100);
+        Ok(Some(Watchdog {
+            ceiling_mb: ceiling as f64,
+            mark_mb:    ceiling as f64 * percent as f64 / 100.0,
+            every:      Duration::from_millis(num("ZK_MEMORY_CHECK_MS")?.unwrap_or(500).max(50)),
+            over:       AtomicBool::new(false),
+            aborted:    AtomicU64::new(0),
+        }))
+    }
+
+    /// Refuse a new proof while memory is above the mark.
+    pub fn check(&self) -> Result<(), ResourceExceeded> {
+        if !self.over.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        Err(ResourceExceeded {
+            reason:      format!("memory is near its {} MB ceiling", self.ceiling_mb),
+            retry_after: RETRY_AFTER,
+        })
+    }
+
+    pub fn report(&self) -> WatchdogReport {
+        WatchdogReport {
+            ceiling_mb: self.ceiling_mb,
+            mark_mb:    self.mark_mb,
+            over:       self.over.load(Ordering::Relaxed),
+            aborted:    self.aborted.load(Ordering::Relaxed),
+        }
+    }
+
+    /// One sample: total resident memory, and a worker killed if it is at the mark.
+    fn sample(&self) {
+        let workers: Vec<_> = worker::running().into_iter()
+            .map(|(pid, priority)| (pid, priority, scaling::proc_rss_mb(&pid.to_string()).unwrap_or(0.0)))
+            .collect();
+        let Some(own) = scaling::rss_mb() else { return };
+        let total = own + workers.iter().map(|w| w.2).sum::<f64>();
+        let over = total >= self.mark_mb;
+        if over != self.over.swap(over, Ordering::Relaxed) {
+            tracing::warn!(total_mb = total, mark_mb = self.mark_mb, "memory {} the watchdog mark", if over { "reached" } else { "back below" });
+        }
+        if !over {
+            return;
+        }
+        let victim = workers.iter()
+            .min_by(|a, b| a.1.cmp(&b.1).then(b.2.total_cmp(&a.2)));
+        if let Some(&(pid, priority, rss)) = victim {
+            if worker::abort(pid) {
+                self.aborted.fetch_add(1, Ordering::Relaxed);
+                tracing::error!(pid, ?priority, rss_mb = rss, total_mb = total, "memory watchdog aborted a prover worker");
+            }
+        }
+    }
+}
+
+pub fn spawn(watchdog: Arc<Watchdog>) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(watchdog.every);
+        loop {
+            tick.tick().await;
+            watchdog.sample();
+        }
+    });
+}
@@ -0,0 +1,248 @@
+//! Signed event webhooks.
+//! Two independent hooks share this sender:
+//!
+//! * billing (`ZK_BILLING_*`): every completed proof's [`UsageRecord`] as
+//!   `{ "id", "type": "proof.completed", "usage": { … } }`;
+//! * lifecycle (`ZK_EVENTS_*`): proof lifecycle notices such as
+//!   `{ "id", "type": "proof.revoked", "revocation": { … } }`.
+//!
+//! Each is enabled by `<PREFIX>_URL` and signed with `<PREFIX>_SECRET` (HMAC) or
+//! `<PREFIX>_ED25519_KEY` (hex seed) under key id `<PREFIX>_KEY_ID`:
+//!
+//! ```text
+//! x-zk-event-id:   <event id>             (also sent as Idempotency-Key)
+//! x-zk-timestamp:  <unix seconds>
+//! x-zk-key-id:     <key id, default "default">
+//! x-zk-signature:  v1=<hex HMAC-SHA256(secret, "<timestamp>.<body>")> | ed25519=<hex signature>
+//! ```
+//!
+//! Receivers check it with `kyc_core::webhook::Verifier`.
+//!
+//! Delivery runs off the request path with exponential backoff.  Retries resend the same event id,
+//! so receivers dedup on it; a 409 is taken as "already have it".  Events are written to the
+//! outbox (`outbox.rs`) before they are queued, so a restart resumes them, and events that are
+//! given up on go to the dead-letter store (`deadletter.rs`) for replay.
+
+use anyhow::{bail, Result};
+use kyc_core::webhook::{Signer, KEY_ID_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::mpsc;
+
+use crate::{deadletter::{DeadLetters, Kind}, outbox::Outbox, usage::UsageRecord};
+
+const ATTEMPTS:   u32 = 6;
+const FIRST_WAIT: Duration = Duration::from_secs(1);
+/// Pending events beyond this are dropped (and logged) rather than buffered without bound.
+const QUEUE:      usize = 10_000;
+/// Recently delivered ids, so an event handed in twice is sent once.
+const SEEN:       usize = 4_096;
+
+/// One delivery: `{ "id", "type", …data }` on the wire.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Event {
+    /// UUID; stable across retries, replays and restarts.
+    pub id:   String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(flatten)]
+    pub data: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Event {
+    /// `kind` event with a fresh id and one data field.
+    pub fn new(kind: &str, field: &str, value: impl Serialize) -> Self {
+        Self::with_id(uuid::Uuid::new_v4().to_string(), kind, field, value)
+    }
+
+    fn with_id(id: String, kind: &str, field: &str, value: impl Serialize) -> Self {
+        let mut data = serde_json::Map::new();
+        data.insert(field.to_string(), serde_json::to_value(value).unwrap_or_default());
+        Event { id, kind: kind.to_string(), data }
+    }
+}
+
+pub struct Webhook {
+    kind:   Kind,
+    tx:     mpsc::Sender<Event>,
+    dead:   Option<Arc<DeadLetters>>,
+    outbox: Option<Arc<Outbox>>,
+}
+
+struct Sender {
+    kind:   Kind,
+    url:    String,
+    signer: Signer,
+    client: reqwest::Client,
+    seen:   HashSet<String>,
+    order:  VecDeque<String>,
+    dead:   Option<Arc<DeadLetters>>,
+    outbox: Option<Arc<Outbox>>,
+}
+
+impl Webhook {
+    /// Build the `kind` hook from its `ZK_<PREFIX>_*` variables; `None` when its URL is unset.
+    pub fn from_env(kind: Kind, dead: Option<Arc<DeadLetters>>, outbox: Option<Arc<Outbox>>) -> Result<Option<Self>> {
+        let prefix = match kind {
+            Kind::Billing => "ZK_BILLING",
+            Kind::Event   => "ZK_EVENTS",
+        };
+        let var = |name: &str| std::env::var(format!("{prefix}_{name}")).ok().filter(|v| !v.is_empty());
+        let Some(url) = var("URL") else { return Ok(None) };
+        let key_id = var("KEY_ID").unwrap_or_else(|| "default".into());
+        let signer = match (var("SECRET"), var("ED25519_KEY")) {
+            (_, Some(seed))   => Signer::ed25519_from_hex(key_id, &seed)?,
+            (Some(secret), _) => Signer::Hmac { key_id, secret: secret.into_bytes() },
+            _ => bail!("{prefix}_URL is set but neither {prefix}_SECRET nor {prefix}_ED25519_KEY is"),
+        };
+        if let Some(pk) = signer.public_key_hex() {
+            tracing::info!(key_id = signer.key_id(), ?kind, "webhooks signed with Ed25519 public key {pk}");
+        }
+        let (tx, rx) = mpsc::channel(QUEUE);
+        let sender = Sender {
+            kind,
+            url,
+            signer,
+            client: reqwest::Client::builder().timeout(Duration::from_secs(10)).build()?,
+            seen:   HashSet::new(),
+            order:  VecDeque::new(),
+            dead:   dead.clone(),
+            outbox: outbox.clone(),
+        };
+        tokio::spawn(sender.run(rx));
+        let hook = Self { kind, tx, dead, outbox };
+        hook.resume();
+        Ok(Some(hook))
+    }
+
+    /// Re-queue this hook's events that a previous process left in the outbox.
+    fn resume(&self) {
+        let Some(outbox) = &self.outbox else { return };
+        let pending = match outbox.pending() {
+            Ok(p)  => p,
+            Err(e) => {
+                tracing::error!("reading outbox: {e:#}");
+                return;
+            }
+        };
+        let mine: Vec<_> = pending.into_iter().filter(|i| i.kind == self.kind).collect();
+        if !mine.is_empty() {
+            tracing::info!(kind = ?self.kind, "resuming {} events from the outbox", mine.len());
+        }
+        for intent in mine {
+            match serde_json::from_value::<Event>(intent.payload) {
+                Ok(ev) => self.queue(ev),
+                Err(e) => tracing::error!(event = %intent.id, "unreadable outbox entry: {e}"),
+            }
+        }
+    }
+
+    /// `proof.completed` billing event for `rec`, under the usage id.
+    pub fn emit_usage(&self, rec: UsageRecord) {
+        self.emit(Event::with_id(rec.id.clone(), "proof.completed", "usage", rec));
+    }
+
+    /// Record the event in the outbox, then queue it; never blocks on the network.
+    pub fn emit(&self, ev: Event) {
+        if let Some(outbox) = &self.outbox {
+            if let Err(e) = outbox.record(self.kind, &ev.id, &ev) {
+                tracing::error!(event = %ev.id, "writing outbox: {e:#}");
+            }
+        }
+        self.queue(ev);
+    }
+
+    fn queue(&self, ev: Event) {
+        if let Err(e) = self.tx.try_send(ev) {
+            let reason = e.to_string();
+            let ev = e.into_inner();
+            match &self.dead {
+                Some(d) => d.put(self.kind, &ev, &reason),
+                None    => tracing::error!(event = %ev.id, kind = %ev.kind, "webhook event dropped: {reason}"),
+            }
+            settle(self.outbox.as_deref(), &ev.id);
+        }
+    }
+}
+
+/// The event has reached a final outcome; forget its outbox entry.
+fn settle(outbox: Option<&Outbox>, id: &str) {
+    if let Some(o) = outbox {
+        o.done(id);
+    }
+}
+
+impl Sender {
+    async fn run(mut self, mut rx: mpsc::Receiver<Event>) {
+        while let Some(ev) = rx.recv().await {
+            if self.seen.contains(&ev.id) {
+                settle(self.outbox.as_deref(), &ev.id);
+                continue;
+            }
+            match self.deliver(&ev).await {
+                Ok(()) => {
+                    settle(self.outbox.as_deref(), &ev.id);
+                    self.remember(ev.id);
+                }
+                Err(e) => {
+                    match &self.dead {
+                        Some(d) => d.put(self.kind, &ev, &format!("{e:#}")),
+                        None    => tracing::error!(event = %ev.id, kind = %ev.kind, "webhook event undeliverable: {e:#}"),
+                    }
+                    settle(self.outbox.as_deref(), &ev.id);
+                }
+            }
+        }
+    }
+
+    fn remember(&mut self, id: String) {
+        if self.order.len() == SEEN {
+            if let Some(old) = self.order.pop_front() {
+                self.seen.remove(&old);
+            }
+        }
+        self.seen.insert(id.clone());
+        self.order.push_back(id);
+    }
+
+    async fn deliver(&self, ev: &Event) -> Result<()> {
+        let body = serde_json::to_string(ev)?;
+        let mut wait = FIRST_WAIT;
+        for attempt in 1..=ATTEMPTS {
+            let ts = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let res = self.client
+                .post(&self.url)
+                .header("content-type", "application/json")
+                .header("idempotency-key", &ev.id)
+                .header("x-zk-event-id", &ev.id)
+                .header(TIMESTAMP_HEADER, ts.to_string())
+                .header(KEY_ID_HEADER, self.signer.key_id())
+                .header(SIGNATURE_HEADER, self.signer.sign(ts, body.as_bytes()))
+                .body(body.clone())
+                .send().await;
+            match res {
+                Ok(r) if r.status().is_success() || r.status() == reqwest::StatusCode::CONFLICT => {
+                    return Ok(());
+                }
+                // Other client errors will not improve on retry.
+                Ok(r) if r.status().is_client_error()
+                    && r.status() != reqwest::StatusCode::REQUEST_TIMEOUT
+                    && r.status() != reqwest::StatusCode::TOO_MANY_REQUESTS =>
+                {
+                    bail!("webhook endpoint rejected event: {}", r.status());
+                }
+                Ok(r)  => tracing::warn!(event = %ev.id, attempt, "webhook endpoint returned {}", r.status()),
+                Err(e) => tracing::warn!(event = %ev.id, attempt, "webhook endpoint unreachable: {e}"),
+            }
+            if attempt < ATTEMPTS {
+                tokio::time::sleep(wait).await;
+                wait *= 2;
+            }
+        }
+        bail!("gave up after {ATTEMPTS} attempts")
+    }
+}
@@ -0,0 +1,352 @@
+//! Subprocess prover isolation (`ZK_PROVER_MODE=subprocess`).
+//! Each proof runs in a fresh `zk_server --prove-worker` process: the job goes in as bincode on
+//! stdin and the [`ProofRun`] comes back on stdout.  A runaway proof can then be bounded and
+//! killed without touching other jobs:
+//!
+//! * `ZK_PROVER_MEMORY_MB` — address-space rlimit of the worker, and `memory.max` of its cgroup;
+//! * `ZK_PROVER_CGROUP` — a delegated cgroup v2 directory; each worker gets its own child group;
+//! * `ZK_PROVER_TIMEOUT_SECS` — the worker is SIGKILLed after this long;
+//! * `ZK_NUMA_NODES` — each worker is pinned to one NUMA node (see numa.rs).
+//!
+//! Running workers are registered with their [`Priority`] so the memory watchdog can pick one
+//! to abort (see watchdog.rs).
+//!
+//! CPU time and peak RSS are taken from the reaped worker's rusage, so usage metering stays
+//! accurate.  The default, `inline`, proves on the calling thread.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    numa::Numa,
+    prover::{self, ParamCache, ProofRun, ProverPanic},
+    watchdog::ResourceExceeded,
+};
+
+/// First argument that turns the server binary into a one-shot proving worker.
+pub const WORKER_ARG: &str = "--prove-worker";
+
+/// Whose proof a worker runs; the watchdog aborts lower priorities first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Canary re-proofs, pre-issuance, step sweeps and self-tests.
+    Background,
+    /// A client's prove request or job.
+    Request,
+}
+
+struct Running {
+    pid:      u32,
+    priority: Priority,
+    aborted:  Arc<AtomicBool>,
+}
+
+/// Workers currently proving.
+static RUNNING: Mutex<Vec<Running>> = Mutex::new(Vec::new());
+
+/// Keeps a worker in [`RUNNING`] until dropped.
+struct Registered {
+    pid:     u32,
+    aborted: Arc<AtomicBool>,
+}
+
+impl Registered {
+    fn new(pid: u32, priority: Priority) -> Self {
+        let aborted = Arc::new(AtomicBool::new(false));
+        RUNNING.lock().unwrap().push(Running { pid, priority, aborted: aborted.clone() });
+        Registered { pid, aborted }
+    }
+}
+
+impl Drop for Registered {
+    fn drop(&mut self) {
+        RUNNING.lock().unwrap().retain(|w| w.pid != self.pid);
+    }
+}
+
+/// Pids and priorities of the workers proving right now.
+pub fn running() -> Vec<(u32, Priority)> {
+    RUNNING.lock().unwrap().iter().map(|w| (w.pid, w.priority)).collect()
+}
+
+/// SIGKILL worker `pid`, failing its job with [`ResourceExceeded`]; false if it already finished.
+pub fn abort(pid: u32) -> bool {
+    let running = RUNNING.lock().unwrap();
+    let Some(w) = running.iter().find(|w| w.pid == pid) else { return false };
+    w.aborted.store(true, Ordering::Release);
+    unsafe { libc::kill(pid as libc::pid_t, libc::SIGKILL) };
+    true
+}
+
+#[derive(Clone, Debug)]
+pub enum Isolation {
+    Inline,
+    Subprocess {
+        memory_mb: Option<u64>,
+        timeout:   Option<Duration>,
+        cgroup:    Option<PathBuf>,
+        numa:      Option<Arc<Numa>>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct Job {
+    wasm:   PathBuf,
+    invoke: String,
+    args:   Vec<String>,
+    step:   usize,
+}
+
+#[derive(Serialize, Deserialize)]
+enum Reply {
+    Proved(ProofRun),
+    Failed(String),
+    Panicked(String),
+}
+
+/// Returned (inside `anyhow::Error`) when a worker was killed or exited without a reply.
+#[derive(Debug)]
+pub struct WorkerKilled {
+    pub reason:    String,
+    /// Killed for running past its timeout, rather than crashing.
+    pub timed_out: bool,
+}
+
+impl std::fmt::Display for WorkerKilled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "prover worker died: {}", self.reason)
+    }
+}
+
+impl std::error::Error for WorkerKilled {}
+
+impl Isolation {
+    /// `timeout` kills a subprocess worker; it does not apply inline.
+    pub fn from_env(timeout: Option<Duration>) -> Result<Self> {
+        let num = |k: &str| -> Result<Option<u64>> {
+            std::env::var(k).ok().filter(|v| !v.is_empty())
+                .map(|v| v.parse().with_context(|| format!("{k} must be a number")))
+                .transpose()
+        };
+        match std::env::var("ZK_PROVER_MODE").as_deref() {
+            Ok("inline") | Err(_) => Ok(Isolation::Inline),
+            Ok("subprocess") => Ok(Isolation::Subprocess {
+                memory_mb: num("ZK_PROVER_MEMORY_MB")?,
+                timeout,
+                cgroup:    std::env::var("ZK_PROVER_CGROUP").ok().filter(|c| !c.is_empty()).map(PathBuf::from),
+                numa:      Numa::from_env()?.map(Arc::new),
+            }),
+            Ok(other) => bail!("unknown ZK_PROVER_MODE {other:?} (inline | subprocess)"),
+        }
+    }
+
+    /// Worker processes with this isolation's memory cap, cgroup and NUMA placement, killed
+    /// after `timeout`.
+    pub fn subprocess_with_timeout(&self, timeout: Duration) -> Isolation {
+        match self {
+            Isolation::Inline => Isolation::Subprocess { memory_mb: None, timeout: Some(timeout), cgroup: None, numa: None },
+            Isolation::Subprocess { memory_mb, cgroup, numa, .. } => Isolation::Subprocess {
+                memory_mb: *memory_mb,
+                timeout:   Some(timeout),
+                cgroup:    cgroup.clone(),
+                numa:      numa.clone(),
+            },
+        }
+    }
+
+    /// NUMA placement of worker processes, when enabled.
+    pub fn numa(&self) -> Option<&Numa> {
+        match self {
+            Isolation::Subprocess { numa, .. } => numa.as_deref(),
+            Isolation::Inline => None,
+        }
+    }
+
+    /// [`prover::run`], in a worker process when so configured.  Worker processes are
+    /// short-lived and run their own setup; only inline jobs use `params`.
+    pub fn run(&self, params: &ParamCache, wasm: &Path, invoke: &str, args: Vec<String>, step: usize, priority: Priority) -> Result<ProofRun> {
+        match self {
+            Isolation::Inline => prover::run(params, wasm, invoke, args, step),
+            Isolation::Subprocess { memory_mb, timeout, cgroup, numa } => {
+                let job = Job { wasm: wasm.to_path_buf(), invoke: invoke.to_string(), args, step };
+                run_worker(&job, *memory_mb, *timeout, cgroup.as_deref(), numa.as_deref(), priority)
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn run_worker(
+    job: &Job,
+    memory_mb: Option<u64>,
+    timeout: Option<Duration>,
+    cgroup: Option<&Path>,
+    numa: Option<&Numa>,
+    priority: Priority,
+) -> Result<ProofRun> {
+    use std::os::unix::process::{CommandExt, ExitStatusExt};
+
+    let mut cmd = Command::new(std::env::current_exe().context("locating zk_server binary")?);
+    cmd.arg(WORKER_ARG)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit());
+    let placement = numa.map(|n| n.place());
+    let pin = placement.as_ref().map(|p| p.pin());
+    if memory_mb.is_some() || pin.is_some() {
+        let bytes = memory_mb.map(|mb| mb.saturating_mul(1 << 20) as libc::rlim_t);
+        // Only async-signal-safe calls between fork and exec.
+        unsafe {
+            cmd.pre_exec(move || {
+                if let Some(bytes) = bytes {
+                    let lim = libc::rlimit { rlim_cur: bytes, rlim_max: bytes };
+                    if libc::setrlimit(libc::RLIMIT_AS, &lim) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                if let Some(pin) = &pin {
+                    pin.apply()?;
+                }
+                Ok(())
+            });
+        }
+    }
+    let mut child = cmd.spawn().context("spawning prover worker")?;
+    let pid = child.id();
+    let registered = Registered::new(pid, priority);
+    let group = cgroup.and_then(|parent| match join_cgroup(parent, pid, memory_mb) {
+        Ok(g)  => Some(g),
+        Err(e) => { tracing::warn!("prover worker {pid} not placed in a cgroup: {e:#}"); None }
+    });
+
+    let input = bincode::serialize(job)?;
+    let mut stdin = child.stdin.take().expect("piped stdin");
+    let mut stdout = child.stdout.take().expect("piped stdout");
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+
+    let done = Arc::new(AtomicBool::new(false));
+    let timed_out = Arc::new(AtomicBool::new(false));
+    if let Some(limit) = timeout {
+        let (done, timed_out) = (done.clone(), timed_out.clone());
+        std::thread::spawn(move || {
+            let t0 = Instant::now();
+            while !done.load(Ordering::Acquire) {
+                if t0.elapsed() >= limit {
+                    timed_out.store(true, Ordering::Release);
+                    unsafe { libc::kill(pid as libc::pid_t, libc::SIGKILL) };
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        });
+    }
+
+    let mut out = Vec::new();
+    let read = stdout.read_to_end(&mut out);
+    // Reap with wait4 (not `Child::wait`) to get the worker's rusage.
+    let mut status = 0;
+    let mut ru: libc::rusage = unsafe { core::mem::zeroed() };
+    let reaped = unsafe { libc::wait4(pid as libc::pid_t, &mut status, 0, &mut ru) };
+    done.store(true, Ordering::Release);
+    let aborted = registered.aborted.load(Ordering::Acquire);
+    drop(registered);
+    let _ = writer.join();
+    if let Some(g) = group {
+        let _ = std::fs::remove_dir(g);
+    }
+    if reaped < 0 {
+        return Err(std::io::Error::last_os_error()).context("waiting for prover worker");
+    }
+    read.context("reading prover worker output")?;
+
+    let status = std::process::ExitStatus::from_raw(status);
+    if let Some(sig) = status.signal() {
+        if aborted {
+            return Err(ResourceExceeded::aborted().into());
+        }
+        let timed_out = timed_out.load(Ordering::Acquire);
+        let reason = if timed_out {
+            format!("killed after exceeding ZK_PROVER_TIMEOUT_SECS ({}s)", timeout.map_or(0, |t| t.as_secs()))
+        } else {
+            format!("killed by signal {sig} (memory limit?)")
+        };
+        return Err(WorkerKilled { reason, timed_out }.into());
+    }
+    let reply: Reply = bincode::deserialize(&out).map_err(|_| WorkerKilled {
+        reason:    format!("exited with {status} without a result"),
+        timed_out: false,
+    })?;
+    match reply {
+        Reply::Proved(mut run) => {
+            let secs = |t: libc::timeval| t.tv_sec as f64 + t.tv_usec as f64 / 1e6;
+            // ru_maxrss is in KiB on Linux.
+            let cpu_sec = secs(ru.ru_utime) + secs(ru.ru_stime);
+            run.worker = Some((cpu_sec, ru.ru_maxrss as f64 / 1024.0));
+            if let Some(p) = &placement {
+                p.record(cpu_sec);
+            }
+            Ok(run)
+        }
+        Reply::Failed(e)   => Err(anyhow::anyhow!(e)),
+        Reply::Panicked(m) => Err(ProverPanic { message: m }.into()),
+    }
+}
+
+#[cfg(not(unix))]
+fn run_worker(_: &Job, _: Option<u64>, _: Option<Duration>, _: Option<&Path>, _: Option<&Numa>, _: Priority) -> Result<ProofRun> {
+    bail!("ZK_PROVER_MODE=subprocess is only supported on unix")
+}
+
+/// Give worker `pid` its own child group of `parent`, capped at `memory_mb`.
+fn join_cgroup(parent: &Path, pid: u32, memory_mb: Option<u64>) -> Result<PathBuf> {
+    let group = parent.join(format!("zk-prove-{pid}"));
+    std::fs::create_dir(&group).with_context(|| format!("creating {}", group.display()))?;
+    if let Some(mb) = memory_mb {
+        std::fs::write(group.join("memory.max"), (mb << 20).to_string())
+            .with_context(|| format!("setting {}/memory.max", group.display()))?;
+        let _ = std::fs::write(group.join("memory.swap.max"), "0");
+    }
+    std::fs::write(group.join("cgroup.procs"), pid.to_string())
+        .with_context(|| format!("moving {pid} into {}", group.display()))?;
+    Ok(group)
+}
+
+/// Worker side: read one job from stdin, prove it, write the reply to stdout and exit.
+#[cfg(unix)]
+pub fn serve() -> Result<()> {
+    use std::os::unix::io::FromRawFd;
+
+    // Keep the reply channel to ourselves: anything the prover prints goes to stderr.
+    let reply_fd = unsafe { libc::dup(1) };
+    anyhow::ensure!(reply_fd >= 0 && unsafe { libc::dup2(2, 1) } >= 0, "redirecting worker stdout");
+    let mut reply_out = unsafe { std::fs::File::from_raw_fd(reply_fd) };
+
+    let mut input = Vec::new();
+    std::io::stdin().read_to_end(&mut input)?;
+    let job: Job = bincode::deserialize(&input).context("decoding prover job")?;
+    let reply = match prover::run(&ParamCache::from_env(), &job.wasm, &job.invoke, job.args, job.step) {
+        Ok(run) => Reply::Proved(run),
+        Err(e)  => match e.downcast::<ProverPanic>() {
+            Ok(p)  => Reply::Panicked(p.message),
+            Err(e) => Reply::Failed(format!("{e:#}")),
+        },
+    };
+    reply_out.write_all(&bincode::serialize(&reply)?)?;
+    reply_out.flush()?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn serve() -> Result<()> {
+    bail!("{WORKER_ARG} is only supported on unix")
+}